@@ -0,0 +1,126 @@
+//! Standard workloads, each run as compiled ("naive") and optimized.
+//!
+//! ```text
+//! cargo bench
+//! ```
+
+use std::convert::TryFrom;
+use std::io;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_bf::ast::parse;
+use rust_bf::ir::{compile, compile_source, BrainfuckIR};
+use rust_bf::optimizer::optimize;
+use rust_bf::packed::{load, pack};
+use rust_bf::{Interpreter, Program};
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+const DBFI: &str = include_str!("../tests/fixtures/dbfi.bf");
+
+/// Compiles `src`, optimized or not.
+fn build(src: &str, optimized: bool) -> Vec<BrainfuckIR> {
+    let ir = compile(&parse(src).unwrap());
+    if optimized {
+        optimize(&ir)
+    } else {
+        ir
+    }
+}
+
+/// Runs `program` with output going nowhere.
+fn run(program: Vec<BrainfuckIR>, input: &[u8]) {
+    Interpreter::from_ir(program).input(input).output(io::sink()).run().unwrap();
+}
+
+/// Benchmarks `src` in a group named `name`, compiled once up front.
+fn workload(c: &mut Criterion, name: &str, src: &str, input: &[u8]) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for (label, optimized) in [("naive", false), ("optimized", true)] {
+        let program = build(src, optimized);
+        group.bench_with_input(BenchmarkId::from_parameter(label), &program, |b, program| {
+            b.iter(|| run(program.clone(), input));
+        });
+    }
+    group.finish();
+}
+
+/// Parsing and compiling are part of the measurement here.
+fn hello_world(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hello_world");
+    for (label, optimized) in [("naive", false), ("optimized", true)] {
+        group.bench_function(label, |b| b.iter(|| run(build(HELLO, optimized), b"")));
+    }
+    group.finish();
+}
+
+/// Seven nested loops of ten, 10^7 trips through the innermost one.
+fn counting_loop(c: &mut Criterion) {
+    let mut src = String::new();
+    for _ in 0..7 {
+        src.push_str(">++++++++++[");
+    }
+    for _ in 0..7 {
+        src.push_str("-]<");
+    }
+    workload(c, "counting_loop", &src, b"");
+}
+
+/// A brainfuck interpreter in brainfuck running hello world, loop and branch heavy.
+fn self_interpreter(c: &mut Criterion) {
+    let input = format!("{}!", HELLO);
+    workload(c, "self_interpreter", DBFI, input.as_bytes());
+}
+
+/// Walks the pointer over a wide tape, touching every cell on the way.
+fn cell_churn(c: &mut Criterion) {
+    // grow the tape to 30000 cells in one pass
+    let grow = ">+".repeat(30_000);
+    workload(c, "cell_churn/grow", &grow, b"");
+
+    // sweep 1000 cells back and forth 100 times
+    let sweep = format!("++++++++++[>++++++++++[>{}{}<-]<-]", ">+".repeat(1000), "<".repeat(1000));
+    workload(c, "cell_churn/sweep", &sweep, b"");
+}
+
+/// Adds to a block of 64 cells 10000 times, like keying a cipher state,
+/// run with `--features simd` for the SSE2 path.
+fn add_range(c: &mut Criterion) {
+    let block = format!("{}{}", vec!["+++"; 64].join(">"), "<".repeat(63));
+    let src = format!("++++++++++[>++++++++++[>++++++++++[>++++++++++[>{}<-]<-]<-]<-]", block);
+    workload(c, "add_range", &src, b"");
+}
+
+/// Scans right and back left across 30000 nonzero cells 100 times, with
+/// `[>]` and `[<]` loops the optimizer turns into word at a time searches.
+fn scan(c: &mut Criterion) {
+    let src = format!(">>{}<[<]<{}[>>[>]<[<]<-]", "+>".repeat(30_000), "+".repeat(100));
+    workload(c, "scan", &src, b"");
+}
+
+/// Many runs of a short program, on a new interpreter each time or on one reset in between.
+fn reuse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reuse");
+    group.bench_function("new", |b| b.iter(|| Interpreter::new(HELLO).unwrap().output(io::sink()).run().unwrap()));
+    let mut interpreter = Interpreter::from_ir(Vec::new()).output(io::sink());
+    interpreter.set_program(Program::try_from(HELLO).unwrap());
+    group.bench_function("reset", |b| {
+        b.iter(|| {
+            interpreter.reset();
+            interpreter.run().unwrap();
+        })
+    });
+    group.finish();
+}
+
+/// Loading the self-interpreter into IR, from its source or packed.
+fn loading(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loading");
+    let packed = pack(DBFI.as_bytes()).unwrap();
+    group.bench_function("text", |b| b.iter(|| compile_source(DBFI.as_bytes()).unwrap()));
+    group.bench_function("packed", |b| b.iter(|| load(&packed).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, hello_world, counting_loop, self_interpreter, cell_churn, add_range, scan, reuse, loading);
+criterion_main!(benches);
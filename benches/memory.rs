@@ -0,0 +1,56 @@
+//! Peak heap use of running and compiling a multi-megabyte program, which
+//! criterion cannot measure.
+//!
+//! ```text
+//! cargo bench --bench memory
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+use rust_bf::Interpreter;
+
+/// The system allocator, keeping count of the bytes in use and the most there were.
+struct Counting;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let in_use = IN_USE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(in_use, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Prints how far above what was in use before `run` the heap grew.
+fn measure(name: &str, run: impl FnOnce()) {
+    let before = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    run();
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    println!("{:<10} {:>6.1} MiB", name, peak as f64 / (1 << 20) as f64);
+}
+
+fn main() {
+    // 4 MiB of ops inside a single loop, all of which the VM keeps until the loop ends
+    let src = format!("+[-{}]", "+>-<".repeat(1 << 20));
+    measure("vm", || {
+        let mut status = new_brainfuck_status();
+        run_vm_bytes(&mut status, src.as_bytes(), &mut io::empty(), &mut io::sink()).unwrap();
+    });
+    measure("compile", || {
+        Interpreter::new(&src).unwrap();
+    });
+}
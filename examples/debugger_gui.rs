@@ -0,0 +1,31 @@
+//! A small brainfuck debugger window built on the `egui` widget.
+//!
+//! ```text
+//! cargo run --example debugger_gui --features egui,eframe
+//! ```
+
+use rust_bf::gui::BrainfuckDebuggerWidget;
+
+/// echoes its input, used when no program file is given
+const ECHO: &str = ",[.,]";
+
+struct DebuggerApp {
+    debugger: BrainfuckDebuggerWidget,
+}
+
+impl eframe::App for DebuggerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| self.debugger.ui(ui));
+    }
+}
+
+fn main() -> eframe::Result<()> {
+    let source = std::env::args().nth(1)
+        .map(|file| std::fs::read_to_string(file).expect("cannot read program"))
+        .unwrap_or_else(|| String::from(ECHO));
+    eframe::run_native(
+        "rust_bf debugger",
+        eframe::NativeOptions::default(),
+        Box::new(move |_cc| Ok(Box::new(DebuggerApp { debugger: BrainfuckDebuggerWidget::new(&source) }))),
+    )
+}
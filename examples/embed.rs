@@ -0,0 +1,29 @@
+//! Runs a brainfuck program from another crate, capturing its output.
+//!
+//! ```text
+//! cargo run --example embed
+//! ```
+
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// prints "Hello World!\n"
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+fn main() -> Result<(), BrainfuckError> {
+    // capture the output in memory instead of writing to stdout
+    let mut output = Vec::new();
+    Interpreter::new(HELLO)?
+        .input(&b""[..])
+        .output(&mut output)
+        // the program only needs a handful of cells
+        .with_memory_limit(64)
+        .run()?;
+    print!("{}", String::from_utf8_lossy(&output));
+
+    // limits are reported as errors rather than aborting the process
+    match Interpreter::new(">>>>>>>>+")?.with_memory_limit(4).run() {
+        Err(err) => println!("stopped: {}", err),
+        Ok(()) => println!("finished"),
+    }
+    Ok(())
+}
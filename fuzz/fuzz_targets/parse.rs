@@ -0,0 +1,14 @@
+//! Arbitrary bytes must only ever produce parse errors, never panics.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_bf::{ast, ir, optimizer};
+
+fuzz_target!(|data: &[u8]| {
+    let src = String::from_utf8_lossy(data);
+    if let Ok(ast) = ast::parse(&src) {
+        // whatever parses must compile and optimize too
+        optimizer::optimize(&ir::compile(&ast));
+    }
+});
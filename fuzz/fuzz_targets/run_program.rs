@@ -0,0 +1,62 @@
+//! Runs well-formed programs under step and memory limits.
+//!
+//! The input is program text, a NUL byte and the bytes fed to `,`.
+//! Non-op characters are dropped, unmatched `]` are skipped and missing
+//! `]` are appended, so every input turns into a balanced program.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// longest program generated, in ops
+const MAX_OPS: usize = 4096;
+/// IR nodes run before the program is aborted
+const STEP_LIMIT: u64 = 100_000;
+/// bytes the tape may allocate
+const MEMORY_LIMIT: usize = 1 << 16;
+
+/// Turns arbitrary bytes into a balanced program.
+fn program(bytes: &[u8]) -> String {
+    let mut src = String::new();
+    let mut depth = 0;
+    for &byte in bytes.iter().take(MAX_OPS) {
+        match byte {
+            b'[' => depth += 1,
+            b']' if depth == 0 => continue,
+            b']' => depth -= 1,
+            b'+' | b'-' | b'<' | b'>' | b'.' | b',' => (),
+            _ => continue,
+        }
+        src.push(byte as char);
+    }
+    for _ in 0..depth {
+        src.push(']');
+    }
+    src
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (src, input) = match data.iter().position(|&byte| byte == 0) {
+        Some(split) => (&data[..split], &data[split + 1..]),
+        None => (data, &[][..]),
+    };
+    let src = program(src);
+
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::new(&src)
+        .expect("generated programs are balanced")
+        .with_step_limit(STEP_LIMIT)
+        .with_memory_limit(MEMORY_LIMIT)
+        .input(input)
+        .output(&mut output);
+    let result = interpreter.run();
+    assert!(interpreter.tape().len() <= MEMORY_LIMIT);
+    match result {
+        Ok(()) => assert!(interpreter.is_halted()),
+        Err(BrainfuckError::StepLimitExceeded { limit }) => assert_eq!(limit, STEP_LIMIT),
+        Err(BrainfuckError::MemoryLimitExceeded { limit, .. }) => assert_eq!(limit, MEMORY_LIMIT),
+        Err(BrainfuckError::PointerUnderflow { .. }) => (),
+        Err(err) => panic!("unexpected error: {}", err),
+    }
+});
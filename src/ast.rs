@@ -0,0 +1,196 @@
+use alloc::vec::Vec;
+
+use crate::error::BrainfuckError;
+use crate::op::BrainfuckOp;
+
+/// Where an op was found in the source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// byte offset into the source
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+}
+
+/// A node of the brainfuck syntax tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BrainfuckNode {
+    /// a single op, never `LoopStartOp`, `LoopEndOp` or `MonoStateOp`
+    Op(BrainfuckOp, Position),
+    /// a `[ ... ]` loop
+    Loop {
+        /// ops between the brackets
+        body: Vec<BrainfuckNode>,
+        /// position of the `[`
+        start: Position,
+        /// position of the `]`
+        end: Position,
+    },
+}
+
+impl Drop for BrainfuckNode {
+    /// Drops the loops inside this one one after another instead of each
+    /// inside the one around it, so that deep nesting cannot overflow the stack.
+    fn drop(&mut self) {
+        if let BrainfuckNode::Loop { body, .. } = self {
+            let mut nodes = core::mem::take(body);
+            while let Some(mut node) = nodes.pop() {
+                if let BrainfuckNode::Loop { body, .. } = &mut node {
+                    nodes.append(body);
+                }
+            }
+        }
+    }
+}
+
+/// The ops of brainfuck source and where they are, read straight from its
+/// bytes without copying them, see [`tokens`].
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    /// the whole source
+    src: &'a [u8],
+    /// position of the next byte
+    position: Position,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (BrainfuckOp, Position);
+
+    fn next(&mut self) -> Option<(BrainfuckOp, Position)> {
+        while let Some(&byte) = self.src.get(self.position.offset) {
+            let position = self.position;
+            self.position.offset += 1;
+            // bytes continuing a UTF-8 character are part of its column
+            if byte & 0xc0 != 0x80 {
+                self.position.column += 1;
+            }
+            if byte == b'\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            }
+            // every op is ASCII, other bytes on their own are comments
+            match BrainfuckOp::from_char(byte as char) {
+                BrainfuckOp::MonoStateOp => (),
+                op => return Some((op, position)),
+            }
+        }
+        None
+    }
+}
+
+/// Returns the ops of `src`, comments skipped, with their positions.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::tokens;
+/// use rust_bf::BrainfuckOp;
+///
+/// let found: Vec<_> = tokens("ä+\n .".as_bytes()).map(|(op, at)| (op, at.offset, at.line, at.column)).collect();
+/// assert_eq!(found, [(BrainfuckOp::IncrementValueOp, 2, 1, 2), (BrainfuckOp::PrintOp, 5, 2, 2)]);
+/// ```
+pub fn tokens(src: &[u8]) -> Tokens<'_> {
+    Tokens { src, position: Position { offset: 0, line: 1, column: 1 } }
+}
+
+/// Checks that the brackets of `src` match, failing like [`parse`] does
+/// without building the syntax tree.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::check_brackets;
+///
+/// assert!(check_brackets(b"+[-[>]<]").is_ok());
+/// assert_eq!(check_brackets(b"+[-[>]<").unwrap_err().to_string(), "unmatched '[' at line 1, column 2");
+/// ```
+pub fn check_brackets(src: &[u8]) -> Result<(), BrainfuckError> {
+    let mut open = Vec::new();
+    for (op, position) in tokens(src) {
+        match op {
+            BrainfuckOp::LoopStartOp => open.push(position),
+            BrainfuckOp::LoopEndOp => {
+                open.pop().ok_or(BrainfuckError::UnmatchedClose(position))?;
+            },
+            _ => (),
+        }
+    }
+    match open.pop() {
+        Some(start) => Err(BrainfuckError::UnmatchedOpen(start)),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether `src` has a `,` and whether it has a `.`, to tell
+/// whether a program needs input wired up or output captured before it
+/// runs. Comments do not count, and the brackets need not match.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::contains_io;
+///
+/// assert_eq!(contains_io("+[,.]"), (true, true));
+/// assert_eq!(contains_io("print. this, [-]"), (true, true));
+/// assert_eq!(contains_io("++[>+<-]>."), (false, true));
+/// ```
+pub fn contains_io(src: &str) -> (bool, bool) {
+    let (mut reads, mut prints) = (false, false);
+    for (op, _) in tokens(src.as_bytes()) {
+        match op {
+            BrainfuckOp::ReadOp => reads = true,
+            BrainfuckOp::PrintOp => prints = true,
+            _ => continue,
+        }
+        if reads && prints {
+            break;
+        }
+    }
+    (reads, prints)
+}
+
+/// Parses brainfuck source into a syntax tree, dropping everything that is not an op.
+///
+/// # Arguments
+///
+/// * `src` - brainfuck source, comments allowed
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::{parse, BrainfuckNode};
+///
+/// let ast = parse("+[-]").unwrap();
+/// assert_eq!(ast.len(), 2);
+/// assert!(matches!(ast[1], BrainfuckNode::Loop { .. }));
+/// assert!(parse("[").is_err());
+/// ```
+pub fn parse(src: &str) -> Result<Vec<BrainfuckNode>, BrainfuckError> {
+    // the outermost body lives at the bottom of the stack
+    // every `[` pushes a new body along with its position
+    let mut stack: Vec<(Vec<BrainfuckNode>, Position)> = Vec::new();
+    let mut body: Vec<BrainfuckNode> = Vec::new();
+
+    for (op, position) in tokens(src.as_bytes()) {
+        match op {
+            BrainfuckOp::LoopStartOp => {
+                stack.push((body, position));
+                body = Vec::new();
+            },
+            BrainfuckOp::LoopEndOp => {
+                let (outer, start) = stack.pop().ok_or(BrainfuckError::UnmatchedClose(position))?;
+                let inner = core::mem::replace(&mut body, outer);
+                body.push(BrainfuckNode::Loop { body: inner, start, end: position });
+            },
+            op => body.push(BrainfuckNode::Op(op, position)),
+        }
+    }
+
+    // report the innermost `[` that was never closed
+    match stack.pop() {
+        Some((_, start)) => Err(BrainfuckError::UnmatchedOpen(start)),
+        None => Ok(body),
+    }
+}
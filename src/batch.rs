@@ -0,0 +1,396 @@
+//! Runs a manifest of programs and compares what they print with what
+//! they should print, for grading a pile of submissions in one go, and
+//! [`run_many`] runs many programs at once from within a program.
+//!
+//! A manifest is a small subset of TOML, one `[[entry]]` table per program:
+//!
+//! ```toml
+//! # paths are relative to the manifest
+//! [[entry]]
+//! program = "hello.bf"
+//! expected = "hello.out"
+//!
+//! [[entry]]
+//! program = "cat.bf"
+//! input = "cat.in"
+//! expected = "cat.in"
+//! step_limit = 100000
+//! ```
+
+use std::any::Any;
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::io::{LimitedOutput, OutputLimitAction};
+use crate::{BrainfuckError, Interpreter, Program};
+
+/// most lines of expected and actual output a diff shows
+const DIFF_LINES: usize = 3;
+
+/// IR nodes a run with a timeout takes between looks at the clock
+const TIME_CHECK_STEPS: u32 = 1 << 16;
+
+/// A program to run, with the input it gets and the output it should print.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchEntry {
+    /// program file
+    pub program: PathBuf,
+    /// file the program reads with `,`, no input at all if `None`
+    pub input: Option<PathBuf>,
+    /// file holding the output the program should print
+    pub expected: PathBuf,
+    /// most IR nodes the program may run, no limit if `None`
+    pub step_limit: Option<u64>,
+}
+
+/// An error in a manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestError {
+    /// line of the manifest the error is on, starting at 1
+    pub line: usize,
+    /// what is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+/// How running an entry went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchOutcome {
+    /// the program printed the expected output
+    Pass,
+    /// the program ran to its end but printed something else, with a short diff
+    WrongOutput(String),
+    /// a file could not be read, the program did not compile or it failed while running
+    Error(String),
+}
+
+impl BatchOutcome {
+    /// Returns whether the entry passed.
+    pub fn passed(&self) -> bool {
+        *self == BatchOutcome::Pass
+    }
+}
+
+/// Parses a manifest, paths in it are taken relative to `base`.
+///
+/// # Example
+///
+/// ```
+/// use std::path::Path;
+/// use rust_bf::batch::parse_manifest;
+///
+/// let entries = parse_manifest("[[entry]]\nprogram = \"a.bf\"\nexpected = \"a.out\"\nstep_limit = 1000\n", Path::new("tests")).unwrap();
+/// assert_eq!(entries[0].program, Path::new("tests/a.bf"));
+/// assert_eq!(entries[0].input, None);
+/// assert_eq!(entries[0].step_limit, Some(1000));
+///
+/// let err = parse_manifest("[[entry]]\nprogram = \"a.bf\"\n", Path::new(".")).unwrap_err();
+/// assert_eq!(err.to_string(), "line 1: entry has no `expected` file");
+/// ```
+pub fn parse_manifest(text: &str, base: &Path) -> Result<Vec<BatchEntry>, ManifestError> {
+    // every entry with the line its table starts on, fields are checked once it is complete
+    let mut tables: Vec<(usize, BatchTable)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: &str| ManifestError { line: i + 1, message: String::from(message) };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "[[entry]]" {
+            tables.push((i + 1, BatchTable::default()));
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| error("expected `key = value` or `[[entry]]`"))?;
+        let table = match tables.last_mut() {
+            Some((_, table)) => table,
+            None => return Err(error("fields must come after an `[[entry]]` line")),
+        };
+        let value = value.trim();
+        match key.trim() {
+            "program" => table.program = Some(base.join(string(value).ok_or_else(|| error("`program` must be a string"))?)),
+            "input" => table.input = Some(base.join(string(value).ok_or_else(|| error("`input` must be a string"))?)),
+            "expected" => table.expected = Some(base.join(string(value).ok_or_else(|| error("`expected` must be a string"))?)),
+            "step_limit" => {
+                table.step_limit = Some(value.replace('_', "").parse().map_err(|_| error("`step_limit` must be a number"))?)
+            },
+            key => return Err(error(&format!("unknown field `{}`", key))),
+        }
+    }
+
+    tables
+        .into_iter()
+        .map(|(line, table)| {
+            let missing = |field: &str| ManifestError { line, message: format!("entry has no `{}` file", field) };
+            Ok(BatchEntry {
+                program: table.program.ok_or_else(|| missing("program"))?,
+                input: table.input,
+                expected: table.expected.ok_or_else(|| missing("expected"))?,
+                step_limit: table.step_limit,
+            })
+        })
+        .collect()
+}
+
+/// Fields of an `[[entry]]` table seen so far.
+#[derive(Default)]
+struct BatchTable {
+    program: Option<PathBuf>,
+    input: Option<PathBuf>,
+    expected: Option<PathBuf>,
+    step_limit: Option<u64>,
+}
+
+/// Returns `line` up to a `#` that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+/// Returns the contents of a basic TOML string, `None` if `value` is not one.
+fn string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut string = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '\\' => string.push('\\'),
+                '"' => string.push('"'),
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                _ => return None,
+            },
+            '"' => return None,
+            c => string.push(c),
+        }
+    }
+    Some(string)
+}
+
+/// Runs a single entry on a fresh interpreter.
+pub fn run_entry(entry: &BatchEntry) -> BatchOutcome {
+    let read = |path: &Path| fs::read(path).map_err(|err| BatchOutcome::Error(format!("cannot read {}: {}", path.display(), err)));
+    let result = (|| {
+        let src = fs::read_to_string(&entry.program)
+            .map_err(|err| BatchOutcome::Error(format!("cannot read {}: {}", entry.program.display(), err)))?;
+        let input = match &entry.input {
+            Some(path) => read(path)?,
+            None => Vec::new(),
+        };
+        let expected = read(&entry.expected)?;
+
+        let mut output = Vec::new();
+        let mut interpreter = Interpreter::new(&src).map_err(|err| BatchOutcome::Error(err.to_string()))?;
+        if let Some(limit) = entry.step_limit {
+            interpreter = interpreter.with_step_limit(limit);
+        }
+        interpreter.input(&input[..]).output(&mut output).run().map_err(|err| BatchOutcome::Error(err.to_string()))?;
+        Ok(if output == expected { BatchOutcome::Pass } else { BatchOutcome::WrongOutput(diff(&expected, &output)) })
+    })();
+    result.unwrap_or_else(|outcome| outcome)
+}
+
+/// Runs every entry on `jobs` threads, each on a fresh interpreter, and
+/// returns their outcomes in the order of `entries`.
+pub fn run_batch(entries: &[BatchEntry], jobs: usize) -> Vec<BatchOutcome> {
+    parallel_map(entries, jobs, run_entry)
+}
+
+/// Limits every run of [`run_many`] and [`execute_with_limits`] gets on its own.
+///
+/// A run that hits one fails with an error of its own:
+/// [`BrainfuckError::StepLimitExceeded`], [`BrainfuckError::TimeLimitExceeded`],
+/// [`BrainfuckError::MemoryLimitExceeded`] or [`BrainfuckError::OutputLimitExceeded`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// most IR nodes a program may run, no limit if `None`
+    pub step_limit: Option<u64>,
+    /// longest a program may run, no limit if `None`
+    pub timeout: Option<Duration>,
+    /// most bytes the tape of a program may allocate, no limit if `None`
+    pub memory_limit: Option<usize>,
+    /// most bytes a program may print, no limit if `None`
+    pub max_output: Option<usize>,
+}
+
+/// What a program that ran to its end did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// everything the program printed
+    pub output: Vec<u8>,
+    /// IR nodes run
+    pub steps: u64,
+}
+
+/// Runs every program on its input at once, one thread per CPU, and
+/// returns how each run went in the order of `programs`.
+///
+/// Every program runs on an interpreter of its own with its own limits,
+/// and reads and prints to buffers rather than stdin and stdout. A run
+/// that panics fails with [`BrainfuckError::Panicked`], the others go on.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use rust_bf::batch::{run_many, Limits};
+/// use rust_bf::{BrainfuckError, Program};
+///
+/// let programs = vec![
+///     (Program::try_from(",+.").unwrap(), b"a".to_vec()),
+///     (Program::try_from("+[]").unwrap(), Vec::new()),
+/// ];
+/// let results = run_many(&programs, Limits { step_limit: Some(100), ..Limits::default() });
+/// assert_eq!(results[0].as_ref().unwrap().output, b"b");
+/// assert!(matches!(results[1], Err(BrainfuckError::StepLimitExceeded { limit: 100 })));
+/// ```
+pub fn run_many(programs: &[(Program, Vec<u8>)], limits: Limits) -> Vec<Result<RunOutcome, BrainfuckError>> {
+    let jobs = thread::available_parallelism().map_or(1, |jobs| jobs.get());
+    parallel_map(programs, jobs, |(program, input)| {
+        // the interpreter is made on the thread that runs it, a panic takes nothing shared down with it
+        panic::catch_unwind(AssertUnwindSafe(|| run_limited(program, input, limits)))
+            .unwrap_or_else(|panic| Err(BrainfuckError::Panicked(panic_message(&*panic))))
+    })
+}
+
+/// Returns the message a panic was raised with.
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| String::from(*message))
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Runs `program` on `input` within `limits` and returns what it printed,
+/// the call a playground runs untrusted submissions with.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use rust_bf::batch::{execute_with_limits, Limits};
+/// use rust_bf::BrainfuckError;
+///
+/// let limits = Limits { timeout: Some(Duration::from_millis(100)), max_output: Some(3), ..Limits::default() };
+/// assert_eq!(execute_with_limits(",+.", b"a", limits).unwrap(), b"b");
+/// let err = execute_with_limits("+[]", b"", limits).unwrap_err();
+/// assert!(matches!(err, BrainfuckError::TimeLimitExceeded { .. }));
+/// let err = execute_with_limits("+[.]", b"", limits).unwrap_err();
+/// assert!(matches!(err, BrainfuckError::OutputLimitExceeded { limit: 3 }));
+/// ```
+pub fn execute_with_limits(program: &str, input: &[u8], limits: Limits) -> Result<Vec<u8>, BrainfuckError> {
+    let (output, _, result) = run_within(Interpreter::new(program)?.input(input), limits);
+    result.map(|()| output)
+}
+
+/// Runs `program` on `input` within `limits`.
+fn run_limited(program: &Program, input: &[u8], limits: Limits) -> Result<RunOutcome, BrainfuckError> {
+    let mut interpreter = Interpreter::from_ir(Vec::new()).input(input);
+    interpreter.set_program(program.clone());
+    let (output, steps, result) = run_within(interpreter, limits);
+    result.map(|()| RunOutcome { output, steps })
+}
+
+/// Runs `interpreter` within `limits`, returning what it printed and the
+/// steps it took however it ended.
+pub(crate) fn run_within(interpreter: Interpreter<'_>, limits: Limits) -> (Vec<u8>, u64, Result<(), BrainfuckError>) {
+    let mut output = Vec::new();
+    let mut interpreter = interpreter;
+    if let Some(limit) = limits.step_limit {
+        interpreter = interpreter.with_step_limit(limit);
+    }
+    if let Some(limit) = limits.memory_limit {
+        interpreter = interpreter.with_memory_limit(limit);
+    }
+    let limit = limits.max_output.unwrap_or(usize::MAX);
+    let mut interpreter = interpreter.output(LimitedOutput::new(&mut output, limit, OutputLimitAction::Error));
+    let result = match limits.timeout {
+        None => interpreter.run(),
+        Some(limit) => run_until(&mut interpreter, Instant::now() + limit, limit),
+    };
+    let steps = interpreter.steps();
+    drop(interpreter);
+    (output, steps, result)
+}
+
+/// Runs `interpreter` to its end unless that takes past `deadline`.
+fn run_until(interpreter: &mut Interpreter<'_>, deadline: Instant, limit: Duration) -> Result<(), BrainfuckError> {
+    loop {
+        for _ in 0..TIME_CHECK_STEPS {
+            if !interpreter.step()? {
+                return Ok(());
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(BrainfuckError::TimeLimitExceeded { limit });
+        }
+    }
+}
+
+/// Calls `f` on every item on `jobs` threads, returning the results in the order of `items`.
+fn parallel_map<T: Sync, R: Send>(items: &[T], jobs: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let item = match items.get(i) {
+                    Some(item) => item,
+                    None => break,
+                };
+                let result = f(item);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|result| result.expect("every item was run")).collect()
+}
+
+/// Formats the first lines where `expected` and `actual` part ways, `-`
+/// before expected lines and `+` before actual ones.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::batch::diff;
+///
+/// assert_eq!(diff(b"a\nb\nc\n", b"a\nx\nc\n"), "line 2:\n- b\n- c\n+ x\n+ c\n");
+/// assert_eq!(diff(b"ab", b"a"), "line 1:\n- ab\n+ a\n");
+/// ```
+pub fn diff(expected: &[u8], actual: &[u8]) -> String {
+    let expected = String::from_utf8_lossy(expected);
+    let actual = String::from_utf8_lossy(actual);
+    let expected: Vec<&str> = expected.split_inclusive('\n').collect();
+    let actual: Vec<&str> = actual.split_inclusive('\n').collect();
+    let first = expected.iter().zip(&actual).take_while(|(e, a)| e == a).count();
+
+    let mut text = format!("line {}:\n", first + 1);
+    for (sign, lines) in [('-', &expected), ('+', &actual)] {
+        for line in lines.iter().skip(first).take(DIFF_LINES) {
+            text.push_str(&format!("{} {}\n", sign, line.strip_suffix('\n').unwrap_or(line)));
+        }
+    }
+    text
+}
@@ -0,0 +1,195 @@
+//! BrainCopter, brainfuck programs drawn as PNG images, enable the
+//! `braincopter` feature to use it.
+//!
+//! Every pixel is an instruction, `(11 * r + 5 * g + b) % 36` of its color
+//! picks which one:
+//!
+//! | value | instruction            |
+//! |-------|------------------------|
+//! | 0     | `>`                    |
+//! | 1     | `<`                    |
+//! | 2     | `+`                    |
+//! | 3     | `-`                    |
+//! | 4     | `.`                    |
+//! | 5     | `,`                    |
+//! | 6     | `[`                    |
+//! | 7     | `]`                    |
+//! | 8     | turn clockwise         |
+//! | 9     | turn counterclockwise  |
+//! | other | nothing                |
+//!
+//! The program starts at the top left pixel heading right, and ends once
+//! it walks off the image.
+
+use std::fmt;
+use std::io::Cursor;
+
+use image::{ImageError, ImageFormat, Rgb, RgbImage};
+
+/// the instruction of values 0 to 7
+const OPS: [char; 8] = ['>', '<', '+', '-', '.', ',', '[', ']'];
+
+/// value of a pixel that turns clockwise
+const TURN_CLOCKWISE: u32 = 8;
+
+/// value of a pixel that turns counterclockwise
+const TURN_COUNTERCLOCKWISE: u32 = 9;
+
+/// the colors [`encode_braincopter`] draws values 0 to 9 with, apart enough to tell at a glance
+const PALETTE: [[u8; 3]; 10] = [
+    [230, 25, 81],
+    [60, 180, 97],
+    [255, 225, 32],
+    [0, 130, 217],
+    [245, 130, 79],
+    [145, 30, 204],
+    [70, 240, 232],
+    [240, 50, 249],
+    [128, 128, 156],
+    [0, 0, 9],
+];
+
+/// the color of pixels that do nothing, white has value 15
+const BLANK: [u8; 3] = [255, 255, 255];
+
+/// Error reported by [`decode_braincopter`] if the bytes are not a PNG image.
+///
+/// Any image is a program that halts: every pixel turns the path in a way
+/// that can be undone, so a path that came in past the left edge has to
+/// walk back out of the image at some point.
+#[derive(Debug)]
+pub struct ImageDecodeError(pub ImageError);
+
+impl fmt::Display for ImageDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot decode image: {}", self.0)
+    }
+}
+
+impl std::error::Error for ImageDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Errors reported by [`encode_braincopter`].
+#[derive(Debug)]
+pub enum EncodeError {
+    /// the image holds fewer ops than the program has, a row holds
+    /// `width - 2` of them, the outer columns turn the path
+    TooSmall {
+        /// ops in the program
+        ops: usize,
+        /// ops an image of the given size holds
+        capacity: usize,
+    },
+    /// the image could not be written as PNG
+    Image(ImageError),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::TooSmall { ops, capacity } => {
+                write!(f, "the program has {} ops, but the image only holds {}", ops, capacity)
+            },
+            EncodeError::Image(err) => write!(f, "cannot encode image: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Image(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the instruction value of a pixel.
+fn value(Rgb([r, g, b]): Rgb<u8>) -> u32 {
+    (11 * r as u32 + 5 * g as u32 + b as u32) % 36
+}
+
+/// Reads the brainfuck program drawn in a BrainCopter PNG image.
+///
+/// The program is returned with only its ops, the path pixels that turn
+/// it and pixels that do nothing leave nothing behind.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::braincopter::{decode_braincopter, encode_braincopter};
+///
+/// let png = encode_braincopter("++[>+<-]>.", 5, 4).unwrap();
+/// assert_eq!(decode_braincopter(&png).unwrap(), "++[>+<-]>.");
+/// ```
+pub fn decode_braincopter(image_bytes: &[u8]) -> Result<String, ImageDecodeError> {
+    let image = image::load_from_memory_with_format(image_bytes, ImageFormat::Png).map_err(ImageDecodeError)?.to_rgb8();
+    let (width, height) = image.dimensions();
+    let mut src = String::new();
+    let (mut x, mut y) = (0i64, 0i64);
+    // heading right, `y` grows downwards
+    let (mut dx, mut dy) = (1i64, 0i64);
+    while (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+        match value(*image.get_pixel(x as u32, y as u32)) {
+            op @ 0..=7 => src.push(OPS[op as usize]),
+            TURN_CLOCKWISE => (dx, dy) = (-dy, dx),
+            TURN_COUNTERCLOCKWISE => (dx, dy) = (dy, -dx),
+            _ => (),
+        }
+        x += dx;
+        y += dy;
+    }
+    Ok(src)
+}
+
+/// Draws the ops of `src` as a BrainCopter PNG image of `width` by
+/// `height` pixels, comments are left out.
+///
+/// The path snakes through the image: left to right along the first row,
+/// back along the second and so on, turning in the outer columns. The
+/// pixels it does not need are white.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::braincopter::{encode_braincopter, EncodeError};
+///
+/// assert!(encode_braincopter("+.", 4, 1).is_ok());
+/// assert!(matches!(encode_braincopter("+.+", 4, 1), Err(EncodeError::TooSmall { ops: 3, capacity: 2 })));
+/// ```
+pub fn encode_braincopter(src: &str, width: u32, height: u32) -> Result<Vec<u8>, EncodeError> {
+    let ops: Vec<u32> = src.chars().filter_map(|c| OPS.iter().position(|&op| op == c)).map(|op| op as u32).collect();
+    let per_row = width.saturating_sub(2) as usize;
+    let capacity = per_row * height as usize;
+    if ops.len() > capacity {
+        return Err(EncodeError::TooSmall { ops: ops.len(), capacity });
+    }
+
+    let mut image = RgbImage::from_pixel(width, height, Rgb(BLANK));
+    for (row, chunk) in ops.chunks(per_row.max(1)).enumerate() {
+        let y = row as u32;
+        let more = (row + 1) * per_row < ops.len();
+        // even rows head right, odd rows head left, both arrive from the row above
+        let (first, last) = if row % 2 == 0 { (0, width - 1) } else { (width - 1, 0) };
+        if row > 0 {
+            // the turn of the row above sent the path down, this one heads it along the row
+            let turn = if row % 2 == 0 { TURN_COUNTERCLOCKWISE } else { TURN_CLOCKWISE };
+            image.put_pixel(first, y, Rgb(PALETTE[turn as usize]));
+        }
+        for (i, &op) in chunk.iter().enumerate() {
+            let x = if row % 2 == 0 { 1 + i as u32 } else { width - 2 - i as u32 };
+            image.put_pixel(x, y, Rgb(PALETTE[op as usize]));
+        }
+        if more {
+            let turn = if row % 2 == 0 { TURN_CLOCKWISE } else { TURN_COUNTERCLOCKWISE };
+            image.put_pixel(last, y, Rgb(PALETTE[turn as usize]));
+        }
+    }
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).map_err(EncodeError::Image)?;
+    Ok(png)
+}
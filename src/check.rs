@@ -0,0 +1,259 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ast::{BrainfuckNode, Position};
+use crate::op::BrainfuckOp;
+
+/// A suspicious construct found by [`check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Warning {
+    /// stable code, `W001` and up
+    pub code: &'static str,
+    /// where the construct starts
+    pub position: Position,
+    /// what is suspicious about it
+    pub message: &'static str,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: warning[{}]: {}", self.position.line, self.position.column, self.code, self.message)
+    }
+}
+
+/// Returns whether the cell under the pointer is used before it is
+/// overwritten or the program ends, `None` if that cannot be told
+/// without running the program.
+///
+/// `rest` are the ops following a `,`, `in_loop` says whether they are
+/// followed by a `]` that tests the cell or by the end of the program.
+fn cell_used(rest: &[BrainfuckNode], in_loop: bool) -> Option<bool> {
+    let mut offset = 0;
+    for node in rest {
+        match node {
+            BrainfuckNode::Op(op, _) => match op {
+                BrainfuckOp::IncrementPtrOp => offset += 1,
+                BrainfuckOp::DecrementPtrOp => offset -= 1,
+                BrainfuckOp::ReadOp if offset == 0 => return Some(false),
+                _ if offset == 0 => return Some(true),
+                _ => (),
+            },
+            // the loop tests the cell, or it may move the pointer anywhere
+            BrainfuckNode::Loop { .. } => return if offset == 0 { Some(true) } else { None },
+        }
+    }
+    match (in_loop, offset) {
+        (false, _) => Some(false),
+        (true, 0) => Some(true),
+        (true, _) => None,
+    }
+}
+
+/// Returns the net pointer movement of `nodes`, `None` if a loop inside moves the pointer.
+fn net_movement(nodes: &[BrainfuckNode]) -> Option<isize> {
+    // the nodes left of every body being walked, `nodes` at the bottom,
+    // and how far each moved the pointer so far
+    let mut bodies = vec![(nodes.iter(), 0)];
+    while let Some((rest, offset)) = bodies.last_mut() {
+        match rest.next() {
+            Some(BrainfuckNode::Op(BrainfuckOp::IncrementPtrOp, _)) => *offset += 1,
+            Some(BrainfuckNode::Op(BrainfuckOp::DecrementPtrOp, _)) => *offset -= 1,
+            Some(BrainfuckNode::Op(..)) => (),
+            Some(BrainfuckNode::Loop { body, .. }) => bodies.push((body.iter(), 0)),
+            None => {
+                let offset = *offset;
+                bodies.pop();
+                if bodies.is_empty() {
+                    return Some(offset);
+                } else if offset != 0 {
+                    return None;
+                }
+            },
+        }
+    }
+    unreachable!("the walk ends with `nodes`")
+}
+
+/// Returns whether `body`, started `offset` cells right of a cell, leaves
+/// that cell alone, printing it at most, and moves the pointer back to where
+/// it started.
+fn keeps_cell(body: &[BrainfuckNode], offset: isize) -> bool {
+    // the nodes left of every body being walked, `body` at the bottom,
+    // with how far right of the cell it started and the pointer is now
+    let mut bodies = vec![(body.iter(), offset, offset)];
+    while let Some((rest, start, offset)) = bodies.last_mut() {
+        match rest.next() {
+            Some(BrainfuckNode::Op(BrainfuckOp::IncrementPtrOp, _)) => *offset += 1,
+            Some(BrainfuckNode::Op(BrainfuckOp::DecrementPtrOp, _)) => *offset -= 1,
+            Some(BrainfuckNode::Op(BrainfuckOp::PrintOp, _)) => (),
+            Some(BrainfuckNode::Op(..)) | Some(BrainfuckNode::Loop { .. }) if *offset == 0 => return false,
+            Some(BrainfuckNode::Op(..)) => (),
+            Some(BrainfuckNode::Loop { body, .. }) => {
+                let offset = *offset;
+                bodies.push((body.iter(), offset, offset));
+            },
+            // every loop inside has to come back to where it started too
+            None if offset != start => return false,
+            None => {
+                bodies.pop();
+            },
+        }
+    }
+    true
+}
+
+/// Returns the net pointer movement of `body` and what it adds to the cell
+/// it starts on, `None` if it reads into that cell or has a loop that may
+/// change it or move the pointer.
+fn cell_effect(body: &[BrainfuckNode]) -> Option<(isize, i32)> {
+    let mut offset = 0;
+    let mut delta = 0;
+    for node in body {
+        match node {
+            BrainfuckNode::Op(BrainfuckOp::IncrementPtrOp, _) => offset += 1,
+            BrainfuckNode::Op(BrainfuckOp::DecrementPtrOp, _) => offset -= 1,
+            BrainfuckNode::Op(BrainfuckOp::IncrementValueOp, _) if offset == 0 => delta += 1,
+            BrainfuckNode::Op(BrainfuckOp::DecrementValueOp, _) if offset == 0 => delta -= 1,
+            BrainfuckNode::Op(BrainfuckOp::ReadOp, _) if offset == 0 => return None,
+            BrainfuckNode::Op(..) => (),
+            BrainfuckNode::Loop { body, .. } => {
+                if offset == 0 || !keeps_cell(body, offset) {
+                    return None;
+                }
+            },
+        }
+    }
+    Some((offset, delta))
+}
+
+/// Collects warnings for the whole program `ast` and every loop body in it.
+fn check_bodies(ast: &[BrainfuckNode], warnings: &mut Vec<Warning>) {
+    // every body being checked with the nodes left of it and how deep it
+    // is nested, the whole program at depth 0 at the bottom
+    let mut bodies = vec![(ast, ast.iter().enumerate(), 0)];
+    while let Some((nodes, rest, depth)) = bodies.last_mut() {
+        let (nodes, depth) = (*nodes, *depth);
+        let (i, node) = match rest.next() {
+            Some(next) => next,
+            None => {
+                bodies.pop();
+                continue;
+            },
+        };
+        match node {
+            BrainfuckNode::Op(BrainfuckOp::ReadOp, position) => {
+                if cell_used(&nodes[i + 1..], depth > 0) == Some(false) {
+                    warnings.push(Warning {
+                        code: "W002",
+                        position: *position,
+                        message: "value read by ',' is never used",
+                    });
+                }
+            },
+            BrainfuckNode::Op(..) => (),
+            BrainfuckNode::Loop { body, start, .. } => {
+                // the cell is zero at program start and right after a loop
+                let after_loop = i > 0 && matches!(nodes[i - 1], BrainfuckNode::Loop { .. });
+                if (depth == 0 && i == 0) || after_loop {
+                    warnings.push(Warning {
+                        code: "W001",
+                        position: *start,
+                        message: "loop never runs since the cell is zero here (probable comment)",
+                    });
+                } else if body.is_empty() {
+                    warnings.push(Warning {
+                        code: "W003",
+                        position: *start,
+                        message: "empty loop never terminates if the cell is nonzero",
+                    });
+                } else {
+                    match cell_effect(body) {
+                        Some((0, 0)) => warnings.push(Warning {
+                            code: "W005",
+                            position: *start,
+                            message: "loop never terminates if the cell is nonzero, its body leaves the cell unchanged",
+                        }),
+                        Some((0, delta)) if delta > 0 => warnings.push(Warning {
+                            code: "W006",
+                            position: *start,
+                            message: "loop counts the cell up and only ends by wrapping around",
+                        }),
+                        _ => (),
+                    }
+                }
+                bodies.push((body, body.iter().enumerate(), depth + 1));
+            },
+        }
+    }
+}
+
+/// Statically checks a parsed program for suspicious constructs.
+///
+/// | code | construct |
+/// |------|-----------|
+/// | W001 | a loop at program start or right after another loop, which never runs |
+/// | W002 | a `,` whose value is overwritten or never used |
+/// | W003 | an empty `[]` loop, which hangs if the cell is nonzero |
+/// | W004 | straight-line code moving the pointer left of the starting cell |
+/// | W005 | a loop whose body leaves the cell unchanged, which hangs once entered |
+/// | W006 | a loop counting the cell up, which never ends without wrapping cells |
+///
+/// W005 and W006 only look past inner loops that stay clear of the cell,
+/// they are hints rather than proof that a loop hangs, see `--detect-hangs`
+/// for catching hangs as they happen. Warnings are sorted by position.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::check::check;
+///
+/// let warnings = check(&parse("[comment]\n+[],,.<").unwrap());
+/// let found: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+/// assert_eq!(found, vec![
+///     "1:1: warning[W001]: loop never runs since the cell is zero here (probable comment)",
+///     "2:2: warning[W003]: empty loop never terminates if the cell is nonzero",
+///     "2:4: warning[W002]: value read by ',' is never used",
+///     "2:7: warning[W004]: pointer moves left of the starting cell",
+/// ]);
+///
+/// let warnings = check(&parse("+[>+<.]>[+]+[>>[-]<<]").unwrap());
+/// let codes: Vec<&str> = warnings.iter().map(|w| w.code).collect();
+/// assert_eq!(codes, ["W005", "W006", "W005"]);
+///
+/// // the inner loop counts the cell down
+/// assert!(check(&parse("++++[>++[<->-]<]").unwrap()).is_empty());
+/// ```
+pub fn check(ast: &[BrainfuckNode]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    check_bodies(ast, &mut warnings);
+
+    // follow the pointer along the top level until a loop moves it by an unknown amount
+    let mut offset = 0;
+    for node in ast {
+        match node {
+            BrainfuckNode::Op(BrainfuckOp::IncrementPtrOp, _) => offset += 1,
+            BrainfuckNode::Op(BrainfuckOp::DecrementPtrOp, position) => {
+                offset -= 1;
+                if offset < 0 {
+                    warnings.push(Warning {
+                        code: "W004",
+                        position: *position,
+                        message: "pointer moves left of the starting cell",
+                    });
+                    break;
+                }
+            },
+            BrainfuckNode::Op(..) => (),
+            BrainfuckNode::Loop { body, .. } => {
+                if net_movement(body) != Some(0) {
+                    break;
+                }
+            },
+        }
+    }
+
+    warnings.sort_by_key(|warning| warning.position.offset);
+    warnings
+}
@@ -0,0 +1,229 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::ir::BrainfuckIR;
+
+/// number of cells of the generated tape
+const TAPE_SIZE: usize = 30000;
+
+/// Escapes `name` for use inside a quoted LLVM string.
+fn escape(name: &str) -> String {
+    let mut escaped = String::new();
+    for byte in name.bytes() {
+        if byte.is_ascii_graphic() && byte != b'"' && byte != b'\\' || byte == b' ' {
+            escaped.push(byte as char);
+        } else {
+            let _ = write!(escaped, "\\{:02X}", byte);
+        }
+    }
+    escaped
+}
+
+/// State of the function body being emitted.
+struct Emitter {
+    /// emitted lines, the loop header `phi`s are patched in afterwards
+    lines: Vec<String>,
+    /// counter for fresh SSA names
+    next_value: usize,
+    /// counter for loop labels
+    next_loop: usize,
+    /// label of the basic block being emitted
+    block: String,
+    /// SSA value holding the current tape pointer
+    ptr: String,
+}
+
+impl Emitter {
+    fn value(&mut self) -> String {
+        self.next_value += 1;
+        format!("%v{}", self.next_value)
+    }
+
+    fn emit(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// loads the current cell and returns its SSA value
+    fn load(&mut self) -> String {
+        let value = self.value();
+        self.emit(format!("  {} = load i8, i8* {}", value, self.ptr));
+        value
+    }
+}
+
+/// Translates brainfuck IR into textual LLVM IR (the `.ll` format).
+///
+/// The generated module defines `main`, which runs the program on a
+/// zeroed tape of 30000 8-bit wrapping cells and uses `putchar`/`getchar`
+/// for I/O. A `,` at end of input stores 0. Pointers are
+/// emitted in the typed `i8*` form understood by LLVM 16 and earlier.
+///
+/// # Arguments
+///
+/// * `ir`          - compiled program
+/// * `module_name` - name recorded as the module ID and source file name
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::codegen::llvm::to_llvm_ir;
+/// use rust_bf::ir::compile;
+///
+/// let ir = compile(&parse("++++++++[>++++++++<-]>+.").unwrap());
+/// let module = to_llvm_ir(&ir, "hello");
+/// assert!(module.contains("define i32 @main()"));
+/// assert!(module.contains("call i32 @putchar"));
+/// ```
+pub fn to_llvm_ir(ir: &[BrainfuckIR], module_name: &str) -> String {
+    let mut emitter = Emitter {
+        lines: Vec::new(),
+        next_value: 0,
+        next_loop: 0,
+        block: String::from("entry"),
+        ptr: String::from("%base"),
+    };
+    // loop id, index of the header `phi` line, the block and pointer
+    // the loop was entered from, and the pointer defined by the `phi`
+    let mut loops: Vec<(usize, usize, String, String, String)> = Vec::new();
+
+    for node in ir {
+        match *node {
+            BrainfuckIR::Add(n) => {
+                let old = emitter.load();
+                let new = emitter.value();
+                // the cells are 8-bit, so only the low byte of `n` matters
+                emitter.emit(format!("  {} = add i8 {}, {}", new, old, n as i8));
+                emitter.emit(format!("  store i8 {}, i8* {}", new, emitter.ptr));
+            },
+            BrainfuckIR::Move(n) => {
+                let ptr = emitter.value();
+                emitter.emit(format!("  {} = getelementptr inbounds i8, i8* {}, i64 {}", ptr, emitter.ptr, n));
+                emitter.ptr = ptr;
+            },
+            BrainfuckIR::Print => {
+                let cell = emitter.load();
+                let wide = emitter.value();
+                let result = emitter.value();
+                emitter.emit(format!("  {} = zext i8 {} to i32", wide, cell));
+                emitter.emit(format!("  {} = call i32 @putchar(i32 {})", result, wide));
+            },
+            BrainfuckIR::Read => {
+                let input = emitter.value();
+                emitter.emit(format!("  {} = call i32 @getchar()", input));
+                let eof = emitter.value();
+                emitter.emit(format!("  {} = icmp eq i32 {}, -1", eof, input));
+                let byte = emitter.value();
+                emitter.emit(format!("  {} = trunc i32 {} to i8", byte, input));
+                let new = emitter.value();
+                emitter.emit(format!("  {} = select i1 {}, i8 0, i8 {}", new, eof, byte));
+                emitter.emit(format!("  store i8 {}, i8* {}", new, emitter.ptr));
+            },
+            BrainfuckIR::LoopStart(_) => {
+                let id = emitter.next_loop;
+                emitter.next_loop += 1;
+                emitter.emit(format!("  br label %loop{}", id));
+                emitter.emit(format!("loop{}:", id));
+
+                // the pointer at the header is either the one we entered with
+                // or the one we came back with, patched in at `LoopEnd`
+                let ptr = emitter.value();
+                loops.push((id, emitter.lines.len(), emitter.block.clone(), emitter.ptr.clone(), ptr.clone()));
+                emitter.emit(String::new());
+                emitter.ptr = ptr;
+
+                let cell = emitter.load();
+                let zero = emitter.value();
+                emitter.emit(format!("  {} = icmp eq i8 {}, 0", zero, cell));
+                emitter.emit(format!("  br i1 {}, label %done{}, label %body{}", zero, id, id));
+                emitter.emit(format!("body{}:", id));
+                emitter.block = format!("body{}", id);
+            },
+            BrainfuckIR::LoopEnd(_) => {
+                // `compile` only produces balanced loops
+                let (id, phi, entry_block, entry_ptr, header_ptr) = loops.pop().expect("unbalanced IR");
+                emitter.emit(format!("  br label %loop{}", id));
+                emitter.lines[phi] = format!(
+                    "  {} = phi i8* [ {}, %{} ], [ {}, %{} ]",
+                    header_ptr, entry_ptr, entry_block, emitter.ptr, emitter.block
+                );
+                emitter.emit(format!("done{}:", id));
+                emitter.block = format!("done{}", id);
+                emitter.ptr = header_ptr;
+            },
+            BrainfuckIR::Scan(n) => {
+                let id = emitter.next_loop;
+                emitter.next_loop += 1;
+                let (entry_block, entry_ptr) = (emitter.block.clone(), emitter.ptr.clone());
+                emitter.emit(format!("  br label %loop{}", id));
+                emitter.emit(format!("loop{}:", id));
+                let ptr = emitter.value();
+                let next = emitter.value();
+                emitter.emit(format!("  {} = phi i8* [ {}, %{} ], [ {}, %body{} ]", ptr, entry_ptr, entry_block, next, id));
+                emitter.ptr = ptr.clone();
+                let cell = emitter.load();
+                let zero = emitter.value();
+                emitter.emit(format!("  {} = icmp eq i8 {}, 0", zero, cell));
+                emitter.emit(format!("  br i1 {}, label %done{}, label %body{}", zero, id, id));
+                emitter.emit(format!("body{}:", id));
+                emitter.emit(format!("  {} = getelementptr inbounds i8, i8* {}, i64 {}", next, ptr, n));
+                emitter.emit(format!("  br label %loop{}", id));
+                emitter.emit(format!("done{}:", id));
+                emitter.block = format!("done{}", id);
+            },
+            BrainfuckIR::Clear => emitter.emit(format!("  store i8 0, i8* {}", emitter.ptr)),
+            // ops the interpreters run once turned on, not compiled
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber | BrainfuckIR::Reset => (),
+            BrainfuckIR::MulAdd { offset, factor } => {
+                let value = emitter.load();
+                let target = emitter.value();
+                emitter.emit(format!("  {} = getelementptr inbounds i8, i8* {}, i64 {}", target, emitter.ptr, offset));
+                let old = emitter.value();
+                emitter.emit(format!("  {} = load i8, i8* {}", old, target));
+                let product = emitter.value();
+                emitter.emit(format!("  {} = mul i8 {}, {}", product, value, factor as i8));
+                let new = emitter.value();
+                emitter.emit(format!("  {} = add i8 {}, {}", new, old, product));
+                emitter.emit(format!("  store i8 {}, i8* {}", new, target));
+            },
+            BrainfuckIR::AddRange { len, n } => {
+                // left to LLVM to vectorize
+                for i in 0..len {
+                    let cell = emitter.value();
+                    emitter.emit(format!("  {} = getelementptr inbounds i8, i8* {}, i64 {}", cell, emitter.ptr, i));
+                    let old = emitter.value();
+                    emitter.emit(format!("  {} = load i8, i8* {}", old, cell));
+                    let new = emitter.value();
+                    emitter.emit(format!("  {} = add i8 {}, {}", new, old, n as i8));
+                    emitter.emit(format!("  store i8 {}, i8* {}", new, cell));
+                }
+            },
+        }
+    }
+
+    let mut module = String::new();
+    let name = escape(module_name);
+    let _ = writeln!(module, "; ModuleID = '{}'", name);
+    let _ = writeln!(module, "source_filename = \"{}\"", name);
+    module.push('\n');
+    module.push_str("declare i32 @putchar(i32)\n");
+    module.push_str("declare i32 @getchar()\n");
+    module.push_str("declare void @llvm.memset.p0i8.i64(i8*, i8, i64, i1)\n\n");
+    module.push_str("define i32 @main() {\n");
+    module.push_str("entry:\n");
+    let _ = writeln!(module, "  %tape = alloca [{} x i8]", TAPE_SIZE);
+    let _ = writeln!(module, "  %base = getelementptr inbounds [{} x i8], [{} x i8]* %tape, i64 0, i64 0", TAPE_SIZE, TAPE_SIZE);
+    let _ = writeln!(module, "  call void @llvm.memset.p0i8.i64(i8* %base, i8 0, i64 {}, i1 false)", TAPE_SIZE);
+    for line in &emitter.lines {
+        module.push_str(line);
+        module.push('\n');
+    }
+    module.push_str("  ret i32 0\n");
+    module.push_str("}\n");
+    module
+}
@@ -0,0 +1,5 @@
+//! Backends that translate brainfuck IR into other languages.
+
+#[cfg(feature = "llvm_codegen")]
+pub mod llvm;
+pub mod subleq;
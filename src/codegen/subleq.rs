@@ -0,0 +1,370 @@
+//! Subleq, the one instruction computer, as a compile target.
+//!
+//! A Subleq program is a memory image of `i32` words, which is both code
+//! and data. Every instruction is three words `a b c` and means
+//! `mem[b] -= mem[a]; if mem[b] <= 0 goto c`, otherwise execution goes on
+//! with the next three words. Jumping to a negative address halts. I/O
+//! uses address -1: `-1 b c` reads a byte into `mem[b]`, `a -1 c` prints
+//! the low byte of `mem[a]`, both go on with the next instruction.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::ir::BrainfuckIR;
+
+/// number of cells of the generated tape
+const TAPE_SIZE: usize = 30000;
+
+/// The address of a word, resolved once the layout is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Addr {
+    /// a data word, as an index into `Emitter::data`
+    Data(usize),
+    /// operand 0 (`a`) or 1 (`b`) of an emitted instruction, patched at run time
+    Operand(usize, usize),
+    /// the first tape cell, where patched operands point until they are patched
+    Tape,
+    /// address -1, for I/O
+    Io,
+}
+
+/// Where an instruction goes when the result is not positive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+    /// the next instruction either way
+    Next,
+    /// the instruction a label was placed at
+    Label(usize),
+    /// address -1
+    Halt,
+}
+
+/// State of the program being emitted.
+struct Emitter {
+    /// emitted instructions
+    code: Vec<(Addr, Addr, Target)>,
+    /// index of the instruction every label was placed at
+    labels: Vec<usize>,
+    /// initial value of every data word
+    data: Vec<i32>,
+}
+
+/// always zero between instructions
+const Z: Addr = Addr::Data(0);
+/// scratch word, always zero between macros
+const T: Addr = Addr::Data(1);
+/// value of the current cell while an op works on it
+const V: Addr = Addr::Data(2);
+/// loop counter of `MulAdd`
+const W: Addr = Addr::Data(3);
+/// scratch word for wrapping cell values
+const U: Addr = Addr::Data(4);
+/// address of the current cell, starts at the first tape cell
+const PTR: Addr = Addr::Data(5);
+
+impl Emitter {
+    fn subleq(&mut self, a: Addr, b: Addr, c: Target) {
+        self.code.push((a, b, c));
+    }
+
+    /// Returns a new label, to be placed with [`Emitter::place`].
+    fn label(&mut self) -> usize {
+        self.labels.push(usize::MAX);
+        self.labels.len() - 1
+    }
+
+    /// Makes `label` point at the next instruction.
+    fn place(&mut self, label: usize) {
+        self.labels[label] = self.code.len();
+    }
+
+    /// Returns a data word holding `value`, shared by everything that needs it.
+    fn constant(&mut self, value: i32) -> Addr {
+        // the named words come first, their values change
+        match self.data.iter().skip(6).position(|&word| word == value) {
+            Some(index) => Addr::Data(index + 6),
+            None => {
+                self.data.push(value);
+                Addr::Data(self.data.len() - 1)
+            },
+        }
+    }
+
+    fn jump(&mut self, label: usize) {
+        self.subleq(Z, Z, Target::Label(label));
+    }
+
+    fn clear(&mut self, x: Addr) {
+        self.subleq(x, x, Target::Next);
+    }
+
+    /// `dst += src` in three instructions
+    fn add(&mut self, src: Addr, dst: Addr) {
+        self.subleq(src, T, Target::Next);
+        self.subleq(T, dst, Target::Next);
+        self.clear(T);
+    }
+
+    /// `dst = src` in four instructions
+    fn copy(&mut self, src: Addr, dst: Addr) {
+        self.clear(dst);
+        self.add(src, dst);
+    }
+
+    /// `V = mem[PTR]`
+    fn load(&mut self) {
+        self.clear(V);
+        // the patch takes four instructions
+        let at = self.code.len() + 4;
+        self.copy(PTR, Addr::Operand(at, 0));
+        self.subleq(Addr::Tape, T, Target::Next);
+        self.subleq(T, V, Target::Next);
+        self.clear(T);
+    }
+
+    /// `mem[PTR] = V`
+    fn store(&mut self) {
+        // three patches of four instructions each
+        let at = self.code.len() + 12;
+        self.copy(PTR, Addr::Operand(at, 0));
+        self.copy(PTR, Addr::Operand(at, 1));
+        self.copy(PTR, Addr::Operand(at + 2, 1));
+        self.subleq(Addr::Tape, Addr::Tape, Target::Next);
+        self.subleq(V, T, Target::Next);
+        self.subleq(T, Addr::Tape, Target::Next);
+        self.clear(T);
+    }
+
+    /// Adds `n` to the current cell, wrapping at 256.
+    fn add_cell(&mut self, n: i32) {
+        let n = n.rem_euclid(256);
+        if n == 0 {
+            return;
+        }
+        self.load();
+        let minus_n = self.constant(-n);
+        self.subleq(minus_n, V, Target::Next);
+        // `V` is below 511 now, take 256 off once if it is 256 or more
+        let wrap = self.label();
+        let done = self.label();
+        let c256 = self.constant(256);
+        self.copy(c256, U);
+        self.subleq(V, U, Target::Label(wrap));
+        self.jump(done);
+        self.place(wrap);
+        self.subleq(c256, V, Target::Next);
+        self.place(done);
+        self.store();
+    }
+
+    /// Moves the pointer `n` cells.
+    fn move_ptr(&mut self, n: i32) {
+        if n != 0 {
+            let minus_n = self.constant(-n);
+            self.subleq(minus_n, PTR, Target::Next);
+        }
+    }
+
+    /// Returns the memory image.
+    fn layout(mut self) -> Vec<i32> {
+        let data_start = 3 * self.code.len();
+        let tape_start = data_start + self.data.len();
+        self.data[5] = tape_start as i32;
+        let address = |addr: Addr| match addr {
+            Addr::Data(index) => (data_start + index) as i32,
+            Addr::Operand(instruction, operand) => (3 * instruction + operand) as i32,
+            Addr::Tape => tape_start as i32,
+            Addr::Io => -1,
+        };
+
+        let mut memory = Vec::with_capacity(tape_start + TAPE_SIZE);
+        for (i, &(a, b, c)) in self.code.iter().enumerate() {
+            memory.push(address(a));
+            memory.push(address(b));
+            memory.push(match c {
+                Target::Next => 3 * (i + 1) as i32,
+                Target::Label(label) => 3 * self.labels[label] as i32,
+                Target::Halt => -1,
+            });
+        }
+        memory.extend_from_slice(&self.data);
+        memory.resize(tape_start + TAPE_SIZE, 0);
+        memory
+    }
+}
+
+/// Translates brainfuck IR into a Subleq memory image.
+///
+/// The image holds the code, a few words of data and a zeroed tape of
+/// 30000 8-bit wrapping cells. Subleq has no indirect addressing, so every
+/// access to the current cell first patches the address of the cell into
+/// the instructions that access it. A `,` at end of input stores 0, moving
+/// the pointer off either end of the tape is undefined.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::codegen::subleq::{run_subleq, to_subleq};
+/// use rust_bf::ir::compile;
+///
+/// let program = to_subleq(&compile(&parse("++++++++[>++++++++<-]>+.").unwrap()));
+/// assert_eq!(run_subleq(&program, b"").unwrap(), b"A");
+/// ```
+pub fn to_subleq(ir: &[BrainfuckIR]) -> Vec<i32> {
+    let mut emitter = Emitter { code: Vec::new(), labels: Vec::new(), data: alloc::vec![0; 6] };
+    // head and exit label of every open loop
+    let mut loops: Vec<(usize, usize)> = Vec::new();
+
+    for node in ir {
+        match *node {
+            BrainfuckIR::Add(n) => emitter.add_cell(n),
+            BrainfuckIR::Move(n) => emitter.move_ptr(n),
+            BrainfuckIR::Print => {
+                emitter.load();
+                emitter.subleq(V, Addr::Io, Target::Next);
+            },
+            BrainfuckIR::Read => {
+                emitter.subleq(Addr::Io, V, Target::Next);
+                emitter.store();
+            },
+            BrainfuckIR::LoopStart(_) => {
+                let head = emitter.label();
+                let exit = emitter.label();
+                emitter.place(head);
+                emitter.load();
+                // cells are never negative, so not positive means zero
+                emitter.subleq(Z, V, Target::Label(exit));
+                loops.push((head, exit));
+            },
+            BrainfuckIR::LoopEnd(_) => {
+                let (head, exit) = loops.pop().expect("loops are balanced");
+                emitter.jump(head);
+                emitter.place(exit);
+            },
+            BrainfuckIR::Scan(n) => {
+                let head = emitter.label();
+                let exit = emitter.label();
+                emitter.place(head);
+                emitter.load();
+                emitter.subleq(Z, V, Target::Label(exit));
+                emitter.move_ptr(n);
+                emitter.jump(head);
+                emitter.place(exit);
+            },
+            BrainfuckIR::Clear => {
+                emitter.clear(V);
+                emitter.store();
+            },
+            // ops the interpreters run once turned on, not compiled
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber | BrainfuckIR::Reset => (),
+            BrainfuckIR::MulAdd { offset, factor } => {
+                // add `factor` to the target once for every unit in the current cell
+                emitter.load();
+                emitter.copy(V, W);
+                emitter.move_ptr(offset);
+                let head = emitter.label();
+                let exit = emitter.label();
+                emitter.place(head);
+                emitter.subleq(Z, W, Target::Label(exit));
+                emitter.add_cell(factor);
+                let one = emitter.constant(1);
+                emitter.subleq(one, W, Target::Next);
+                emitter.jump(head);
+                emitter.place(exit);
+                emitter.move_ptr(-offset);
+            },
+            BrainfuckIR::AddRange { len, n } => {
+                for i in 0..len {
+                    if i > 0 {
+                        emitter.move_ptr(1);
+                    }
+                    emitter.add_cell(n);
+                }
+                emitter.move_ptr(1 - len as i32);
+            },
+        }
+    }
+    emitter.subleq(Z, Z, Target::Halt);
+    emitter.layout()
+}
+
+/// Errors reported by [`run_subleq`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubleqError {
+    /// the instruction at `ip` uses an address outside of memory
+    AddressOutOfRange {
+        /// address of the instruction
+        ip: usize,
+        /// the address outside of memory
+        address: i64,
+    },
+}
+
+impl fmt::Display for SubleqError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubleqError::AddressOutOfRange { ip, address } => {
+                write!(f, "instruction at {} uses address {}, outside of memory", ip, address)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubleqError {}
+
+/// Runs a Subleq memory image until it jumps to a negative address and
+/// returns everything it printed.
+///
+/// Reading past the end of `input` stores 0. A program that never halts
+/// runs forever.
+///
+/// # Arguments
+///
+/// * `program` - memory image, code and data
+/// * `input`   - bytes read from address -1
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::codegen::subleq::{run_subleq, SubleqError};
+///
+/// // mem[10] -= mem[9], which is 0 - -72, then print mem[10] and halt
+/// let program = [9, 10, 3, 10, -1, 6, 11, 11, -1, -72, 0, 0];
+/// assert_eq!(run_subleq(&program, b"").unwrap(), b"H");
+/// assert_eq!(run_subleq(&[0, 100, -1], b""), Err(SubleqError::AddressOutOfRange { ip: 0, address: 100 }));
+/// ```
+pub fn run_subleq(program: &[i32], input: &[u8]) -> Result<Vec<u8>, SubleqError> {
+    let mut memory = program.to_vec();
+    let mut input = input.iter();
+    let mut output = Vec::new();
+    let len = memory.len();
+    let mut ip: i64 = 0;
+    while ip >= 0 {
+        let at = ip as usize;
+        // an address is a valid index into memory, or -1 for I/O where allowed
+        let word = |address: i64| SubleqError::AddressOutOfRange { ip: at, address };
+        if at + 2 >= len {
+            return Err(word(ip + 2));
+        }
+        let (a, b, c) = (memory[at] as i64, memory[at + 1] as i64, memory[at + 2] as i64);
+        let index = |address: i64| usize::try_from(address).ok().filter(|&address| address < len).ok_or_else(|| word(address));
+        if a == -1 {
+            memory[index(b)?] = input.next().copied().unwrap_or(0) as i32;
+            ip += 3;
+        } else if b == -1 {
+            output.push(memory[index(a)?] as u8);
+            ip += 3;
+        } else {
+            let (a, b) = (index(a)?, index(b)?);
+            memory[b] = memory[b].wrapping_sub(memory[a]);
+            ip = if memory[b] <= 0 { c } else { ip + 3 };
+        }
+    }
+    Ok(output)
+}
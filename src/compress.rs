@@ -0,0 +1,389 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::ir::BrainfuckIR;
+
+/// most cells [`synthesize_small`] sets up before printing
+const MAX_CELLS: usize = 8;
+
+/// largest loop count [`synthesize_small`] tries for setting up its cells
+const MAX_FACTOR: u32 = 20;
+
+/// Appends `n` copies of `up` if `n` is positive, of `down` otherwise.
+fn push_repeated(out: &mut String, n: i32, up: char, down: char) {
+    let c = if n > 0 { up } else { down };
+    for _ in 0..n.unsigned_abs() {
+        out.push(c);
+    }
+}
+
+/// Returns the `+`/`-` count that adds `delta` to an 8-bit cell with the fewest ops.
+fn plain(delta: u8) -> i32 {
+    if delta <= 128 { delta as i32 } else { delta as i32 - 256 }
+}
+
+/// Returns the cheapest `(counter, step, rest)` such that a loop run
+/// `counter` times adding `step`, followed by `rest` more, adds `delta`,
+/// along with its length in ops.
+fn multiplication(delta: u8) -> (i32, i32, i32, usize) {
+    let mut best = (0, 0, plain(delta), usize::MAX);
+    for counter in 2..=32i32 {
+        for step in -32..=32i32 {
+            if step == 0 {
+                continue;
+            }
+            let rest = plain(delta.wrapping_sub((counter * step) as u8));
+            // `>` counter `[<` step `>-]<` rest
+            let cost = (counter + step.abs() + rest.abs()) as usize + 7;
+            if cost < best.3 {
+                best = (counter, step, rest, cost);
+            }
+        }
+    }
+    best
+}
+
+/// Rewrites IR into short source while remembering what it knows about the tape.
+struct Compressor {
+    /// compressed source so far
+    out: String,
+    /// cell values, keyed by offset from where tracking started, `None` where unknown
+    known: BTreeMap<i64, Option<u8>>,
+    /// whether cells missing from `known` are zero, true until the first loop or read
+    fresh: bool,
+    /// pointer, relative to where tracking started
+    ptr: i64,
+}
+
+impl Compressor {
+    /// Returns a compressor at the start of a program, the tape all zeros.
+    fn new() -> Compressor {
+        Compressor { out: String::new(), known: BTreeMap::new(), fresh: true, ptr: 0 }
+    }
+
+    /// Returns the value of the cell `offset` cells right of the pointer, if known.
+    fn value(&self, offset: i64) -> Option<u8> {
+        match self.known.get(&(self.ptr + offset)) {
+            Some(&value) => value,
+            None if self.fresh => Some(0),
+            None => None,
+        }
+    }
+
+    /// Forgets everything about the tape.
+    fn forget(&mut self) {
+        self.known.clear();
+        self.fresh = false;
+    }
+
+    /// Emits the shortest code adding `delta` to the current cell.
+    ///
+    /// The cell to the right holds the loop counter when that is shorter,
+    /// which needs it to be known zero. It is zero again afterwards.
+    fn add(&mut self, delta: u8) {
+        let (counter, step, rest, cost) = multiplication(delta);
+        if self.value(1) == Some(0) && cost < plain(delta).unsigned_abs() as usize {
+            self.out.push('>');
+            push_repeated(&mut self.out, counter, '+', '-');
+            self.out.push_str("[<");
+            push_repeated(&mut self.out, step, '+', '-');
+            self.out.push_str(">-]<");
+            push_repeated(&mut self.out, rest, '+', '-');
+        } else {
+            push_repeated(&mut self.out, plain(delta), '+', '-');
+        }
+        let value = self.value(0).map(|value| value.wrapping_add(delta));
+        self.known.insert(self.ptr, value);
+    }
+
+    /// Emits code setting the current cell to `target`.
+    fn set(&mut self, target: u8) {
+        match self.value(0) {
+            // reuse what the cell holds if that is no longer than clearing it first
+            Some(value) => {
+                let delta = target.wrapping_sub(value);
+                if plain(delta).unsigned_abs() <= 3 + plain(target).unsigned_abs() || delta == 0 {
+                    if delta != 0 {
+                        self.add(delta);
+                    }
+                    return;
+                }
+                self.clear_and_add(target);
+            },
+            None => self.clear_and_add(target),
+        }
+    }
+
+    /// Emits `[-]` and then adds `target`.
+    fn clear_and_add(&mut self, target: u8) {
+        self.out.push_str("[-]");
+        self.known.insert(self.ptr, Some(0));
+        if target != 0 {
+            self.add(target);
+        }
+    }
+
+    /// Compresses `ir[start..end]`, a whole program or a loop body.
+    fn compress(&mut self, ir: &[BrainfuckIR], start: usize, end: usize) {
+        let mut i = start;
+        while i < end {
+            match ir[i] {
+                BrainfuckIR::Add(n) => self.add(n as u8),
+                BrainfuckIR::Move(n) => {
+                    push_repeated(&mut self.out, n, '>', '<');
+                    self.ptr += n as i64;
+                },
+                BrainfuckIR::Print => self.out.push('.'),
+                #[cfg(feature = "self_modifying")]
+                BrainfuckIR::SelfWrite => {
+                    // the run may have it off, when the cell stays as it was
+                    self.out.push('=');
+                    self.known.insert(self.ptr, None);
+                },
+                #[cfg(feature = "debug_ops")]
+                BrainfuckIR::PrintNumber => self.out.push('$'),
+                #[cfg(feature = "debug_ops")]
+                BrainfuckIR::Reset => {
+                    // the run may have them off, when the tape stays as it was
+                    self.out.push('!');
+                    self.forget();
+                },
+                BrainfuckIR::Read => {
+                    self.out.push(',');
+                    self.known.insert(self.ptr, None);
+                },
+                BrainfuckIR::Clear => {
+                    // `Clear` followed by `Add` sets the cell to a constant
+                    let target = match ir.get(i + 1) {
+                        Some(&BrainfuckIR::Add(n)) => {
+                            i += 1;
+                            n as u8
+                        },
+                        _ => 0,
+                    };
+                    self.set(target);
+                },
+                BrainfuckIR::MulAdd { .. } => {
+                    // `optimize` ends every run of `MulAdd`s with a `Clear`
+                    let mut clear = i;
+                    while matches!(ir[clear], BrainfuckIR::MulAdd { .. }) {
+                        clear += 1;
+                    }
+                    assert_eq!(ir[clear], BrainfuckIR::Clear, "MulAdd without a Clear");
+                    self.multiply(&ir[i..clear]);
+                    i = clear;
+                },
+                BrainfuckIR::AddRange { len, n } => {
+                    for i in 0..len {
+                        if i > 0 {
+                            self.out.push('>');
+                            self.ptr += 1;
+                        }
+                        self.add(n as u8);
+                    }
+                    push_repeated(&mut self.out, 1 - len as i32, '>', '<');
+                    self.ptr -= len as i64 - 1;
+                },
+                BrainfuckIR::LoopStart(loop_end) => {
+                    if self.value(0) != Some(0) {
+                        self.out.push('[');
+                        self.forget();
+                        self.compress(ir, i + 1, loop_end);
+                        self.out.push(']');
+                        self.forget();
+                        self.known.insert(self.ptr, Some(0));
+                    }
+                    // a loop on a cell known to be zero never runs
+                    i = loop_end;
+                },
+                BrainfuckIR::Scan(n) => {
+                    if self.value(0) != Some(0) {
+                        self.out.push('[');
+                        push_repeated(&mut self.out, n, '>', '<');
+                        self.out.push(']');
+                        self.forget();
+                        self.known.insert(self.ptr, Some(0));
+                    }
+                },
+                BrainfuckIR::LoopEnd(_) => unreachable!("loop bodies are compressed as a whole"),
+            }
+            i += 1;
+        }
+    }
+
+    /// Emits a multiplication loop for `muls` and the `Clear` after them.
+    fn multiply(&mut self, muls: &[BrainfuckIR]) {
+        let counter = self.value(0);
+        if counter == Some(0) {
+            // nothing to multiply, and the cell is already clear
+            return;
+        }
+        self.out.push_str("[-");
+        let mut offset = 0;
+        for node in muls {
+            if let BrainfuckIR::MulAdd { offset: target, factor } = *node {
+                push_repeated(&mut self.out, target - offset, '>', '<');
+                push_repeated(&mut self.out, plain(factor as u8), '+', '-');
+                offset = target;
+                let value = match (counter, self.value(target as i64)) {
+                    (Some(n), Some(value)) => Some(value.wrapping_add(n.wrapping_mul(factor as u8))),
+                    _ => None,
+                };
+                self.known.insert(self.ptr + target as i64, value);
+            }
+        }
+        push_repeated(&mut self.out, -offset, '>', '<');
+        self.out.push(']');
+        self.known.insert(self.ptr, Some(0));
+    }
+}
+
+/// Returns the number of ops `ir` takes written out naively.
+fn naive_length(ir: &[BrainfuckIR]) -> usize {
+    ir.iter()
+        .map(|node| match *node {
+            BrainfuckIR::Add(n) | BrainfuckIR::Move(n) => n.unsigned_abs() as usize,
+            BrainfuckIR::Clear => 3,
+            BrainfuckIR::Scan(n) => n.unsigned_abs() as usize + 2,
+            // `[-`, the trip to the target and back, and `]`
+            BrainfuckIR::MulAdd { offset, factor } => 2 * offset.unsigned_abs() as usize + factor.unsigned_abs() as usize,
+            // the ops, the `>` between them and the way back
+            BrainfuckIR::AddRange { len, n } => len * n.unsigned_abs() as usize + 2 * (len - 1),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Writes compiled or optimized IR back as short brainfuck source.
+///
+/// Cells known to hold a value are set with the fewest ops, a constant is
+/// built with a multiplication loop when the cell to its right is known to
+/// be zero, a cell about to be cleared or set keeps what it holds when
+/// adding the difference is shorter, and loops and clears on cells known
+/// to be zero are dropped. The result behaves like the IR as long as the
+/// IR runs without an error, and ends with a comment line giving the
+/// compression ratio.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::compress::compress;
+/// use rust_bf::ir::compile;
+/// use rust_bf::optimizer::optimize;
+/// use rust_bf::Interpreter;
+///
+/// let src = format!("{}.>{}.[-]+++.", "+".repeat(65), "+".repeat(66));
+/// let compressed = compress(&optimize(&compile(&parse(&src).unwrap())));
+/// assert_eq!(compressed, ">++++++++[<++++++++>-]<+.>>++++++[<+++++++++++>-]<.[-]+++.\ncompressed 141 ops to 58 (41 percent)\n");
+///
+/// // both print the same
+/// assert_eq!(Interpreter::run_string(&compressed, "").unwrap(), Interpreter::run_string(&src, "").unwrap());
+/// ```
+pub fn compress(ir: &[BrainfuckIR]) -> String {
+    let mut compressor = Compressor::new();
+    compressor.compress(ir, 0, ir.len());
+
+    let before = naive_length(ir);
+    let after = compressor.out.len();
+    compressor.out.push_str(&format!(
+        "\ncompressed {} ops to {} ({} percent)\n",
+        before, after, after * 100 / before.max(1)
+    ));
+    compressor.out
+}
+
+/// Returns a program that prints `target` and reads no input.
+///
+/// Every byte is built in cell 0 from the one printed before it, with
+/// a multiplication loop using cell 1 as the counter where that is
+/// shorter than adding the difference, or by clearing the cell first.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::compress::synthesize;
+/// use rust_bf::Interpreter;
+///
+/// let src = synthesize(b"Hello");
+/// assert_eq!(src, ">++++++++[<+++++++++>-]<.>++++[<+++++++>-]<+.+++++++..+++.");
+/// assert_eq!(Interpreter::run_string(&src, "").unwrap(), "Hello");
+/// ```
+pub fn synthesize(target: &[u8]) -> String {
+    let mut compressor = Compressor::new();
+    for &byte in target {
+        compressor.set(byte);
+        compressor.out.push('.');
+    }
+    compressor.out
+}
+
+/// Returns a program that prints `target` and reads no input, like
+/// [`synthesize`] but spending more time to make it shorter.
+///
+/// A single loop first sets a few cells to multiples of the same count,
+/// near the values `target` holds most, and every byte is then built from
+/// whichever cell is closest to it, counting the moves to get there. The
+/// shortest program of every cell and loop count tried, or of
+/// [`synthesize`], is kept.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::compress::{synthesize, synthesize_small};
+/// use rust_bf::Interpreter;
+///
+/// let target = "Hello, World!";
+/// let src = synthesize_small(target.as_bytes());
+/// assert!(src.len() < synthesize(target.as_bytes()).len());
+/// assert_eq!(Interpreter::run_string(&src, "").unwrap(), target);
+/// ```
+pub fn synthesize_small(target: &[u8]) -> String {
+    let mut best = synthesize(target);
+    let mut sorted = target.to_vec();
+    sorted.sort_unstable();
+    for cells in 1..=MAX_CELLS.min(sorted.len()) {
+        // the middle byte of each of `cells` equal parts of the sorted target
+        let centers: Vec<u8> = (0..cells).map(|i| sorted[(2 * i + 1) * sorted.len() / (2 * cells)]).collect();
+        for factor in 2..=MAX_FACTOR {
+            let src = synthesize_from_cells(target, &centers, factor);
+            if src.len() < best.len() {
+                best = src;
+            }
+        }
+    }
+    best
+}
+
+/// Returns a program that sets cell `i + 1` to the multiple of `factor`
+/// closest to `centers[i]` in one loop counting down cell 0, then prints
+/// every byte of `target` from the cell that is cheapest to get it from.
+fn synthesize_from_cells(target: &[u8], centers: &[u8], factor: u32) -> String {
+    let mut out = String::new();
+    push_repeated(&mut out, factor as i32, '+', '-');
+    out.push('[');
+    // cell 0 is the counter, zero once the loop is done
+    let mut cells = alloc::vec![0u8];
+    for &center in centers {
+        let times = ((center as u32 + factor / 2) / factor).min(255 / factor);
+        out.push('>');
+        push_repeated(&mut out, times as i32, '+', '-');
+        cells.push((times * factor) as u8);
+    }
+    push_repeated(&mut out, -(centers.len() as i32), '>', '<');
+    out.push_str("-]");
+
+    let mut ptr = 0;
+    for &byte in target {
+        let cost = |cell: usize| cell.abs_diff(ptr) + plain(byte.wrapping_sub(cells[cell])).unsigned_abs() as usize;
+        let next = (0..cells.len()).min_by_key(|&cell| cost(cell)).unwrap_or(0);
+        push_repeated(&mut out, next as i32 - ptr as i32, '>', '<');
+        push_repeated(&mut out, plain(byte.wrapping_sub(cells[next])), '+', '-');
+        out.push('.');
+        cells[next] = byte;
+        ptr = next;
+    }
+    out
+}
@@ -0,0 +1,117 @@
+//! Which ops of a program ran, see [`BrainfuckVMStatus::record_coverage`](crate::vm::BrainfuckVMStatus::record_coverage).
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::op::BrainfuckOp;
+
+/// styles of the HTML report, the colors follow grcov
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+pre { font-size: 1.1em; line-height: 1.4; }
+.hit { background: #c5e8b7; }
+.miss { background: #f6c5c5; }
+.badge { padding: 0.1em 0.5em; border-radius: 0.3em; color: white; }
+.high { background: #45a747; }
+.medium { background: #d9a62e; }
+.low { background: #c9302c; }";
+
+/// Whether each op of a program ran at least once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// for every op, comments not counted, whether it ran
+    executed: Vec<bool>,
+}
+
+impl CoverageReport {
+    /// Returns for every op, comments not counted, whether it ran.
+    pub fn executed(&self) -> &[bool] {
+        &self.executed
+    }
+
+    /// Returns the share of ops that ran, in percent, 100 for a program without ops.
+    pub fn coverage_percentage(&self) -> f64 {
+        if self.executed.is_empty() {
+            return 100.0;
+        }
+        let hit = self.executed.iter().filter(|&&hit| hit).count();
+        hit as f64 * 100.0 / self.executed.len() as f64
+    }
+
+    /// Returns the index of every op that never ran, comments not counted.
+    pub fn uncovered_positions(&self) -> Vec<usize> {
+        self.executed.iter().enumerate().filter(|&(_, &hit)| !hit).map(|(i, _)| i).collect()
+    }
+
+    /// Renders `src`, the program the report is for, as an HTML page with
+    /// the ops that ran on green and those that never ran on red.
+    ///
+    /// The `]` of a loop that was always skipped never ran either.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let src = "+[-] >[+]";
+    /// let mut status = new_brainfuck_status();
+    /// status.record_coverage();
+    /// for c in src.chars() {
+    ///     run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new()).unwrap();
+    /// }
+    /// let html = status.coverage().unwrap().to_html(src);
+    /// assert!(html.contains("<span class=\"hit\">+[-]</span> <span class=\"hit\">&gt;[</span><span class=\"miss\">+]</span>"));
+    /// ```
+    pub fn to_html(&self, src: &str) -> String {
+        let percentage = self.coverage_percentage();
+        let level = if percentage >= 90.0 {
+            "high"
+        } else if percentage >= 75.0 {
+            "medium"
+        } else {
+            "low"
+        };
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Coverage</title>\n<style>\n{}\n</style>\n</head>\n<body>\n\
+             <h1>Coverage <span class=\"badge {}\">{:.1}%</span></h1>\n<p>{} of {} ops ran</p>\n<pre>",
+            STYLE, level, percentage, self.executed.len() - self.uncovered_positions().len(), self.executed.len()
+        );
+
+        // ops past the end of the report never got to the VM, they count as never run
+        let mut ops = self.executed.iter().copied().chain(core::iter::repeat(false));
+        let mut open: Option<bool> = None;
+        for c in src.chars() {
+            let hit = if BrainfuckOp::from_char(c) == BrainfuckOp::MonoStateOp { None } else { ops.next() };
+            if open.is_some() && hit != open {
+                html.push_str("</span>");
+                open = None;
+            }
+            if let (Some(hit), None) = (hit, open) {
+                html.push_str(if hit { "<span class=\"hit\">" } else { "<span class=\"miss\">" });
+                open = Some(hit);
+            }
+            match c {
+                '&' => html.push_str("&amp;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                c => html.push(c),
+            }
+        }
+        if open.is_some() {
+            html.push_str("</span>");
+        }
+        html.push_str("</pre>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Adds an op that has not run yet.
+    pub(crate) fn push(&mut self) {
+        self.executed.push(false);
+    }
+
+    /// Records that op `index` ran.
+    pub(crate) fn hit(&mut self, index: usize) {
+        self.executed[index] = true;
+    }
+}
@@ -0,0 +1,197 @@
+//! HTML documentation for literate brainfuck programs, enable the `docgen` feature to use it.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// where the rendered docs point readers for a look at the tape while the program runs
+const TAPE_VISUALIZATION_URL: &str = "https://github.com/BlueCocoa/rust-brainfuck#screenshot";
+
+/// styles for the page and the op classes of [`highlight`]
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 48em; margin: 2em auto; line-height: 1.5; }
+pre.bf { background: #f6f8fa; padding: 1em; overflow-x: auto; }
+.bf-value { color: #22863a; }
+.bf-move { color: #005cc5; }
+.bf-io { color: #d73a49; font-weight: bold; }
+.bf-loop { color: #6f42c1; font-weight: bold; }
+.bf-comment { color: #6a737d; }";
+
+/// A run of source lines that render the same way.
+enum Block {
+    /// `=== title` line
+    Header(String),
+    /// comment lines up to a blank line, joined into one paragraph
+    Paragraph(String),
+    /// consecutive code lines
+    Code(String),
+}
+
+/// Returns whether `c` is one of the eight ops.
+fn is_op(c: char) -> bool {
+    "+-<>.,[]".contains(c)
+}
+
+/// Returns whether `line` is code rather than prose, meaning ops make up
+/// at least half of what is not whitespace.
+///
+/// Prose often has a `.` or `,` in it, so a single op does not make a line code.
+fn is_code(line: &str) -> bool {
+    let visible = line.chars().filter(|c| !c.is_whitespace()).count();
+    let ops = line.chars().filter(|&c| is_op(c)).count();
+    ops > 0 && ops * 2 >= visible
+}
+
+/// Appends `c` to `html`, escaped for use in HTML.
+fn push_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        c => html.push(c),
+    }
+}
+
+/// Escapes `text` for use in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        push_escaped(&mut escaped, c);
+    }
+    escaped
+}
+
+/// Returns the highlighting class of `c`.
+fn class(c: char) -> &'static str {
+    match c {
+        '+' | '-' => "bf-value",
+        '<' | '>' => "bf-move",
+        '.' | ',' => "bf-io",
+        '[' | ']' => "bf-loop",
+        _ => "bf-comment",
+    }
+}
+
+/// Wraps every run of characters of the same class in a `span`, whitespace
+/// stays outside so that it does not start a new span.
+fn highlight(code: &str) -> String {
+    let mut html = String::new();
+    let mut open: Option<&str> = None;
+    for c in code.chars() {
+        if c.is_whitespace() {
+            html.push(c);
+            continue;
+        }
+        let class = class(c);
+        if open != Some(class) {
+            if open.is_some() {
+                html.push_str("</span>");
+            }
+            html.push_str(&format!("<span class=\"{}\">", class));
+            open = Some(class);
+        }
+        push_escaped(&mut html, c);
+    }
+    if open.is_some() {
+        html.push_str("</span>");
+    }
+    html
+}
+
+/// Splits `src` into headers, paragraphs and code blocks.
+fn blocks(src: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    for line in src.lines() {
+        let trimmed = line.trim();
+        if let Some(title) = trimmed.strip_prefix("===") {
+            blocks.push(Block::Header(String::from(title.trim_matches(|c: char| c == '=' || c.is_whitespace()))));
+        } else if trimmed.is_empty() {
+            // a blank line ends a paragraph, an empty paragraph keeps the next one from joining it
+            if let Some(Block::Paragraph(_)) = blocks.last() {
+                blocks.push(Block::Paragraph(String::new()));
+            }
+        } else if is_code(line) {
+            match blocks.last_mut() {
+                Some(Block::Code(code)) => {
+                    code.push('\n');
+                    code.push_str(line);
+                },
+                _ => blocks.push(Block::Code(String::from(line))),
+            }
+        } else {
+            match blocks.last_mut() {
+                Some(Block::Paragraph(text)) => {
+                    if !text.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(trimmed);
+                },
+                _ => blocks.push(Block::Paragraph(String::from(trimmed))),
+            }
+        }
+    }
+    blocks
+}
+
+/// Renders a commented program as a standalone HTML page.
+///
+/// Lines starting with `===` are section headers, which also make up the
+/// table of contents. Lines mostly made of ops are code, shown with every
+/// kind of op highlighted. Everything else is prose, consecutive prose
+/// lines form a paragraph until a blank line. The page ends with a link
+/// to a visualization of the tape.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::docgen::generate_docs;
+///
+/// let html = generate_docs("=== Setup\nPut 8 in the first cell\n++++++++\n=== Output\n[>+<-]>.\n");
+/// assert!(html.contains("<li><a href=\"#section-1\">Setup</a></li>"));
+/// assert!(html.contains("<h2 id=\"section-2\">Output</h2>"));
+/// assert!(html.contains("<p>Put 8 in the first cell</p>"));
+/// assert!(html.contains("<span class=\"bf-loop\">[</span><span class=\"bf-move\">&gt;</span>"));
+/// ```
+pub fn generate_docs(src: &str) -> String {
+    let blocks = blocks(src);
+    let headers: Vec<&str> = blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Header(title) => Some(title.as_str()),
+            _ => None,
+        })
+        .collect();
+    let title = headers.first().copied().unwrap_or("Brainfuck program");
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        escape(title), STYLE
+    );
+    if !headers.is_empty() {
+        html.push_str("<nav>\n<h2>Contents</h2>\n<ol>\n");
+        for (i, header) in headers.iter().enumerate() {
+            html.push_str(&format!("<li><a href=\"#section-{}\">{}</a></li>\n", i + 1, escape(header)));
+        }
+        html.push_str("</ol>\n</nav>\n");
+    }
+
+    let mut section = 0;
+    for block in &blocks {
+        match block {
+            Block::Header(title) => {
+                section += 1;
+                html.push_str(&format!("<h2 id=\"section-{}\">{}</h2>\n", section, escape(title)));
+            },
+            Block::Paragraph(text) if text.is_empty() => (),
+            Block::Paragraph(text) => html.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Code(code) => html.push_str(&format!("<pre class=\"bf\"><code>{}</code></pre>\n", highlight(code))),
+        }
+    }
+
+    html.push_str(&format!(
+        "<footer>\n<p><a href=\"{}\">See the tape while a program runs</a></p>\n</footer>\n</body>\n</html>\n",
+        TAPE_VISUALIZATION_URL
+    ));
+    html
+}
@@ -0,0 +1,168 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::ast::{BrainfuckNode, Position};
+
+/// ops shown in a block label before it gets truncated
+const MAX_LABEL_OPS: usize = 16;
+
+/// Control-flow graph under construction, nodes and edges are kept
+/// in creation order so that the output is deterministic.
+struct Graph {
+    /// `(label, shape)` of every node, indexed by node id
+    nodes: Vec<(String, &'static str)>,
+    /// `(from, to, label)`
+    edges: Vec<(usize, usize, &'static str)>,
+}
+
+impl Graph {
+    fn add_node(&mut self, label: String, shape: &'static str) -> usize {
+        self.nodes.push((label, shape));
+        self.nodes.len() - 1
+    }
+
+    /// connects every pending edge to `to`
+    fn connect(&mut self, pending: &[(usize, &'static str)], to: usize) {
+        for &(from, label) in pending {
+            self.edges.push((from, to, label));
+        }
+    }
+}
+
+/// Returns the label for a source line range.
+fn lines_label(first: Position, last: Position) -> String {
+    if first.line == last.line {
+        format!("line {}", first.line)
+    } else {
+        format!("lines {}-{}", first.line, last.line)
+    }
+}
+
+/// Adds a basic block for a run of straight-line ops and returns its id.
+fn add_block(graph: &mut Graph, ops: &[(char, Position)]) -> usize {
+    let mut label: String = ops.iter().take(MAX_LABEL_OPS).map(|&(c, _)| c).collect();
+    if ops.len() > MAX_LABEL_OPS {
+        label.push_str("...");
+    }
+    label.push_str("\\n");
+    label.push_str(&lines_label(ops[0].1, ops[ops.len() - 1].1));
+    graph.add_node(label, "box")
+}
+
+/// A body being added to the graph by [`build`].
+struct Body<'a> {
+    /// nodes of the body not added yet
+    rest: core::slice::Iter<'a, BrainfuckNode>,
+    /// straight-line ops waiting to become a basic block
+    block: Vec<(char, Position)>,
+    /// edges that flow into whatever node comes next
+    pending: Vec<(usize, &'static str)>,
+    /// `[` node, first node of the body and position of `]` of the loop
+    /// this is the body of, `None` for the whole program
+    around: Option<(usize, usize, Position)>,
+}
+
+impl Body<'_> {
+    /// Turns the waiting straight-line ops into a basic block, if there are any.
+    fn flush(&mut self, graph: &mut Graph) {
+        if !self.block.is_empty() {
+            let id = add_block(graph, &self.block);
+            graph.connect(&self.pending, id);
+            self.pending = vec![(id, "")];
+            self.block.clear();
+        }
+    }
+}
+
+/// Adds `nodes` to the graph.
+///
+/// `pending` holds the edges that flow into whatever node comes next,
+/// the edges leaving the last node of `nodes` are returned.
+fn build(graph: &mut Graph, nodes: &[BrainfuckNode], pending: Vec<(usize, &'static str)>) -> Vec<(usize, &'static str)> {
+    // every body being added, `nodes` at the bottom
+    let mut bodies = vec![Body { rest: nodes.iter(), block: Vec::new(), pending, around: None }];
+
+    loop {
+        let current = bodies.last_mut().expect("`nodes` is finished last");
+        match current.rest.next() {
+            Some(BrainfuckNode::Op(op, position)) => {
+                if let Some(c) = op.as_char() {
+                    current.block.push((c, *position));
+                }
+            },
+            Some(BrainfuckNode::Loop { body, start, end }) => {
+                current.flush(graph);
+
+                // `[` falls into the body when the cell is nonzero
+                let loop_start = graph.add_node(format!("[\\nline {}", start.line), "diamond");
+                graph.connect(&current.pending, loop_start);
+                let body_entry = graph.nodes.len();
+                let pending = vec![(loop_start, "enter")];
+                bodies.push(Body { rest: body.iter(), block: Vec::new(), pending, around: Some((loop_start, body_entry, *end)) });
+            },
+            None => {
+                current.flush(graph);
+                let finished = bodies.pop().expect("a body is being added");
+                let (loop_start, body_entry, end) = match finished.around {
+                    Some(around) => around,
+                    None => return finished.pending,
+                };
+
+                // `]` jumps back to the body when the cell is nonzero
+                let loop_end = graph.add_node(format!("]\\nline {}", end.line), "diamond");
+                graph.connect(&finished.pending, loop_end);
+                graph.edges.push((loop_end, body_entry, "back"));
+
+                // leave the loop either by skipping it or by exiting at `]`
+                let outer = bodies.last_mut().expect("a loop is inside another body");
+                outer.pending = vec![(loop_start, "skip"), (loop_end, "exit")];
+            },
+        }
+    }
+}
+
+/// Renders the control-flow graph of a program in Graphviz DOT format.
+///
+/// Basic blocks of straight-line ops become boxes labelled with their
+/// (truncated) ops and source lines, every `[` and `]` becomes a diamond
+/// with `enter`/`skip` and `back`/`exit` edges respectively.
+///
+/// # Arguments
+///
+/// * `ast` - parsed program
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::{ast, dot};
+///
+/// let ast = ast::parse("+[-]").unwrap();
+/// let graph = dot::to_dot(&ast);
+/// assert!(graph.starts_with("digraph brainfuck {"));
+/// assert!(graph.contains("[label=\"back\"]"));
+/// ```
+pub fn to_dot(ast: &[BrainfuckNode]) -> String {
+    let mut graph = Graph { nodes: Vec::new(), edges: Vec::new() };
+    let entry = graph.add_node(String::from("entry"), "oval");
+    let pending = build(&mut graph, ast, vec![(entry, "")]);
+    let exit = graph.add_node(String::from("exit"), "oval");
+    graph.connect(&pending, exit);
+
+    let mut out = String::from("digraph brainfuck {\n    node [fontname=\"monospace\"];\n");
+    for (id, (label, shape)) in graph.nodes.iter().enumerate() {
+        // writing into a String never fails
+        let _ = writeln!(out, "    n{} [label=\"{}\", shape={}];", id, label, shape);
+    }
+    for (from, to, label) in graph.edges {
+        if label.is_empty() {
+            let _ = writeln!(out, "    n{} -> n{};", from, to);
+        } else {
+            let _ = writeln!(out, "    n{} -> n{} [label=\"{}\"];", from, to, label);
+        }
+    }
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,100 @@
+//! Programs passed around base64-encoded, for playgrounds and other places
+//! where the eight ops need escaping, enable the `base64` feature to use it.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use base64::alphabet::URL_SAFE;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD as BASE64};
+use base64::engine::DecodePaddingMode;
+use base64::{DecodeError, Engine};
+
+use crate::error::BrainfuckError;
+use crate::Interpreter;
+
+/// Decodes base64 `encoded` into brainfuck source.
+///
+/// Whitespace around the encoded text is ignored, it has to be padded
+/// standard base64 otherwise and decode to UTF-8.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::encoding::decode_source;
+/// use rust_bf::BrainfuckError;
+///
+/// assert_eq!(decode_source("KysrWy1dLg==\n").unwrap(), "+++[-].");
+/// let err = decode_source("+++[-].").unwrap_err();
+/// assert!(matches!(err, BrainfuckError::InvalidBase64(_)));
+/// assert_eq!(err.to_string(), "program is not valid base64: Invalid symbol 91, offset 3.");
+/// ```
+pub fn decode_source(encoded: &str) -> Result<String, BrainfuckError> {
+    let bytes = BASE64.decode(encoded.trim()).map_err(|err| BrainfuckError::InvalidBase64(err.to_string()))?;
+    String::from_utf8(bytes).map_err(|_| BrainfuckError::InvalidBase64(String::from("it decodes to bytes that are not UTF-8")))
+}
+
+/// URL-safe base64, written without padding and read with or without it
+const URL_SAFE_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new().with_encode_padding(false).with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Encodes the brainfuck source `src` as URL-safe base64 without padding,
+/// which can go in a link or a chat message as it is, see [`decode_url_safe`].
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::encoding::encode_url_safe;
+///
+/// assert_eq!(encode_url_safe("+++[->>+<<]."), "KysrWy0-Pis8PF0u");
+/// ```
+pub fn encode_url_safe(src: &str) -> String {
+    URL_SAFE_BASE64.encode(src)
+}
+
+/// Decodes URL-safe base64 `encoded` into brainfuck source, padded or not.
+///
+/// Whitespace around the encoded text is ignored. Errors tell the offset
+/// of the character that cannot be decoded, counted from the start of
+/// `encoded`.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::encoding::decode_url_safe;
+///
+/// assert_eq!(decode_url_safe(" KysrWy0-Pis8PF0u\n").unwrap(), "+++[->>+<<].");
+/// assert_eq!(decode_url_safe("Ky4").unwrap(), decode_url_safe("Ky4=").unwrap());
+/// let err = decode_url_safe(" KysrWy0+Pis8PF0u").unwrap_err();
+/// assert_eq!(err.to_string(), "program is not valid base64: `+` at offset 8 is not URL-safe base64");
+/// ```
+pub fn decode_url_safe(encoded: &str) -> Result<String, BrainfuckError> {
+    let trimmed = encoded.trim();
+    let start = encoded.len() - encoded.trim_start().len();
+    let at = |offset: usize, byte: u8| format!("`{}` at offset {}", char::from(byte).escape_default(), start + offset);
+    let bytes = URL_SAFE_BASE64.decode(trimmed).map_err(|err| {
+        BrainfuckError::InvalidBase64(match err {
+            DecodeError::InvalidByte(offset, byte) => format!("{} is not URL-safe base64", at(offset, byte)),
+            DecodeError::InvalidLastSymbol(offset, byte) => format!("{} leaves bits over at the end", at(offset, byte)),
+            DecodeError::InvalidLength(len) => format!("{} characters cannot be base64", len),
+            DecodeError::InvalidPadding => String::from("the `=` padding at the end is wrong"),
+        })
+    })?;
+    String::from_utf8(bytes).map_err(|_| BrainfuckError::InvalidBase64(String::from("it decodes to bytes that are not UTF-8")))
+}
+
+/// Decodes base64 `encoded` into source and runs it on `input` in memory
+/// like [`Interpreter::run_string`].
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::encoding::decode_and_run;
+///
+/// // ",[.,]" echoes its input
+/// assert_eq!(decode_and_run("LFsuLF0=", "echo").unwrap(), "echo");
+/// ```
+pub fn decode_and_run(encoded: &str, input: &str) -> Result<String, BrainfuckError> {
+    Interpreter::run_string(&decode_source(encoded)?, input)
+}
@@ -0,0 +1,224 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use crate::ast::Position;
+use crate::interpreter::Snapshot;
+
+/// Errors reported by the brainfuck compiler and VM.
+#[derive(Debug)]
+pub enum BrainfuckError {
+    /// a `[` at the given position has no matching `]`
+    UnmatchedOpen(Position),
+    /// a `]` at the given position has no matching `[`
+    UnmatchedClose(Position),
+    /// the tape would need more bytes than the interpreter's memory limit
+    MemoryLimitExceeded {
+        /// bytes the tape needed
+        requested: usize,
+        /// configured limit in bytes
+        limit: usize,
+    },
+    /// the instruction at `position` moved the pointer left of cell 0
+    PointerUnderflow {
+        /// index of the instruction in the compiled program
+        position: usize,
+    },
+    /// the instruction at `position` moved the pointer past the last address of the VM tape
+    PointerOverflow {
+        /// index of the op among the ops of the program
+        position: usize,
+    },
+    /// writing the output of `.` failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// the program ran more steps than the interpreter's step limit
+    StepLimitExceeded {
+        /// configured limit in IR steps
+        limit: u64,
+    },
+    /// the interrupt flag of the VM was set while the program ran
+    Interrupted,
+    /// the program printed more bytes than a [`LimitedOutput`](crate::io::LimitedOutput) passes on
+    OutputLimitExceeded {
+        /// configured limit in bytes
+        limit: usize,
+    },
+    /// the `,` at `position` ran with no input to read, see [`EofMode::Error`](crate::io::EofMode::Error)
+    InputRequired {
+        /// index of the instruction in the compiled program
+        position: usize,
+    },
+    /// the VM would track more distinct cells than its tape limit, see
+    /// [`BrainfuckVMStatus::set_max_tape`](crate::vm::BrainfuckVMStatus::set_max_tape)
+    TapeMemoryExceeded {
+        /// configured limit in cells
+        limit: usize,
+    },
+    /// the program ran longer than the time limit of
+    /// [`batch::Limits`](crate::batch::Limits)
+    #[cfg(feature = "std")]
+    TimeLimitExceeded {
+        /// configured limit
+        limit: core::time::Duration,
+    },
+    /// the run panicked with this message, reported by [`batch::run_many`](crate::batch::run_many) instead of unwinding
+    #[cfg(feature = "std")]
+    Panicked(String),
+    /// a base64-encoded program could not be decoded, for this reason
+    #[cfg(feature = "base64")]
+    InvalidBase64(String),
+    /// the loop starting at `position` went round more times in a row than
+    /// the VM's threshold with its cell unchanged, see
+    /// [`BrainfuckVMStatus::set_hang_threshold`](crate::vm::BrainfuckVMStatus::set_hang_threshold)
+    LoopHang {
+        /// index of the loop's `[` in the compiled program
+        position: usize,
+        /// how often the loop went round before it was stopped
+        iterations: u64,
+    },
+    /// the run reached a breakpoint set with [`Interpreter::set_breakpoint`](crate::Interpreter::set_breakpoint)
+    BreakpointHit {
+        /// index of the instruction in the compiled program, which has not run yet
+        position: usize,
+        /// state of the interpreter at the breakpoint
+        vm_state: Snapshot,
+    },
+    /// the run changed a cell watched with [`Interpreter::set_watchpoint`](crate::Interpreter::set_watchpoint)
+    WatchpointTriggered {
+        /// address of the cell
+        cell: usize,
+        /// what the cell held before
+        old_value: u8,
+        /// what it holds now
+        new_value: u8,
+        /// index of the instruction that changed it in the compiled program, which has run
+        instruction_position: usize,
+    },
+    /// the instruction at `position` took a cell past the largest value it
+    /// holds or below 0 while cells do not wrap, see
+    /// [`OverflowMode::Error`](crate::vm::OverflowMode::Error)
+    CellOverflow {
+        /// index of the instruction, among the ops of the program for the VM
+        /// and in the compiled program for the interpreter
+        position: usize,
+        /// address of the cell
+        cell: i64,
+        /// what the instruction would have left in the cell, which keeps what it held
+        value: i64,
+    },
+}
+
+impl fmt::Display for BrainfuckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrainfuckError::UnmatchedOpen(at) => write!(f, "unmatched '[' at line {}, column {}", at.line, at.column),
+            BrainfuckError::UnmatchedClose(at) => write!(f, "unmatched ']' at line {}, column {}", at.line, at.column),
+            BrainfuckError::MemoryLimitExceeded { requested, limit } => {
+                write!(f, "tape needs {} bytes, exceeding the memory limit of {} bytes", requested, limit)
+            },
+            BrainfuckError::PointerUnderflow { position } => {
+                write!(f, "instruction {} moved the pointer left of cell 0", position)
+            },
+            BrainfuckError::PointerOverflow { position } => {
+                write!(f, "instruction {} moved the pointer past the end of the tape", position)
+            },
+            #[cfg(feature = "std")]
+            BrainfuckError::Io(err) => write!(f, "cannot write output: {}", err),
+            BrainfuckError::StepLimitExceeded { limit } => write!(f, "program ran past the step limit of {} steps", limit),
+            BrainfuckError::Interrupted => write!(f, "program was interrupted"),
+            BrainfuckError::OutputLimitExceeded { limit } => write!(f, "program printed more than the output limit of {} bytes", limit),
+            BrainfuckError::InputRequired { position } => write!(f, "instruction {} reads input but there is none", position),
+            BrainfuckError::TapeMemoryExceeded { limit } => write!(f, "program touched more than the tape limit of {} cells", limit),
+            #[cfg(feature = "std")]
+            BrainfuckError::TimeLimitExceeded { limit } => write!(f, "program ran past the time limit of {:?}", limit),
+            #[cfg(feature = "std")]
+            BrainfuckError::Panicked(message) => write!(f, "run panicked: {}", message),
+            #[cfg(feature = "base64")]
+            BrainfuckError::InvalidBase64(reason) => write!(f, "program is not valid base64: {}", reason),
+            BrainfuckError::LoopHang { position, iterations } => {
+                write!(f, "loop at instruction {} went round {} times without its cell changing", position, iterations)
+            },
+            BrainfuckError::BreakpointHit { position, .. } => write!(f, "instruction {} is a breakpoint", position),
+            BrainfuckError::WatchpointTriggered { cell, old_value, new_value, instruction_position } => {
+                write!(f, "instruction {} changed watched cell {} from {} to {}", instruction_position, cell, old_value, new_value)
+            },
+            BrainfuckError::CellOverflow { position, cell, value } => {
+                write!(f, "instruction {} took cell {} to {}, out of the range of a cell", position, cell, value)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BrainfuckError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BrainfuckError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Formats `err` the way compilers do: `line:column: error: message`,
+/// then the source line with a caret under the offending character and
+/// what is wrong there.
+///
+/// Only bracket errors point into the source, every other error gets
+/// its message and a note on what to do about it.
+///
+/// # Arguments
+///
+/// * `src` - the source `err` was reported for
+/// * `err` - error to format
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::{ast::parse, format_error};
+///
+/// let src = "+++\n>[<+>-\n";
+/// let err = parse(src).unwrap_err();
+/// assert_eq!(format_error(src, &err), "\
+/// 2:2: error: unmatched '['
+///     >[<+>-
+///      ^ this '[' is never closed by a ']'
+/// ");
+/// ```
+pub fn format_error(src: &str, err: &BrainfuckError) -> String {
+    let (at, message, label) = match err {
+        BrainfuckError::UnmatchedOpen(at) => (at, "unmatched '['", "this '[' is never closed by a ']'"),
+        BrainfuckError::UnmatchedClose(at) => (at, "unmatched ']'", "this ']' has no '[' to close"),
+        _ => {
+            let note = match err {
+                BrainfuckError::MemoryLimitExceeded { .. } => "raise the memory limit or use fewer cells",
+                BrainfuckError::PointerUnderflow { .. } => "the tape starts at cell 0, there is nothing left of it",
+                BrainfuckError::PointerOverflow { .. } => "the tape ends at its fixed size, or at the addresses a 32-bit pointer can hold",
+                BrainfuckError::StepLimitExceeded { .. } => "the program may never halt, or needs a higher step limit",
+                BrainfuckError::Interrupted => "the run stopped at a loop, the program is left where it was",
+                BrainfuckError::OutputLimitExceeded { .. } => "raise the output limit, or truncate the output instead",
+                BrainfuckError::InputRequired { .. } => "give the program some input, or let end of input store a value",
+                BrainfuckError::TapeMemoryExceeded { .. } => "raise the tape limit, or keep the program to fewer cells",
+                #[cfg(feature = "std")]
+                BrainfuckError::TimeLimitExceeded { .. } => "the program may never halt, or needs a higher time limit",
+                #[cfg(feature = "std")]
+                BrainfuckError::Panicked(_) => "this is a bug in the interpreter, the other runs are not affected",
+                #[cfg(feature = "base64")]
+                BrainfuckError::InvalidBase64(_) => "check the program was encoded whole, in the base64 alphabet it is read with",
+                BrainfuckError::LoopHang { .. } => "the loop never changes the cell it tests, so it never ends",
+                BrainfuckError::BreakpointHit { .. } => "resume the interpreter to run on from the breakpoint",
+                BrainfuckError::WatchpointTriggered { .. } => "resume the interpreter to run on from the watchpoint",
+                BrainfuckError::CellOverflow { .. } => "let cells wrap around or saturate, or keep the value in range",
+                _ => "the output went away or cannot take more bytes",
+            };
+            return format!("error: {}\nnote: {}\n", err, note);
+        },
+    };
+
+    let line_start = src[..at.offset].rfind('\n').map_or(0, |newline| newline + 1);
+    let line_end = src[at.offset..].find('\n').map_or(src.len(), |newline| at.offset + newline);
+    let line = src[line_start..line_end].trim_end_matches('\r');
+    // keep tabs so that the caret lines up however wide they are shown
+    let padding: String = src[line_start..at.offset].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    format!("{}:{}: error: {}\n    {}\n    {}^ {}\n", at.line, at.column, message, line, padding, label)
+}
@@ -0,0 +1,102 @@
+//! Classic programs that ship with the crate, to try things out on, see
+//! `rust_bf --list-examples`.
+
+use alloc::vec::Vec;
+
+/// A program embedded in the crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Example {
+    /// what `--example` takes
+    pub name: &'static str,
+    /// what the program does, in a few words
+    pub description: &'static str,
+    /// brainfuck source
+    pub source: &'static str,
+}
+
+/// every embedded program, sorted by name
+pub static EXAMPLES: &[Example] = &[
+    Example {
+        name: "cat",
+        description: "copies its input to its output",
+        source: include_str!("examples/cat.bf"),
+    },
+    Example {
+        name: "hello",
+        description: "prints Hello World!",
+        source: include_str!("examples/hello.bf"),
+    },
+    Example {
+        name: "quine",
+        description: "prints its own source",
+        source: include_str!("examples/quine.bf"),
+    },
+    Example {
+        name: "rot13",
+        description: "rotates the letters of its input by 13",
+        source: include_str!("examples/rot13.bf"),
+    },
+    Example {
+        name: "sierpinski",
+        description: "draws a Sierpinski triangle",
+        source: include_str!("examples/sierpinski.bf"),
+    },
+];
+
+/// edits a misspelled name may be away from the example [`closest`] suggests
+const MAX_DISTANCE: usize = 3;
+
+/// Returns the example called `name`.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::examples::find;
+/// use rust_bf::Interpreter;
+///
+/// let rot13 = find("rot13").unwrap();
+/// assert_eq!(Interpreter::run_string(rot13.source, "Hello").unwrap(), "Uryyb");
+/// assert!(find("rot14").is_none());
+/// ```
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// Returns the example whose name is the fewest edits away from `name`,
+/// `None` if none of them is close.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::examples::closest;
+///
+/// assert_eq!(closest("sierpinsky").unwrap().name, "sierpinski");
+/// assert_eq!(closest("Hello").unwrap().name, "hello");
+/// assert!(closest("mandelbrot").is_none());
+/// ```
+pub fn closest(name: &str) -> Option<&'static Example> {
+    let name = name.to_ascii_lowercase();
+    EXAMPLES
+        .iter()
+        .map(|example| (edit_distance(&name, example.name), example))
+        .filter(|&(distance, _)| distance <= MAX_DISTANCE)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, example)| example)
+}
+
+/// Returns how many characters have to be inserted, removed or replaced to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // distances from the start of `a` read so far to every start of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let replaced = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
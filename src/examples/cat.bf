@@ -0,0 +1,2 @@
+echo input until end of input
+,[.,]
@@ -0,0 +1,22 @@
+rot13: rotate every letter of the input by 13 places and copy everything else
+
+cells: c  n  d  r  q  scratch x2 and then  t  plus  minus  q  letter
+read c and copy it into n as c minus 1
+,[
+  [->+>+<<]>>[-<<+>>]<-
+  d is 32 then divmod leaves r = n mod 32 and q = n div 32
+  >>++++[<++++++++>-]<<
+  [->-[>+>>]>[+[-<+>]>+>>]<<<<<]
+  >[-]>>[->>>>>+<<<<<]
+  divmod r by 13 leaves t = r div 13
+  >+++[<++++>-]<+<
+  [->-[>+>>]>[+[-<+>]>+>>]<<<<<]
+  >[-]>[-]>
+  t is 0 for the first half of the alphabet and 1 for the second
+  >+<[->->+<<[[-]>>-<<]]
+  q is 2 for upper and 3 for lower case letters
+  >>>[-[->+<[-[[-]>-<]]]]>
+  [<<<[<<<<<<<+++++++++++++>>>>>>>-]>[<<<<<<<<------------->>>>>>>>-]>>-]
+  <<<[-]>[-]
+  <<<<<<<<.,
+]
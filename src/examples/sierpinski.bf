@@ -0,0 +1,16 @@
+sierpinski: print 16 rows of Pascal's triangle mod 2 as a triangle of stars
+
+cells: rows  spaces  t  t  0  space  newline  0  then a column of 5 cells
+each: active  bit  char  t  t
+++++++++++++++++>+++++++++++++++>>>++++[>++++++++<-]>>++++++++++>>+>+<<<<<<<<<
+[
+  the leading spaces
+  >[->+>+<<]>>[-<<+>>]<[>>>.<<<-]>>>>>>
+  a star for every set bit and a space for every clear one
+  [>>>++++[<++++++++>-]<<[>++++++++++>>+<<<-]>>>[<<<+>>>-]<<.[-]>++++[<++++++++>-]<.[-]>>>]
+  <<<<<[<<<<<]>>>.
+  the next row: a new column on the right and every bit xor the one left of it
+  >>[>>>>>]+<<<<<
+  [>[->+>+<<]>>[-<<+>>]<[>>>>>>+<<[>>-<<-]>>[<<+>>-]<<<<<<-]<<<<<<<]
+  <<-<-
+]
@@ -0,0 +1,171 @@
+//! Programs annotated with what their loops do, to learn from programs
+//! written by others, see `rust_bf explain`.
+//!
+//! Every line of the source is followed by a note under each loop that
+//! starts on it, telling what the optimizer makes of the loop, and under
+//! each straight run of ops that moves the pointer, telling how far it
+//! moves in all:
+//!
+//! ```
+//! use rust_bf::explain::explain;
+//!
+//! assert_eq!(explain("copy it: ++[->+>+<<] then >>[>]").unwrap(), "\
+//! copy it: ++[->+>+<<] then >>[>]
+//!            ^ multiply loop: cell[+1] += cell, cell[+2] += cell, then cell = 0
+//!                           ~~ pointer +2
+//!                             ^ scan loop: moves right 1 cell at a time to a zero cell
+//! ");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::ast::{self, BrainfuckNode, Position};
+use crate::error::BrainfuckError;
+use crate::ir::{self, BrainfuckIR};
+use crate::op::BrainfuckOp;
+use crate::optimizer;
+
+/// A note on part of a line of the source.
+struct Note {
+    /// 1-based line
+    line: usize,
+    /// column the note starts at
+    column: usize,
+    /// what marks the columns it is about, `^` under the bracket of a loop
+    /// or `~` under every op of a run
+    marker: String,
+    /// what the note says
+    text: String,
+}
+
+/// Straight-line ops seen so far on one line.
+struct Segment {
+    /// where the first op is
+    start: Position,
+    /// column of the last op
+    end: usize,
+    /// how far the ops move the pointer
+    moves: i32,
+    /// whether any of them is a `>` or `<`
+    moved: bool,
+}
+
+/// Returns what the loop with `body` does, as the optimizer sees it.
+fn describe_loop(body: &[BrainfuckNode]) -> String {
+    // the optimizer leaves every loop with another inside alone, and
+    // compiling the body of each of many nested loops would take long
+    if body.iter().any(|node| matches!(node, BrainfuckNode::Loop { .. })) {
+        return String::from("general loop");
+    }
+    let replacement = match optimizer::simple_loop(&ir::compile(body)) {
+        Some(replacement) => replacement,
+        None => return String::from("general loop"),
+    };
+    match replacement[..] {
+        [BrainfuckIR::Scan(n)] => {
+            let direction = if n > 0 { "right" } else { "left" };
+            let cells = if n.abs() == 1 { "cell" } else { "cells" };
+            format!("scan loop: moves {} {} {} at a time to a zero cell", direction, n.abs(), cells)
+        },
+        [BrainfuckIR::Clear] => String::from("clear loop: cell = 0"),
+        _ => {
+            let targets: Vec<String> = replacement
+                .iter()
+                .filter_map(|node| match *node {
+                    BrainfuckIR::MulAdd { offset, factor } => {
+                        let (sign, factor) = if factor < 0 { ("-", -factor) } else { ("+", factor) };
+                        let times = if factor == 1 { String::new() } else { format!("{} * ", factor) };
+                        Some(format!("cell[{:+}] {}= {}cell", offset, sign, times))
+                    },
+                    _ => None,
+                })
+                .collect();
+            format!("multiply loop: {}, then cell = 0", targets.join(", "))
+        },
+    }
+}
+
+/// Adds the note on `segment`, if it moved the pointer, to `notes`.
+fn flush(segment: Option<Segment>, notes: &mut Vec<Note>) {
+    if let Some(segment) = segment.filter(|segment| segment.moved) {
+        notes.push(Note {
+            line: segment.start.line,
+            column: segment.start.column,
+            marker: "~".repeat(segment.end - segment.start.column + 1),
+            text: format!("pointer {:+}", segment.moves),
+        });
+    }
+}
+
+/// Adds the notes on `nodes` to `notes`, and on the loops in them that
+/// are not explained as a whole.
+fn annotate(nodes: &[BrainfuckNode], notes: &mut Vec<Note>) {
+    // every body being annotated with the nodes left of it and the
+    // straight-line ops seen last in it, `nodes` at the bottom
+    let mut bodies: Vec<(core::slice::Iter<'_, BrainfuckNode>, Option<Segment>)> = vec![(nodes.iter(), None)];
+    while let Some((rest, segment)) = bodies.last_mut() {
+        let node = match rest.next() {
+            Some(node) => node,
+            None => {
+                flush(segment.take(), notes);
+                bodies.pop();
+                continue;
+            },
+        };
+        match node {
+            BrainfuckNode::Op(op, at) => {
+                let segment = match segment {
+                    Some(segment) if segment.start.line == at.line => segment,
+                    _ => {
+                        flush(segment.take(), notes);
+                        segment.insert(Segment { start: *at, end: at.column, moves: 0, moved: false })
+                    },
+                };
+                segment.end = at.column;
+                match op {
+                    BrainfuckOp::IncrementPtrOp => segment.moves += 1,
+                    BrainfuckOp::DecrementPtrOp => segment.moves -= 1,
+                    _ => continue,
+                }
+                segment.moved = true;
+            },
+            BrainfuckNode::Loop { body, start, .. } => {
+                flush(segment.take(), notes);
+                let text = describe_loop(body);
+                let general = text == "general loop";
+                notes.push(Note { line: start.line, column: start.column, marker: String::from("^"), text });
+                if general {
+                    bodies.push((body.iter(), None));
+                }
+            },
+        }
+    }
+}
+
+/// Returns `src` with notes under its lines on what its loops do and how
+/// far its straight runs of ops move the pointer.
+///
+/// A loop that only moves, one that only clears the cell and one that
+/// multiplies it into others are explained as a whole, the loops and runs
+/// of ops inside any other loop get notes of their own.
+pub fn explain(src: &str) -> Result<String, BrainfuckError> {
+    let mut notes = Vec::new();
+    annotate(&ast::parse(src)?, &mut notes);
+    notes.sort_by_key(|note| (note.line, note.column));
+
+    let mut text = String::new();
+    let mut notes = notes.into_iter().peekable();
+    for (i, line) in src.lines().enumerate() {
+        text.push_str(line);
+        text.push('\n');
+        while let Some(note) = notes.next_if(|note| note.line == i + 1) {
+            // formatting width is capped at 65535, far shorter than a line can be
+            text.push_str(&" ".repeat(note.column - 1));
+            text.push_str(&format!("{} {}\n", note.marker, note.text));
+        }
+    }
+    Ok(text)
+}
@@ -0,0 +1,163 @@
+//! How far the tape pointer can get, worked out from the IR without
+//! running the program.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::ir::BrainfuckIR;
+
+/// The cells a program can reach, relative to the cell it starts on.
+///
+/// Loops count as running once. A loop that ends where it started reaches
+/// the same cells however often it runs, one that ends elsewhere drifts
+/// further each time and is listed in `unbounded_loops`: `min` and `max`
+/// then only hold for a single run of it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TapeExtent {
+    /// the leftmost cell reached, 0 or less
+    pub min: isize,
+    /// the rightmost cell reached, 0 or more
+    pub max: isize,
+    /// IR index of the `LoopStart` or `Scan` of every loop that moves the pointer
+    pub unbounded_loops: Vec<usize>,
+}
+
+impl TapeExtent {
+    /// Returns whether `min` and `max` hold however often the loops run.
+    pub fn is_bounded(&self) -> bool {
+        self.unbounded_loops.is_empty()
+    }
+
+    /// Returns how many cells lie from `min` to `max`, the tape a bounded program needs.
+    pub fn cells(&self) -> usize {
+        (self.max - self.min) as usize + 1
+    }
+
+    /// Widens the extent to cell `offset`.
+    fn reach(&mut self, offset: isize) {
+        self.min = self.min.min(offset);
+        self.max = self.max.max(offset);
+    }
+}
+
+impl fmt::Display for TapeExtent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cells {} to {}", self.min, self.max)?;
+        if !self.is_bounded() {
+            write!(f, ", unbounded because of the loop at")?;
+            for start in &self.unbounded_loops {
+                write!(f, " {}", start)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why [`min_tape_size`] cannot size the tape of a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// a loop moves the pointer as far as the data it runs on says
+    Inconclusive,
+    /// the pointer can get left of the first cell, which no tape size helps with
+    LeftOfStart,
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::Inconclusive => write!(f, "a loop moves the pointer by an amount that depends on the data"),
+            AnalysisError::LeftOfStart => write!(f, "the pointer can move left of the first cell"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AnalysisError {}
+
+/// Returns how many cells the tape of `ir` needs, the pointer starting on
+/// the first of them, so that an embedder can allocate no more.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::extent::{min_tape_size, AnalysisError};
+/// use rust_bf::ir::compile;
+///
+/// let size = |src| min_tape_size(&compile(&parse(src).unwrap()));
+/// assert_eq!(size("++++++++[>++++++++<-]>+."), Ok(2));
+/// assert_eq!(size(""), Ok(1));
+/// assert_eq!(size(",[>+<-]>>>"), Ok(4));
+/// assert_eq!(size(",[>,]"), Err(AnalysisError::Inconclusive));
+/// assert_eq!(size(">[-]<<"), Err(AnalysisError::LeftOfStart));
+/// ```
+pub fn min_tape_size(ir: &[BrainfuckIR]) -> Result<usize, AnalysisError> {
+    let extent = tape_extent(ir);
+    if !extent.is_bounded() {
+        return Err(AnalysisError::Inconclusive);
+    }
+    if extent.min < 0 {
+        return Err(AnalysisError::LeftOfStart);
+    }
+    Ok(extent.max as usize + 1)
+}
+
+/// Works out the cells the compiled or optimized `ir` can reach.
+///
+/// `MulAdd` and `AddRange` reach the cells they write to as well, a
+/// `Scan` counts as the loop it was optimized from.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::extent::tape_extent;
+/// use rust_bf::ir::compile;
+///
+/// let extent = tape_extent(&compile(&parse("<<+>>>>[->+<]").unwrap()));
+/// assert_eq!((extent.min, extent.max, extent.cells()), (-2, 3, 6));
+/// assert!(extent.is_bounded());
+///
+/// // the loop at index 1 walks right until it finds a zero cell
+/// let extent = tape_extent(&compile(&parse("+[>+]<<").unwrap()));
+/// assert_eq!(extent.unbounded_loops, vec![1]);
+/// assert_eq!(extent.to_string(), "cells -1 to 1, unbounded because of the loop at 1");
+/// ```
+pub fn tape_extent(ir: &[BrainfuckIR]) -> TapeExtent {
+    let mut extent = TapeExtent::default();
+    walk(ir, 0..ir.len(), 0, &mut extent);
+    extent
+}
+
+/// Walks `ir[range]` with the pointer on cell `offset`, returning the cell it ends on.
+fn walk(ir: &[BrainfuckIR], range: Range<usize>, mut offset: isize, extent: &mut TapeExtent) -> isize {
+    let mut i = range.start;
+    while i < range.end {
+        match ir[i] {
+            BrainfuckIR::Move(n) => {
+                offset += n as isize;
+                extent.reach(offset);
+            },
+            BrainfuckIR::MulAdd { offset: target, .. } => extent.reach(offset + target as isize),
+            BrainfuckIR::AddRange { len, .. } => extent.reach(offset + len as isize - 1),
+            // a loop moving `n` cells each time it runs
+            BrainfuckIR::Scan(n) => {
+                offset += n as isize;
+                extent.reach(offset);
+                extent.unbounded_loops.push(i);
+            },
+            BrainfuckIR::LoopStart(end) => {
+                let after = walk(ir, i + 1..end, offset, extent);
+                if after != offset {
+                    extent.unbounded_loops.push(i);
+                }
+                offset = after;
+                i = end;
+            },
+            _ => (),
+        }
+        i += 1;
+    }
+    offset
+}
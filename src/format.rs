@@ -0,0 +1,186 @@
+use alloc::string::String;
+
+use crate::ast;
+use crate::error::BrainfuckError;
+use crate::op::BrainfuckOp;
+
+/// Options of [`format_program`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// maximum number of ops on a line, not counting indentation
+    pub width: usize,
+    /// spaces per loop depth
+    pub indent: usize,
+    /// drop comment text instead of keeping it at the end of lines
+    pub strip_comments: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions { width: 60, indent: 2, strip_comments: false }
+    }
+}
+
+/// Line being laid out by [`format_program`].
+struct Line {
+    /// ops on the line
+    ops: String,
+    /// loop depth the line is indented to
+    depth: usize,
+    /// `[` and `]` sit on a line of their own, nothing but a comment may follow them
+    closed: bool,
+}
+
+/// Writes `line` and the comment, if any, to `out` and starts an empty line.
+fn flush(out: &mut String, line: &mut Line, comment: &mut String, options: &FormatOptions) {
+    if line.ops.is_empty() && comment.is_empty() {
+        return;
+    }
+    for _ in 0..line.depth * options.indent {
+        out.push(' ');
+    }
+    out.push_str(&line.ops);
+    if !comment.is_empty() {
+        if !line.ops.is_empty() {
+            out.push_str("  ");
+        }
+        out.push_str(comment);
+    }
+    out.push('\n');
+    line.ops.clear();
+    line.closed = false;
+    comment.clear();
+}
+
+/// Rewrites a program with consistent formatting.
+///
+/// Straight-line ops are wrapped at `options.width`, every `[` and `]` gets
+/// a line of its own and loop bodies are indented one level deeper. Comment
+/// text has its whitespace collapsed and ends the line it appears on, unless
+/// `options.strip_comments` is set. Formatting never changes the op sequence
+/// and formatting a formatted program again yields the same text.
+///
+/// # Arguments
+///
+/// * `src`     - brainfuck source
+/// * `options` - layout settings
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::format::{format_program, FormatOptions};
+/// use rust_bf::ir::compile;
+///
+/// let src = "set up ++[>+ move <-]";
+/// let formatted = format_program(src, &FormatOptions::default()).unwrap();
+/// assert_eq!(formatted, "set up\n++\n[\n  >+  move\n  <-\n]\n");
+///
+/// // same ops, and formatting is idempotent
+/// assert_eq!(compile(&parse(src).unwrap()), compile(&parse(&formatted).unwrap()));
+/// assert_eq!(format_program(&formatted, &FormatOptions::default()).unwrap(), formatted);
+/// ```
+pub fn format_program(src: &str, options: &FormatOptions) -> Result<String, BrainfuckError> {
+    // refuse to lay out loops that do not match
+    ast::check_brackets(src.as_bytes())?;
+    Ok(layout(src, options))
+}
+
+/// Strips everything but the ops from a program and lays them out like
+/// [`format_program`] does, at most `width` ops a line and loop bodies
+/// indented by two spaces a level.
+///
+/// Unlike [`format_program`] this never fails: a `]` without a `[` stays
+/// at the depth it was found at and a `[` that is never closed just leaves
+/// the rest of the program indented, so broken programs can be read too.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::format::format_source;
+///
+/// let src = "++++[>++[>+++<-]<-]>>.";
+/// assert_eq!(format_source(src, 60), "\
+/// ++++
+/// [
+///   >++
+///   [
+///     >+++<-
+///   ]
+///   <-
+/// ]
+/// >>.
+/// ");
+///
+/// assert_eq!(format_source("+ wrap + these + ops +", 3), "+++\n+\n");
+/// assert_eq!(format_source("]+[+", 60), "]\n+\n[\n  +\n");
+/// ```
+pub fn format_source(src: &str, width: usize) -> String {
+    layout(src, &FormatOptions { width, strip_comments: true, ..FormatOptions::default() })
+}
+
+/// Lays out `src`, which [`format_program`] has checked, or not for [`format_source`].
+fn layout(src: &str, options: &FormatOptions) -> String {
+    let mut out = String::new();
+    let mut line = Line { ops: String::new(), depth: 0, closed: false };
+    let mut comment = String::new();
+    let mut depth = 0;
+    // whether whitespace was skipped since the last comment character
+    let mut space = false;
+
+    for character in src.chars() {
+        let op = BrainfuckOp::from_char(character);
+        if op == BrainfuckOp::MonoStateOp {
+            if options.strip_comments {
+                continue;
+            }
+            if line.ops.is_empty() {
+                line.depth = depth;
+            }
+            if character.is_whitespace() {
+                space = true;
+            } else {
+                if space && !comment.is_empty() {
+                    comment.push(' ');
+                }
+                comment.push(character);
+                space = false;
+            }
+            continue;
+        }
+
+        // a comment ends the line it was found on
+        if !comment.is_empty() {
+            flush(&mut out, &mut line, &mut comment, options);
+        }
+        space = false;
+
+        match op {
+            BrainfuckOp::LoopStartOp => {
+                flush(&mut out, &mut line, &mut comment, options);
+                line.ops.push('[');
+                line.depth = depth;
+                line.closed = true;
+                depth += 1;
+            },
+            BrainfuckOp::LoopEndOp => {
+                flush(&mut out, &mut line, &mut comment, options);
+                depth = depth.saturating_sub(1);
+                line.ops.push(']');
+                line.depth = depth;
+                line.closed = true;
+            },
+            _ => {
+                if line.closed || line.ops.len() >= options.width.max(1) {
+                    flush(&mut out, &mut line, &mut comment, options);
+                }
+                if line.ops.is_empty() {
+                    line.depth = depth;
+                }
+                line.ops.push(character);
+            },
+        }
+    }
+    flush(&mut out, &mut line, &mut comment, options);
+    out
+}
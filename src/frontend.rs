@@ -0,0 +1,223 @@
+//! Dialects that spell the eight ops differently, decoded into
+//! [`BrainfuckOp`]s and run like any other program, see `rust_bf --lang`.
+//!
+//! A dialect is a [`Frontend`], [`by_name`] finds the built-in ones and
+//! [`to_source`] turns what they decoded back into brainfuck source:
+//!
+//! ```
+//! use rust_bf::frontend::{by_name, to_source};
+//! use rust_bf::Interpreter;
+//!
+//! let ops = by_name("ook").unwrap().parse(b"Ook. Ook. Ook. Ook. Ook! Ook.").unwrap();
+//! assert_eq!(to_source(&ops), "++.");
+//! assert_eq!(Interpreter::run_string(&to_source(&ops), "").unwrap(), "\u{2}");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::op::BrainfuckOp;
+
+/// Why a [`Frontend`] could not decode a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// byte offset of what could not be decoded
+    pub offset: usize,
+    /// what is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Returns the error at byte `offset` saying `message`.
+fn error(offset: usize, message: impl Into<String>) -> ParseError {
+    ParseError { offset, message: message.into() }
+}
+
+/// A way of writing down brainfuck programs.
+pub trait Frontend {
+    /// Decodes the program `src` into the ops it stands for.
+    fn parse(&self, src: &[u8]) -> Result<Vec<BrainfuckOp>, ParseError>;
+}
+
+/// Plain brainfuck, every byte that is not one of the eight ops is a comment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Brainfuck;
+
+impl Frontend for Brainfuck {
+    fn parse(&self, src: &[u8]) -> Result<Vec<BrainfuckOp>, ParseError> {
+        Ok(src
+            .iter()
+            .map(|&byte| BrainfuckOp::from_char(byte as char))
+            .filter(|&op| op != BrainfuckOp::MonoStateOp)
+            .collect())
+    }
+}
+
+/// [Ook!](https://esolangs.org/wiki/Ook!), every op a pair of `Ook.`,
+/// `Ook?` and `Ook!`, separated by whitespace or not at all.
+///
+/// | pair        | op  |
+/// |-------------|-----|
+/// | `Ook. Ook?` | `>` |
+/// | `Ook? Ook.` | `<` |
+/// | `Ook. Ook.` | `+` |
+/// | `Ook! Ook!` | `-` |
+/// | `Ook! Ook.` | `.` |
+/// | `Ook. Ook!` | `,` |
+/// | `Ook! Ook?` | `[` |
+/// | `Ook? Ook!` | `]` |
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::frontend::{Frontend, Ook};
+/// use rust_bf::BrainfuckOp;
+///
+/// assert_eq!(Ook.parse(b"Ook! Ook?Ook? Ook!").unwrap(), [BrainfuckOp::LoopStartOp, BrainfuckOp::LoopEndOp]);
+/// assert_eq!(Ook.parse(b"Ook? Ook?").unwrap_err().to_string(), "byte 0: `Ook? Ook?` is not an op");
+/// assert_eq!(Ook.parse(b"Ook. Eek!").unwrap_err().to_string(), "byte 5: expected `Ook.`, `Ook?` or `Ook!`");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ook;
+
+impl Frontend for Ook {
+    fn parse(&self, src: &[u8]) -> Result<Vec<BrainfuckOp>, ParseError> {
+        let mut ops = Vec::new();
+        // the first word of a pair and where it starts
+        let mut first: Option<(u8, usize)> = None;
+        let mut i = 0;
+        while i < src.len() {
+            if src[i].is_ascii_whitespace() {
+                i += 1;
+                continue;
+            }
+            let word = match src[i..] {
+                [b'O', b'o', b'k', mark @ (b'.' | b'?' | b'!'), ..] => mark,
+                _ => return Err(error(i, "expected `Ook.`, `Ook?` or `Ook!`")),
+            };
+            match first.take() {
+                None => first = Some((word, i)),
+                Some((mark, start)) => ops.push(match (mark, word) {
+                    (b'.', b'?') => BrainfuckOp::IncrementPtrOp,
+                    (b'?', b'.') => BrainfuckOp::DecrementPtrOp,
+                    (b'.', b'.') => BrainfuckOp::IncrementValueOp,
+                    (b'!', b'!') => BrainfuckOp::DecrementValueOp,
+                    (b'!', b'.') => BrainfuckOp::PrintOp,
+                    (b'.', b'!') => BrainfuckOp::ReadOp,
+                    (b'!', b'?') => BrainfuckOp::LoopStartOp,
+                    (b'?', b'!') => BrainfuckOp::LoopEndOp,
+                    _ => return Err(error(start, "`Ook? Ook?` is not an op")),
+                }),
+            }
+            i += 4;
+        }
+        match first {
+            Some((_, start)) => Err(error(start, "the last `Ook` has no second half")),
+            None => Ok(ops),
+        }
+    }
+}
+
+/// [Spoon](https://esolangs.org/wiki/Spoon), every op a string of `0`
+/// and `1` bits, no string the start of another, whitespace between them
+/// ignored.
+///
+/// | bits       | op                     |
+/// |------------|------------------------|
+/// | `1`        | `+`                    |
+/// | `000`      | `-`                    |
+/// | `010`      | `>`                    |
+/// | `011`      | `<`                    |
+/// | `0011`     | `]`                    |
+/// | `00100`    | `[`                    |
+/// | `001010`   | `.`                    |
+/// | `0010110`  | `,`                    |
+/// | `00101110` | dump the tape, skipped |
+/// | `00101111` | end the program        |
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::frontend::{to_source, Frontend, Spoon};
+///
+/// assert_eq!(to_source(&Spoon.parse(b"1 1 00100 000 0011 001010").unwrap()), "++[-].");
+/// // nothing after the end of the program is decoded
+/// assert_eq!(to_source(&Spoon.parse(b"1 00101111 hello").unwrap()), "+");
+/// assert_eq!(Spoon.parse(b"1 2").unwrap_err().to_string(), "byte 2: `2` is not a bit");
+/// assert_eq!(Spoon.parse(b"1 0010").unwrap_err().to_string(), "byte 2: `0010` ends before its op does");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spoon;
+
+/// The bit strings of [`Spoon`], `None` for the tape dump.
+const SPOON_OPS: [(&[u8], Option<BrainfuckOp>); 9] = [
+    (b"1", Some(BrainfuckOp::IncrementValueOp)),
+    (b"000", Some(BrainfuckOp::DecrementValueOp)),
+    (b"010", Some(BrainfuckOp::IncrementPtrOp)),
+    (b"011", Some(BrainfuckOp::DecrementPtrOp)),
+    (b"0011", Some(BrainfuckOp::LoopEndOp)),
+    (b"00100", Some(BrainfuckOp::LoopStartOp)),
+    (b"001010", Some(BrainfuckOp::PrintOp)),
+    (b"0010110", Some(BrainfuckOp::ReadOp)),
+    (b"00101110", None),
+];
+
+/// bits that end a [`Spoon`] program
+const SPOON_EXIT: &[u8] = b"00101111";
+
+impl Frontend for Spoon {
+    fn parse(&self, src: &[u8]) -> Result<Vec<BrainfuckOp>, ParseError> {
+        let mut ops = Vec::new();
+        // bits of the op being read and where it starts
+        let mut bits = Vec::new();
+        let mut start = 0;
+        for (i, &byte) in src.iter().enumerate() {
+            match byte {
+                b'0' | b'1' => (),
+                _ if byte.is_ascii_whitespace() => continue,
+                _ => return Err(error(i, format!("`{}` is not a bit", byte.escape_ascii()))),
+            }
+            if bits.is_empty() {
+                start = i;
+            }
+            bits.push(byte);
+            if bits == SPOON_EXIT {
+                return Ok(ops);
+            }
+            if let Some(&(_, op)) = SPOON_OPS.iter().find(|(code, _)| *code == &bits[..]) {
+                ops.extend(op);
+                bits.clear();
+            }
+        }
+        if !bits.is_empty() {
+            let message = format!("`{}` ends before its op does", String::from_utf8_lossy(&bits));
+            return Err(error(start, message));
+        }
+        Ok(ops)
+    }
+}
+
+/// Returns the built-in front-end called `name`: `brainfuck`, `ook` or `spoon`.
+pub fn by_name(name: &str) -> Option<&'static dyn Frontend> {
+    match name {
+        "brainfuck" => Some(&Brainfuck),
+        "ook" => Some(&Ook),
+        "spoon" => Some(&Spoon),
+        _ => None,
+    }
+}
+
+/// Writes `ops` as brainfuck source.
+pub fn to_source(ops: &[BrainfuckOp]) -> String {
+    ops.iter().filter_map(|op| op.as_char()).collect()
+}
@@ -0,0 +1,188 @@
+//! An `egui` widget for stepping through brainfuck programs.
+
+use std::cell::RefCell;
+use std::io::{self, Cursor, Write};
+use std::rc::Rc;
+
+use egui::text::LayoutJob;
+use egui::{Color32, TextFormat};
+
+use crate::ir::BrainfuckIR;
+use crate::Interpreter;
+
+/// cells shown on either side of the pointer
+const TAPE_RADIUS: usize = 10;
+/// steps run per frame while "Run" is active
+const STEPS_PER_FRAME: usize = 10_000;
+
+/// Output sink shared between the interpreter and the widget that displays it.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the source text of an IR node.
+fn node_text(node: BrainfuckIR) -> String {
+    let (c, n) = match node {
+        BrainfuckIR::Add(n) => (if n > 0 { '+' } else { '-' }, n.unsigned_abs() as usize),
+        BrainfuckIR::Move(n) => (if n > 0 { '>' } else { '<' }, n.unsigned_abs() as usize),
+        BrainfuckIR::Print => ('.', 1),
+        BrainfuckIR::Read => (',', 1),
+        BrainfuckIR::LoopStart(_) => ('[', 1),
+        BrainfuckIR::LoopEnd(_) => (']', 1),
+        // optimized nodes have no source of their own
+        node => return format!("{{{}}}", node),
+    };
+    std::iter::repeat_n(c, n).collect()
+}
+
+/// Interactive brainfuck debugger for `egui` applications.
+///
+/// Shows the program with the current instruction highlighted, the tape
+/// around the pointer, an input field, the output so far and
+/// step/run/reset buttons. Editing the input takes effect on reset.
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bf::gui::BrainfuckDebuggerWidget;
+///
+/// let mut debugger = BrainfuckDebuggerWidget::new(",[.,]");
+/// # let ctx = egui::Context::default();
+/// egui::CentralPanel::default().show(&ctx, |ui| debugger.ui(ui));
+/// ```
+pub struct BrainfuckDebuggerWidget {
+    /// program being debugged
+    source: String,
+    /// input fed to `,`
+    input: String,
+    /// `None` if the program does not compile
+    interpreter: Option<Interpreter<'static>>,
+    /// everything written by `.` so far
+    output: SharedOutput,
+    /// last error, compile or runtime
+    error: Option<String>,
+    /// whether "Run" is active
+    running: bool,
+}
+
+impl BrainfuckDebuggerWidget {
+    /// Returns a debugger for `source`, stopped at the first instruction.
+    pub fn new(source: &str) -> Self {
+        let mut widget = BrainfuckDebuggerWidget {
+            source: String::from(source),
+            input: String::new(),
+            interpreter: None,
+            output: SharedOutput::default(),
+            error: None,
+            running: false,
+        };
+        widget.reset();
+        widget
+    }
+
+    /// Restarts the program with the current input.
+    pub fn reset(&mut self) {
+        self.output = SharedOutput::default();
+        self.running = false;
+        self.error = None;
+        self.interpreter = match Interpreter::new(&self.source) {
+            Ok(interpreter) => Some(
+                interpreter
+                    .input(Cursor::new(self.input.clone().into_bytes()))
+                    .output(self.output.clone()),
+            ),
+            Err(err) => {
+                self.error = Some(err.to_string());
+                None
+            },
+        };
+    }
+
+    /// Runs up to `steps` instructions, stopping on errors.
+    fn advance(&mut self, steps: usize) {
+        if let Some(interpreter) = &mut self.interpreter {
+            for _ in 0..steps {
+                match interpreter.step() {
+                    Ok(true) => (),
+                    Ok(false) => {
+                        self.running = false;
+                        break;
+                    },
+                    Err(err) => {
+                        self.error = Some(err.to_string());
+                        self.running = false;
+                        break;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Draws the debugger into `ui`.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let halted = self.interpreter.as_ref().is_none_or(|i| i.is_halted()) || self.error.is_some();
+            if ui.add_enabled(!halted, egui::Button::new("Step")).clicked() {
+                self.advance(1);
+            }
+            let run_label = if self.running { "Pause" } else { "Run" };
+            if ui.add_enabled(!halted, egui::Button::new(run_label)).clicked() {
+                self.running = !self.running;
+            }
+            if ui.button("Reset").clicked() {
+                self.reset();
+            }
+        });
+
+        if self.running {
+            self.advance(STEPS_PER_FRAME);
+            ui.ctx().request_repaint();
+        }
+
+        if let Some(interpreter) = &self.interpreter {
+            // program, current instruction highlighted
+            let mut job = LayoutJob::default();
+            for (i, &node) in interpreter.program().iter().enumerate() {
+                let mut format = TextFormat { font_id: egui::FontId::monospace(14.0), ..Default::default() };
+                if i == interpreter.instruction_pointer() {
+                    format.background = Color32::YELLOW;
+                    format.color = Color32::BLACK;
+                }
+                job.append(&node_text(node), 0.0, format);
+            }
+            ui.label(job);
+
+            // tape around the pointer
+            let ptr = interpreter.tape_pointer();
+            let first = ptr.saturating_sub(TAPE_RADIUS);
+            ui.horizontal(|ui| {
+                for index in first..first + 2 * TAPE_RADIUS {
+                    let value = interpreter.tape().get(index).copied().unwrap_or(0);
+                    let text = egui::RichText::new(format!("{}\n{:3}", index, value)).monospace();
+                    let text = if index == ptr { text.strong().color(Color32::YELLOW) } else { text };
+                    ui.label(text);
+                }
+            });
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+
+        ui.label("Input (applied on reset)");
+        ui.text_edit_singleline(&mut self.input);
+        ui.label("Output");
+        let mut output = String::from_utf8_lossy(&self.output.0.borrow()).into_owned();
+        ui.add(egui::TextEdit::multiline(&mut output).interactive(false));
+    }
+}
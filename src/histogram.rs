@@ -0,0 +1,244 @@
+//! How often each op runs, see [`Interpreter::with_histogram`], and which
+//! values the cells hold, see [`Interpreter::cell_value_histogram`].
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+
+use crate::error::BrainfuckError;
+use crate::ir::BrainfuckIR;
+use crate::{BrainfuckOp, Interpreter};
+
+/// the eight ops, in the order of their `BrainfuckOp` discriminant
+const OPS: [BrainfuckOp; 8] = [
+    BrainfuckOp::IncrementValueOp,
+    BrainfuckOp::DecrementValueOp,
+    BrainfuckOp::IncrementPtrOp,
+    BrainfuckOp::DecrementPtrOp,
+    BrainfuckOp::PrintOp,
+    BrainfuckOp::ReadOp,
+    BrainfuckOp::LoopStartOp,
+    BrainfuckOp::LoopEndOp,
+];
+
+/// Number of times each op ran.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstructionHistogram {
+    /// runs of every op, indexed by its `BrainfuckOp` discriminant
+    counts: [u64; 8],
+}
+
+impl InstructionHistogram {
+    /// Returns how often `op` ran, 0 for `MonoStateOp` and the ops of the
+    /// `self_modifying` and `debug_ops` features.
+    pub fn count(&self, op: BrainfuckOp) -> u64 {
+        self.counts.get(op as usize).copied().unwrap_or(0)
+    }
+
+    /// Returns how many ops ran in total.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the op that ran most often, the first in `+-><.,[]` order
+    /// on a tie, and `MonoStateOp` if none ran.
+    pub fn most_frequent(&self) -> BrainfuckOp {
+        let mut most = BrainfuckOp::MonoStateOp;
+        let mut count = 0;
+        for (&op, &n) in OPS.iter().zip(&self.counts) {
+            if n > count {
+                most = op;
+                count = n;
+            }
+        }
+        most
+    }
+
+    /// Returns a line for every op with how often it ran and its share of
+    /// all ops run, ops that never ran included.
+    pub fn as_percentage_table(&self) -> String {
+        let total = self.total();
+        let mut table = String::new();
+        for (&op, &n) in OPS.iter().zip(&self.counts) {
+            let share = if total == 0 { 0.0 } else { n as f64 * 100.0 / total as f64 };
+            table.push_str(&format!("{} {:>12} {:>6.1}%\n", op.as_char().unwrap_or(' '), n, share));
+        }
+        table
+    }
+
+    /// Adds `n` runs of the ops `up` if `n` is positive, of `down` otherwise.
+    fn add(&mut self, n: i32, up: BrainfuckOp, down: BrainfuckOp) {
+        let op = if n > 0 { up } else { down };
+        self.counts[op as usize] += n.unsigned_abs() as u64;
+    }
+
+    /// Counts the ops `node` stands for.
+    fn record(&mut self, node: &BrainfuckIR) {
+        match *node {
+            BrainfuckIR::Add(n) => self.add(n, BrainfuckOp::IncrementValueOp, BrainfuckOp::DecrementValueOp),
+            BrainfuckIR::Move(n) => self.add(n, BrainfuckOp::IncrementPtrOp, BrainfuckOp::DecrementPtrOp),
+            BrainfuckIR::Print => self.counts[BrainfuckOp::PrintOp as usize] += 1,
+            BrainfuckIR::Read => self.counts[BrainfuckOp::ReadOp as usize] += 1,
+            BrainfuckIR::LoopStart(_) => self.counts[BrainfuckOp::LoopStartOp as usize] += 1,
+            BrainfuckIR::LoopEnd(_) => self.counts[BrainfuckOp::LoopEndOp as usize] += 1,
+            BrainfuckIR::Clear => {
+                self.counts[BrainfuckOp::LoopStartOp as usize] += 1;
+                self.counts[BrainfuckOp::DecrementValueOp as usize] += 1;
+                self.counts[BrainfuckOp::LoopEndOp as usize] += 1;
+            },
+            BrainfuckIR::MulAdd { factor, .. } => self.add(factor, BrainfuckOp::IncrementValueOp, BrainfuckOp::DecrementValueOp),
+            BrainfuckIR::AddRange { len, n } => {
+                for _ in 0..len {
+                    self.add(n, BrainfuckOp::IncrementValueOp, BrainfuckOp::DecrementValueOp);
+                }
+            },
+            BrainfuckIR::Scan(n) => {
+                self.counts[BrainfuckOp::LoopStartOp as usize] += 1;
+                self.add(n, BrainfuckOp::IncrementPtrOp, BrainfuckOp::DecrementPtrOp);
+                self.counts[BrainfuckOp::LoopEndOp as usize] += 1;
+            },
+            // not one of the eight ops
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber | BrainfuckIR::Reset => (),
+        }
+    }
+}
+
+/// An [`Interpreter`] that counts the ops it runs.
+///
+/// A node of compiled IR counts as the ops it was compiled from, so runs
+/// of [`Interpreter::new`] are counted exactly. Nodes made by the optimizer
+/// count as a single pass of the loop they replace: `Clear` as `[-]`,
+/// `Scan` as `[>]` or `[<]` and `MulAdd` as its `+` or `-` ops, leaving
+/// out the moves to its target.
+/// `AddRange` counts as its `+` or `-` ops and the `Move` after it as the
+/// `>` ops between them, which is exact.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::{BrainfuckOp, Interpreter};
+///
+/// let mut interpreter = Interpreter::new("++[>+++<-]>.").unwrap().output(Vec::new()).with_histogram();
+/// interpreter.run().unwrap();
+/// let histogram = interpreter.histogram();
+/// assert_eq!(histogram.count(BrainfuckOp::IncrementValueOp), 8);
+/// assert_eq!(histogram.count(BrainfuckOp::LoopEndOp), 2);
+/// assert_eq!(histogram.total(), 19);
+/// assert_eq!(histogram.most_frequent(), BrainfuckOp::IncrementValueOp);
+/// assert!(histogram.as_percentage_table().starts_with("+            8   42.1%\n-            2   10.5%\n"));
+/// ```
+pub struct HistogramInterpreter<'a> {
+    /// interpreter running the program
+    interpreter: Interpreter<'a>,
+    /// ops run so far
+    histogram: InstructionHistogram,
+}
+
+impl<'a> HistogramInterpreter<'a> {
+    /// Returns the counts so far.
+    pub fn histogram(&self) -> &InstructionHistogram {
+        &self.histogram
+    }
+
+    /// Returns the interpreter, for its tape and pointers.
+    pub fn interpreter(&self) -> &Interpreter<'a> {
+        &self.interpreter
+    }
+
+    /// Runs the program until it halts.
+    pub fn run(&mut self) -> Result<(), BrainfuckError> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    /// Runs a single IR node like [`Interpreter::step`], counting it if it ran without an error.
+    pub fn step(&mut self) -> Result<bool, BrainfuckError> {
+        let node = self.interpreter.program().get(self.interpreter.instruction_pointer()).copied();
+        let ran = self.interpreter.step()?;
+        if let (true, Some(node)) = (ran, node) {
+            self.histogram.record(&node);
+        }
+        Ok(ran)
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Returns an interpreter that also counts how often each op runs,
+    /// see [`HistogramInterpreter`].
+    pub fn with_histogram(self) -> HistogramInterpreter<'a> {
+        HistogramInterpreter { interpreter: self, histogram: InstructionHistogram::default() }
+    }
+}
+
+/// How many cells hold each value, cells holding zero left out.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::Interpreter;
+///
+/// let mut interpreter = Interpreter::new("+>++>+>>>+++").unwrap();
+/// interpreter.run().unwrap();
+/// let histogram = interpreter.cell_value_histogram();
+/// assert_eq!(histogram.nonzero_cells(), 4);
+/// assert_eq!(histogram.cells_holding(1), 2);
+/// assert_eq!(histogram.values().collect::<Vec<_>>(), [(1, 2), (2, 1), (3, 1)]);
+/// assert_eq!(histogram.max_value(), Some(3));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CellValueHistogram {
+    /// number of cells holding every value that some cell holds
+    counts: BTreeMap<u32, usize>,
+}
+
+impl CellValueHistogram {
+    /// Counts `values`, zeros left out.
+    pub(crate) fn from_values(values: impl Iterator<Item = u32>) -> CellValueHistogram {
+        let mut counts = BTreeMap::new();
+        for value in values.filter(|&value| value != 0) {
+            *counts.entry(value).or_insert(0) += 1;
+        }
+        CellValueHistogram { counts }
+    }
+
+    /// Returns the number of cells that are not zero.
+    pub fn nonzero_cells(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Returns how many cells hold `value`, 0 for zero itself.
+    pub fn cells_holding(&self, value: u32) -> usize {
+        self.counts.get(&value).copied().unwrap_or(0)
+    }
+
+    /// Returns every value some cell holds with the number of cells holding it, lowest value first.
+    pub fn values(&self) -> impl Iterator<Item = (u32, usize)> + '_ {
+        self.counts.iter().map(|(&value, &count)| (value, count))
+    }
+
+    /// Returns the highest value of any cell, `None` if all cells are zero.
+    pub fn max_value(&self) -> Option<u32> {
+        self.counts.keys().next_back().copied()
+    }
+
+    /// Returns a line for every value some cell holds, with how many
+    /// cells hold it and their share of the non-zero cells.
+    pub fn as_table(&self) -> String {
+        let total = self.nonzero_cells();
+        let mut table = String::new();
+        for (value, count) in self.values() {
+            table.push_str(&format!("{:>10} {:>12} {:>6.1}%\n", value, count, count as f64 * 100.0 / total as f64));
+        }
+        table
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Returns how many cells hold each value, scanning the tape.
+    pub fn cell_value_histogram(&self) -> CellValueHistogram {
+        CellValueHistogram::from_values(self.tape().iter().map(|&value| value as u32))
+    }
+}
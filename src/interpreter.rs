@@ -0,0 +1,1191 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::string::String;
+#[cfg(feature = "debug_ops")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::error::BrainfuckError;
+use crate::io::Discard;
+use crate::io::{BrainfuckInput, BrainfuckOutput, EofMode};
+use crate::ir::{self, BrainfuckIR};
+use crate::program::Program;
+use crate::vm::{self, BrainfuckVMStatus, OverflowMode};
+
+/// When a watchpoint set with [`Interpreter::set_watchpoint`] stops the
+/// program, tested every time its cell changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchTrigger {
+    /// whatever the cell changes to
+    AnyChange,
+    /// once the cell changes to this value
+    Equals(u8),
+    /// once the cell changes to a value above this one
+    GreaterThan(u8),
+    /// once the cell changes to a value below this one
+    LessThan(u8),
+}
+
+impl WatchTrigger {
+    /// Returns whether a cell that changed to `value` stops the program.
+    fn fires(self, value: u8) -> bool {
+        match self {
+            WatchTrigger::AnyChange => true,
+            WatchTrigger::Equals(n) => value == n,
+            WatchTrigger::GreaterThan(n) => value > n,
+            WatchTrigger::LessThan(n) => value < n,
+        }
+    }
+}
+
+/// Execution state of an [`Interpreter`], taken with [`Interpreter::snapshot`].
+///
+/// I/O and limits are not part of the state, set them again on the
+/// interpreter returned by [`Interpreter::from_snapshot`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// compiled program
+    program: Vec<BrainfuckIR>,
+    /// index of the next IR node to run
+    ip: usize,
+    /// cells allocated so far
+    tape: Vec<u8>,
+    /// current cell of the tape
+    tape_ptr: usize,
+    /// IR nodes run so far
+    steps: u64,
+}
+
+impl Snapshot {
+    /// Returns the index of the next IR node to run.
+    pub fn instruction_pointer(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the cells allocated so far.
+    pub fn tape(&self) -> &[u8] {
+        &self.tape
+    }
+
+    /// Returns the cells allocated so far to change them, or allocate more.
+    pub fn tape_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.tape
+    }
+
+    /// Returns the index of the current cell.
+    pub fn tape_pointer(&self) -> usize {
+        self.tape_ptr
+    }
+
+    /// Moves the pointer to cell `ptr`, which the tape grows to when the program goes on.
+    pub fn set_tape_pointer(&mut self, ptr: usize) {
+        self.tape_ptr = ptr;
+    }
+
+    /// Returns the number of IR nodes run so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+/// What [`Interpreter::dry_run`] found out about a program.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DryRunReport {
+    /// ops run, counted like [`BrainfuckVMStatus::ops_executed`]
+    pub instructions_executed: u64,
+    /// the rightmost cell the pointer was on
+    pub max_tape_ptr: usize,
+    /// the leftmost cell the pointer was on, negative if it went left of cell 0
+    pub min_tape_ptr: i64,
+    /// how many different cells were written to
+    pub cells_touched: usize,
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "instructions executed: {}", self.instructions_executed)?;
+        writeln!(f, "tape pointer:          {} to {}", self.min_tape_ptr, self.max_tape_ptr)?;
+        writeln!(f, "cells touched:         {}", self.cells_touched)
+    }
+}
+
+/// Cells of an [`Interpreter`] tape, shared with its forks until one of them writes.
+///
+/// Reads go through `Deref`, writes through [`CowTape::make_mut`], which
+/// copies the cells first if a fork still shares them.
+#[derive(Clone, Debug, Default)]
+struct CowTape {
+    /// cells allocated so far
+    cells: Rc<Vec<u8>>,
+}
+
+impl CowTape {
+    /// Returns the cells for writing, copying them if they are shared.
+    fn make_mut(&mut self) -> &mut Vec<u8> {
+        Rc::make_mut(&mut self.cells)
+    }
+
+    /// Drops all cells, keeping the allocation unless a fork shares it.
+    fn clear(&mut self) {
+        match Rc::get_mut(&mut self.cells) {
+            Some(cells) => cells.clear(),
+            None => self.cells = Rc::default(),
+        }
+    }
+}
+
+impl Deref for CowTape {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.cells
+    }
+}
+
+impl From<Vec<u8>> for CowTape {
+    fn from(cells: Vec<u8>) -> CowTape {
+        CowTape { cells: Rc::new(cells) }
+    }
+}
+
+/// Runs compiled brainfuck IR.
+///
+/// The tape starts at cell 0 and grows to the right as the pointer moves,
+/// cells are 8-bit and wrap around. A `,` at end of input stores 0
+/// unless [`Interpreter::with_eof_mode`] says otherwise, and fails
+/// after [`Interpreter::without_input`].
+pub struct Interpreter<'a> {
+    /// compiled program, shared with forks
+    program: Arc<[BrainfuckIR]>,
+    /// index of the next IR node to run
+    ip: usize,
+    /// cells allocated so far
+    tape: CowTape,
+    /// current cell of the tape
+    tape_ptr: usize,
+    /// maximum number of bytes the tape may allocate
+    memory_limit: usize,
+    /// whether moves wrap around a tape of `memory_limit` cells
+    wrap_tape: bool,
+    /// IR nodes run so far
+    steps: u64,
+    /// maximum number of IR nodes to run
+    step_limit: u64,
+    /// what `,` stores at end of input
+    eof_mode: EofMode,
+    /// where `,` reads from, `None` if the program gets no input
+    input: Option<Box<dyn BrainfuckInput + 'a>>,
+    /// where `.` writes to
+    output: Box<dyn BrainfuckOutput + 'a>,
+    /// IR indices the program stops at before running them
+    breakpoints: BTreeSet<usize>,
+    /// the breakpoint [`Interpreter::resume`] runs past once instead of stopping at
+    resumed: Option<usize>,
+    /// the breakpoint the last run stopped at, if it did not go on since
+    paused: Option<usize>,
+    /// cells the program stops after changing, and on what change
+    watchpoints: BTreeMap<usize, WatchTrigger>,
+    /// what adding past 255 or below 0 does
+    overflow: OverflowMode,
+    /// whether `$` and `!` run rather than do nothing
+    #[cfg(feature = "debug_ops")]
+    debug_ops: bool,
+    /// whether `=` runs rather than does nothing
+    #[cfg(feature = "self_modifying")]
+    self_modifying: bool,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Compiles `src` into a new interpreter.
+    ///
+    /// With `std` the interpreter reads stdin and writes stdout,
+    /// otherwise input is empty and output is discarded until
+    /// [`Interpreter::input`] and [`Interpreter::output`] say otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut output = Vec::new();
+    /// Interpreter::new("++++++++[>++++++++<-]>+.").unwrap()
+    ///     .output(&mut output)
+    ///     .run()
+    ///     .unwrap();
+    /// assert_eq!(output, b"A");
+    /// ```
+    pub fn new(src: &str) -> Result<Interpreter<'a>, BrainfuckError> {
+        Ok(Interpreter::from_ir(ir::compile_source(src.as_bytes())?))
+    }
+
+    /// Runs `src` on `input` in memory and returns everything it printed.
+    ///
+    /// The easiest way to run a program without touching stdin or stdout.
+    /// Output that is not valid UTF-8 is converted lossily, with `U+FFFD`
+    /// standing in for invalid bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `src`   - brainfuck source
+    /// * `input` - text fed to `,`, end of input reads as 0
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// assert_eq!(Interpreter::run_string(",[.,]", "echo").unwrap(), "echo");
+    /// ```
+    pub fn run_string(src: &str, input: &str) -> Result<String, BrainfuckError> {
+        let mut output = Vec::new();
+        Interpreter::new(src)?.input(input.as_bytes()).output(&mut output).run()?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// Runs `src` to the end without any I/O and reports what it took.
+    ///
+    /// Output is dropped and every `,` reads 0. The program runs on the VM
+    /// a normal run of `rust_bf` uses, so the pointer may go left of cell 0
+    /// just as it may there. A program that never halts never returns, one
+    /// that fails returns the error it ran into.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let report = Interpreter::dry_run(",++++[>++<-]>.>>").unwrap();
+    /// assert_eq!(report.instructions_executed, 31);
+    /// assert_eq!((report.min_tape_ptr, report.max_tape_ptr), (0, 3));
+    /// // cells 0 and 1, the pointer only passes cells 2 and 3
+    /// assert_eq!(report.cells_touched, 2);
+    /// ```
+    pub fn dry_run(src: &str) -> Result<DryRunReport, BrainfuckError> {
+        Interpreter::dry_run_on(&mut vm::new_brainfuck_status(), src)
+    }
+
+    /// Dry-runs `src` like [`Interpreter::dry_run`] on `status`, with the
+    /// tape and the settings it was given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, TapeBounds};
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_right_only(TapeBounds::Error);
+    /// let err = Interpreter::dry_run_on(&mut status, "><<").unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::PointerUnderflow { position: 2 }));
+    /// ```
+    pub fn dry_run_on(status: &mut BrainfuckVMStatus, src: &str) -> Result<DryRunReport, BrainfuckError> {
+        status.set_eof_mode(EofMode::Zero);
+        status.record_accesses();
+        vm::run_program_on(status, src, &mut &[][..], &mut Discard)?;
+        let (min, max) = status.pointer_extent();
+        let written = status.accesses().expect("accesses are recorded").values().filter(|accesses| accesses.writes > 0).count();
+        // the pointer starts on cell 0, which the rightmost cell is never left of
+        Ok(DryRunReport { instructions_executed: status.ops_executed(), max_tape_ptr: max as usize, min_tape_ptr: min as i64, cells_touched: written })
+    }
+
+    /// Returns a new interpreter for already compiled IR.
+    pub fn from_ir(program: Vec<BrainfuckIR>) -> Interpreter<'a> {
+        #[cfg(feature = "std")]
+        let (input, output): (Box<dyn BrainfuckInput>, Box<dyn BrainfuckOutput>) =
+            (Box::new(std::io::stdin()), Box::new(std::io::stdout()));
+        #[cfg(not(feature = "std"))]
+        let (input, output): (Box<dyn BrainfuckInput>, Box<dyn BrainfuckOutput>) =
+            (Box::new(&[][..]), Box::new(Discard));
+
+        Interpreter {
+            program: program.into(),
+            ip: 0,
+            tape: CowTape::default(),
+            tape_ptr: 0,
+            memory_limit: usize::MAX,
+            wrap_tape: false,
+            steps: 0,
+            step_limit: u64::MAX,
+            eof_mode: EofMode::default(),
+            input: Some(input),
+            output,
+            breakpoints: BTreeSet::new(),
+            resumed: None,
+            paused: None,
+            watchpoints: BTreeMap::new(),
+            overflow: OverflowMode::Wrap,
+            #[cfg(feature = "debug_ops")]
+            debug_ops: false,
+            #[cfg(feature = "self_modifying")]
+            self_modifying: false,
+        }
+    }
+
+    /// Returns an interpreter that continues where `snapshot` was taken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut first = Vec::new();
+    /// let mut interpreter = Interpreter::new("+.+.+.").unwrap().output(&mut first);
+    /// for _ in 0..3 {
+    ///     interpreter.step().unwrap();
+    /// }
+    /// let snapshot = interpreter.snapshot();
+    /// drop(interpreter);
+    ///
+    /// let mut rest = Vec::new();
+    /// Interpreter::from_snapshot(snapshot).output(&mut rest).run().unwrap();
+    /// assert_eq!((first, rest), (vec![1], vec![2, 3]));
+    /// ```
+    pub fn from_snapshot(snapshot: Snapshot) -> Interpreter<'a> {
+        let mut interpreter = Interpreter::from_ir(snapshot.program);
+        interpreter.ip = snapshot.ip;
+        interpreter.tape = CowTape::from(snapshot.tape);
+        interpreter.tape_ptr = snapshot.tape_ptr;
+        interpreter.steps = snapshot.steps;
+        interpreter
+    }
+
+    /// Sets where `,` reads from.
+    pub fn input(mut self, input: impl BrainfuckInput + 'a) -> Self {
+        self.input = Some(Box::new(input));
+        self
+    }
+
+    /// Gives the program no input at all, so that running a `,` fails
+    /// with [`BrainfuckError::InputRequired`] instead of waiting on stdin.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let err = Interpreter::new("+>,").unwrap().without_input().run().unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::InputRequired { position: 2 }));
+    /// ```
+    pub fn without_input(mut self) -> Self {
+        self.input = None;
+        self
+    }
+
+    /// Sets where `.` writes to.
+    pub fn output(mut self, output: impl BrainfuckOutput + 'a) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Caps the memory the tape may allocate, in bytes.
+    ///
+    /// Moving the pointer past the allocated cells fails with
+    /// [`BrainfuckError::MemoryLimitExceeded`] once the tape would
+    /// need more than `bytes`. The default is unlimited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let src = ">".repeat(2000);
+    /// let err = Interpreter::new(&src).unwrap().with_memory_limit(1024).run().unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::MemoryLimitExceeded { requested: 2001, limit: 1024 }));
+    /// ```
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = bytes;
+        self
+    }
+
+    /// Makes the tape circular, `memory_limit` cells long.
+    ///
+    /// Moving right of the last cell lands on cell 0 and moving left of
+    /// cell 0 on the last cell, instead of failing. Without a memory limit
+    /// the tape has no last cell and this does nothing. Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// // `>` from the last of 3 cells lands on cell 0
+    /// let mut interpreter = Interpreter::new(">>>+<-").unwrap().with_memory_limit(3).with_wrap_tape(true);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape_pointer(), 2);
+    /// assert_eq!(interpreter.tape(), &[1, 0, 255]);
+    /// ```
+    pub fn with_wrap_tape(mut self, wrap: bool) -> Self {
+        self.wrap_tape = wrap;
+        self
+    }
+
+    /// Sets what `,` stores once the input is exhausted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    /// use rust_bf::io::EofMode;
+    ///
+    /// let mut interpreter = Interpreter::new("+++,").unwrap().input(&b""[..]).with_eof_mode(EofMode::Unchanged);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[3]);
+    /// ```
+    pub fn with_eof_mode(mut self, mode: EofMode) -> Self {
+        self.eof_mode = mode;
+        self
+    }
+
+    /// Starts the program on a tape holding `cells` from cell 0 on, the
+    /// pointer on cell 0, instead of on a blank tape.
+    ///
+    /// The cells are there even if they take more than the memory limit,
+    /// and [`Interpreter::reset`] clears them like any others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("[-->]").unwrap().with_tape(&[4, 6, 0, 8]);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[2, 4, 0, 8]);
+    /// ```
+    pub fn with_tape(mut self, cells: &[u8]) -> Self {
+        self.tape = CowTape::from(cells.to_vec());
+        self.tape_ptr = 0;
+        self
+    }
+
+    /// Caps the number of IR nodes the program may run.
+    ///
+    /// Once `steps` nodes have run, the next step fails with
+    /// [`BrainfuckError::StepLimitExceeded`], which keeps programs that
+    /// never halt from running forever. The default is unlimited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let err = Interpreter::new("+[]").unwrap().with_step_limit(1000).run().unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::StepLimitExceeded { limit: 1000 }));
+    /// ```
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.step_limit = steps;
+        self
+    }
+
+    /// Sets what adding to a cell past 255 or below 0 does, it wraps
+    /// around by default.
+    ///
+    /// `Add`, `MulAdd` and `AddRange` nodes fail or saturate on the sum they
+    /// add, like the `+` and `-` ops they were compiled from when those all
+    /// go the same way. A `MulAdd` fails before its loop counts down, and
+    /// `Clear` clears even a cell `[+]` could not take to 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::OverflowMode;
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let mut interpreter = Interpreter::new("-->+++").unwrap().with_overflow_mode(OverflowMode::Saturate);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[0, 3]);
+    ///
+    /// let err = Interpreter::new(">-").unwrap().with_overflow_mode(OverflowMode::Error).run().unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::CellOverflow { position: 1, cell: 1, value: -1 }));
+    /// ```
+    pub fn with_overflow_mode(mut self, mode: OverflowMode) -> Self {
+        self.overflow = mode;
+        self
+    }
+
+    /// Makes `$` print the current cell as a decimal number and `!` set
+    /// every cell to 0 and the pointer back to cell 0, they do nothing
+    /// otherwise. Off by default.
+    ///
+    /// Turning them on changes the language: a `$` or `!` in the comments
+    /// of a program written for plain brainfuck runs too. The number `$`
+    /// prints goes to the output even from [`Interpreter::run_until_output`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut output = Vec::new();
+    /// Interpreter::new("+++$!$").unwrap().output(&mut output).run().unwrap();
+    /// assert!(output.is_empty());
+    ///
+    /// let mut interpreter = Interpreter::new("++++++++++++$!$").unwrap().output(&mut output).with_debug_ops(true);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[0]);
+    /// drop(interpreter);
+    /// assert_eq!(output, b"120");
+    /// ```
+    #[cfg(feature = "debug_ops")]
+    pub fn with_debug_ops(mut self, enabled: bool) -> Self {
+        self.debug_ops = enabled;
+        self
+    }
+
+    /// Makes `=` write its own character, 61, into the current cell, it
+    /// does nothing otherwise. Off by default.
+    ///
+    /// Like [`Interpreter::with_debug_ops`] this changes the language, an
+    /// `=` in the comments of a plain brainfuck program runs too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("+ = 1").unwrap();
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[1]);
+    ///
+    /// let mut interpreter = Interpreter::new("+ = 1").unwrap().with_self_modifying(true);
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), &[61]);
+    /// ```
+    #[cfg(feature = "self_modifying")]
+    pub fn with_self_modifying(mut self, enabled: bool) -> Self {
+        self.self_modifying = enabled;
+        self
+    }
+
+    /// Makes sure the cell under the pointer is allocated.
+    fn ensure_cell(&mut self) -> Result<(), BrainfuckError> {
+        self.ensure_len(self.tape_ptr + 1)
+    }
+
+    /// Grows the tape to `requested` cells unless it already has them.
+    fn ensure_len(&mut self, requested: usize) -> Result<(), BrainfuckError> {
+        if requested <= self.tape.len() {
+            return Ok(());
+        }
+        if requested > self.memory_limit {
+            return Err(BrainfuckError::MemoryLimitExceeded { requested, limit: self.memory_limit });
+        }
+        self.tape.make_mut().resize(requested, 0);
+        Ok(())
+    }
+
+    /// Adds `n` to the allocated cell at `address`, past 255 or below 0 the
+    /// way the overflow mode says.
+    fn add_to_cell(&mut self, address: usize, n: i64) -> Result<(), BrainfuckError> {
+        let value = self.tape[address] as i64 + n;
+        self.tape.make_mut()[address] = match self.overflow {
+            OverflowMode::Wrap => value as u8,
+            OverflowMode::Saturate => value.clamp(0, 255) as u8,
+            OverflowMode::Error if (0..=255).contains(&value) => value as u8,
+            OverflowMode::Error => {
+                return Err(BrainfuckError::CellOverflow { position: self.ip, cell: address as i64, value });
+            },
+        };
+        Ok(())
+    }
+
+    /// Adds `n` to `len` cells starting at the current one, for `AddRange`.
+    ///
+    /// Past the memory limit it fails the way the `>` ops it was optimized
+    /// from would, after adding to the cells up to the limit.
+    fn add_range(&mut self, len: usize, n: i32) -> Result<(), BrainfuckError> {
+        let wraps = self.wrap_tape && self.memory_limit != usize::MAX && self.memory_limit > 0;
+        let end = self.tape_ptr + len;
+        if wraps && end > self.memory_limit {
+            // the range runs past the end of the circular tape, a cell at a time
+            self.ensure_len(self.memory_limit)?;
+            for i in 0..len {
+                self.add_to_cell((self.tape_ptr + i) % self.memory_limit, n as i64)?;
+            }
+            return Ok(());
+        }
+        let stop = end.min(self.memory_limit);
+        self.ensure_len(stop)?;
+        if self.overflow == OverflowMode::Wrap {
+            add_cells(&mut self.tape.make_mut()[self.tape_ptr..stop], n as u8);
+        } else {
+            for address in self.tape_ptr..stop {
+                self.add_to_cell(address, n as i64)?;
+            }
+        }
+        if stop < end {
+            return Err(BrainfuckError::MemoryLimitExceeded { requested: stop + 1, limit: self.memory_limit });
+        }
+        Ok(())
+    }
+
+    /// Moves the pointer `n` cells at a time until it is on a zero cell, for `Scan`.
+    ///
+    /// Single cells either way are searched for a word at a time, other
+    /// steps and circular tapes move a cell at a time, failing the way the
+    /// loop the scan was optimized from would.
+    fn scan(&mut self, n: i32) -> Result<(), BrainfuckError> {
+        let wraps = self.wrap_tape && self.memory_limit != usize::MAX && self.memory_limit > 0;
+        match n {
+            1 if !wraps => {
+                // cells past the end of the tape are zero
+                let found = find_zero(&self.tape[self.tape_ptr..]);
+                self.tape_ptr = found.map_or(self.tape.len(), |at| self.tape_ptr + at);
+                return self.ensure_cell();
+            },
+            -1 if !wraps => {
+                return match rfind_zero(&self.tape[..=self.tape_ptr]) {
+                    Some(at) => {
+                        self.tape_ptr = at;
+                        Ok(())
+                    },
+                    None => {
+                        self.tape_ptr = 0;
+                        Err(BrainfuckError::PointerUnderflow { position: self.ip })
+                    },
+                };
+            },
+            _ => (),
+        }
+        while self.tape[self.tape_ptr] != 0 {
+            self.tape_ptr = match self.cell_index(n) {
+                Some(moved) => moved,
+                None => return Err(BrainfuckError::PointerUnderflow { position: self.ip }),
+            };
+            self.ensure_cell()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the cell `n` cells right of the current one,
+    /// `None` if that is left of cell 0 on a tape that does not wrap.
+    fn cell_index(&self, n: i32) -> Option<usize> {
+        let moved = self.tape_ptr as isize + n as isize;
+        if self.wrap_tape && self.memory_limit != usize::MAX && self.memory_limit > 0 {
+            return Some(moved.rem_euclid(self.memory_limit as isize) as usize);
+        }
+        usize::try_from(moved).ok()
+    }
+
+    /// Returns the compiled program.
+    pub fn program(&self) -> &[BrainfuckIR] {
+        &self.program
+    }
+
+    /// Returns the index of the next IR node to run.
+    pub fn instruction_pointer(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the number of IR nodes run so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns the index of the current cell.
+    pub fn tape_pointer(&self) -> usize {
+        self.tape_ptr
+    }
+
+    /// Returns the cells allocated so far, cells past the end are zero.
+    pub fn tape(&self) -> &[u8] {
+        &self.tape
+    }
+
+    /// Captures the execution state, see [`Interpreter::from_snapshot`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            program: self.program.to_vec(),
+            ip: self.ip,
+            tape: self.tape.to_vec(),
+            tape_ptr: self.tape_ptr,
+            steps: self.steps,
+        }
+    }
+
+    /// Returns an independent copy of the interpreter in its current state.
+    ///
+    /// The fork has the same program, tape, pointers, limits, breakpoints,
+    /// watchpoints and step count, and goes its own way from there. The two share the program
+    /// and the tape until one of them writes a cell, so forking does not
+    /// copy the tape. I/O is not forked, the fork starts with the I/O of
+    /// [`Interpreter::from_ir`] until [`Interpreter::input`] and
+    /// [`Interpreter::output`] say otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("+>,").unwrap().input(&b"a"[..]);
+    /// interpreter.step().unwrap();
+    /// interpreter.step().unwrap();
+    /// // the fork reads something else
+    /// let mut fork = interpreter.fork().input(&b"b"[..]);
+    /// fork.run().unwrap();
+    /// interpreter.run().unwrap();
+    /// assert_eq!(interpreter.tape(), b"\x01a");
+    /// assert_eq!(fork.tape(), b"\x01b");
+    /// ```
+    pub fn fork(&self) -> Interpreter<'a> {
+        let mut fork = Interpreter::from_ir(Vec::new());
+        fork.program = Arc::clone(&self.program);
+        fork.ip = self.ip;
+        fork.tape = self.tape.clone();
+        fork.tape_ptr = self.tape_ptr;
+        fork.memory_limit = self.memory_limit;
+        fork.wrap_tape = self.wrap_tape;
+        fork.steps = self.steps;
+        fork.step_limit = self.step_limit;
+        fork.eof_mode = self.eof_mode;
+        fork.breakpoints = self.breakpoints.clone();
+        fork.watchpoints = self.watchpoints.clone();
+        fork.overflow = self.overflow;
+        #[cfg(feature = "debug_ops")]
+        {
+            fork.debug_ops = self.debug_ops;
+        }
+        #[cfg(feature = "self_modifying")]
+        {
+            fork.self_modifying = self.self_modifying;
+        }
+        fork
+    }
+
+    /// Puts the interpreter back at the start of its program with an empty
+    /// tape, so that it can run again.
+    ///
+    /// The instruction pointer, tape pointer and step count go back to 0
+    /// and every cell to zero, the tape keeps its allocation. Settings
+    /// survive: cells stay 8 bits wide, the memory and step limits, wrapping
+    /// and the end of input mode stay as they were set, and so do
+    /// breakpoints, watchpoints, input and output, the last two going on where the last
+    /// run left them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("+++>+").unwrap();
+    /// interpreter.run().unwrap();
+    /// interpreter.reset();
+    /// assert_eq!((interpreter.instruction_pointer(), interpreter.tape_pointer()), (0, 0));
+    /// assert!(interpreter.tape().is_empty());
+    /// ```
+    pub fn reset(&mut self) {
+        self.ip = 0;
+        self.tape.clear();
+        self.tape_ptr = 0;
+        self.steps = 0;
+        self.resumed = None;
+        self.paused = None;
+    }
+
+    /// Puts the program, instruction pointer, tape and step count of
+    /// `snapshot` into the interpreter, keeping its I/O, limits,
+    /// breakpoints and watchpoints.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.program = snapshot.program.into();
+        self.ip = snapshot.ip;
+        self.tape = CowTape::from(snapshot.tape);
+        self.tape_ptr = snapshot.tape_ptr;
+        self.steps = snapshot.steps;
+        self.resumed = None;
+    }
+
+    /// Stops the program before it runs the IR node at `position`, with
+    /// [`BrainfuckError::BreakpointHit`] reporting the state it stopped in.
+    ///
+    /// The program goes on from there with [`Interpreter::resume`], the
+    /// state of the interpreter can be changed first with
+    /// [`Interpreter::restore`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::{BrainfuckError, Interpreter};
+    ///
+    /// let mut output = Vec::new();
+    /// let mut interpreter = Interpreter::new("+++[>++<-]>.").unwrap().output(&mut output);
+    /// // the `.`, after the loop
+    /// interpreter.set_breakpoint(8);
+    /// let mut state = match interpreter.run() {
+    ///     Err(BrainfuckError::BreakpointHit { position: 8, vm_state }) => vm_state,
+    ///     result => panic!("{:?}", result),
+    /// };
+    /// assert_eq!(state.tape(), &[0, 6]);
+    /// state.tape_mut()[1] = b'A';
+    /// interpreter.restore(state);
+    /// interpreter.resume().unwrap();
+    /// drop(interpreter);
+    /// assert_eq!(output, b"A");
+    /// ```
+    pub fn set_breakpoint(&mut self, position: usize) {
+        self.breakpoints.insert(position);
+    }
+
+    /// Removes the breakpoint at `position`, if there is one.
+    pub fn clear_breakpoint(&mut self, position: usize) {
+        self.breakpoints.remove(&position);
+    }
+
+    /// Removes every breakpoint.
+    pub fn clear_all_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Runs the program on from the breakpoint or watchpoint it stopped at
+    /// until it halts, stops again or fails, like [`Interpreter::run`].
+    pub fn resume(&mut self) -> Result<(), BrainfuckError> {
+        self.resumed = self.paused.take();
+        self.run()
+    }
+
+    /// Stops the program right after an IR node changes the cell at
+    /// `cell_address` in the way `trigger` says, with
+    /// [`BrainfuckError::WatchpointTriggered`] telling the values before and
+    /// after and the node that changed it. A cell has one watchpoint, setting
+    /// another replaces it.
+    ///
+    /// The node has run when the program stops, it goes on with the next one
+    /// with [`Interpreter::resume`] or [`Interpreter::step`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::{BrainfuckError, Interpreter, WatchTrigger};
+    ///
+    /// // counts cell 1 up by 3 four times
+    /// let mut interpreter = Interpreter::new("++++[>+++<-]").unwrap();
+    /// interpreter.set_watchpoint(1, WatchTrigger::GreaterThan(7));
+    /// match interpreter.run() {
+    ///     Err(BrainfuckError::WatchpointTriggered { cell: 1, old_value: 6, new_value: 9, instruction_position: 3 }) => (),
+    ///     result => panic!("{:?}", result),
+    /// }
+    /// assert_eq!(interpreter.tape(), &[2, 9]);
+    /// assert!(matches!(interpreter.resume(), Err(BrainfuckError::WatchpointTriggered { new_value: 12, .. })));
+    /// interpreter.clear_watchpoint(1);
+    /// interpreter.resume().unwrap();
+    /// assert_eq!(interpreter.tape(), &[0, 12]);
+    /// ```
+    pub fn set_watchpoint(&mut self, cell_address: usize, trigger: WatchTrigger) {
+        self.watchpoints.insert(cell_address, trigger);
+    }
+
+    /// Removes the watchpoint on the cell at `cell_address`, if there is one.
+    pub fn clear_watchpoint(&mut self, cell_address: usize) {
+        self.watchpoints.remove(&cell_address);
+    }
+
+    /// Removes every watchpoint.
+    pub fn clear_all_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Returns the values of the watched cells, in address order.
+    fn watched_values(&self) -> Vec<u8> {
+        self.watchpoints.keys().map(|&cell| self.tape.get(cell).copied().unwrap_or(0)).collect()
+    }
+
+    /// Swaps in `program` and resets the interpreter, see [`Interpreter::reset`].
+    ///
+    /// `program` was compiled when it was parsed, and is shared rather than
+    /// copied, so running one program many times only parses it once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use rust_bf::{Interpreter, Program};
+    ///
+    /// let programs = [Program::try_from("+++.").unwrap(), Program::try_from("++.").unwrap()];
+    /// let mut output = Vec::new();
+    /// let mut interpreter = Interpreter::from_ir(Vec::new()).output(&mut output);
+    /// for program in &programs {
+    ///     interpreter.set_program(program.clone());
+    ///     interpreter.run().unwrap();
+    /// }
+    /// drop(interpreter);
+    /// assert_eq!(output, [3, 2]);
+    /// ```
+    pub fn set_program(&mut self, program: Program) {
+        self.program = program.shared_ir();
+        self.reset();
+    }
+
+    /// Returns whether the program has run to its end.
+    pub fn is_halted(&self) -> bool {
+        self.ip >= self.program.len()
+    }
+
+    /// Runs the program until it halts.
+    pub fn run(&mut self) -> Result<(), BrainfuckError> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    /// Runs a single IR node.
+    ///
+    /// Returns `false` without doing anything once the program has halted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("+>++").unwrap();
+    /// while interpreter.step().unwrap() {}
+    /// assert_eq!(interpreter.tape(), &[1, 2]);
+    /// ```
+    pub fn step(&mut self) -> Result<bool, BrainfuckError> {
+        self.step_into(None)
+    }
+
+    /// Runs up to `n` IR nodes, like calling [`Interpreter::step`] `n`
+    /// times, and returns how many ran, fewer than `n` once the program
+    /// halts.
+    ///
+    /// An error stops the batch, the nodes that ran before it stay run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new("+>++>+++").unwrap();
+    /// assert_eq!(interpreter.step_n(3).unwrap(), 3);
+    /// assert_eq!(interpreter.tape(), &[1, 2]);
+    /// assert_eq!(interpreter.step_n(100).unwrap(), 2);
+    /// assert_eq!(interpreter.step_n(100).unwrap(), 0);
+    /// ```
+    pub fn step_n(&mut self, n: u64) -> Result<u64, BrainfuckError> {
+        let mut ran = 0;
+        while ran < n && self.step_into(None)? {
+            ran += 1;
+        }
+        Ok(ran)
+    }
+
+    /// Runs the program until its next `.` and returns the byte it prints
+    /// instead of writing it to the output, `None` once the program halts.
+    ///
+    /// Calling it again after an error returns the error again, the
+    /// program does not get past it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new(",+.+.").unwrap().input(&b"A"[..]);
+    /// assert_eq!(interpreter.run_until_output().unwrap(), Some(b'B'));
+    /// assert_eq!(interpreter.run_until_output().unwrap(), Some(b'C'));
+    /// assert_eq!(interpreter.run_until_output().unwrap(), None);
+    /// ```
+    pub fn run_until_output(&mut self) -> Result<Option<u8>, BrainfuckError> {
+        let mut printed = None;
+        while printed.is_none() {
+            if !self.step_into(Some(&mut printed))? {
+                break;
+            }
+        }
+        Ok(printed)
+    }
+
+    /// Runs a single IR node like [`Interpreter::step`], storing the byte
+    /// a `.` prints in `printed` instead of writing it if given.
+    fn step_into(&mut self, printed: Option<&mut Option<u8>>) -> Result<bool, BrainfuckError> {
+        if self.is_halted() {
+            return Ok(false);
+        }
+        if !self.breakpoints.is_empty() && self.breakpoints.contains(&self.ip) && self.resumed.take() != Some(self.ip) {
+            self.paused = Some(self.ip);
+            return Err(BrainfuckError::BreakpointHit { position: self.ip, vm_state: self.snapshot() });
+        }
+        self.resumed = None;
+        self.paused = None;
+        if self.steps >= self.step_limit {
+            return Err(BrainfuckError::StepLimitExceeded { limit: self.step_limit });
+        }
+        self.steps += 1;
+        self.ensure_cell()?;
+        let position = self.ip;
+        // only looked at with watchpoints, this runs for every node
+        let watched = if self.watchpoints.is_empty() { Vec::new() } else { self.watched_values() };
+        match self.program[self.ip] {
+            BrainfuckIR::Add(n) => self.add_to_cell(self.tape_ptr, n as i64)?,
+            BrainfuckIR::Move(n) => {
+                self.tape_ptr = match self.cell_index(n) {
+                    Some(moved) => moved,
+                    None => return Err(BrainfuckError::PointerUnderflow { position: self.ip }),
+                };
+                self.ensure_cell()?;
+            },
+            BrainfuckIR::Print => match printed {
+                Some(printed) => *printed = Some(self.tape[self.tape_ptr]),
+                None => self.output.write_byte(self.tape[self.tape_ptr])?,
+            },
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite if self.self_modifying => self.tape.make_mut()[self.tape_ptr] = b'=',
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber if self.debug_ops => {
+                for digit in self.tape[self.tape_ptr].to_string().bytes() {
+                    self.output.write_byte(digit)?;
+                }
+            },
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::Reset if self.debug_ops => {
+                self.tape.clear();
+                self.tape_ptr = 0;
+            },
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber | BrainfuckIR::Reset => (),
+            BrainfuckIR::Read => {
+                let input = match &mut self.input {
+                    Some(input) => input.read_byte(),
+                    None => return Err(BrainfuckError::InputRequired { position: self.ip }),
+                };
+                let cell = &mut self.tape.make_mut()[self.tape_ptr];
+                *cell = match (input, self.eof_mode) {
+                    (Some(byte), _) => byte,
+                    (None, EofMode::Zero) => 0,
+                    (None, EofMode::MinusOne) => 255,
+                    (None, EofMode::Unchanged) => *cell,
+                    (None, EofMode::Error) => return Err(BrainfuckError::InputRequired { position: self.ip }),
+                };
+            },
+            BrainfuckIR::LoopStart(end) => {
+                if self.tape[self.tape_ptr] == 0 {
+                    self.ip = end;
+                }
+            },
+            BrainfuckIR::LoopEnd(start) => {
+                if self.tape[self.tape_ptr] != 0 {
+                    self.ip = start;
+                }
+            },
+            BrainfuckIR::Clear => self.tape.make_mut()[self.tape_ptr] = 0,
+            BrainfuckIR::MulAdd { offset, factor } => {
+                let value = self.tape[self.tape_ptr];
+                // the loop this came from never touches the target if the cell is zero
+                if value != 0 {
+                    let target = match self.cell_index(offset) {
+                        Some(target) => target,
+                        None => return Err(BrainfuckError::PointerUnderflow { position: self.ip }),
+                    };
+                    let current = self.tape_ptr;
+                    self.tape_ptr = target;
+                    let result = self.ensure_cell();
+                    self.tape_ptr = current;
+                    result?;
+                    self.add_to_cell(target, value as i64 * factor as i64)?;
+                }
+            },
+            BrainfuckIR::AddRange { len, n } => self.add_range(len, n)?,
+            BrainfuckIR::Scan(n) => self.scan(n)?,
+        }
+        self.ip += 1;
+        for ((&cell, trigger), old_value) in self.watchpoints.iter().zip(watched) {
+            let new_value = self.tape.get(cell).copied().unwrap_or(0);
+            if new_value != old_value && trigger.fires(new_value) {
+                return Err(BrainfuckError::WatchpointTriggered { cell, old_value, new_value, instruction_position: position });
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Yields the bytes the program prints one at a time, running it only as
+/// far as the next `.` on every call, see [`Interpreter::run_until_output`].
+///
+/// Nothing is written to the output of the interpreter.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::Interpreter;
+///
+/// let hello = Interpreter::new("++++++++[>+++++++++<-]>.+++++++++++++++++++++++++++++++++.").unwrap();
+/// assert_eq!(hello.collect::<Result<Vec<u8>, _>>().unwrap(), b"Hi");
+///
+/// // prints 1, 2, 3, ... forever
+/// let counting = Interpreter::new("+[.+]").unwrap();
+/// assert_eq!(counting.take(3).map(Result::unwrap).collect::<Vec<_>>(), [1, 2, 3]);
+/// ```
+impl Iterator for Interpreter<'_> {
+    type Item = Result<u8, BrainfuckError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.run_until_output().transpose()
+    }
+}
+
+/// bytes of the words [`find_zero`] and [`rfind_zero`] test at once
+const WORD: usize = core::mem::size_of::<u64>();
+
+/// Returns whether the word of cells `word` holds a zero byte.
+fn has_zero(word: &[u8]) -> bool {
+    let mut bytes = [0; WORD];
+    bytes.copy_from_slice(word);
+    let word = u64::from_ne_bytes(bytes);
+    word.wrapping_sub(0x0101_0101_0101_0101) & !word & 0x8080_8080_8080_8080 != 0
+}
+
+/// Returns the index of the first zero cell, skipping words without one.
+fn find_zero(cells: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    for word in cells.chunks_exact(WORD) {
+        if has_zero(word) {
+            break;
+        }
+        start += WORD;
+    }
+    cells[start..].iter().position(|&cell| cell == 0).map(|at| start + at)
+}
+
+/// Returns the index of the last zero cell, skipping words without one.
+fn rfind_zero(cells: &[u8]) -> Option<usize> {
+    let mut end = cells.len();
+    for word in cells.rchunks_exact(WORD) {
+        if has_zero(word) {
+            break;
+        }
+        end -= WORD;
+    }
+    cells[..end].iter().rposition(|&cell| cell == 0)
+}
+
+/// Adds `n` to every cell, 16 at a time with SSE2.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn add_cells(cells: &mut [u8], n: u8) {
+    use core::arch::x86_64::{__m128i, _mm_add_epi8, _mm_loadu_si128, _mm_set1_epi8, _mm_storeu_si128};
+
+    let mut chunks = cells.chunks_exact_mut(16);
+    // SAFETY: SSE2 is part of every x86_64 CPU, the loads and stores are
+    // unaligned and every chunk is exactly 16 bytes
+    unsafe {
+        let add = _mm_set1_epi8(n as i8);
+        for chunk in &mut chunks {
+            let ptr = chunk.as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(ptr, _mm_add_epi8(_mm_loadu_si128(ptr), add));
+        }
+    }
+    for cell in chunks.into_remainder() {
+        *cell = cell.wrapping_add(n);
+    }
+}
+
+/// Adds `n` to every cell.
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn add_cells(cells: &mut [u8], n: u8) {
+    for cell in cells {
+        *cell = cell.wrapping_add(n);
+    }
+}
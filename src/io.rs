@@ -0,0 +1,216 @@
+//! Byte I/O used by the VM, so that embedders can plug in their own source and sink.
+
+use crate::error::BrainfuckError;
+
+/// What `,` stores once the input is exhausted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EofMode {
+    /// store 0, the default
+    #[default]
+    Zero,
+    /// store -1, which is 255 in 8-bit cells
+    MinusOne,
+    /// leave the cell as it is
+    Unchanged,
+    /// fail with [`BrainfuckError::InputRequired`], for runs where nobody is there to type
+    Error,
+}
+
+/// Where `,` reads bytes from.
+pub trait BrainfuckInput {
+    /// Returns the next input byte, `None` at end of input.
+    ///
+    /// A 0 byte is `Some(0)` like any other, only `None` makes `,` store
+    /// what the [`EofMode`] says.
+    fn read_byte(&mut self) -> Option<u8>;
+}
+
+/// Where `.` writes bytes to.
+///
+/// A failed write stops the program with the returned error.
+///
+/// # Example
+///
+/// ```
+/// use std::io::{self, Write};
+/// use rust_bf::{BrainfuckError, Interpreter};
+///
+/// /// Sink that takes `room` bytes, then fails like a closed pipe.
+/// struct ClosedAfter { room: usize, written: Vec<u8> }
+///
+/// impl Write for ClosedAfter {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         if self.room == 0 {
+///             return Err(io::ErrorKind::BrokenPipe.into());
+///         }
+///         let n = buf.len().min(self.room);
+///         self.room -= n;
+///         self.written.extend_from_slice(&buf[..n]);
+///         Ok(n)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut sink = ClosedAfter { room: 3, written: Vec::new() };
+/// // prints forever unless the write error stops it
+/// let err = Interpreter::new("+[.]").unwrap().output(&mut sink).run().unwrap_err();
+/// assert!(matches!(err, BrainfuckError::Io(ref err) if err.kind() == io::ErrorKind::BrokenPipe));
+/// assert_eq!(sink.written, [1, 1, 1]);
+/// ```
+pub trait BrainfuckOutput {
+    /// Writes a single output byte.
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError>;
+}
+
+/// What [`LimitedOutput`] does with bytes past its limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputLimitAction {
+    /// stop the program with [`BrainfuckError::OutputLimitExceeded`], the default
+    #[default]
+    Error,
+    /// drop them and let the program run on
+    Truncate,
+}
+
+/// Output sink that passes at most `limit` bytes on to another one.
+///
+/// Keeps programs printing in an endless loop from filling the disk or
+/// the memory of a capture buffer.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::io::{LimitedOutput, OutputLimitAction};
+/// use rust_bf::{BrainfuckError, Interpreter};
+///
+/// // prints forever
+/// let mut output = Vec::new();
+/// let err = Interpreter::new("+[.]").unwrap()
+///     .output(LimitedOutput::new(&mut output, 5, OutputLimitAction::Error))
+///     .run()
+///     .unwrap_err();
+/// assert!(matches!(err, BrainfuckError::OutputLimitExceeded { limit: 5 }));
+/// assert_eq!(output, [1; 5]);
+///
+/// // prints 3, 2 and 1, then tests the last cell
+/// let mut output = LimitedOutput::new(Vec::new(), 1, OutputLimitAction::Truncate);
+/// let mut interpreter = Interpreter::new("+++[.-]>++").unwrap().output(&mut output);
+/// interpreter.run().unwrap();
+/// assert_eq!(interpreter.tape(), &[0, 2]);
+/// drop(interpreter);
+/// assert_eq!((output.written(), output.dropped()), (1, 2));
+/// assert_eq!(output.into_inner(), [3]);
+/// ```
+#[derive(Debug)]
+pub struct LimitedOutput<O> {
+    /// where bytes within the limit go
+    inner: O,
+    /// most bytes passed on
+    limit: usize,
+    /// what happens to bytes past the limit
+    action: OutputLimitAction,
+    /// bytes passed on so far
+    written: usize,
+    /// bytes dropped past the limit
+    dropped: usize,
+}
+
+impl<O> LimitedOutput<O> {
+    /// Returns a sink passing up to `limit` bytes on to `inner`.
+    pub fn new(inner: O, limit: usize, action: OutputLimitAction) -> LimitedOutput<O> {
+        LimitedOutput { inner, limit, action, written: 0, dropped: 0 }
+    }
+
+    /// Returns the number of bytes passed on.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Returns the number of bytes dropped with [`OutputLimitAction::Truncate`].
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Returns the sink bytes were passed on to.
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+}
+
+impl<O: BrainfuckOutput> BrainfuckOutput for LimitedOutput<O> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError> {
+        if self.written < self.limit {
+            self.inner.write_byte(byte)?;
+            self.written += 1;
+            return Ok(());
+        }
+        match self.action {
+            OutputLimitAction::Error => Err(BrainfuckError::OutputLimitExceeded { limit: self.limit }),
+            OutputLimitAction::Truncate => {
+                self.dropped += 1;
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Lets an interpreter borrow the sink, so that its counts can be read after the run.
+#[cfg(feature = "std")]
+impl<O: BrainfuckOutput> BrainfuckOutput for &mut LimitedOutput<O> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError> {
+        (**self).write_byte(byte)
+    }
+}
+
+/// Output sink that drops everything, used when no output is configured
+/// without `std` and by dry runs.
+pub(crate) struct Discard;
+
+impl BrainfuckOutput for Discard {
+    fn write_byte(&mut self, _byte: u8) -> Result<(), BrainfuckError> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> BrainfuckInput for R {
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut byte = [0u8];
+        // read errors end the input just like EOF does
+        self.read_exact(&mut byte).ok().map(|_| byte[0])
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> BrainfuckOutput for W {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError> {
+        self.write_all(&[byte]).map_err(BrainfuckError::Io)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl BrainfuckInput for &[u8] {
+    fn read_byte(&mut self) -> Option<u8> {
+        let (&byte, rest) = self.split_first()?;
+        *self = rest;
+        Some(byte)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl BrainfuckOutput for alloc::vec::Vec<u8> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError> {
+        self.push(byte);
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<O: BrainfuckOutput + ?Sized> BrainfuckOutput for &mut O {
+    fn write_byte(&mut self, byte: u8) -> Result<(), BrainfuckError> {
+        (**self).write_byte(byte)
+    }
+}
@@ -0,0 +1,222 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ast::{self, BrainfuckNode};
+use crate::error::BrainfuckError;
+use crate::op::BrainfuckOp;
+
+/// The compiled intermediate representation of a brainfuck program.
+///
+/// Runs of `+`/`-` and `>`/`<` are folded into a single node and
+/// loops are flattened into a pair of nodes that know each other's index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrainfuckIR {
+    /// add to the current cell, folded from `+` and `-`
+    Add(i32),
+    /// move the tape pointer, folded from `>` and `<`
+    Move(i32),
+    /// `.`
+    Print,
+    /// `,`
+    Read,
+    /// `[`, holds the index of the matching `LoopEnd`
+    LoopStart(usize),
+    /// `]`, holds the index of the matching `LoopStart`
+    LoopEnd(usize),
+    /// set the current cell to zero, optimized from `[-]` and `[+]`
+    Clear,
+    /// add the current cell times `factor` to the cell at `offset`,
+    /// optimized from multiplication loops like `[->++<]`
+    MulAdd {
+        /// distance of the target cell from the current cell
+        offset: i32,
+        /// how many times the current cell is added
+        factor: i32,
+    },
+    /// `=`, set the current cell to the character of the op itself where
+    /// the interpreter has self-modification on, compiled programs leave it out
+    #[cfg(feature = "self_modifying")]
+    SelfWrite,
+    /// `$`, print the current cell as a decimal number where the
+    /// interpreter has debugging ops on, compiled programs leave it out
+    #[cfg(feature = "debug_ops")]
+    PrintNumber,
+    /// `!`, set every cell to zero and the pointer back to cell 0 where
+    /// the interpreter has debugging ops on, compiled programs leave it out
+    #[cfg(feature = "debug_ops")]
+    Reset,
+    /// add `n` to `len` cells starting at the current one, leaving the
+    /// pointer where it is, optimized from runs like `+>+>+>+`
+    AddRange {
+        /// number of cells
+        len: usize,
+        /// what is added to each of them
+        n: i32,
+    },
+    /// move the tape pointer `n` cells at a time until it is on a zero
+    /// cell, optimized from scan loops like `[>]` and `[<]`
+    Scan(i32),
+}
+
+impl fmt::Display for BrainfuckIR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrainfuckIR::Add(n) => write!(f, "add {}", n),
+            BrainfuckIR::Move(n) => write!(f, "move {}", n),
+            BrainfuckIR::Print => write!(f, "print"),
+            BrainfuckIR::Read => write!(f, "read"),
+            BrainfuckIR::LoopStart(end) => write!(f, "loop_start {}", end),
+            BrainfuckIR::LoopEnd(start) => write!(f, "loop_end {}", start),
+            BrainfuckIR::Clear => write!(f, "clear"),
+            BrainfuckIR::MulAdd { offset, factor } => write!(f, "mul_add {} {}", offset, factor),
+            BrainfuckIR::AddRange { len, n } => write!(f, "add_range {} {}", len, n),
+            BrainfuckIR::Scan(n) => write!(f, "scan {}", n),
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite => write!(f, "self_write"),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::PrintNumber => write!(f, "print_number"),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::Reset => write!(f, "reset"),
+        }
+    }
+}
+
+/// Appends an `Add` or `Move` to `ir`, folding it into the previous node if possible.
+pub(crate) fn push_folded(ir: &mut Vec<BrainfuckIR>, node: BrainfuckIR) {
+    let folded = match (ir.last().copied(), node) {
+        (Some(BrainfuckIR::Add(a)), BrainfuckIR::Add(b)) => Some(BrainfuckIR::Add(a + b)),
+        (Some(BrainfuckIR::Move(a)), BrainfuckIR::Move(b)) => Some(BrainfuckIR::Move(a + b)),
+        _ => None,
+    };
+    match folded {
+        // `+-` and `><` cancel out completely
+        Some(BrainfuckIR::Add(0)) | Some(BrainfuckIR::Move(0)) => { ir.pop(); },
+        Some(folded) => *ir.last_mut().unwrap() = folded,
+        None => ir.push(node),
+    }
+}
+
+/// Lowers the op `op` of a syntax tree into `ir`.
+fn lower(op: BrainfuckOp, ir: &mut Vec<BrainfuckIR>) {
+    match op {
+        BrainfuckOp::IncrementValueOp => push_folded(ir, BrainfuckIR::Add(1)),
+        BrainfuckOp::DecrementValueOp => push_folded(ir, BrainfuckIR::Add(-1)),
+        BrainfuckOp::IncrementPtrOp => push_folded(ir, BrainfuckIR::Move(1)),
+        BrainfuckOp::DecrementPtrOp => push_folded(ir, BrainfuckIR::Move(-1)),
+        BrainfuckOp::PrintOp => ir.push(BrainfuckIR::Print),
+        BrainfuckOp::ReadOp => ir.push(BrainfuckIR::Read),
+        #[cfg(feature = "self_modifying")]
+        BrainfuckOp::SelfWriteOp => ir.push(BrainfuckIR::SelfWrite),
+        #[cfg(feature = "debug_ops")]
+        BrainfuckOp::PrintNumberOp => ir.push(BrainfuckIR::PrintNumber),
+        #[cfg(feature = "debug_ops")]
+        BrainfuckOp::ResetOp => ir.push(BrainfuckIR::Reset),
+        // loops are `Loop` nodes and comments never make it into the tree
+        _ => (),
+    }
+}
+
+/// Compiles a parsed program into IR.
+///
+/// Comments never make it into the syntax tree, so runs of ops are
+/// folded even when comment text is interspersed between them. The tree
+/// is walked without recursion, however deeply its loops nest.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::{compile, BrainfuckIR};
+///
+/// let ir = compile(&parse("+++[->>+<<]").unwrap());
+/// assert_eq!(ir, vec![
+///     BrainfuckIR::Add(3),
+///     BrainfuckIR::LoopStart(6),
+///     BrainfuckIR::Add(-1),
+///     BrainfuckIR::Move(2),
+///     BrainfuckIR::Add(1),
+///     BrainfuckIR::Move(-2),
+///     BrainfuckIR::LoopEnd(1),
+/// ]);
+///
+/// let ir = compile(&parse("> one + > two\n + > three +").unwrap());
+/// assert_eq!(ir, compile(&parse(">+>+>+").unwrap()));
+///
+/// let ir = compile(&parse("+ a + b + c > d > e").unwrap());
+/// assert_eq!(ir, vec![BrainfuckIR::Add(3), BrainfuckIR::Move(2)]);
+/// ```
+pub fn compile(ast: &[BrainfuckNode]) -> Vec<BrainfuckIR> {
+    let mut ir = Vec::new();
+    // the nodes left of every body being lowered, with the index of the
+    // `LoopStart` of its loop, `None` for the program itself
+    let mut bodies = vec![(ast.iter(), None)];
+    while let Some((nodes, start)) = bodies.last_mut() {
+        match nodes.next() {
+            Some(BrainfuckNode::Op(op, _)) => lower(*op, &mut ir),
+            Some(BrainfuckNode::Loop { body, .. }) => {
+                // the index of the `LoopEnd` is only known after the body is lowered
+                bodies.push((body.iter(), Some(ir.len())));
+                ir.push(BrainfuckIR::LoopStart(0));
+            },
+            None => {
+                if let Some(start) = *start {
+                    ir[start] = BrainfuckIR::LoopStart(ir.len());
+                    ir.push(BrainfuckIR::LoopEnd(start));
+                }
+                bodies.pop();
+            },
+        }
+    }
+    ir
+}
+
+/// Compiles brainfuck source straight into IR, the same IR as
+/// [`compile`] makes of the syntax tree [`ast::parse`] builds, without
+/// building the tree.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::{compile, compile_source};
+///
+/// let src = "+++[->>+<<] comments are skipped";
+/// assert_eq!(compile_source(src.as_bytes()).unwrap(), compile(&parse(src).unwrap()));
+/// assert_eq!(compile_source(b"[+]]").unwrap_err().to_string(), "unmatched ']' at line 1, column 4");
+/// ```
+pub fn compile_source(src: &[u8]) -> Result<Vec<BrainfuckIR>, BrainfuckError> {
+    let mut ir = Vec::new();
+    // index and position of every `[` still waiting for its `]`
+    let mut loops = Vec::new();
+    for (op, position) in ast::tokens(src) {
+        match op {
+            BrainfuckOp::IncrementValueOp => push_folded(&mut ir, BrainfuckIR::Add(1)),
+            BrainfuckOp::DecrementValueOp => push_folded(&mut ir, BrainfuckIR::Add(-1)),
+            BrainfuckOp::IncrementPtrOp => push_folded(&mut ir, BrainfuckIR::Move(1)),
+            BrainfuckOp::DecrementPtrOp => push_folded(&mut ir, BrainfuckIR::Move(-1)),
+            BrainfuckOp::PrintOp => ir.push(BrainfuckIR::Print),
+            BrainfuckOp::ReadOp => ir.push(BrainfuckIR::Read),
+            #[cfg(feature = "self_modifying")]
+            BrainfuckOp::SelfWriteOp => ir.push(BrainfuckIR::SelfWrite),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::PrintNumberOp => ir.push(BrainfuckIR::PrintNumber),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::ResetOp => ir.push(BrainfuckIR::Reset),
+            BrainfuckOp::LoopStartOp => {
+                loops.push((ir.len(), position));
+                ir.push(BrainfuckIR::LoopStart(0));
+            },
+            BrainfuckOp::LoopEndOp => {
+                let (start, _) = loops.pop().ok_or(BrainfuckError::UnmatchedClose(position))?;
+                ir[start] = BrainfuckIR::LoopStart(ir.len());
+                ir.push(BrainfuckIR::LoopEnd(start));
+            },
+            BrainfuckOp::MonoStateOp => (),
+        }
+    }
+    match loops.pop() {
+        Some((_, start)) => Err(BrainfuckError::UnmatchedOpen(start)),
+        None => Ok(ir),
+    }
+}
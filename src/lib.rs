@@ -0,0 +1,69 @@
+//! Brainfuck Interpreter in Rust——Yet Another Way to Kill Your Brain
+//!
+//! The interpreter only needs `alloc`, build without the default `std`
+//! feature for `no_std` targets and plug in your own I/O through
+//! [`io::BrainfuckInput`] and [`io::BrainfuckOutput`].
+//!
+//! [`Interpreter::run_string`] runs a program on a string and returns what it printed:
+//!
+//! ```
+//! let output = rust_bf::Interpreter::run_string("++++++++[>++++++++<-]>+.", "").unwrap();
+//! assert_eq!(output, "A");
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod ast;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "braincopter")]
+pub mod braincopter;
+pub mod check;
+pub mod codegen;
+pub mod compress;
+pub mod coverage;
+#[cfg(feature = "docgen")]
+pub mod docgen;
+pub mod dot;
+#[cfg(feature = "base64")]
+pub mod encoding;
+mod error;
+pub mod examples;
+pub mod explain;
+pub mod extent;
+pub mod format;
+pub mod frontend;
+#[cfg(feature = "egui")]
+pub mod gui;
+pub mod histogram;
+mod interpreter;
+pub mod io;
+pub mod ir;
+pub mod minify;
+pub mod multitape;
+mod op;
+pub mod optimizer;
+pub mod packed;
+pub mod preprocess;
+mod program;
+pub mod quine;
+#[cfg(feature = "std")]
+pub mod repl;
+pub mod selftest;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sourcemap;
+#[cfg(feature = "std")]
+pub mod tcp;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod validate;
+pub mod verify;
+pub mod vm;
+
+pub use error::{format_error, BrainfuckError};
+pub use interpreter::{DryRunReport, Interpreter, Snapshot, WatchTrigger};
+pub use op::BrainfuckOp;
+pub use program::Program;
@@ -1,233 +1,1407 @@
-use std::io::{self, BufReader, BufRead, Read};
-use std::collections::HashMap;
-use std::vec::Vec;
-
-/// The `BrainfuckOp` type.
-enum BrainfuckOp {
-    IncrementValueOp, /// +
-    DecrementValueOp, /// -
-    IncrementPtrOp,   /// >
-    DecrementPtrOp,   /// <
-    PrintOp,          /// .
-    ReadOp,           /// ,
-    LoopStartOp,      /// [
-    LoopEndOp,        /// ]
-    MonoStateOp
-}
-
-/// Brainfuck virtual machine status
-struct BrainfuckVMStatus {
-    /// virtual infinity length tape
-    tape: HashMap<i32, i32>,
-    /// current cell of the tape
-    tape_ptr: i32,
-    /// used for keeping track of all valid brainfuck_op
-    instruction: Vec<char>,
-    /// current brainfuck_op index
-    instruction_ptr_current: i32,
-    /// keeping track of loops
-    instruction_loop_ptr: Vec<i32>,
-    
-    /// flag of skipping loop, e.g
-    /// +-[[[------------++++++++++-.>>[>]>>>--<<<<<<--]]]++++
-    ///   ^skipping from, but we need all                ^end of skipping
-    ///      instructions inside.
-    jump_loop: i32
-}
-
-/// Returns a new brainfuck VM status.
-///
-/// # Example
-///
-/// ```
-/// let status = new_brainfuck_status();
-/// ```
-fn new_brainfuck_status() -> BrainfuckVMStatus {
-    BrainfuckVMStatus {
-        tape: HashMap::new(),
-        tape_ptr: 0,
-        instruction: Vec::new(),
-        instruction_ptr_current: -1,
-        instruction_loop_ptr: Vec::new(),
-        jump_loop: 0
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::{self, BufReader, BufRead, LineWriter, Read, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use rust_bf::{ast, batch, check, compress, dot, examples, explain, format_error, frontend, ir, optimizer, packed, preprocess, repl, selftest, verify, BrainfuckError, Interpreter};
+use rust_bf::format::{format_program, FormatOptions};
+use rust_bf::frontend::Frontend;
+use rust_bf::io::{BrainfuckInput, BrainfuckOutput, EofMode, LimitedOutput, OutputLimitAction};
+use rust_bf::minify::minify_program;
+use rust_bf::tcp::BrainfuckServer;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, BrainfuckVMStatus, CellAccesses, OverflowMode, TapeBounds, TraceEvent};
+
+/// Command line options
+struct Options {
+    /// subcommand, `None` runs the program
+    command: Option<String>,
+    /// program file, the program is read from stdin if not given
+    file: Option<String>,
+    /// program source given with `-e` instead of a file
+    inline: Option<String>,
+    /// take the program, from `-e`, a file or stdin, as URL-safe base64 of its source
+    decode_b64: bool,
+    /// minify the program before `encode` encodes it
+    minify: bool,
+    /// emit the program in this format instead of running it
+    emit: Option<String>,
+    /// where to write emitted output, stdout if not given
+    output: Option<String>,
+    /// layout settings for `fmt`
+    format: FormatOptions,
+    /// let `minify` cancel no-op pairs and leading comment loops
+    aggressive: bool,
+    /// try harder for a shorter program in `generate`
+    optimize_size: bool,
+    /// make `check` fail when there are warnings
+    deny_warnings: bool,
+    /// print the compiled IR instead of running the program
+    print_ir: bool,
+    /// print the optimized IR next to it
+    optimize: bool,
+    /// report the run time and ops executed to stderr
+    time: bool,
+    /// report how many cells hold each value to stderr once the program ends
+    cell_values: bool,
+    /// print how far the pointer got and what tape the program needs after it ran
+    memory_report: bool,
+    /// compare the naive and the optimized run instead of running the program
+    verify: bool,
+    /// run without I/O and report the cost instead of running the program
+    dry_run: bool,
+    /// run the conformance checks instead of a program
+    self_test: bool,
+    /// run random programs to look for interpreter panics instead of a program
+    fuzz: bool,
+    /// seed of the first random program
+    seed: u64,
+    /// how many random programs to run
+    count: u64,
+    /// ops in every random program
+    length: usize,
+    /// run lines typed at a prompt instead of a program
+    repl: bool,
+    /// fetch the program from this URL instead of a file
+    url: Option<String>,
+    /// take PROGRAM as the base64-encoded source instead of a file name
+    base64: bool,
+    /// dialect the program is written in, `None` for brainfuck
+    lang: Option<&'static dyn Frontend>,
+    /// expand macros before parsing, also done for `.bfm` files
+    pp: bool,
+    /// splice in `#include` lines without expanding macros, `--pp` does both
+    allow_include: bool,
+    /// run the embedded example of this name instead of a file
+    example: Option<String>,
+    /// list the embedded examples instead of running a program
+    list_examples: bool,
+    /// how long to wait for the URL to answer
+    timeout_secs: u64,
+    /// keep fetched programs in the temp directory and reuse them
+    cache: bool,
+    /// write how often each cell was read and written to this file
+    heatmap: Option<String>,
+    /// write the heatmap as CSV instead of a bar chart
+    heatmap_csv: bool,
+    /// write an HTML page showing which ops ran to this file
+    cover: Option<String>,
+    /// write every op run as a line of JSON to this file
+    trace_json: Option<String>,
+    /// write every op run as a line of text to this file
+    trace: Option<String>,
+    /// only trace every this many ops
+    trace_sample: u64,
+    /// what the first Ctrl-C does: `dump`, `save` or `abort`, `None` leaves it to the system
+    on_interrupt: Option<String>,
+    /// where `--on-interrupt=save` writes the tape
+    save_state: Option<String>,
+    /// times in a row a loop may go round with its cell unchanged before the run stops
+    hang_threshold: Option<u64>,
+    /// whether `$` prints the cell as a number and `!` resets the tape
+    debug_ops: bool,
+    /// whether `=` writes its own character into the cell
+    self_modifying: bool,
+    /// fail at the first `,` instead of reading stdin
+    no_stdin: bool,
+    /// most bytes the program may print
+    max_output: Option<usize>,
+    /// what happens to bytes past `max_output`
+    max_output_action: OutputLimitAction,
+    /// most distinct cells the tape may track
+    max_tape: Option<usize>,
+    /// cells of a fixed-size tape, `None` for a tape without ends
+    tape_size: Option<usize>,
+    /// what moving off the ends of the fixed-size tape does, or off cell 0
+    /// of a right-only one
+    tape_bounds: Option<TapeBounds>,
+    /// whether `--tape=right-only` took away the cells left of cell 0
+    right_only: bool,
+    /// what `+` past the largest value of a cell and `-` below 0 do
+    overflow: OverflowMode,
+    /// threads `batch` runs entries on, or `serve` answers requests on
+    jobs: Option<usize>,
+    /// port `serve` listens on
+    port: u16,
+    /// let `serve` answer the plain TCP protocol instead of HTTP
+    tcp: bool,
+    /// most steps a program run by `serve` may take
+    max_steps: Option<u64>,
+}
+
+/// times in a row a loop may go round with its cell unchanged under `--detect-hangs`
+const DEFAULT_HANG_THRESHOLD: u64 = 100_000;
+
+/// Prints usage and exits.
+fn usage() -> ! {
+    eprintln!("usage: rust_bf [--time] [--cell-values] [--memory-report] [--heatmap FILE [--heatmap-format=text|csv]] [PROGRAM]");
+    eprintln!("       rust_bf [--trace FILE] [--trace-json FILE] [--trace-sample N] [--cover FILE] [PROGRAM]");
+    eprintln!("       rust_bf [--on-interrupt=dump|abort | --on-interrupt=save --save-state FILE] [PROGRAM]");
+    eprintln!("       rust_bf [--detect-hangs [--hang-threshold N]] [--debug-ops] [--self-modifying] [PROGRAM]");
+    eprintln!("       rust_bf [--no-stdin] [--max-output N [--max-output-action=error|truncate]] [--max-tape N] PROGRAM");
+    eprintln!("       rust_bf --tape-size N [--tape-bounds=wrap|error|saturate] [PROGRAM]");
+    eprintln!("       rust_bf [--tape=both | --tape=right-only [--tape-bounds=error|saturate]] [PROGRAM]");
+    eprintln!("       rust_bf [--overflow=wrap|error|saturate] [PROGRAM]");
+    eprintln!("       rust_bf [--time] [--heatmap FILE] --url URL [--timeout-secs N] [--cache]");
+    eprintln!("       rust_bf [--time] [--no-stdin] --base64 BASE64");
+    eprintln!("       rust_bf [--decode-b64] [-e SOURCE | PROGRAM]");
+    eprintln!("       rust_bf [--pp | --allow-include] PROGRAM.bfm");
+    eprintln!("       rust_bf --lang brainfuck|ook|spoon [PROGRAM]");
+    eprintln!("       rust_bf --list-examples | --example NAME");
+    eprintln!("       rust_bf [--no-stdin] [--max-tape N] --repl");
+    eprintln!("       rust_bf --emit dot|llvm|html|bfc|bfc-runs [-o FILE] [PROGRAM]");
+    eprintln!("       rust_bf --print-ir [--optimize] [PROGRAM]");
+    eprintln!("       rust_bf --verify PROGRAM|DIRECTORY");
+    eprintln!("       rust_bf --dry-run [PROGRAM]");
+    eprintln!("       rust_bf [--no-stdin] [--max-tape N] --self-test");
+    eprintln!("       rust_bf --fuzz [--seed S] [--count N] [--length N] [--max-steps N]");
+    eprintln!("       rust_bf fmt [--width N] [--indent N] [--strip-comments] [-o FILE] [PROGRAM]");
+    eprintln!("       rust_bf minify [--aggressive] [-o FILE] [PROGRAM]");
+    eprintln!("       rust_bf check [--deny-warnings] [PROGRAM]");
+    eprintln!("       rust_bf explain [-o FILE] [PROGRAM]");
+    eprintln!("       rust_bf generate [--optimize-size] [-o FILE] [TEXT]");
+    eprintln!("       rust_bf encode [--minify] [-o FILE] [-e SOURCE | PROGRAM]");
+    eprintln!("       rust_bf batch [--jobs N] MANIFEST");
+    eprintln!("       rust_bf serve [--port N] [--jobs N] [--max-steps N] [--max-output N]");
+    eprintln!("       rust_bf serve --tcp [--port N] [--max-steps N] [--max-output N]");
+    process::exit(2);
+}
+
+/// Parses command line options from `args`.
+fn parse_options(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        command: None,
+        file: None,
+        inline: None,
+        decode_b64: false,
+        minify: false,
+        emit: None,
+        output: None,
+        format: FormatOptions::default(),
+        aggressive: false,
+        optimize_size: false,
+        deny_warnings: false,
+        print_ir: false,
+        optimize: false,
+        time: false,
+        cell_values: false,
+        memory_report: false,
+        verify: false,
+        dry_run: false,
+        self_test: false,
+        fuzz: false,
+        seed: 0,
+        count: 1000,
+        length: 64,
+        repl: false,
+        url: None,
+        base64: false,
+        lang: None,
+        pp: false,
+        allow_include: false,
+        example: None,
+        list_examples: false,
+        timeout_secs: 30,
+        cache: false,
+        heatmap: None,
+        heatmap_csv: false,
+        cover: None,
+        trace_json: None,
+        trace: None,
+        trace_sample: 1,
+        on_interrupt: None,
+        hang_threshold: None,
+        debug_ops: false,
+        self_modifying: false,
+        save_state: None,
+        no_stdin: false,
+        max_output: None,
+        max_output_action: OutputLimitAction::Error,
+        max_tape: None,
+        tape_size: None,
+        tape_bounds: None,
+        right_only: false,
+        overflow: OverflowMode::Wrap,
+        jobs: None,
+        port: 8080,
+        tcp: false,
+        max_steps: None,
+    };
+    let mut args = args.peekable();
+    if let Some(command) = args.next_if(|arg| matches!(arg.as_str(), "fmt" | "minify" | "check" | "batch" | "serve" | "generate" | "encode" | "explain")) {
+        options.command = Some(command);
     }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--emit" => options.emit = Some(args.next().unwrap_or_else(|| usage())),
+            "-o" => options.output = Some(args.next().unwrap_or_else(|| usage())),
+            "-e" => options.inline = Some(args.next().unwrap_or_else(|| usage())),
+            "--decode-b64" => options.decode_b64 = true,
+            "--minify" => options.minify = true,
+            "--width" => options.format.width = parse_number(args.next()),
+            "--indent" => options.format.indent = parse_number(args.next()),
+            "--strip-comments" => options.format.strip_comments = true,
+            "--aggressive" => options.aggressive = true,
+            "--optimize-size" => options.optimize_size = true,
+            "--deny-warnings" => options.deny_warnings = true,
+            "--print-ir" => options.print_ir = true,
+            "--optimize" => options.optimize = true,
+            "--time" => options.time = true,
+            "--cell-values" => options.cell_values = true,
+            "--memory-report" => options.memory_report = true,
+            "--verify" => options.verify = true,
+            "--dry-run" => options.dry_run = true,
+            "--self-test" => options.self_test = true,
+            "--fuzz" => options.fuzz = true,
+            "--seed" => options.seed = parse_number(args.next()) as u64,
+            "--count" => options.count = parse_number(args.next()) as u64,
+            "--length" => options.length = parse_number(args.next()),
+            "--repl" => options.repl = true,
+            "--url" => options.url = Some(args.next().unwrap_or_else(|| usage())),
+            "--timeout-secs" => options.timeout_secs = parse_number(args.next()) as u64,
+            "--cache" => options.cache = true,
+            "--base64" => options.base64 = true,
+            "--pp" => options.pp = true,
+            "--allow-include" => options.allow_include = true,
+            "--lang" => options.lang = Some(args.next().and_then(|name| frontend::by_name(&name)).unwrap_or_else(|| usage())),
+            "--example" => options.example = Some(args.next().unwrap_or_else(|| usage())),
+            "--list-examples" => options.list_examples = true,
+            "--heatmap" => options.heatmap = Some(args.next().unwrap_or_else(|| usage())),
+            "--heatmap-format=text" => options.heatmap_csv = false,
+            "--heatmap-format=csv" => options.heatmap_csv = true,
+            "--cover" => options.cover = Some(args.next().unwrap_or_else(|| usage())),
+            "--trace-json" => options.trace_json = Some(args.next().unwrap_or_else(|| usage())),
+            "--trace" => options.trace = Some(args.next().unwrap_or_else(|| usage())),
+            "--trace-sample" => options.trace_sample = parse_number(args.next()).max(1) as u64,
+            "--on-interrupt=dump" | "--on-interrupt=save" | "--on-interrupt=abort" => {
+                options.on_interrupt = Some(arg["--on-interrupt=".len()..].to_string())
+            },
+            "--save-state" => options.save_state = Some(args.next().unwrap_or_else(|| usage())),
+            "--detect-hangs" => options.hang_threshold = options.hang_threshold.or(Some(DEFAULT_HANG_THRESHOLD)),
+            "--hang-threshold" => options.hang_threshold = Some(parse_number(args.next()) as u64),
+            "--debug-ops" => options.debug_ops = true,
+            "--self-modifying" => options.self_modifying = true,
+            "--no-stdin" => options.no_stdin = true,
+            "--max-output" => options.max_output = Some(parse_number(args.next())),
+            "--max-output-action=error" => options.max_output_action = OutputLimitAction::Error,
+            "--max-output-action=truncate" => options.max_output_action = OutputLimitAction::Truncate,
+            "--max-tape" => options.max_tape = Some(parse_number(args.next())),
+            "--tape-size" => options.tape_size = Some(parse_number(args.next()).clamp(1, i32::MAX as usize)),
+            "--tape-bounds=wrap" => options.tape_bounds = Some(TapeBounds::Wrap),
+            "--tape-bounds=error" => options.tape_bounds = Some(TapeBounds::Error),
+            "--tape-bounds=saturate" => options.tape_bounds = Some(TapeBounds::Saturate),
+            "--tape=both" => options.right_only = false,
+            "--tape=right-only" => options.right_only = true,
+            "--overflow=wrap" => options.overflow = OverflowMode::Wrap,
+            "--overflow=error" => options.overflow = OverflowMode::Error,
+            "--overflow=saturate" => options.overflow = OverflowMode::Saturate,
+            "--jobs" => options.jobs = Some(parse_number(args.next()).max(1)),
+            "--port" => options.port = u16::try_from(parse_number(args.next())).unwrap_or_else(|_| usage()),
+            "--tcp" => options.tcp = true,
+            "--max-steps" => options.max_steps = Some(parse_number(args.next()) as u64),
+            "-h" | "--help" => usage(),
+            _ if options.file.is_none() && options.inline.is_none() && !arg.starts_with('-') => options.file = Some(arg),
+            _ => usage(),
+        }
+    }
+    if options.tape_bounds.is_some() && options.tape_size.is_none() && !options.right_only {
+        usage();
+    }
+    if options.right_only && (options.tape_size.is_some() || options.tape_bounds == Some(TapeBounds::Wrap)) {
+        usage();
+    }
+    if options.on_interrupt.as_deref() == Some("save") && options.save_state.is_none() {
+        usage();
+    }
+    options
 }
 
-/// Returns next corresponding BrainfuckOp of given `character`.
-///
-/// # Arguments
-///
-/// * `status`    - A mutable var that holds the status of current brainfuck VM status
-/// * `character` - char type op (may be invalid brainfuck op)
-/// * `via_loop`  - Due to the way I wrote, a flag is needed to avoid re-adding ops 
+/// Parses the value of a numeric option.
+fn parse_number(value: Option<String>) -> usize {
+    value.and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
+}
+
+/// Fetches a program over HTTP, from the cache if `options.cache` is set and it was fetched before.
+#[cfg(feature = "network")]
+fn fetch(url: &str, options: &Options) -> Result<String, String> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::time::Duration;
+
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    let cached = env::temp_dir().join(format!("rust_bf-{:016x}.bf", hasher.finish()));
+    if options.cache {
+        if let Ok(src) = fs::read_to_string(&cached) {
+            return Ok(src);
+        }
+    }
+
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(options.timeout_secs)).build();
+    let response = match agent.get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) => {
+            return Err(format!("{} answered {} {}", url, status, response.status_text()));
+        },
+        Err(err) => return Err(err.to_string()),
+    };
+    if response.status() != 200 {
+        return Err(format!("{} answered {} {}", url, response.status(), response.status_text()));
+    }
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body).map_err(|err| format!("cannot read {}: {}", url, err))?;
+    let src = String::from_utf8(body).map_err(|_| format!("{} is not valid UTF-8", url))?;
+
+    if options.cache {
+        // a failed cache write only means fetching again next time
+        let _ = fs::write(&cached, &src);
+    }
+    Ok(src)
+}
+
+/// Returns whether macros in the program are expanded, with `--pp` or for `.bfm` files.
+fn preprocessed(options: &Options) -> bool {
+    options.pp || options.file.as_deref().is_some_and(|file| file.ends_with(".bfm"))
+}
+
+/// Reads the whole program like [`read_plain_source`], with the files it
+/// includes spliced in with `--allow-include` or if [`preprocessed`], and
+/// its macros expanded if [`preprocessed`] says so.
 ///
-/// # Example
+/// Errors in the includes and macros, and brackets of the expanded program
+/// that do not match, are reported at their place in the file they are in.
+fn read_source(options: &Options) -> io::Result<String> {
+    let mut src = read_plain_source(options)?;
+    if let Some(lang) = options.lang {
+        let ops = lang.parse(src.as_bytes()).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        src = frontend::to_source(&ops);
+    }
+    let pp = preprocessed(options);
+    if !pp && !options.allow_include {
+        return Ok(src);
+    }
+    let main = match &options.file {
+        Some(file) if !options.base64 => file.clone(),
+        _ => String::from("<program>"),
+    };
+    let included = preprocess::include(&main, &src, preprocess::load_relative).unwrap_or_else(|err| {
+        eprintln!("{}: error: {}", location(&main, &err.file, err.position), err.message);
+        process::exit(1);
+    });
+    if !pp {
+        if let Err(err) = ast::parse(&included.source) {
+            include_error(&main, &included, err);
+        }
+        return Ok(included.source);
+    }
+    let expanded = preprocess::preprocess(&included.source).unwrap_or_else(|err| {
+        let origin = included.origin(err.position.offset);
+        eprintln!("{}: error: {}", location(&main, origin.file, origin.position), err.message);
+        process::exit(1);
+    });
+    if let Err(err) = ast::parse(&expanded.source) {
+        include_error(&main, &included, expanded.map_error(err));
+    }
+    Ok(expanded.source)
+}
+
+/// A line read at the prompt of [`repl`].
+enum Prompted {
+    /// a line to run
+    Line(String),
+    /// Ctrl-C dropped the line being typed, only caught with the `readline` feature
+    #[cfg_attr(not(feature = "readline"), allow(dead_code))]
+    Cancelled,
+    /// Ctrl-D or the end of stdin
+    End,
+}
+
+/// Reads lines with arrow-key editing, and the history of earlier sessions a line up.
+#[cfg(feature = "readline")]
+struct Prompt(rustyline::DefaultEditor);
+
+#[cfg(feature = "readline")]
+impl Prompt {
+    fn new(history: &repl::BrainfuckREPLHistory) -> io::Result<Prompt> {
+        let mut editor = rustyline::DefaultEditor::new().map_err(io::Error::other)?;
+        for entry in history.entries() {
+            editor.add_history_entry(entry.as_str()).map_err(io::Error::other)?;
+        }
+        Ok(Prompt(editor))
+    }
+
+    fn read(&mut self) -> io::Result<Prompted> {
+        match self.0.readline("bf> ") {
+            Ok(line) => Ok(Prompted::Line(line)),
+            Err(rustyline::error::ReadlineError::Interrupted) => Ok(Prompted::Cancelled),
+            Err(rustyline::error::ReadlineError::Eof) => Ok(Prompted::End),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    fn add(&mut self, line: &str) {
+        let _ = self.0.add_history_entry(line);
+    }
+}
+
+/// Reads plain lines from stdin, rebuild with the `readline` feature for line editing.
+#[cfg(not(feature = "readline"))]
+struct Prompt;
+
+#[cfg(not(feature = "readline"))]
+impl Prompt {
+    fn new(_: &repl::BrainfuckREPLHistory) -> io::Result<Prompt> {
+        Ok(Prompt)
+    }
+
+    fn read(&mut self) -> io::Result<Prompted> {
+        eprint!("bf> ");
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(Prompted::End);
+        }
+        Ok(Prompted::Line(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+
+    fn add(&mut self, _: &str) {}
+}
+
+/// Runs lines typed at a prompt on a single VM until Ctrl-D, keeping
+/// them in the history file, see [`repl::history_path`].
 ///
-/// ```
-/// let status = new_brainfuck_status();
-/// let next_op = next_op(&mut status, '+', false);
-/// ```
-fn next_op(status: &mut BrainfuckVMStatus, character: char, via_loop: bool) -> BrainfuckOp {
-    // match BrainfuckOp for character
-    let op = match character {
-        '+' => BrainfuckOp::IncrementValueOp,
-        '-' => BrainfuckOp::DecrementValueOp,
-        '>' => BrainfuckOp::IncrementPtrOp,
-        '<' => BrainfuckOp::DecrementPtrOp,
-        '.' => BrainfuckOp::PrintOp,
-        ',' => BrainfuckOp::ReadOp,
-        '[' => BrainfuckOp::LoopStartOp,
-        ']' => BrainfuckOp::LoopEndOp,
-        // invaild char for brainfuck
-        // monostate is returned
-        _   => BrainfuckOp::MonoStateOp,
+/// A loop left open runs once a later line closes it. An error is
+/// reported and the VM starts over with an empty tape.
+fn repl(options: &Options) -> io::Result<()> {
+    let fresh = || {
+        let mut status = new_brainfuck_status();
+        set_tape(options, &mut status);
+        set_debug_ops(options, &mut status);
+        set_self_modifying(options, &mut status);
+        if options.no_stdin {
+            status.set_eof_mode(EofMode::Error);
+        }
+        status
     };
-    
-    // do not append the char_op if we're retriving the next op inside a loop_op
-    if !via_loop {
-        match op {
-            BrainfuckOp::MonoStateOp => (),
-            _ => {
-                // save char_op to instruction
-                status.instruction.push(character);
-                // increse the ptr of current instruction
-                status.instruction_ptr_current += 1;
-            }
+    let path = repl::history_path();
+    let mut history = match &path {
+        Some(path) => repl::BrainfuckREPLHistory::load(path).unwrap_or_else(|err| {
+            eprintln!("warning: cannot read {}: {}", path.display(), err);
+            repl::BrainfuckREPLHistory::new()
+        }),
+        None => repl::BrainfuckREPLHistory::new(),
+    };
+    let mut prompt = Prompt::new(&history)?;
+    let mut input: Box<dyn BrainfuckInput> = if options.no_stdin { Box::new(io::empty()) } else { Box::new(io::stdin()) };
+    let mut output = io::stdout();
+    let mut status = fresh();
+    loop {
+        let typed = match prompt.read()? {
+            Prompted::Line(line) => line,
+            Prompted::Cancelled => continue,
+            Prompted::End => break,
         };
+        let line = match history.resolve(&typed) {
+            Ok(line) => line.to_string(),
+            Err(message) => {
+                eprintln!("error: {}", message);
+                continue;
+            },
+        };
+        history.push(&line);
+        prompt.add(&line);
+        if let Err(err) = run_vm_bytes(&mut status, line.as_bytes(), &mut *input, &mut output) {
+            output.flush()?;
+            eprintln!("error: {}", err);
+            status = fresh();
+        }
+        output.flush()?;
     }
-    // return next op
-    op
+    if let Some(path) = &path {
+        if let Err(err) = history.save(path) {
+            eprintln!("warning: cannot write {}: {}", path.display(), err);
+        }
+    }
+    Ok(())
 }
 
-/// Run brainfuck VM
-///
-/// # Arguments
-///
-/// * `status`    - A mutable var that holds the status of current brainfuck VM status
-/// * `char_op`   - char type op (may be invalid brainfuck op)
-/// * `via_loop`  - Due to the way I wrote, a flag is needed to avoid re-adding ops 
-///
-/// # Example
-///
-/// ```
-/// let status = new_brainfuck_status();
-/// run_vm(&mut status, '+', false);
-/// ```
-fn run_vm(status: &mut BrainfuckVMStatus, char_op: char, via_loop: bool) {
-    // get next op from char_op
-    let op = next_op(status, char_op, via_loop);
-    match op {
-        BrainfuckOp::IncrementValueOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                let count = status.tape.entry(status.tape_ptr).or_insert(0);
-                *count += 1;
-            }
-        },
-        BrainfuckOp::DecrementValueOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                let count = status.tape.entry(status.tape_ptr).or_insert(0);
-                *count -= 1;
+/// Returns `line:column` of `at` in `file` for an error message, prefixed
+/// with the file unless it is the program `main` itself.
+fn location(main: &str, file: &str, at: ast::Position) -> String {
+    if file == main {
+        format!("{}:{}", at.line, at.column)
+    } else {
+        format!("{}:{}:{}", file, at.line, at.column)
+    }
+}
+
+/// Reports the bracket error `err` in the source of `included` at its
+/// place in the file it is in, and exits.
+fn include_error(main: &str, included: &preprocess::Included, err: BrainfuckError) -> ! {
+    let (at, located): (_, fn(ast::Position) -> BrainfuckError) = match err {
+        BrainfuckError::UnmatchedOpen(at) => (at, BrainfuckError::UnmatchedOpen),
+        BrainfuckError::UnmatchedClose(at) => (at, BrainfuckError::UnmatchedClose),
+        err => compile_error(&included.source, err),
+    };
+    let origin = included.origin(at.offset);
+    if origin.file != main {
+        eprint!("{}:", origin.file);
+    }
+    compile_error(origin.source, located(origin.position))
+}
+
+/// Reads the whole program from the example, URL, base64, `-e` source or
+/// file in `options`, or from stdin, decoding the last three with `--decode-b64`.
+fn read_plain_source(options: &Options) -> io::Result<String> {
+    if let Some(name) = &options.example {
+        if let Some(example) = examples::find(name) {
+            return Ok(example.source.to_string());
+        }
+        match examples::closest(name) {
+            Some(example) => eprintln!("error: no example named '{}', did you mean '{}'?", name, example.name),
+            None => eprintln!("error: no example named '{}', see --list-examples", name),
+        }
+        process::exit(2);
+    }
+    if options.base64 {
+        let encoded = options.file.as_deref().unwrap_or_else(|| usage());
+        #[cfg(feature = "base64")]
+        return Ok(rust_bf::encoding::decode_source(encoded).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }));
+        #[cfg(not(feature = "base64"))]
+        {
+            let _ = encoded;
+            eprintln!("error: cannot decode the program, rebuild with the `base64` feature");
+            process::exit(2);
+        }
+    }
+    if let Some(url) = &options.url {
+        #[cfg(feature = "network")]
+        return Ok(fetch(url, options).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }));
+        #[cfg(not(feature = "network"))]
+        {
+            eprintln!("error: cannot fetch {}, rebuild with the `network` feature", url);
+            process::exit(2);
+        }
+    }
+    let src = match (&options.inline, &options.file) {
+        (Some(src), _) => src.clone(),
+        (None, Some(file)) => fs::read(file).map(source_text)?,
+        (None, None) => {
+            let mut src = Vec::new();
+            io::stdin().read_to_end(&mut src)?;
+            source_text(src)
+        }
+    };
+    if !options.decode_b64 {
+        return Ok(src);
+    }
+    #[cfg(feature = "base64")]
+    return Ok(rust_bf::encoding::decode_url_safe(&src).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }));
+    #[cfg(not(feature = "base64"))]
+    {
+        eprintln!("error: cannot decode the program, rebuild with the `base64` feature");
+        process::exit(2);
+    }
+}
+
+/// Turns the bytes of a program into its source, without copying them if
+/// they are UTF-8. Other bytes can only be comments and become U+FFFD,
+/// unless they are a [packed](packed) program.
+fn source_text(bytes: Vec<u8>) -> String {
+    if packed::is_packed(&bytes) {
+        let ops = packed::unpack(&bytes).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        return frontend::to_source(&ops);
+    }
+    String::from_utf8(bytes).unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned())
+}
+
+/// Writes emitted `text` to the output in `options`, or to stdout.
+fn write_output(options: &Options, text: impl AsRef<[u8]>) -> io::Result<()> {
+    match &options.output {
+        Some(file) => fs::write(file, text),
+        None => io::stdout().write_all(text.as_ref()),
+    }
+}
+
+/// Lays out the IR one node per line, the optimized IR in a second column if given.
+fn ir_listing(ir: &[ir::BrainfuckIR], optimized: Option<&[ir::BrainfuckIR]>) -> String {
+    let mut text = String::new();
+    let optimized = match optimized {
+        Some(optimized) => optimized,
+        None => {
+            for (i, node) in ir.iter().enumerate() {
+                text.push_str(&format!("{:5}  {}\n", i, node));
             }
+            return text;
         },
-        BrainfuckOp::IncrementPtrOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                status.tape_ptr += 1;
-            }
+    };
+
+    let left: Vec<String> = ir.iter().map(|node| node.to_string()).collect();
+    let width = left.iter().map(String::len).max().unwrap_or(0).max("compiled".len());
+    text.push_str(&format!("{:5}  {:width$}  {}\n", "", "compiled", "optimized", width = width));
+    for i in 0..ir.len().max(optimized.len()) {
+        let right = optimized.get(i).map(|node| node.to_string()).unwrap_or_default();
+        let line = format!("{:5}  {:width$}  {}", i, left.get(i).map_or("", String::as_str), right, width = width);
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Verifies a single program, `NAME.in` next to it is used as input. A
+/// byte printed differently is followed by where in the source the op
+/// that printed it in the naive run is.
+///
+/// Returns whether both runs agree.
+fn verify_file(path: &Path) -> io::Result<bool> {
+    let src = fs::read_to_string(path)?;
+    let input = fs::read(path.with_extension("in")).unwrap_or_default();
+    match verify::verify(&src, &input) {
+        Ok(None) => {
+            println!("{}: ok", path.display());
+            Ok(true)
         },
-        BrainfuckOp::DecrementPtrOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                status.tape_ptr -= 1;
+        Ok(Some(divergence)) => {
+            println!("{}: {}", path.display(), divergence);
+            if let verify::Divergence::Output { naive_position: Some(op), .. } = divergence {
+                if let Some((_, at)) = ast::tokens(src.as_bytes()).nth(op) {
+                    println!("{}:{}:{}: the naive run printed it here", path.display(), at.line, at.column);
+                }
             }
+            Ok(false)
         },
-        BrainfuckOp::PrintOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                // take cell from tape
-                let out = *status.tape.entry(status.tape_ptr).or_insert(0);
-                // print as char
-                print!("{}", (out % 255) as u8 as char);
-            }
+        Err(err) => {
+            println!("{}: error: {}", path.display(), err);
+            Ok(false)
         },
-        BrainfuckOp::ReadOp => {
-            // skip actual action if we're skipping loop
-            if status.jump_loop == 0 {
-                // Rust read a single char from stdin
-                // https://stackoverflow.com/a/30679861
-                let input: Option<i32> = std::io::stdin()
-                    .bytes() 
-                    .next()
-                    .and_then(|result| result.ok())
-                    .map(|byte| byte as i32);
-                // store in tape
-                let out = status.tape.entry(status.tape_ptr).or_insert(0);
-                *out = input.unwrap();
+    }
+}
+
+/// Lays out the outcome of every entry as a table, diffs of wrong output
+/// indented below their entry, and a line with the totals. Programs are
+/// named by their path relative to the manifest directory `base`.
+fn batch_report(base: &Path, entries: &[batch::BatchEntry], outcomes: &[batch::BatchOutcome]) -> String {
+    let names: Vec<String> = entries
+        .iter()
+        .map(|entry| entry.program.strip_prefix(base).unwrap_or(&entry.program).display().to_string())
+        .collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0).max("program".len());
+    let mut report = format!("{:width$}  result\n", "program", width = width);
+    for (name, outcome) in names.iter().zip(outcomes) {
+        match outcome {
+            batch::BatchOutcome::Pass => report.push_str(&format!("{:width$}  PASS\n", name, width = width)),
+            batch::BatchOutcome::WrongOutput(diff) => {
+                report.push_str(&format!("{:width$}  FAIL wrong output\n", name, width = width));
+                for line in diff.lines() {
+                    report.push_str(&format!("    {}\n", line));
+                }
+            },
+            batch::BatchOutcome::Error(err) => report.push_str(&format!("{:width$}  FAIL {}\n", name, err, width = width)),
+        }
+    }
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    report.push_str(&format!("{} of {} passed\n", passed, outcomes.len()));
+    report
+}
+
+/// Answers `POST /run` requests on `options.port` until the process is killed.
+#[cfg(feature = "server")]
+fn serve(options: &Options) -> io::Result<()> {
+    use rust_bf::server::{self, ServerConfig};
+
+    let mut config = ServerConfig::default();
+    config.workers = options.jobs.unwrap_or(config.workers);
+    config.limits.step_limit = options.max_steps.or(config.limits.step_limit);
+    config.limits.max_output = options.max_output.or(config.limits.max_output);
+    let listener = std::net::TcpListener::bind(("127.0.0.1", options.port))?;
+    // with `--port 0` this is the only way to learn the port
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    server::serve(listener, config)
+}
+
+/// Answers `POST /run` requests on `options.port` until the process is killed.
+#[cfg(not(feature = "server"))]
+fn serve(_: &Options) -> io::Result<()> {
+    eprintln!("error: cannot serve, rebuild with the `server` feature");
+    process::exit(2);
+}
+
+/// Gives the tape of `status` the size, sides and limit of `--tape-size`,
+/// `--tape` and `--max-tape`, and its cells the overflow of `--overflow`.
+fn set_tape(options: &Options, status: &mut BrainfuckVMStatus) {
+    status.set_overflow_mode(options.overflow);
+    if let Some(cells) = options.max_tape {
+        status.set_max_tape(cells);
+    }
+    if let Some(cells) = options.tape_size {
+        status.set_tape_size(cells, options.tape_bounds.unwrap_or(TapeBounds::Error));
+    }
+    if options.right_only {
+        status.set_right_only(options.tape_bounds.unwrap_or(TapeBounds::Error));
+    }
+}
+
+/// Turns on `$` and `!` in `status` for `--debug-ops`.
+#[cfg(feature = "debug_ops")]
+fn set_debug_ops(options: &Options, status: &mut BrainfuckVMStatus) {
+    status.set_debug_ops(options.debug_ops);
+}
+
+/// Fails for `--debug-ops`, the VM has no `$` and `!` without the `debug_ops` feature.
+#[cfg(not(feature = "debug_ops"))]
+fn set_debug_ops(options: &Options, _status: &mut BrainfuckVMStatus) {
+    if options.debug_ops {
+        eprintln!("error: cannot run `$` and `!`, rebuild with the `debug_ops` feature");
+        process::exit(2);
+    }
+}
+
+/// Turns on `=` in `status` for `--self-modifying`.
+#[cfg(feature = "self_modifying")]
+fn set_self_modifying(options: &Options, status: &mut BrainfuckVMStatus) {
+    status.set_self_modifying(options.self_modifying);
+}
+
+/// Fails for `--self-modifying`, the VM has no `=` without the `self_modifying` feature.
+#[cfg(not(feature = "self_modifying"))]
+fn set_self_modifying(options: &Options, _status: &mut BrainfuckVMStatus) {
+    if options.self_modifying {
+        eprintln!("error: cannot run `=`, rebuild with the `self_modifying` feature");
+        process::exit(2);
+    }
+}
+
+/// Runs `options.count` random programs from `options.seed` on, printing
+/// the ones that panicked and exiting with 1 if any did.
+#[cfg(feature = "testing")]
+fn fuzz(options: &Options) -> ! {
+    let (mut panics, mut errors) = (0, 0);
+    for i in 0..options.count {
+        // one seed makes one program, so the seed is all it takes to run it again
+        let seed = options.seed.wrapping_add(i);
+        let report = rust_bf::testing::fuzz_random(seed, options.length, options.max_steps.unwrap_or(100_000));
+        for (program, err) in report.errors {
+            match err {
+                BrainfuckError::Panicked(message) => {
+                    println!("seed {}: panicked on {}: {}", seed, program.iter().collect::<String>(), message);
+                    panics += 1;
+                },
+                _ => errors += 1,
             }
-        },
-        BrainfuckOp::LoopStartOp => {
-            // if and only if 1) `current_cell_value != 0`
-            //                2) and we're not do the skipping
-            // we can record the starting index of the if instruction
-            // besides, if we're in condition 1)
-            // the if statement should be also skipped
-            let cell = status.tape.entry(status.tape_ptr).or_insert(0);
-            if *cell != 0 && status.jump_loop == 0 {
-                status.instruction_loop_ptr.push(status.instruction_ptr_current);
-            } else {
-                status.jump_loop += 1;
+        }
+    }
+    println!(
+        "tested {} programs, {} panicked, {} stopped with an error",
+        thousands(options.count), thousands(panics), thousands(errors)
+    );
+    process::exit(if panics > 0 { 1 } else { 0 });
+}
+
+/// Runs random programs, which needs the `testing` feature.
+#[cfg(not(feature = "testing"))]
+fn fuzz(_: &Options) -> ! {
+    eprintln!("error: cannot fuzz, rebuild with the `testing` feature");
+    process::exit(2);
+}
+
+/// Answers programs sent over plain TCP on `options.port` until the process is killed.
+fn serve_tcp(options: &Options) -> io::Result<()> {
+    let server = BrainfuckServer::bind(("127.0.0.1", options.port))?;
+    let mut limits = server.limits();
+    limits.step_limit = options.max_steps.or(limits.step_limit);
+    limits.max_output = options.max_output.or(limits.max_output);
+    eprintln!("listening on {}", server.local_addr()?);
+    server.with_limits(limits).serve()
+}
+
+/// Prints a compile error in `src` and exits.
+fn compile_error(src: &str, err: BrainfuckError) -> ! {
+    eprint!("{}", format_error(src, &err));
+    process::exit(1);
+}
+
+/// Formats cell accesses as a table with a bar per cell scaled to the
+/// busiest one, or as CSV, leaving out cells that were never accessed.
+fn heatmap_report(accesses: &BTreeMap<i32, CellAccesses>, csv: bool) -> String {
+    let mut report = String::new();
+    if csv {
+        report.push_str("cell,reads,writes\n");
+        for (cell, access) in accesses {
+            report.push_str(&format!("{},{},{}\n", cell, access.reads, access.writes));
+        }
+        return report;
+    }
+
+    const BAR_WIDTH: u64 = 40;
+    let busiest = accesses.values().map(|access| access.reads + access.writes).max().unwrap_or(0).max(1);
+    report.push_str(&format!("{:>8} {:>12} {:>12}\n", "cell", "reads", "writes"));
+    let mut previous = None;
+    for (&cell, access) in accesses {
+        if let Some(previous) = previous {
+            if cell - previous > 1 {
+                report.push_str(&format!("{:>8} cells {} to {} untouched\n", "...", previous + 1, cell - 1));
             }
-        },
-        BrainfuckOp::LoopEndOp => {
-            // decrease the jump_loop value if we encounter the `]`
-            // and we were previously doing the skip
-            if status.jump_loop != 0 {
-                status.jump_loop -= 1;
-            } else {
-                // if we were not in skipping
-                // then we need to check the loop condition, `current_cell_value != 0`
-                let cell = *status.tape.entry(status.tape_ptr).or_insert(0);
-                if cell != 0 {
-                    // loop the instruction until condition satisfies no more
-                    while *status.tape.entry(status.tape_ptr).or_insert(0) != 0 {
-                        // save current instruction pointer
-                        let current = status.instruction_ptr_current;
-                        // start the loop right after the index of `[`
-                        if let Some(last) = status.instruction_loop_ptr.last().cloned() {
-                            status.instruction_ptr_current = last + 1;
-                            while status.instruction_ptr_current < current {
-                                // run one op at a time
-                                // until the next op is the corresponding `]`
-                                let current_op = status.instruction[status.instruction_ptr_current as usize];
-                                run_vm(status, current_op, true);
-                                status.instruction_ptr_current += 1;
-                            }
-                            // restore the current instruction pointer
-                            status.instruction_ptr_current = current;
-                        }
-                    }
-                    // pop current loop starting index
-                    status.instruction_loop_ptr.pop();
-                }
+        }
+        previous = Some(cell);
+        let bar = "#".repeat(((access.reads + access.writes) * BAR_WIDTH).div_ceil(busiest) as usize);
+        report.push_str(&format!("{:>8} {:>12} {:>12} {}\n", cell, thousands(access.reads), thousands(access.writes), bar));
+    }
+    report
+}
+
+/// Formats a trace event as one line of JSON, see the README for the fields.
+fn trace_json(event: &TraceEvent) -> String {
+    let mut json = format!(
+        "{{\"step\":{},\"ip\":{},\"op\":\"{}\",\"ptr\":{},\"cell_before\":{},\"cell_after\":{}",
+        event.step, event.ip, event.op, event.ptr, event.cell_before, event.cell_after
+    );
+    if matches!(event.op, '.' | ',') {
+        match event.byte {
+            Some(byte) => json.push_str(&format!(",\"byte\":{}", byte)),
+            None => json.push_str(",\"byte\":null"),
+        }
+    }
+    json.push('}');
+    json
+}
+
+/// Formats `n` with a comma between every three digits.
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut text = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            text.push(',');
+        }
+        text.push(digit);
+    }
+    text
+}
+
+/// Formats `value` with a k, M or G suffix once it gets that large.
+fn scaled(value: f64) -> String {
+    match value {
+        v if v >= 1e9 => format!("{:.2}G", v / 1e9),
+        v if v >= 1e6 => format!("{:.2}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.2}k", v / 1e3),
+        v => format!("{:.0}", v),
+    }
+}
+
+/// Formats `seconds` in seconds, milliseconds or microseconds, whichever reads best.
+fn duration(seconds: f64) -> String {
+    match seconds {
+        s if s >= 1.0 => format!("{:.2}s", s),
+        s if s >= 1e-3 => format!("{:.2}ms", s * 1e3),
+        s => format!("{:.2}µs", s * 1e6),
+    }
+}
+
+/// set by the first Ctrl-C, the VM stops at the next loop once it is
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// set for `--on-interrupt=abort`, which exits on the first Ctrl-C
+static ABORT: AtomicBool = AtomicBool::new(false);
+
+/// Marks the run as interrupted, returning whether the program should end
+/// at once because it already was or should abort.
+///
+/// A second Ctrl-C always ends the program, even if the first one got
+/// stuck. The handlers end it without flushing stdout, which the VM may
+/// hold locked where the signal came in, [`stop`] flushes it otherwise.
+fn interrupt() -> bool {
+    INTERRUPTED.swap(true, Ordering::SeqCst) || ABORT.load(Ordering::SeqCst)
+}
+
+/// Makes Ctrl-C set [`INTERRUPTED`] instead of killing the process.
+#[cfg(unix)]
+fn install_interrupt_handler() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        if interrupt() {
+            // SAFETY: `_exit` is async-signal-safe, it runs no cleanup of the runtime
+            unsafe { libc::_exit(130) }
+        }
+    }
+    // SAFETY: the handler only touches atomics and calls `_exit`
+    unsafe {
+        libc::signal(libc::SIGINT, on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+/// Makes Ctrl-C set [`INTERRUPTED`] instead of killing the process.
+#[cfg(windows)]
+fn install_interrupt_handler() {
+    /// `CTRL_C_EVENT`, other events such as closing the console keep their default
+    const CTRL_C_EVENT: u32 = 0;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(u32) -> i32>, add: i32) -> i32;
+        fn ExitProcess(code: u32) -> !;
+    }
+    unsafe extern "system" fn on_ctrl(event: u32) -> i32 {
+        if event != CTRL_C_EVENT {
+            return 0;
+        }
+        if interrupt() {
+            // runs no cleanup of the runtime, which the main thread may be in the middle of
+            ExitProcess(130);
+        }
+        1
+    }
+    // SAFETY: the handler only touches atomics and calls `ExitProcess`
+    unsafe {
+        SetConsoleCtrlHandler(Some(on_ctrl), 1);
+    }
+}
+
+/// Ctrl-C keeps killing the process where there is no way to catch it.
+#[cfg(not(any(unix, windows)))]
+fn install_interrupt_handler() {}
+
+/// Returns how far the pointer got, whether it used cells left of cell 0
+/// and the fixed-size tape the run fits, for `--memory-report`.
+fn memory_report(status: &BrainfuckVMStatus) -> String {
+    let (min, max) = status.pointer_extent();
+    let cells = (max as i64 - min as i64 + 1) as u64;
+    let mut report = format!(
+        "pointer reached cells {} to {}, {} cells touched\n",
+        min, max, thousands(status.allocated_cells() as u64)
+    );
+    if min < 0 {
+        let used = match status.negative_cells() {
+            1 => String::from("1 cell"),
+            n => format!("{} cells", thousands(n as u64)),
+        };
+        report.push_str(&format!(
+            "went left of cell 0 by {} cells and touched {} there, a fixed tape needs {} cells with the pointer starting at cell {}\n",
+            thousands(min.unsigned_abs() as u64), used, thousands(cells), thousands(min.unsigned_abs() as u64)
+        ));
+    } else {
+        report.push_str(&format!("never went left of cell 0, a fixed tape needs {} cells\n", thousands(cells)));
+    }
+    report
+}
+
+/// Returns the tape and how far the run got, for `--on-interrupt`.
+fn state_dump(status: &BrainfuckVMStatus) -> String {
+    let mut dump = format!(
+        "interrupted after {} ops, pointer at cell {}\n",
+        thousands(status.ops_executed()), status.tape_ptr()
+    );
+    for (cell, value) in status.cells() {
+        dump.push_str(&format!("{:>8}: {}\n", cell, value));
+    }
+    dump
+}
+
+/// Stops the run on `err`, dumping or saving the tape first if it was interrupted.
+fn stop(options: &Options, status: &BrainfuckVMStatus, src: &[u8], err: BrainfuckError) -> ! {
+    if let BrainfuckError::LoopHang { position, iterations } = err {
+        // the VM counts ops, which are the ops of the source in order
+        if let Some((_, at)) = ast::tokens(src).nth(position) {
+            let _ = io::stdout().flush();
+            eprintln!("error: {}:{}: loop went round {} times without its cell changing", at.line, at.column, thousands(iterations));
+            process::exit(1);
+        }
+    }
+    if !matches!(err, BrainfuckError::Interrupted) {
+        runtime_error(err);
+    }
+    let _ = io::stdout().flush();
+    match (options.on_interrupt.as_deref(), &options.save_state) {
+        (Some("save"), Some(path)) => {
+            if let Err(err) = fs::write(path, state_dump(status)) {
+                eprintln!("error: cannot save state to {}: {}", path, err);
+                process::exit(1);
             }
+            eprintln!("interrupted, state saved to {}", path);
         },
-        BrainfuckOp::MonoStateOp => ()
+        _ => eprint!("{}", state_dump(status)),
     }
+    process::exit(130);
+}
+
+/// Loads the program file in `options` if it is a [packed](packed) one,
+/// `None` for a source file or a program from anywhere else.
+fn packed_program(options: &Options) -> io::Result<Option<Vec<ir::BrainfuckIR>>> {
+    let file = match &options.file {
+        Some(file) if options.example.is_none() && !options.base64 && options.url.is_none() && options.inline.is_none() => file,
+        _ => return Ok(None),
+    };
+    let mut magic = Vec::new();
+    File::open(file)?.take(packed::MAGIC.len() as u64).read_to_end(&mut magic)?;
+    if !packed::is_packed(&magic) {
+        return Ok(None);
+    }
+    let ir = packed::load(&fs::read(file)?).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    Ok(Some(ir))
+}
+
+/// Runs the IR a packed program loaded into on an [`Interpreter`], without
+/// turning it back into source for the VM.
+///
+/// The end of input of `--no-stdin`, the output limit, `--overflow` and
+/// `--time` apply, the reports and tape options of the VM do not.
+fn run_packed(options: &Options, ir: Vec<ir::BrainfuckIR>) -> io::Result<()> {
+    let interpreter = Interpreter::from_ir(ir).with_overflow_mode(options.overflow);
+    let mut interpreter = match options.max_output {
+        Some(limit) => interpreter.output(LimitedOutput::new(io::stdout(), limit, options.max_output_action)),
+        None => interpreter.output(io::stdout()),
+    };
+    if options.no_stdin {
+        interpreter = interpreter.without_input();
+    }
+    let started = Instant::now();
+    if let Err(err) = interpreter.run() {
+        runtime_error(err);
+    }
+    if options.time {
+        io::stdout().flush()?;
+        let seconds = started.elapsed().as_secs_f64();
+        let steps = interpreter.steps();
+        eprintln!("executed {} IR nodes in {} ({} nodes/s)", thousands(steps), duration(seconds), scaled(steps as f64 / seconds.max(1e-9)));
+    }
+    Ok(())
+}
+
+/// Prints a runtime error and exits, quietly if the reader of stdout went away.
+fn runtime_error(err: BrainfuckError) -> ! {
+    if let BrainfuckError::Io(err) = &err {
+        if err.kind() == io::ErrorKind::BrokenPipe {
+            process::exit(0);
+        }
+    }
+    eprintln!("error: {}", err);
+    process::exit(1);
 }
 
 fn main() -> io::Result<()> {
+    let options = parse_options(env::args().skip(1));
+
+    if options.command.as_deref() == Some("fmt") {
+        let src = read_source(&options)?;
+        let text = format_program(&src, &options.format).unwrap_or_else(|err| compile_error(&src, err));
+        return write_output(&options, &text);
+    }
+
+    if options.command.as_deref() == Some("minify") {
+        let src = read_source(&options)?;
+        let text = minify_program(&src, options.aggressive).unwrap_or_else(|err| compile_error(&src, err));
+        let saved = src.len() - text.len();
+        eprintln!(
+            "minified {} bytes to {} bytes ({:.1}% smaller)",
+            src.len(), text.len(), saved as f64 * 100.0 / src.len().max(1) as f64
+        );
+        return write_output(&options, &text);
+    }
+
+    if options.command.as_deref() == Some("generate") {
+        // the bytes of TEXT, or of stdin as they are, which need not be UTF-8
+        let target = match &options.file {
+            Some(text) => text.clone().into_bytes(),
+            None => {
+                let mut target = Vec::new();
+                io::stdin().read_to_end(&mut target)?;
+                target
+            },
+        };
+        let mut src = if options.optimize_size { compress::synthesize_small(&target) } else { compress::synthesize(&target) };
+        src.push('\n');
+        return write_output(&options, &src);
+    }
+
+    if options.command.as_deref() == Some("encode") {
+        let mut src = read_source(&options)?;
+        if options.minify {
+            src = minify_program(&src, false).unwrap_or_else(|err| compile_error(&src, err));
+        }
+        #[cfg(feature = "base64")]
+        return write_output(&options, rust_bf::encoding::encode_url_safe(&src) + "\n");
+        #[cfg(not(feature = "base64"))]
+        {
+            let _ = src;
+            eprintln!("error: cannot encode the program, rebuild with the `base64` feature");
+            process::exit(2);
+        }
+    }
+
+    if options.command.as_deref() == Some("explain") {
+        let src = read_source(&options)?;
+        let text = explain::explain(&src).unwrap_or_else(|err| compile_error(&src, err));
+        return write_output(&options, &text);
+    }
+
+    if options.command.as_deref() == Some("check") {
+        let src = read_source(&options)?;
+        let ast = ast::parse(&src).unwrap_or_else(|err| compile_error(&src, err));
+        let warnings = check::check(&ast);
+        let name = options.file.as_deref().unwrap_or("<stdin>");
+        for warning in &warnings {
+            eprintln!("{}:{}", name, warning);
+        }
+        if options.deny_warnings && !warnings.is_empty() {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if options.command.as_deref() == Some("batch") {
+        let path = Path::new(options.file.as_deref().unwrap_or_else(|| usage()));
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        let entries = batch::parse_manifest(&fs::read_to_string(path)?, base)
+            .unwrap_or_else(|err| {
+                eprintln!("error: {}: {}", path.display(), err);
+                process::exit(2);
+            });
+        let outcomes = batch::run_batch(&entries, options.jobs.unwrap_or(1));
+        print!("{}", batch_report(base, &entries, &outcomes));
+        if !outcomes.iter().all(batch::BatchOutcome::passed) {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if options.command.as_deref() == Some("serve") && options.tcp {
+        return serve_tcp(&options);
+    }
+
+    if options.command.as_deref() == Some("serve") {
+        return serve(&options);
+    }
+
+    if options.verify {
+        let path = Path::new(options.file.as_deref().unwrap_or_else(|| usage()));
+        let mut passed = true;
+        if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<_>>()?;
+            files.retain(|file| file.extension().is_some_and(|ext| ext == "bf"));
+            files.sort();
+            for file in &files {
+                passed &= verify_file(file)?;
+            }
+        } else {
+            passed = verify_file(path)?;
+        }
+        if !passed {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if options.list_examples {
+        let width = examples::EXAMPLES.iter().map(|example| example.name.len()).max().unwrap_or(0);
+        for example in examples::EXAMPLES {
+            println!("{:width$}  {}", example.name, example.description, width = width);
+        }
+        return Ok(());
+    }
+
+    if options.self_test {
+        // the same settings a program run gets
+        let results = selftest::self_test(|status| {
+            set_tape(&options, status);
+            if options.no_stdin {
+                status.set_eof_mode(EofMode::Error);
+            }
+        });
+        for check in &results {
+            match &check.failure {
+                None => println!("PASS  {}", check.name),
+                Some(failure) => println!("FAIL  {}: {}", check.name, failure),
+            }
+        }
+        let passed = results.iter().filter(|check| check.passed()).count();
+        println!("{} of {} checks passed", passed, results.len());
+        if passed < results.len() {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if options.fuzz {
+        fuzz(&options);
+    }
+
+    if options.dry_run {
+        let src = read_source(&options)?;
+        // on the tape a normal run would use, so that both end the same way
+        let mut status = new_brainfuck_status();
+        set_tape(&options, &mut status);
+        set_debug_ops(&options, &mut status);
+        set_self_modifying(&options, &mut status);
+        if let Some(iterations) = options.hang_threshold {
+            status.set_hang_threshold(iterations);
+        }
+        match Interpreter::dry_run_on(&mut status, &src) {
+            Ok(report) => print!("{}", report),
+            Err(err) => stop(&options, &status, src.as_bytes(), err),
+        }
+        return Ok(());
+    }
+
+    if options.print_ir {
+        let src = read_source(&options)?;
+        let ir = ir::compile(&ast::parse(&src).unwrap_or_else(|err| compile_error(&src, err)));
+        let optimized = options.optimize.then(|| optimizer::optimize(&ir));
+        return io::stdout().write_all(ir_listing(&ir, optimized.as_deref()).as_bytes());
+    }
+
+    if let Some(format) = &options.emit {
+        let src = read_source(&options)?;
+        let ast = ast::parse(&src).unwrap_or_else(|err| compile_error(&src, err));
+        let text = match format.as_str() {
+            "dot" => dot::to_dot(&ast),
+            // the brackets matched when parsing
+            "bfc" => return write_output(&options, packed::pack(src.as_bytes()).unwrap()),
+            "bfc-runs" => {
+                let ops: Vec<_> = ast::tokens(src.as_bytes()).map(|(op, _)| op).collect();
+                return write_output(&options, packed::write_program(&ops));
+            },
+            #[cfg(feature = "docgen")]
+            "html" => rust_bf::docgen::generate_docs(&src),
+            #[cfg(feature = "llvm_codegen")]
+            "llvm" => {
+                let name = options.file.as_deref().unwrap_or("stdin");
+                rust_bf::codegen::llvm::to_llvm_ir(&rust_bf::ir::compile(&ast), name)
+            },
+            _ => {
+                eprintln!("error: unknown emit format '{}'", format);
+                process::exit(2);
+            }
+        };
+        return write_output(&options, &text);
+    }
+
+    if options.repl {
+        return repl(&options);
+    }
+    if let Some(ir) = packed_program(&options)? {
+        return run_packed(&options, ir);
+    }
+
     // the brainfuck vm
     let mut status = new_brainfuck_status();
-    
-    // read from stdin
-    let buffer = BufReader::new(io::stdin());
-    for line in buffer.lines() {
-        for c in line?.chars() {
-            // handle every character
-            run_vm(&mut status, c, false);
+    if let Some(mode) = &options.on_interrupt {
+        // `abort` still installs the handler, Ctrl-C may be ignored where the program was started
+        ABORT.store(mode == "abort", Ordering::SeqCst);
+        install_interrupt_handler();
+        status.set_interrupt_flag(&INTERRUPTED);
+    }
+    set_tape(&options, &mut status);
+    set_debug_ops(&options, &mut status);
+    set_self_modifying(&options, &mut status);
+    if let Some(iterations) = options.hang_threshold {
+        status.set_hang_threshold(iterations);
+    }
+    if options.heatmap.is_some() {
+        status.record_accesses();
+    }
+    if options.cover.is_some() {
+        status.record_coverage();
+    }
+    if options.trace.is_some() || options.trace_json.is_some() {
+        // written a line at a time, so that the trace is complete up to an error that exits
+        let mut text = options.trace.as_ref().map(File::create).transpose()?.map(LineWriter::new);
+        let mut json = options.trace_json.as_ref().map(File::create).transpose()?.map(LineWriter::new);
+        let sample = options.trace_sample;
+        status.set_trace(Box::new(move |event| {
+            if event.step % sample != 0 {
+                return Ok(());
+            }
+            if let Some(trace) = &mut text {
+                writeln!(trace, "{}", event).map_err(BrainfuckError::Io)?;
+            }
+            if let Some(trace) = &mut json {
+                writeln!(trace, "{}", trace_json(event)).map_err(BrainfuckError::Io)?;
+            }
+            Ok(())
+        }));
+    }
+    // with `--no-stdin` the first `,` fails rather than waiting for input that never comes
+    let mut input: Box<dyn BrainfuckInput> = if options.no_stdin {
+        status.set_eof_mode(EofMode::Error);
+        Box::new(io::empty())
+    } else {
+        Box::new(io::stdin())
+    };
+    let mut output: Box<dyn BrainfuckOutput> = match options.max_output {
+        Some(limit) => Box::new(LimitedOutput::new(io::stdout(), limit, options.max_output_action)),
+        None => Box::new(io::stdout()),
+    };
+    let started = Instant::now();
+
+    // the source as it was run, for the coverage report
+    let mut src = String::new();
+    let whole = options.file.is_some() || options.inline.is_some() || options.url.is_some() || options.example.is_some();
+    if whole || options.decode_b64 || options.pp || options.allow_include {
+        // run the program file, stdin is left for `,`
+        src = read_source(&options)?;
+        if let Err(err) = run_vm_bytes(&mut status, src.as_bytes(), &mut *input, &mut *output) {
+            stop(&options, &status, src.as_bytes(), err);
+        }
+    } else {
+        // read from stdin
+        let buffer = BufReader::new(io::stdin());
+        for line in buffer.lines() {
+            let line = line?;
+            src.push_str(&line);
+            src.push('\n');
+            if let Err(err) = run_vm_bytes(&mut status, line.as_bytes(), &mut *input, &mut *output) {
+                stop(&options, &status, src.as_bytes(), err);
+            }
         }
     }
+
+    if options.time {
+        io::stdout().flush()?;
+        let seconds = started.elapsed().as_secs_f64();
+        let ops = status.ops_executed();
+        eprintln!(
+            "executed {} ops in {} ({} ops/s), peak tape cells: {}",
+            thousands(ops), duration(seconds), scaled(ops as f64 / seconds.max(1e-9)), thousands(status.allocated_cells() as u64)
+        );
+    }
+    if options.cell_values {
+        io::stdout().flush()?;
+        let histogram = status.cell_value_histogram();
+        eprint!(
+            "{} non-zero cells of {} allocated\n{}",
+            thousands(histogram.nonzero_cells() as u64), thousands(status.allocated_cells() as u64), histogram.as_table()
+        );
+    }
+    if options.memory_report {
+        io::stdout().flush()?;
+        eprint!("{}", memory_report(&status));
+    }
+    if let (Some(path), Some(accesses)) = (&options.heatmap, status.accesses()) {
+        fs::write(path, heatmap_report(accesses, options.heatmap_csv))?;
+    }
+    if let (Some(path), Some(coverage)) = (&options.cover, status.coverage()) {
+        fs::write(path, coverage.to_html(&src))?;
+    }
     Ok(())
 }
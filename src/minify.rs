@@ -0,0 +1,103 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::ast;
+use crate::error::BrainfuckError;
+use crate::op::BrainfuckOp;
+
+/// Returns whether `a` followed by `b` has no effect.
+fn cancels(a: char, b: char) -> bool {
+    matches!((a, b), ('+', '-') | ('-', '+') | ('<', '>') | ('>', '<'))
+}
+
+/// Returns the eight ops of `src` with everything else removed.
+///
+/// Unlike [`minify_program`] this never fails, brackets are kept as they
+/// are even if they do not match.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::minify::minify;
+/// use rust_bf::Interpreter;
+///
+/// let src = "read a byte ,\n[ print it . and read the next one , ]\n";
+/// assert_eq!(minify(src), ",[.,]");
+///
+/// // runs the same
+/// assert_eq!(Interpreter::run_string(&minify(src), "echo").unwrap(), Interpreter::run_string(src, "echo").unwrap());
+/// assert_eq!(minify("unmatched ] stays"), "]");
+/// ```
+pub fn minify(src: &str) -> String {
+    src.chars().filter(|&c| BrainfuckOp::from_char(c) != BrainfuckOp::MonoStateOp).collect()
+}
+
+/// Produces the smallest equivalent program text.
+///
+/// Every non-op character is dropped. With `aggressive`, adjacent `+-`,
+/// `-+`, `<>` and `><` pairs are cancelled as well, and loops at the very
+/// start of the program are removed since the cell is still zero there,
+/// which is where brainfuck programs traditionally keep comments.
+///
+/// # Arguments
+///
+/// * `src`        - brainfuck source
+/// * `aggressive` - also cancel no-op pairs and leading comment loops
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::Interpreter;
+/// use rust_bf::minify::minify_program;
+///
+/// let src = "[a comment, really.] ++ >< +++ add [-<+>] -+";
+/// let minified = minify_program(src, true).unwrap();
+/// assert_eq!(minified, "+++++[-<+>]");
+///
+/// // both versions behave the same
+/// for program in &["++++++++[>++++++++<-]>+.", ",[.,]", "++++[>++++[>++++<-]<-]>><>>+-.<<."] {
+///     let run = |src: &str| {
+///         let mut output = Vec::new();
+///         Interpreter::new(src).unwrap().input(&b"xyz"[..]).output(&mut output).run().unwrap();
+///         output
+///     };
+///     assert_eq!(run(program), run(&minify_program(program, true).unwrap()));
+/// }
+/// ```
+pub fn minify_program(src: &str, aggressive: bool) -> Result<String, BrainfuckError> {
+    // refuse to rewrite loops that do not match
+    ast::check_brackets(src.as_bytes())?;
+
+    let ops = minify(src);
+    if !aggressive {
+        return Ok(ops);
+    }
+
+    // cancel pairs with a stack so that `+<>-` goes away entirely,
+    // brackets stay on the stack and keep pairs from cancelling across loops
+    let mut stack: Vec<char> = Vec::new();
+    for c in ops.chars() {
+        match stack.last() {
+            Some(&last) if cancels(last, c) => { stack.pop(); },
+            _ => stack.push(c),
+        }
+    }
+
+    // the first cell is zero until something touches it, so leading loops never run
+    let mut start = 0;
+    while stack.get(start) == Some(&'[') {
+        let mut depth = 0;
+        for (i, &c) in stack.iter().enumerate().skip(start) {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                start = i + 1;
+                break;
+            }
+        }
+    }
+    Ok(stack[start..].iter().collect())
+}
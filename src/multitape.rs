@@ -0,0 +1,196 @@
+//! Brainfuck with several tapes, `T` switches to the next one.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::ast;
+use crate::error::BrainfuckError;
+#[cfg(not(feature = "std"))]
+use crate::io::Discard;
+use crate::io::{BrainfuckInput, BrainfuckOutput};
+
+/// number of tapes `T` cycles through
+pub const TAPE_COUNT: usize = 8;
+
+/// One tape with its own pointer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tape {
+    /// cells allocated so far
+    cells: Vec<u8>,
+    /// current cell of the tape
+    pointer: usize,
+}
+
+impl Tape {
+    /// Returns the cells allocated so far, cells past the end are zero.
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Returns the index of the current cell.
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Returns the current cell, allocating it if needed.
+    fn cell(&mut self) -> &mut u8 {
+        if self.pointer >= self.cells.len() {
+            self.cells.resize(self.pointer + 1, 0);
+        }
+        &mut self.cells[self.pointer]
+    }
+}
+
+/// All tapes of a [`MultiTapeInterpreter`] and which one is active.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultiTapeState {
+    /// tapes 0 to 7
+    tapes: [Tape; TAPE_COUNT],
+    /// index of the tape ops work on
+    active: usize,
+}
+
+impl MultiTapeState {
+    /// Returns tape `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below [`TAPE_COUNT`].
+    pub fn tape(&self, index: usize) -> &Tape {
+        &self.tapes[index]
+    }
+
+    /// Returns the index of the active tape.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+}
+
+/// Runs brainfuck programs extended with `T`, which makes the next of
+/// [`TAPE_COUNT`] tapes active, wrapping around after the last one.
+///
+/// All other ops work on the active tape, every tape keeps its own
+/// pointer. Cells are 8-bit and wrap around, a `,` at end of input stores 0.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::multitape::MultiTapeInterpreter;
+///
+/// // keep 5 on tape 0, compute 2 * 3 on tape 1, then come back to print the 5
+/// let src = "+++++ T ++[>+++<-]> TTTTTTT .";
+/// let mut output = Vec::new();
+/// let mut interpreter = MultiTapeInterpreter::new(src).unwrap().output(&mut output);
+/// interpreter.run().unwrap();
+/// assert_eq!(interpreter.state().active(), 0);
+/// assert_eq!(interpreter.state().tape(1).cells(), &[0, 6]);
+/// drop(interpreter);
+/// assert_eq!(output, [5]);
+/// ```
+pub struct MultiTapeInterpreter<'a> {
+    /// ops of the program, comments dropped
+    ops: Vec<char>,
+    /// index of the matching bracket for every `[` and `]`
+    jumps: Vec<usize>,
+    /// index of the next op to run
+    ip: usize,
+    /// tapes and the active one
+    state: MultiTapeState,
+    /// where `,` reads from
+    input: Box<dyn BrainfuckInput + 'a>,
+    /// where `.` writes to
+    output: Box<dyn BrainfuckOutput + 'a>,
+}
+
+impl<'a> MultiTapeInterpreter<'a> {
+    /// Compiles `src` into a new interpreter.
+    ///
+    /// With `std` the interpreter reads stdin and writes stdout,
+    /// otherwise input is empty and output is discarded until
+    /// [`MultiTapeInterpreter::input`] and [`MultiTapeInterpreter::output`]
+    /// say otherwise.
+    pub fn new(src: &str) -> Result<MultiTapeInterpreter<'a>, BrainfuckError> {
+        // `T` is a comment to the parser, which still checks the brackets
+        ast::check_brackets(src.as_bytes())?;
+
+        let ops: Vec<char> = src.chars().filter(|c| "+-<>.,[]T".contains(*c)).collect();
+        let mut jumps = alloc::vec![0; ops.len()];
+        let mut open = Vec::new();
+        for (i, &op) in ops.iter().enumerate() {
+            match op {
+                '[' => open.push(i),
+                ']' => {
+                    let start = open.pop().expect("brackets are balanced");
+                    jumps[start] = i;
+                    jumps[i] = start;
+                },
+                _ => (),
+            }
+        }
+
+        #[cfg(feature = "std")]
+        let (input, output): (Box<dyn BrainfuckInput>, Box<dyn BrainfuckOutput>) =
+            (Box::new(std::io::stdin()), Box::new(std::io::stdout()));
+        #[cfg(not(feature = "std"))]
+        let (input, output): (Box<dyn BrainfuckInput>, Box<dyn BrainfuckOutput>) =
+            (Box::new(&[][..]), Box::new(Discard));
+
+        Ok(MultiTapeInterpreter { ops, jumps, ip: 0, state: MultiTapeState::default(), input, output })
+    }
+
+    /// Sets where `,` reads from.
+    pub fn input(mut self, input: impl BrainfuckInput + 'a) -> Self {
+        self.input = Box::new(input);
+        self
+    }
+
+    /// Sets where `.` writes to.
+    pub fn output(mut self, output: impl BrainfuckOutput + 'a) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Returns the tapes.
+    pub fn state(&self) -> &MultiTapeState {
+        &self.state
+    }
+
+    /// Runs the program until it halts.
+    pub fn run(&mut self) -> Result<(), BrainfuckError> {
+        while self.ip < self.ops.len() {
+            let tape = &mut self.state.tapes[self.state.active];
+            match self.ops[self.ip] {
+                '+' => {
+                    let cell = tape.cell();
+                    *cell = cell.wrapping_add(1);
+                },
+                '-' => {
+                    let cell = tape.cell();
+                    *cell = cell.wrapping_sub(1);
+                },
+                '>' => tape.pointer += 1,
+                '<' => {
+                    if tape.pointer == 0 {
+                        return Err(BrainfuckError::PointerUnderflow { position: self.ip });
+                    }
+                    tape.pointer -= 1;
+                },
+                '.' => self.output.write_byte(*tape.cell())?,
+                ',' => *tape.cell() = self.input.read_byte().unwrap_or(0),
+                '[' => {
+                    if *tape.cell() == 0 {
+                        self.ip = self.jumps[self.ip];
+                    }
+                },
+                ']' => {
+                    if *tape.cell() != 0 {
+                        self.ip = self.jumps[self.ip];
+                    }
+                },
+                _ => self.state.active = (self.state.active + 1) % TAPE_COUNT,
+            }
+            self.ip += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+/// The `BrainfuckOp` type.
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrainfuckOp {
+    /// `+`
+    IncrementValueOp,
+    /// `-`
+    DecrementValueOp,
+    /// `>`
+    IncrementPtrOp,
+    /// `<`
+    DecrementPtrOp,
+    /// `.`
+    PrintOp,
+    /// `,`
+    ReadOp,
+    /// `[`
+    LoopStartOp,
+    /// `]`
+    LoopEndOp,
+    /// `=`, writes its own character into the current cell, enable the
+    /// `self_modifying` feature to use it and turn it on where the program runs
+    #[cfg(feature = "self_modifying")]
+    SelfWriteOp,
+    /// `$`, prints the current cell as a decimal number, enable the
+    /// `debug_ops` feature to use it and turn it on where the program runs
+    #[cfg(feature = "debug_ops")]
+    PrintNumberOp,
+    /// `!`, sets every cell to 0 and the pointer back to cell 0, enable
+    /// the `debug_ops` feature to use it and turn it on where the program runs
+    #[cfg(feature = "debug_ops")]
+    ResetOp,
+    /// anything that is not a brainfuck op
+    MonoStateOp
+}
+
+impl BrainfuckOp {
+    /// Returns the corresponding BrainfuckOp of given `character`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::BrainfuckOp;
+    ///
+    /// assert_eq!(BrainfuckOp::from_char('+'), BrainfuckOp::IncrementValueOp);
+    /// assert_eq!(BrainfuckOp::from_char('a'), BrainfuckOp::MonoStateOp);
+    /// ```
+    pub fn from_char(character: char) -> BrainfuckOp {
+        match character {
+            '+' => BrainfuckOp::IncrementValueOp,
+            '-' => BrainfuckOp::DecrementValueOp,
+            '>' => BrainfuckOp::IncrementPtrOp,
+            '<' => BrainfuckOp::DecrementPtrOp,
+            '.' => BrainfuckOp::PrintOp,
+            ',' => BrainfuckOp::ReadOp,
+            '[' => BrainfuckOp::LoopStartOp,
+            ']' => BrainfuckOp::LoopEndOp,
+            #[cfg(feature = "self_modifying")]
+            '=' => BrainfuckOp::SelfWriteOp,
+            #[cfg(feature = "debug_ops")]
+            '$' => BrainfuckOp::PrintNumberOp,
+            #[cfg(feature = "debug_ops")]
+            '!' => BrainfuckOp::ResetOp,
+            // invaild char for brainfuck
+            // monostate is returned
+            _   => BrainfuckOp::MonoStateOp,
+        }
+    }
+
+    /// Returns the source character of the op, `None` for `MonoStateOp`.
+    pub fn as_char(self) -> Option<char> {
+        match self {
+            BrainfuckOp::IncrementValueOp => Some('+'),
+            BrainfuckOp::DecrementValueOp => Some('-'),
+            BrainfuckOp::IncrementPtrOp   => Some('>'),
+            BrainfuckOp::DecrementPtrOp   => Some('<'),
+            BrainfuckOp::PrintOp          => Some('.'),
+            BrainfuckOp::ReadOp           => Some(','),
+            BrainfuckOp::LoopStartOp      => Some('['),
+            BrainfuckOp::LoopEndOp        => Some(']'),
+            #[cfg(feature = "self_modifying")]
+            BrainfuckOp::SelfWriteOp      => Some('='),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::PrintNumberOp    => Some('$'),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::ResetOp          => Some('!'),
+            BrainfuckOp::MonoStateOp      => None,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::ir::BrainfuckIR;
+
+/// Returns what a loop with the straight-line `body` can be replaced with,
+/// `None` if it is not a clear, scan or multiplication loop.
+pub(crate) fn simple_loop(body: &[BrainfuckIR]) -> Option<Vec<BrainfuckIR>> {
+    if let [BrainfuckIR::Move(n)] = body {
+        return Some(vec![BrainfuckIR::Scan(*n)]);
+    }
+    if let [BrainfuckIR::Add(n)] = body {
+        // `[-]` and `[+]` both end with a zero cell when cells wrap
+        if n % 2 != 0 {
+            return Some(vec![BrainfuckIR::Clear]);
+        }
+        return None;
+    }
+
+    // a multiplication loop only adds and moves, returns to where
+    // it started and decrements the current cell by one every time
+    let mut offset = 0;
+    let mut decrement = 0;
+    let mut targets: Vec<(i32, i32)> = Vec::new();
+    for node in body {
+        match *node {
+            BrainfuckIR::Move(n) => offset += n,
+            BrainfuckIR::Add(n) if offset == 0 => decrement += n,
+            BrainfuckIR::Add(n) => match targets.iter_mut().find(|(target, _)| *target == offset) {
+                Some((_, factor)) => *factor += n,
+                None => targets.push((offset, n)),
+            },
+            _ => return None,
+        }
+    }
+    if offset != 0 || decrement != -1 {
+        return None;
+    }
+
+    let mut replacement: Vec<BrainfuckIR> = targets
+        .into_iter()
+        .filter(|&(_, factor)| factor != 0)
+        .map(|(offset, factor)| BrainfuckIR::MulAdd { offset, factor })
+        .collect();
+    replacement.push(BrainfuckIR::Clear);
+    Some(replacement)
+}
+
+/// shortest run of `+>+>+` worth an `AddRange`
+const MIN_RANGE: usize = 4;
+
+/// Returns the number of cells the run of `Add(n)`s `ir` starts with adds
+/// `n` to, every one cell right of the one before, and `n`.
+fn add_range(ir: &[BrainfuckIR]) -> Option<(usize, i32)> {
+    let n = match ir.first() {
+        Some(&BrainfuckIR::Add(n)) => n,
+        _ => return None,
+    };
+    let mut len = 1;
+    while let [BrainfuckIR::Move(1), BrainfuckIR::Add(next), ..] = ir[2 * len - 1..] {
+        if next != n {
+            break;
+        }
+        len += 1;
+    }
+    Some((len, n)).filter(|&(len, _)| len >= MIN_RANGE)
+}
+
+/// Replaces clear loops with `Clear`, multiplication loops with `MulAdd`,
+/// scan loops like `[>]` with `Scan` and runs adding the same to at least
+/// four neighbouring cells, like `+>+>+>+`, with `AddRange` and a `Move`
+/// to the last of them.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::{compile, BrainfuckIR};
+/// use rust_bf::optimizer::optimize;
+///
+/// let ir = optimize(&compile(&parse("+++[->++>+<<]>[-]>-->-->-->--.[<<]").unwrap()));
+/// assert_eq!(ir, vec![
+///     BrainfuckIR::Add(3),
+///     BrainfuckIR::MulAdd { offset: 1, factor: 2 },
+///     BrainfuckIR::MulAdd { offset: 2, factor: 1 },
+///     BrainfuckIR::Clear,
+///     BrainfuckIR::Move(1),
+///     BrainfuckIR::Clear,
+///     BrainfuckIR::Move(1),
+///     BrainfuckIR::AddRange { len: 4, n: -2 },
+///     BrainfuckIR::Move(3),
+///     BrainfuckIR::Print,
+///     BrainfuckIR::Scan(-2),
+/// ]);
+/// ```
+pub fn optimize(ir: &[BrainfuckIR]) -> Vec<BrainfuckIR> {
+    let mut optimized = Vec::with_capacity(ir.len());
+    // indices of the `LoopStart`s in `optimized` still waiting for their `LoopEnd`
+    let mut loops: Vec<usize> = Vec::new();
+    let mut i = 0;
+
+    while i < ir.len() {
+        match ir[i] {
+            BrainfuckIR::LoopStart(end) => {
+                if let Some(replacement) = simple_loop(&ir[i + 1..end]) {
+                    optimized.extend(replacement);
+                    i = end + 1;
+                    continue;
+                }
+                loops.push(optimized.len());
+                optimized.push(BrainfuckIR::LoopStart(0));
+            },
+            BrainfuckIR::LoopEnd(_) => {
+                // the IR comes from `compile`, so loops are balanced
+                let start = loops.pop().expect("unbalanced IR");
+                optimized[start] = BrainfuckIR::LoopStart(optimized.len());
+                optimized.push(BrainfuckIR::LoopEnd(start));
+            },
+            BrainfuckIR::Add(_) => {
+                if let Some((len, n)) = add_range(&ir[i..]) {
+                    optimized.push(BrainfuckIR::AddRange { len, n });
+                    optimized.push(BrainfuckIR::Move(len as i32 - 1));
+                    i += 2 * len - 1;
+                    continue;
+                }
+                optimized.push(ir[i]);
+            },
+            node => optimized.push(node),
+        }
+        i += 1;
+    }
+    optimized
+}
@@ -0,0 +1,546 @@
+//! A compact binary form of programs, written by `rust_bf --emit bfc` and
+//! run like any other program file.
+//!
+//! A packed program is laid out as
+//!
+//! | bytes         | what                                                    |
+//! |---------------|---------------------------------------------------------|
+//! | 4             | [`MAGIC`]                                               |
+//! | 1             | [`VERSION`]                                             |
+//! | 4             | number of ops, little endian                            |
+//! | ⌈3 × ops / 8⌉ | the ops, 3 bits each, the first in the lowest bits      |
+//! | 4             | number of loops, little endian                          |
+//! | 8 per loop    | the op index of the `[` and of its `]`, little endian   |
+//!
+//! The jump table at the end lists the loops in the order they start, so
+//! that [`load`] knows where each of them ends without matching brackets.
+//!
+//! Version 2 of the layout, written by [`write_program`], stores runs of
+//! ops instead, which is shorter for programs with long runs of `+`, `-`,
+//! `>` and `<` and no longer than 2 bytes a loop:
+//!
+//! | bytes   | what                                                                     |
+//! |---------|--------------------------------------------------------------------------|
+//! | 4       | [`MAGIC`]                                                                |
+//! | 1       | [`RUNS_VERSION`]                                                         |
+//! | varint  | number of runs                                                           |
+//! | per run | the 3-bit code of the op in a byte, a varint count after `+`, `-`, `>`, `<` |
+//!
+//! Varints are LEB128, 7 bits a byte from the lowest, the high bit set on
+//! every byte but the last. [`unpack`] and [`load`] read both layouts:
+//!
+//! ```
+//! use rust_bf::ir::compile_source;
+//! use rust_bf::packed::{load, pack, unpack, write_program};
+//! use rust_bf::frontend::to_source;
+//!
+//! let src = b"++[->+<] comments are not kept";
+//! let bytes = pack(src).unwrap();
+//! assert_eq!(bytes.len(), 4 + 1 + 4 + 3 + 4 + 8);
+//! assert_eq!(to_source(&unpack(&bytes).unwrap()), "++[->+<]");
+//! assert_eq!(load(&bytes).unwrap(), compile_source(src).unwrap());
+//!
+//! let runs = write_program(&unpack(&bytes).unwrap());
+//! assert_eq!(runs.len(), 4 + 1 + 1 + 2 + 1 + 2 + 2 + 2 + 2 + 1);
+//! assert_eq!(load(&runs).unwrap(), compile_source(src).unwrap());
+//! ```
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+
+use crate::ast;
+use crate::error::BrainfuckError;
+use crate::ir::{self, BrainfuckIR};
+use crate::op::BrainfuckOp;
+
+/// bytes every packed program starts with
+pub const MAGIC: [u8; 4] = *b"\x89BFC";
+
+/// version of the layout written by [`pack`]
+pub const VERSION: u8 = 1;
+
+/// version of the layout of runs of ops written by [`write_program`]
+pub const RUNS_VERSION: u8 = 2;
+
+/// The ops in the order of their 3-bit codes.
+const CODES: [BrainfuckOp; 8] = [
+    BrainfuckOp::IncrementValueOp,
+    BrainfuckOp::DecrementValueOp,
+    BrainfuckOp::IncrementPtrOp,
+    BrainfuckOp::DecrementPtrOp,
+    BrainfuckOp::PrintOp,
+    BrainfuckOp::ReadOp,
+    BrainfuckOp::LoopStartOp,
+    BrainfuckOp::LoopEndOp,
+];
+
+/// Why bytes could not be read as a packed program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackedError {
+    /// the bytes do not start with [`MAGIC`]
+    BadMagic,
+    /// the program was packed in a version of the layout the reader does not read
+    UnsupportedVersion(u8),
+    /// the bytes end in the middle of the header, the ops or the jump table
+    Truncated,
+    /// the entry at this index of the jump table does not match the brackets of the ops
+    InvalidJumpTable {
+        /// index of the entry, the number of entries if a loop has none
+        index: usize,
+    },
+    /// this many bytes follow the jump table or the last run
+    TrailingBytes(usize),
+    /// a run has a byte that is not the code of an op
+    UnknownOp(u8),
+    /// run `run` counts 0 ops, more than 2^31 - 1, or adds up with the
+    /// runs before it to more than that
+    InvalidCount {
+        /// index of the run
+        run: usize,
+    },
+    /// the bracket at this op index of a program of runs has no partner
+    UnmatchedBracket {
+        /// index of the op, the number of ops for a `[` never closed
+        index: usize,
+    },
+}
+
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedError::BadMagic => write!(f, "not a packed program, the magic bytes are missing"),
+            PackedError::UnsupportedVersion(version) => write!(f, "packed program has unsupported version {}", version),
+            PackedError::Truncated => write!(f, "packed program is truncated"),
+            PackedError::InvalidJumpTable { index } => {
+                write!(f, "jump table entry {} does not match the brackets of the packed program", index)
+            },
+            PackedError::TrailingBytes(n) => write!(f, "{} bytes follow the end of the packed program", n),
+            PackedError::UnknownOp(code) => write!(f, "packed program has unknown op code {}", code),
+            PackedError::InvalidCount { run } => write!(f, "run {} of the packed program has an invalid count", run),
+            PackedError::UnmatchedBracket { index } => {
+                write!(f, "bracket at op {} of the packed program has no partner", index)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PackedError {}
+
+/// Returns whether `bytes` start like a packed program, whether or not the
+/// rest of it is valid. No UTF-8 text starts like one.
+pub fn is_packed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Returns the 3-bit code of `op`.
+fn code(op: BrainfuckOp) -> u8 {
+    CODES.iter().position(|&code| code == op).unwrap() as u8
+}
+
+/// Appends `n` in little endian.
+fn push_u32(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+/// Packs the brainfuck source `src`, leaving out its comments.
+///
+/// `=` and the debugging ops have no code of their own and are left out
+/// like comments, as they are where they are not turned on.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::packed::pack;
+///
+/// assert_eq!(pack(b"+-><.,[]").unwrap()[9..12], [0b10_001_000, 0b1_100_011_0, 0b111_110_10]);
+/// assert_eq!(pack(b"[[]").unwrap_err().to_string(), "unmatched '[' at line 1, column 1");
+/// ```
+pub fn pack(src: &[u8]) -> Result<Vec<u8>, BrainfuckError> {
+    let mut ops = Vec::new();
+    // op index of every `[` in order, with the index of its `]` once known
+    let mut jumps = Vec::new();
+    let mut open = Vec::new();
+    for (op, position) in ast::tokens(src) {
+        match op {
+            BrainfuckOp::MonoStateOp => (),
+            // ops the interpreters run once turned on, with no code of their own
+            #[cfg(feature = "self_modifying")]
+            BrainfuckOp::SelfWriteOp => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::PrintNumberOp | BrainfuckOp::ResetOp => (),
+            BrainfuckOp::LoopStartOp => {
+                open.push((jumps.len(), position));
+                jumps.push((ops.len(), 0));
+                ops.push(op);
+            },
+            BrainfuckOp::LoopEndOp => {
+                let (entry, _) = open.pop().ok_or(BrainfuckError::UnmatchedClose(position))?;
+                jumps[entry].1 = ops.len();
+                ops.push(op);
+            },
+            _ => ops.push(op),
+        }
+    }
+    if let Some((_, position)) = open.pop() {
+        return Err(BrainfuckError::UnmatchedOpen(position));
+    }
+
+    let mut bytes = Vec::with_capacity(17 + ops.len() * 3 / 8 + jumps.len() * 8);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(VERSION);
+    push_u32(&mut bytes, ops.len());
+    let start = bytes.len();
+    bytes.resize(start + (ops.len() * 3).div_ceil(8), 0);
+    for (i, &op) in ops.iter().enumerate() {
+        let bit = i * 3;
+        let code = u16::from(code(op)) << (bit % 8);
+        bytes[start + bit / 8] |= code as u8;
+        if code > 0xff {
+            bytes[start + bit / 8 + 1] |= (code >> 8) as u8;
+        }
+    }
+    push_u32(&mut bytes, jumps.len());
+    for (open, close) in jumps {
+        push_u32(&mut bytes, open);
+        push_u32(&mut bytes, close);
+    }
+    Ok(bytes)
+}
+
+/// A packed program split into its parts, the jump table not yet checked.
+struct Parts<'a> {
+    /// number of ops
+    len: usize,
+    /// the packed ops
+    ops: &'a [u8],
+    /// the jump table, 8 bytes an entry
+    jumps: &'a [u8],
+}
+
+impl Parts<'_> {
+    /// Returns the op at index `i`.
+    fn op(&self, i: usize) -> BrainfuckOp {
+        let bit = i * 3;
+        let low = u16::from(self.ops[bit / 8]);
+        let high = self.ops.get(bit / 8 + 1).map_or(0, |&byte| u16::from(byte) << 8);
+        CODES[usize::from(((low | high) >> (bit % 8)) & 7)]
+    }
+
+    /// Returns entry `i` of the jump table.
+    fn jump(&self, i: usize) -> (usize, usize) {
+        let entry = &self.jumps[i * 8..i * 8 + 8];
+        (read_u32(&entry[..4]), read_u32(&entry[4..]))
+    }
+
+    /// Returns the ops one by one, each with the jump table entry of its
+    /// loop if it is a bracket, failing at the first bracket the jump
+    /// table is wrong about.
+    fn walk(&self, mut visit: impl FnMut(BrainfuckOp, usize)) -> Result<(), PackedError> {
+        let entries = self.jumps.len() / 8;
+        let mut next = 0;
+        // entries of the loops the op is in, innermost last
+        let mut open = Vec::new();
+        for i in 0..self.len {
+            let op = self.op(i);
+            match op {
+                BrainfuckOp::LoopStartOp => {
+                    match (next < entries).then(|| self.jump(next)) {
+                        Some((start, end)) if start == i && end > i && end < self.len => (),
+                        _ => return Err(PackedError::InvalidJumpTable { index: next }),
+                    }
+                    open.push(next);
+                    visit(op, next);
+                    next += 1;
+                },
+                BrainfuckOp::LoopEndOp => {
+                    let entry = open.pop().ok_or(PackedError::InvalidJumpTable { index: next })?;
+                    if self.jump(entry).1 != i {
+                        return Err(PackedError::InvalidJumpTable { index: entry });
+                    }
+                    visit(op, entry);
+                },
+                _ => visit(op, 0),
+            }
+        }
+        match open.pop() {
+            Some(entry) => Err(PackedError::InvalidJumpTable { index: entry }),
+            None if next < entries => Err(PackedError::InvalidJumpTable { index: next }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads a little endian `u32` from the four bytes of `bytes`.
+fn read_u32(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(bytes.try_into().unwrap()) as usize
+}
+
+/// Returns the version of the layout of the packed program `bytes`, one
+/// of the two there are.
+fn version(bytes: &[u8]) -> Result<u8, PackedError> {
+    if !is_packed(bytes) {
+        return Err(PackedError::BadMagic);
+    }
+    match bytes.get(4) {
+        None => Err(PackedError::Truncated),
+        Some(&version @ (VERSION | RUNS_VERSION)) => Ok(version),
+        Some(&version) => Err(PackedError::UnsupportedVersion(version)),
+    }
+}
+
+/// Splits `bytes` into the parts of a packed program of [`VERSION`].
+fn split(bytes: &[u8]) -> Result<Parts<'_>, PackedError> {
+    let rest = &bytes[5..];
+    let len = rest.get(..4).map(read_u32).ok_or(PackedError::Truncated)?;
+    // a count too large to fit the bytes there are is as truncated as a short file
+    let packed = len.checked_mul(3).map(|bits| bits.div_ceil(8)).ok_or(PackedError::Truncated)?;
+    let ops = rest.get(4..).and_then(|rest| rest.get(..packed)).ok_or(PackedError::Truncated)?;
+    let rest = &rest[4 + packed..];
+    let entries = rest.get(..4).map(read_u32).ok_or(PackedError::Truncated)?;
+    let table = entries.checked_mul(8).ok_or(PackedError::Truncated)?;
+    let jumps = rest[4..].get(..table).ok_or(PackedError::Truncated)?;
+    match rest.len() - 4 - jumps.len() {
+        0 => Ok(Parts { len, ops, jumps }),
+        n => Err(PackedError::TrailingBytes(n)),
+    }
+}
+
+/// Reads the ops of the packed program `bytes`, of either layout.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::packed::{pack, unpack, PackedError};
+///
+/// let mut bytes = pack(b"+[>]").unwrap();
+/// assert_eq!(unpack(&bytes).unwrap().len(), 4);
+///
+/// assert_eq!(unpack(b"+[>]"), Err(PackedError::BadMagic));
+/// assert_eq!(unpack(&bytes[..bytes.len() - 1]), Err(PackedError::Truncated));
+/// // the `]` moved from op 3 to op 2
+/// bytes[19] = 2;
+/// assert_eq!(unpack(&bytes), Err(PackedError::InvalidJumpTable { index: 0 }));
+/// ```
+pub fn unpack(bytes: &[u8]) -> Result<Vec<BrainfuckOp>, PackedError> {
+    if version(bytes)? == RUNS_VERSION {
+        return read_program(bytes);
+    }
+    let parts = split(bytes)?;
+    let mut ops = Vec::with_capacity(parts.len);
+    parts.walk(|op, _| ops.push(op))?;
+    Ok(ops)
+}
+
+/// Compiles the packed program `bytes` into the same IR as
+/// [`ir::compile_source`] makes of its source, the loops linked up from
+/// the jump table, or straight from the runs of the second layout.
+pub fn load(bytes: &[u8]) -> Result<Vec<BrainfuckIR>, PackedError> {
+    if version(bytes)? == RUNS_VERSION {
+        return Ok(load_runs(&runs(bytes)?));
+    }
+    let parts = split(bytes)?;
+    let mut ir = Vec::new();
+    // IR index of the `LoopStart` of every entry seen so far
+    let mut starts = Vec::with_capacity(parts.jumps.len() / 8);
+    parts.walk(|op, entry| match op {
+        BrainfuckOp::IncrementValueOp => ir::push_folded(&mut ir, BrainfuckIR::Add(1)),
+        BrainfuckOp::DecrementValueOp => ir::push_folded(&mut ir, BrainfuckIR::Add(-1)),
+        BrainfuckOp::IncrementPtrOp => ir::push_folded(&mut ir, BrainfuckIR::Move(1)),
+        BrainfuckOp::DecrementPtrOp => ir::push_folded(&mut ir, BrainfuckIR::Move(-1)),
+        BrainfuckOp::PrintOp => ir.push(BrainfuckIR::Print),
+        BrainfuckOp::ReadOp => ir.push(BrainfuckIR::Read),
+        BrainfuckOp::LoopStartOp => {
+            starts.push(ir.len());
+            ir.push(BrainfuckIR::LoopStart(0));
+        },
+        _ => {
+            let start = starts[entry];
+            ir[start] = BrainfuckIR::LoopStart(ir.len());
+            ir.push(BrainfuckIR::LoopEnd(start));
+        },
+    })?;
+    Ok(ir)
+}
+
+/// Appends `n` as a LEB128 varint.
+fn push_varint(bytes: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+/// Reads a LEB128 varint of at most 32 bits from the start of `bytes`,
+/// returning it and the bytes after it, `None` if the bytes end first or
+/// it does not fit.
+fn read_varint(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let mut n = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(5) {
+        n |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return (n <= u64::from(u32::MAX)).then(|| (n as usize, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Writes `ops` in version 2 of the layout, a run of ops of one kind in a
+/// row as its code and how many there are, so that loading them skips
+/// parsing the source.
+///
+/// Brackets are written as they are, [`read_program`] checks that they
+/// match. `MonoStateOp`, `=` and the debugging ops are left out.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::frontend::{Brainfuck, Frontend};
+/// use rust_bf::packed::{read_program, write_program};
+///
+/// let ops = Brainfuck.parse(&[b'+'; 300]).unwrap();
+/// let bytes = write_program(&ops);
+/// // the code of `+` and 300 in two bytes
+/// assert_eq!(bytes[5..], [1, 0, 0b1010_1100, 0b10]);
+/// assert_eq!(read_program(&bytes).unwrap(), ops);
+/// ```
+pub fn write_program(ops: &[BrainfuckOp]) -> Vec<u8> {
+    let mut runs: Vec<(BrainfuckOp, usize)> = Vec::new();
+    let mut push = |op: BrainfuckOp, n: usize| match runs.last_mut() {
+        Some((last, count)) if *last == op && folds(op) && *count + n <= i32::MAX as usize => *count += n,
+        _ => runs.push((op, n)),
+    };
+    for &op in ops {
+        match op {
+            BrainfuckOp::MonoStateOp => (),
+            #[cfg(feature = "self_modifying")]
+            BrainfuckOp::SelfWriteOp => (),
+            #[cfg(feature = "debug_ops")]
+            BrainfuckOp::PrintNumberOp | BrainfuckOp::ResetOp => (),
+            op => push(op, 1),
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(7 + runs.len() * 2);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(RUNS_VERSION);
+    push_varint(&mut bytes, runs.len());
+    for (op, count) in runs {
+        bytes.push(code(op));
+        if folds(op) {
+            push_varint(&mut bytes, count);
+        }
+    }
+    bytes
+}
+
+/// Returns whether runs of `op` are written with a count.
+fn folds(op: BrainfuckOp) -> bool {
+    matches!(
+        op,
+        BrainfuckOp::IncrementValueOp | BrainfuckOp::DecrementValueOp | BrainfuckOp::IncrementPtrOp | BrainfuckOp::DecrementPtrOp
+    )
+}
+
+/// Reads the runs of a program of [`RUNS_VERSION`], checking the brackets.
+fn runs(bytes: &[u8]) -> Result<Vec<(BrainfuckOp, usize)>, PackedError> {
+    let (len, mut rest) = read_varint(&bytes[5..]).ok_or(PackedError::Truncated)?;
+    // every run takes a byte at least, so the count cannot ask for more room than there are bytes
+    let mut runs = Vec::with_capacity(len.min(rest.len()));
+    // op index of the next op and of every `[` still open
+    let mut index = 0;
+    let mut open = Vec::new();
+    for run in 0..len {
+        let (&byte, after) = rest.split_first().ok_or(PackedError::Truncated)?;
+        let op = *CODES.get(usize::from(byte)).ok_or(PackedError::UnknownOp(byte))?;
+        rest = after;
+        let count = if folds(op) {
+            let (count, after) = read_varint(rest).ok_or(PackedError::Truncated)?;
+            rest = after;
+            count
+        } else {
+            1
+        };
+        // all the runs together stay within what a fold of them can hold
+        if count == 0 || count > i32::MAX as usize - index {
+            return Err(PackedError::InvalidCount { run });
+        }
+        match op {
+            BrainfuckOp::LoopStartOp => open.push(index),
+            BrainfuckOp::LoopEndOp => {
+                open.pop().ok_or(PackedError::UnmatchedBracket { index })?;
+            },
+            _ => (),
+        }
+        runs.push((op, count));
+        index += count;
+    }
+    if let Some(start) = open.pop() {
+        return Err(PackedError::UnmatchedBracket { index: start });
+    }
+    match rest.len() {
+        0 => Ok(runs),
+        n => Err(PackedError::TrailingBytes(n)),
+    }
+}
+
+/// Compiles `runs` into IR, each run of `+`, `-`, `>` or `<` folded into
+/// the node before it like a single op would be.
+///
+/// [`runs`] keeps the ops to 2^31 - 1 in all, so no fold overflows.
+fn load_runs(runs: &[(BrainfuckOp, usize)]) -> Vec<BrainfuckIR> {
+    let mut ir = Vec::new();
+    // IR index of the `LoopStart` of every loop still open
+    let mut starts = Vec::new();
+    for &(op, count) in runs {
+        let n = count as i32;
+        let node = match op {
+            BrainfuckOp::IncrementValueOp => BrainfuckIR::Add(n),
+            BrainfuckOp::DecrementValueOp => BrainfuckIR::Add(-n),
+            BrainfuckOp::IncrementPtrOp => BrainfuckIR::Move(n),
+            BrainfuckOp::DecrementPtrOp => BrainfuckIR::Move(-n),
+            BrainfuckOp::PrintOp => BrainfuckIR::Print,
+            BrainfuckOp::ReadOp => BrainfuckIR::Read,
+            BrainfuckOp::LoopStartOp => {
+                starts.push(ir.len());
+                BrainfuckIR::LoopStart(0)
+            },
+            _ => {
+                // `runs` checked the brackets
+                let start = starts.pop().expect("brackets match");
+                ir[start] = BrainfuckIR::LoopStart(ir.len());
+                BrainfuckIR::LoopEnd(start)
+            },
+        };
+        ir::push_folded(&mut ir, node);
+    }
+    ir
+}
+
+/// Reads the ops of a program [`write_program`] wrote, every run written
+/// out op by op. Programs [`pack`] wrote fail with
+/// [`PackedError::UnsupportedVersion`], [`unpack`] reads both.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::frontend::{Brainfuck, Frontend};
+/// use rust_bf::packed::{read_program, write_program, PackedError};
+///
+/// let bytes = write_program(&Brainfuck.parse(b"+[>]]").unwrap());
+/// assert_eq!(read_program(&bytes), Err(PackedError::UnmatchedBracket { index: 4 }));
+/// assert_eq!(read_program(&bytes[..bytes.len() - 1]), Err(PackedError::Truncated));
+/// ```
+pub fn read_program(bytes: &[u8]) -> Result<Vec<BrainfuckOp>, PackedError> {
+    match version(bytes)? {
+        RUNS_VERSION => (),
+        version => return Err(PackedError::UnsupportedVersion(version)),
+    }
+    let mut ops = Vec::new();
+    for (op, count) in runs(bytes)? {
+        ops.extend(core::iter::repeat_n(op, count));
+    }
+    Ok(ops)
+}
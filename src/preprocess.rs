@@ -0,0 +1,397 @@
+//! Macros for brainfuck written by hand, expanded into plain brainfuck
+//! before parsing, see `rust_bf --pp`.
+//!
+//! `#define name { ... }` defines a macro anywhere in the source and
+//! `@name` expands it anywhere else, before or after the definition and
+//! inside other macros. Names are letters, digits and `_`, not starting
+//! with a digit. An `@` not followed by a name is a comment like any other
+//! character.
+//!
+//! ```text
+//! #define ten { ++++++++++ }
+//! #define times_ten { [>@ten<-]> }
+//! +++++++ @times_ten ++ .
+//! ```
+//!
+//! A line `#include "path.bf"` is replaced by the file it names, relative
+//! to the file it is in, before macros are expanded, see [`include`].
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::ast::Position;
+use crate::error::BrainfuckError;
+
+/// how many macros may be expanded inside each other
+const MAX_DEPTH: usize = 64;
+
+/// what starts a definition
+const DEFINE: &str = "#define";
+
+/// what starts an include line
+const INCLUDE: &str = "#include";
+
+/// what the loader of [`include`] returns, the name and source of a file or why it cannot be loaded
+type Loaded = Result<(String, String), String>;
+
+/// An error in the macros of a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreprocessError {
+    /// where in the source the error is
+    pub position: Position,
+    /// what is wrong there
+    pub message: String,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PreprocessError {}
+
+/// A program with its macros expanded, which remembers where every
+/// character of it came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Expanded {
+    /// the plain brainfuck to parse
+    pub source: String,
+    /// for every byte of `source`, where it was in the original source
+    origins: Vec<Position>,
+}
+
+impl Expanded {
+    /// Returns where the character at `at` in the expanded source was in
+    /// the original source, inside the macro it came from if it did.
+    pub fn original_position(&self, at: Position) -> Position {
+        self.origins.get(at.offset).copied().unwrap_or(at)
+    }
+
+    /// Points the position of a bracket error in the expanded source to the original source.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::ast::parse;
+    /// use rust_bf::preprocess::preprocess;
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let src = "#define open { + [ }\n@open -";
+    /// let expanded = preprocess(src).unwrap();
+    /// let err = expanded.map_error(parse(&expanded.source).unwrap_err());
+    /// // the `[` in the definition on line 1
+    /// assert!(matches!(err, BrainfuckError::UnmatchedOpen(at) if (at.line, at.column) == (1, 18)));
+    /// ```
+    pub fn map_error(&self, err: BrainfuckError) -> BrainfuckError {
+        match err {
+            BrainfuckError::UnmatchedOpen(at) => BrainfuckError::UnmatchedOpen(self.original_position(at)),
+            BrainfuckError::UnmatchedClose(at) => BrainfuckError::UnmatchedClose(self.original_position(at)),
+            err => err,
+        }
+    }
+
+    /// Appends `text`, found at `start` in `src`, to the expanded source.
+    fn push(&mut self, src: &str, start: usize, text: &str) {
+        let mut origin = position(src, start);
+        for c in text.chars() {
+            self.source.push(c);
+            self.origins.extend(core::iter::repeat_n(origin, c.len_utf8()));
+            origin.offset += c.len_utf8();
+            if c == '\n' {
+                origin.line += 1;
+                origin.column = 1;
+            } else {
+                origin.column += 1;
+            }
+        }
+    }
+}
+
+/// An error in the includes of a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncludeError {
+    /// the file the error is in
+    pub file: String,
+    /// where in that file the error is
+    pub position: Position,
+    /// what is wrong there
+    pub message: String,
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file, self.position.line, self.position.column, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IncludeError {}
+
+/// Where a character of an [`Included`] program came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Origin<'a> {
+    /// name of the file it is in
+    pub file: &'a str,
+    /// the whole source of that file
+    pub source: &'a str,
+    /// where in that file it is
+    pub position: Position,
+}
+
+/// A program with every file it includes spliced in, which remembers
+/// which file every part of it came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Included {
+    /// the program with every include line replaced by the file it names
+    pub source: String,
+    /// name and source of every file, the program itself first
+    files: Vec<(String, String)>,
+    /// where each part of `source` starts, the file it is from and its offset in that file, in order
+    parts: Vec<(usize, usize, usize)>,
+}
+
+impl Included {
+    /// Returns where the character at byte `offset` of `source` came from.
+    pub fn origin(&self, offset: usize) -> Origin<'_> {
+        let part = self.parts.iter().rposition(|&(start, _, _)| start <= offset);
+        let (file, file_offset) = match part {
+            Some(part) => {
+                let (start, file, file_offset) = self.parts[part];
+                (file, file_offset + offset - start)
+            },
+            None => (0, 0),
+        };
+        let (file, source) = &self.files[file];
+        Origin { file, source, position: position(source, file_offset) }
+    }
+
+    /// Appends `files[file].1[range]` to the source.
+    fn push(&mut self, file: usize, range: Range<usize>) {
+        if !range.is_empty() {
+            self.parts.push((self.source.len(), file, range.start));
+            self.source.push_str(&self.files[file].1[range]);
+        }
+    }
+}
+
+/// Returns the position of byte `offset` of `src`.
+fn position(src: &str, offset: usize) -> Position {
+    let line_start = src[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    Position {
+        offset,
+        line: src[..offset].matches('\n').count() + 1,
+        column: src[line_start..offset].chars().count() + 1,
+    }
+}
+
+/// Returns the length of the macro name at the start of `text`, 0 if there is none.
+fn name_len(text: &str) -> usize {
+    match text.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(text.len())
+        },
+        _ => 0,
+    }
+}
+
+/// Expands every macro of `src`.
+///
+/// Definitions leave nothing behind in the expanded source, everything
+/// else is kept as it is, comments included.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::preprocess::preprocess;
+///
+/// let expanded = preprocess("#define two { ++ }\n#define four { @two@two }\n@four>@two").unwrap();
+/// assert_eq!(expanded.source, "\n\n  ++  ++  > ++ ");
+///
+/// let err = preprocess("@three").unwrap_err();
+/// assert_eq!(err.to_string(), "1:1: macro `three` is not defined");
+/// ```
+pub fn preprocess(src: &str) -> Result<Expanded, PreprocessError> {
+    let error = |offset, message: String| PreprocessError { position: position(src, offset), message };
+    let mut definitions: BTreeMap<&str, Range<usize>> = BTreeMap::new();
+    // the parts of `src` outside definitions
+    let mut text = Vec::new();
+    let mut rest = 0;
+    while let Some(found) = src[rest..].find(DEFINE) {
+        let start = rest + found;
+        text.push(rest..start);
+        let after = start + DEFINE.len();
+        let name_start = after + (src[after..].len() - src[after..].trim_start().len());
+        let name = &src[name_start..name_start + name_len(&src[name_start..])];
+        if name.is_empty() || name_start == after {
+            return Err(error(start, String::from("expected a macro name after `#define`")));
+        }
+        let after_name = name_start + name.len();
+        let open = after_name + (src[after_name..].len() - src[after_name..].trim_start().len());
+        if !src[open..].starts_with('{') {
+            return Err(error(start, format!("expected `{{` after `#define {}`", name)));
+        }
+        let close = match src[open..].find('}') {
+            Some(close) => open + close,
+            None => return Err(error(start, format!("`#define {}` is never closed by a `}}`", name))),
+        };
+        if let Some(nested) = src[open..close].find(DEFINE) {
+            return Err(error(open + nested, format!("`#define` inside the definition of `{}`", name)));
+        }
+        if definitions.insert(name, open + 1..close).is_some() {
+            return Err(error(start, format!("macro `{}` is defined twice", name)));
+        }
+        rest = close + 1;
+    }
+    text.push(rest..src.len());
+
+    let mut expanded = Expanded::default();
+    let mut stack = Vec::new();
+    for range in text {
+        expand(src, range, &definitions, &mut stack, &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+/// Appends `src[range]` to `expanded` with every macro call in it
+/// expanded, `stack` holding the calls being expanded.
+fn expand<'a>(
+    src: &'a str,
+    range: Range<usize>,
+    definitions: &BTreeMap<&'a str, Range<usize>>,
+    stack: &mut Vec<&'a str>,
+    expanded: &mut Expanded,
+) -> Result<(), PreprocessError> {
+    let error = |offset, message: String| PreprocessError { position: position(src, offset), message };
+    let mut rest = range.start;
+    while let Some(found) = src[rest..range.end].find('@') {
+        let at = rest + found;
+        let name = &src[at + 1..at + 1 + name_len(&src[at + 1..range.end])];
+        if name.is_empty() {
+            expanded.push(src, rest, &src[rest..at + 1]);
+            rest = at + 1;
+            continue;
+        }
+        expanded.push(src, rest, &src[rest..at]);
+        let body = match definitions.get(name) {
+            Some(body) => body.clone(),
+            None => return Err(error(at, format!("macro `{}` is not defined", name))),
+        };
+        if stack.contains(&name) {
+            let mut chain = stack.join(" -> ");
+            chain.push_str(" -> ");
+            chain.push_str(name);
+            return Err(error(at, format!("macro `{}` expands itself: {}", name, chain)));
+        }
+        if stack.len() >= MAX_DEPTH {
+            return Err(error(at, format!("macros are expanded more than {} deep", MAX_DEPTH)));
+        }
+        stack.push(name);
+        expand(src, body, definitions, stack, expanded)?;
+        stack.pop();
+        rest = at + 1 + name.len();
+    }
+    expanded.push(src, rest, &src[rest..range.end]);
+    Ok(())
+}
+
+/// Splices every file `src`, the program in file `name`, includes into it,
+/// and every file those include in turn.
+///
+/// `load` is given the name of the including file and the path as
+/// written, and returns the name and source of the file it names, or why
+/// it cannot. [`load_relative`] loads files relative to the including one.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::preprocess::include;
+///
+/// let load = |_: &str, path: &str| match path {
+///     "two.bf" => Ok((String::from("two.bf"), String::from("++\n"))),
+///     _ => Err(String::from("no such file")),
+/// };
+/// let included = include("main.bf", "+\n#include \"two.bf\"\n.", load).unwrap();
+/// assert_eq!(included.source, "+\n++\n\n.");
+/// let origin = included.origin(3);
+/// assert_eq!((origin.file, origin.position.line, origin.position.column), ("two.bf", 1, 2));
+///
+/// let err = include("main.bf", "\n  #include \"three.bf\"", load).unwrap_err();
+/// assert_eq!(err.to_string(), "main.bf:2:3: cannot include \"three.bf\": no such file");
+/// ```
+pub fn include(
+    name: &str,
+    src: &str,
+    mut load: impl FnMut(&str, &str) -> Result<(String, String), String>,
+) -> Result<Included, IncludeError> {
+    let mut included = Included { files: alloc::vec![(String::from(name), String::from(src))], ..Included::default() };
+    let mut stack = alloc::vec![String::from(name)];
+    splice(&mut included, 0, &mut stack, &mut load)?;
+    Ok(included)
+}
+
+/// Appends file `file` of `included` with its includes spliced in, `stack`
+/// holding the names of the files being spliced.
+fn splice(
+    included: &mut Included,
+    file: usize,
+    stack: &mut Vec<String>,
+    load: &mut dyn FnMut(&str, &str) -> Loaded,
+) -> Result<(), IncludeError> {
+    let (including, src) = included.files[file].clone();
+    let error = |offset, message: String| IncludeError { file: including.clone(), position: position(&src, offset), message };
+    let mut rest = 0;
+    let mut line_start = 0;
+    for line in src.split_inclusive('\n') {
+        let start = line_start;
+        line_start += line.len();
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with(INCLUDE) {
+            continue;
+        }
+        let at = start + line.len() - trimmed.len();
+        let quoted = trimmed[INCLUDE.len()..].trim();
+        let path = match quoted.strip_prefix('"').and_then(|quoted| quoted.strip_suffix('"')) {
+            Some(path) if !path.is_empty() && !path.contains('"') => path,
+            _ => return Err(error(at, String::from("expected a quoted path after `#include`"))),
+        };
+        let (name, source) = load(&including, path)
+            .map_err(|reason| error(at, format!("cannot include \"{}\": {}", path, reason)))?;
+        if stack.contains(&name) {
+            let chain = stack.join(" -> ");
+            return Err(error(at, format!("`{}` includes itself: {} -> {}", name, chain, name)));
+        }
+        if stack.len() > MAX_DEPTH {
+            return Err(error(at, format!("files are included more than {} deep", MAX_DEPTH)));
+        }
+        included.push(file, rest..at);
+        // the newline ending the include line stays
+        rest = start + line.trim_end_matches(['\n', '\r']).len();
+        included.files.push((name.clone(), source));
+        stack.push(name);
+        splice(included, included.files.len() - 1, stack, load)?;
+        stack.pop();
+    }
+    included.push(file, rest..src.len());
+    Ok(())
+}
+
+/// Loads `path` relative to the directory of the file `including`, the
+/// loader [`include`] takes for files on disk.
+#[cfg(feature = "std")]
+pub fn load_relative(including: &str, path: &str) -> Result<(String, String), String> {
+    let resolved = match std::path::Path::new(including).parent() {
+        Some(dir) => dir.join(path),
+        None => std::path::PathBuf::from(path),
+    };
+    let name = resolved.to_string_lossy().into_owned();
+    match std::fs::read_to_string(&resolved) {
+        Ok(source) => Ok((name, source)),
+        Err(err) => Err(format!("{}: {}", name, err)),
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::ast::{self, BrainfuckNode};
+use crate::error::BrainfuckError;
+use crate::ir::{self, BrainfuckIR};
+
+/// A parsed program whose brackets are known to match.
+///
+/// Made with `Program::try_from(src)`, and shown as its ops with every
+/// comment and all whitespace left out, which parses into the same program.
+/// The program is compiled once when it is made, clones share it, see
+/// [`Interpreter::set_program`](crate::Interpreter::set_program). Programs
+/// can be shared between threads, see [`batch::run_many`](crate::batch::run_many).
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use rust_bf::{BrainfuckError, Program};
+///
+/// let program = Program::try_from("print 8: ++++++++[>++++++<-]>.").unwrap();
+/// assert_eq!(program.to_string(), "++++++++[>++++++<-]>.");
+/// assert!(matches!(Program::try_from("[").unwrap_err(), BrainfuckError::UnmatchedOpen(_)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Program {
+    /// syntax tree of the program
+    ast: Arc<[BrainfuckNode]>,
+    /// the program compiled to IR
+    ir: Arc<[BrainfuckIR]>,
+    /// number of loops
+    loops: usize,
+    /// most loops nested inside each other
+    depth: usize,
+}
+
+impl Program {
+    /// Returns the syntax tree.
+    pub fn ast(&self) -> &[BrainfuckNode] {
+        &self.ast
+    }
+
+    /// Returns the program compiled to IR, see [`ir::compile`].
+    pub fn compile(&self) -> Vec<BrainfuckIR> {
+        self.ir.to_vec()
+    }
+
+    /// Returns how many loops the program has.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use rust_bf::Program;
+    ///
+    /// let program = Program::try_from("+[>[-]<[>+<-]]>[.]").unwrap();
+    /// assert_eq!((program.loop_count(), program.max_loop_depth()), (4, 2));
+    /// ```
+    pub fn loop_count(&self) -> usize {
+        self.loops
+    }
+
+    /// Returns how deep loops are nested inside each other at most, 0 for a program without loops.
+    pub fn max_loop_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the compiled program without copying it.
+    pub(crate) fn shared_ir(&self) -> Arc<[BrainfuckIR]> {
+        Arc::clone(&self.ir)
+    }
+}
+
+impl TryFrom<&str> for Program {
+    type Error = BrainfuckError;
+
+    fn try_from(src: &str) -> Result<Program, BrainfuckError> {
+        let ast = ast::parse(src)?;
+        let ir: Arc<[BrainfuckIR]> = ir::compile(&ast).into();
+        let (mut loops, mut depth, mut open) = (0, 0, 0);
+        for node in ir.iter() {
+            match node {
+                BrainfuckIR::LoopStart(_) => {
+                    loops += 1;
+                    open += 1;
+                    depth = depth.max(open);
+                },
+                BrainfuckIR::LoopEnd(_) => open -= 1,
+                _ => (),
+            }
+        }
+        Ok(Program { ast: ast.into(), ir, loops, depth })
+    }
+}
+
+/// Writes the ops of `nodes`, loops with their brackets.
+fn write_nodes(f: &mut fmt::Formatter<'_>, nodes: &[BrainfuckNode]) -> fmt::Result {
+    // the nodes left of every body being written, `nodes` at the bottom
+    let mut bodies = alloc::vec![nodes.iter()];
+    while let Some(rest) = bodies.last_mut() {
+        match rest.next() {
+            Some(BrainfuckNode::Op(op, _)) => write!(f, "{}", op.as_char().expect("the parser keeps ops only"))?,
+            Some(BrainfuckNode::Loop { body, .. }) => {
+                f.write_str("[")?;
+                bodies.push(body.iter());
+            },
+            None => {
+                bodies.pop();
+                if !bodies.is_empty() {
+                    f.write_str("]")?;
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_nodes(f, &self.ast)
+    }
+}
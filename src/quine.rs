@@ -0,0 +1,40 @@
+use alloc::vec::Vec;
+
+use crate::error::BrainfuckError;
+use crate::Interpreter;
+
+/// Returns whether `c` is one of the eight ops.
+fn is_op(c: &char) -> bool {
+    "+-<>.,[]".contains(*c)
+}
+
+/// Returns whether `src` prints its own ops when run with no input.
+///
+/// Everything but the ops is dropped from both the source and the output
+/// before they are compared, so comments neither have to be printed nor
+/// get in the way.
+///
+/// # Arguments
+///
+/// * `src`              - brainfuck source
+/// * `max_instructions` - IR steps the run may take, see [`Interpreter::with_step_limit`]
+///
+/// Fails like the run does, with [`BrainfuckError::StepLimitExceeded`]
+/// for programs that do not halt in time.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::quine::is_quine;
+///
+/// // prints nothing, and so does the program without its ops
+/// assert!(is_quine("a quine that", 100).unwrap());
+/// assert!(!is_quine("++++++++[>+++++<-]>+++.", 1000).unwrap());
+/// assert!(is_quine("+[]", 1000).is_err());
+/// ```
+pub fn is_quine(src: &str, max_instructions: u64) -> Result<bool, BrainfuckError> {
+    let mut output = Vec::new();
+    Interpreter::new(src)?.with_step_limit(max_instructions).input(&[][..]).output(&mut output).run()?;
+    let printed = output.iter().map(|&byte| byte as char).filter(is_op);
+    Ok(printed.eq(src.chars().filter(is_op)))
+}
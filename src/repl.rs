@@ -0,0 +1,94 @@
+//! The line history of `rust_bf --repl`, kept from one session to the next.
+//!
+//! Every line run at the prompt is added as it was typed, and a line that
+//! is just `!N` runs entry `N` again, counting from 1 for the oldest.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// name of the history file in the home directory
+pub const HISTORY_FILE: &str = ".brainfuck_history";
+
+/// most entries kept in the history file, older ones are dropped when saving
+const MAX_ENTRIES: usize = 1000;
+
+/// Lines run at the prompt, oldest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BrainfuckREPLHistory {
+    /// every entry, raw brainfuck source
+    entries: Vec<String>,
+}
+
+impl BrainfuckREPLHistory {
+    /// Returns an empty history.
+    pub fn new() -> BrainfuckREPLHistory {
+        BrainfuckREPLHistory::default()
+    }
+
+    /// Reads the history from `path`, one entry per line, an empty history if there is no such file.
+    pub fn load(path: &Path) -> io::Result<BrainfuckREPLHistory> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(BrainfuckREPLHistory { entries: text.lines().map(String::from).collect() }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BrainfuckREPLHistory::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writes the latest 1000 entries to `path`, one per line.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let start = self.entries.len().saturating_sub(MAX_ENTRIES);
+        let mut text = String::new();
+        for entry in &self.entries[start..] {
+            text.push_str(entry);
+            text.push('\n');
+        }
+        fs::write(path, text)
+    }
+
+    /// Returns every entry, oldest first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Adds `line` unless it is blank or the same as the latest entry.
+    pub fn push(&mut self, line: &str) {
+        if !line.trim().is_empty() && self.entries.last().map(String::as_str) != Some(line) {
+            self.entries.push(String::from(line));
+        }
+    }
+
+    /// Returns the line to run for `line` typed at the prompt, entry `N`
+    /// for a line that is just `!N`.
+    ///
+    /// Any other line runs as it is, also one starting with `!`, which is
+    /// an op with the `debug_ops` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::repl::BrainfuckREPLHistory;
+    ///
+    /// let mut history = BrainfuckREPLHistory::new();
+    /// history.push("++[>+<-]");
+    /// history.push(">.");
+    /// assert_eq!(history.resolve("!1"), Ok("++[>+<-]"));
+    /// assert_eq!(history.resolve("+."), Ok("+."));
+    /// assert_eq!(history.resolve("!+."), Ok("!+."));
+    /// assert_eq!(history.resolve("!3"), Err(String::from("no history entry 3, there are 2")));
+    /// ```
+    pub fn resolve<'a>(&'a self, line: &'a str) -> Result<&'a str, String> {
+        let number = match line.trim().strip_prefix('!') {
+            Some(number) if !number.is_empty() && number.bytes().all(|c| c.is_ascii_digit()) => number,
+            _ => return Ok(line),
+        };
+        let entry = number.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).and_then(|n| self.entries.get(n));
+        entry.map(String::as_str).ok_or_else(|| format!("no history entry {}, there are {}", number, self.entries.len()))
+    }
+}
+
+/// Returns where the history is kept, `~/.brainfuck_history`, `None` without a home directory.
+pub fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(HISTORY_FILE))
+}
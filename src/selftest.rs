@@ -0,0 +1,180 @@
+//! Conformance checks of the VM semantics, see `rust_bf --self-test`.
+//!
+//! Every check runs a small program through [`run_vm`] a character at a
+//! time, the way the command line runs programs, on a VM set up like the
+//! one being checked, and compares what it left behind with what the VM
+//! settings call for.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::BrainfuckError;
+use crate::io::EofMode;
+use crate::vm::{new_brainfuck_status, run_vm, BrainfuckVMStatus};
+
+/// loops nested inside each other by the deep nesting check
+const NESTING: usize = 1000;
+
+/// how far the large moves check moves the pointer each way
+const DISTANCE: usize = 100_000;
+
+/// A check, given how to set up its VMs.
+type Check = fn(&dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String>;
+
+/// Outcome of one check of [`self_test`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckResult {
+    /// what the check is about
+    pub name: &'static str,
+    /// what went wrong, `None` if the check passed
+    pub failure: Option<String>,
+}
+
+impl CheckResult {
+    /// Returns whether the check passed.
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// A finished check program: the VM, what it printed and how it ended.
+struct Run {
+    status: BrainfuckVMStatus,
+    output: Vec<u8>,
+    result: Result<(), BrainfuckError>,
+}
+
+impl Run {
+    /// Fails unless the program ended without an error.
+    fn ok(&self) -> Result<(), String> {
+        self.result.as_ref().map_err(|err| format!("the program failed: {}", err)).map(|_| ())
+    }
+
+    /// Fails unless the cell at `address` holds `expected`.
+    fn cell(&self, address: i32, expected: i32) -> Result<(), String> {
+        let value = self.status.cells().into_iter().find(|&(at, _)| at == address).map_or(0, |(_, value)| value);
+        if value != expected {
+            return Err(format!("cell {} holds {}, not {}", address, value, expected));
+        }
+        Ok(())
+    }
+}
+
+/// Runs `src` on `input` on a fresh VM that `configure` has set up.
+fn run(configure: &dyn Fn(&mut BrainfuckVMStatus), src: &str, mut input: &[u8]) -> Run {
+    let mut status = new_brainfuck_status();
+    configure(&mut status);
+    let mut output = Vec::new();
+    let result = src.chars().try_for_each(|c| run_vm(&mut status, c, false, &mut input, &mut output));
+    Run { status, output, result }
+}
+
+/// `-` on 0 gives the largest cell value, `+` on that gives 0 again, `.` prints the low byte.
+fn cell_wrapping(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let run = run(configure, "-.>-+", b"");
+    run.ok()?;
+    let max = run.status.cell_mask() as i32;
+    run.cell(0, max)?;
+    run.cell(1, 0)?;
+    if run.output != [max as u8] {
+        return Err(format!("`.` printed {:?}, not [{}]", run.output, max as u8));
+    }
+    Ok(())
+}
+
+/// `,` past the end of input stores what the EOF mode says.
+fn end_of_input(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let run = run(configure, "+++,", b"");
+    let expected = match run.status.eof_mode() {
+        EofMode::Zero => 0,
+        EofMode::MinusOne => run.status.cell_mask() as i32,
+        EofMode::Unchanged => 3 & run.status.cell_mask() as i32,
+        EofMode::Error => {
+            return match run.result {
+                Err(BrainfuckError::InputRequired { .. }) => Ok(()),
+                other => Err(format!("`,` ended with {:?} instead of asking for input", other)),
+            }
+        },
+    };
+    run.ok()?;
+    run.cell(0, expected)
+}
+
+/// A 0 byte of input is input like any other.
+fn nul_input(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let run = run(configure, "+++,.", b"\0");
+    run.ok()?;
+    run.cell(0, 0)?;
+    if run.output != [0] {
+        return Err(format!("`.` printed {:?}, not [0]", run.output));
+    }
+    Ok(())
+}
+
+/// Loops inside loops run as often as their cells say, however deep.
+fn deep_nesting(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let product = run(configure, "++[>+++[>++++<-]<-]", b"");
+    product.ok()?;
+    product.cell(2, 24 & product.status.cell_mask() as i32)?;
+    let src = format!("+{}>+<-{}", "[".repeat(NESTING), "]".repeat(NESTING));
+    let deep = run(configure, &src, b"");
+    deep.ok()?;
+    deep.cell(0, 0)?;
+    deep.cell(1, 1)
+}
+
+/// A loop on a 0 cell is skipped with every loop and op inside it.
+fn skipped_loops(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let run = run(configure, "[]>[[+]+[+[,+.]]-.]+", b"x");
+    run.ok()?;
+    run.cell(1, 1)?;
+    if !run.output.is_empty() {
+        return Err(format!("the skipped loop printed {:?}", run.output));
+    }
+    Ok(())
+}
+
+/// The pointer goes far both ways and comes back to the same cells.
+fn large_moves(configure: &dyn Fn(&mut BrainfuckVMStatus)) -> Result<(), String> {
+    let right = ">".repeat(DISTANCE);
+    let left = "<".repeat(DISTANCE);
+    let src = format!("{}+{}{}-{}+", right, left, left, right);
+    let run = run(configure, &src, b"");
+    run.ok()?;
+    run.cell(DISTANCE as i32, 1)?;
+    run.cell(-(DISTANCE as i32), run.status.cell_mask() as i32)?;
+    run.cell(0, 1)?;
+    if run.status.tape_ptr() != 0 {
+        return Err(format!("the pointer is on cell {}, not back on 0", run.status.tape_ptr()));
+    }
+    Ok(())
+}
+
+/// Runs every check on VMs `configure` sets up, in a fixed order.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::io::EofMode;
+/// use rust_bf::selftest::self_test;
+///
+/// assert!(self_test(|_| ()).iter().all(|check| check.passed()));
+/// // 16-bit cells that store -1 at the end of input
+/// let results = self_test(|status| {
+///     status.set_cell_bits(16);
+///     status.set_eof_mode(EofMode::MinusOne);
+/// });
+/// assert!(results.iter().all(|check| check.passed()));
+/// ```
+pub fn self_test(configure: impl Fn(&mut BrainfuckVMStatus)) -> Vec<CheckResult> {
+    let checks: [(&'static str, Check); 6] = [
+        ("cell wrapping", cell_wrapping),
+        ("end of input", end_of_input),
+        ("nul input", nul_input),
+        ("deep nesting", deep_nesting),
+        ("skipped loops", skipped_loops),
+        ("large moves", large_moves),
+    ];
+    checks.iter().map(|&(name, check)| CheckResult { name, failure: check(&configure).err() }).collect()
+}
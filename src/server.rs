@@ -0,0 +1,198 @@
+//! Runs programs sent over HTTP, enable the `server` feature to use it.
+//!
+//! `POST /run` takes a JSON object with the `program` source, its `input`
+//! in base64 and the most steps it may take, `max_steps`, the last two
+//! optional:
+//!
+//! ```text
+//! { "program": ",[.,]", "input": "aGk=", "max_steps": 1000000 }
+//! ```
+//!
+//! and answers with what the program printed in base64, the IR nodes it
+//! ran, how it ended, `halted`, `step_limit` or `error`, and the error
+//! message if it did not halt:
+//!
+//! ```text
+//! { "error": null, "output": "aGk=", "status": "halted", "steps": 5 }
+//! ```
+//!
+//! A program that does not compile is answered with `400 Bad Request`.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::batch::{run_within, Limits};
+use crate::{BrainfuckError, Interpreter};
+
+/// largest request body accepted, in bytes
+const MAX_BODY: usize = 4 << 20;
+
+/// how long a connection may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings of [`serve`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// requests run at the same time, further connections wait for a worker
+    pub workers: usize,
+    /// limits of every run, a request asking for more steps gets `limits.step_limit`
+    pub limits: Limits,
+}
+
+impl Default for ServerConfig {
+    /// Four workers, runs of up to 10^8 steps, 1 MiB of tape and 1 MiB of output.
+    fn default() -> ServerConfig {
+        ServerConfig {
+            workers: 4,
+            limits: Limits { step_limit: Some(100_000_000), timeout: None, memory_limit: Some(1 << 20), max_output: Some(1 << 20) },
+        }
+    }
+}
+
+/// Answers requests on `listener` until accepting a connection fails.
+///
+/// Every request runs on a fresh interpreter of its own, on one of
+/// `config.workers` threads.
+pub fn serve(listener: TcpListener, config: ServerConfig) -> io::Result<()> {
+    let workers = config.workers.max(1);
+    // accepting blocks while every worker is busy and one more connection waits
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(workers);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let stream = match receiver.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => break,
+            };
+            // a client that goes away mid-request only loses its own answer
+            let _ = handle_connection(stream, config.limits);
+        });
+    }
+    for stream in listener.incoming() {
+        if sender.send(stream?).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a single request from `stream` and answers it.
+fn handle_connection(mut stream: TcpStream, limits: Limits) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(usize::MAX);
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (code, body) = match (parts.next(), parts.next()) {
+        (Some("POST"), Some("/run")) if length > MAX_BODY => (413, error_body("request body is too large")),
+        (Some("POST"), Some("/run")) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            handle_run(&body, limits)
+        },
+        (Some(_), Some("/run")) => (405, error_body("use POST to run a program")),
+        _ => (404, error_body("the only endpoint is POST /run")),
+    };
+    let reason = match code {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Payload Too Large",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code, reason, body.len(), body
+    )?;
+    stream.flush()
+}
+
+/// Returns the body of an answer that runs nothing.
+fn error_body(message: &str) -> String {
+    json!({ "output": "", "steps": 0, "status": "error", "error": message }).to_string()
+}
+
+/// Runs the program in the JSON request `body` within `limits`, returning
+/// the status code and JSON body of the answer.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::server::{handle_run, ServerConfig};
+///
+/// let limits = ServerConfig::default().limits;
+/// let (code, body) = handle_run(br#"{"program": ",+.", "input": "QQ=="}"#, limits);
+/// assert_eq!(code, 200);
+/// assert_eq!(body, r#"{"error":null,"output":"Qg==","status":"halted","steps":3}"#);
+///
+/// let (code, body) = handle_run(br#"{"program": "+[]", "max_steps": 10}"#, limits);
+/// assert_eq!(code, 200);
+/// assert!(body.contains(r#""status":"step_limit""#));
+/// ```
+pub fn handle_run(body: &[u8], limits: Limits) -> (u16, String) {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return (400, error_body(&format!("invalid JSON: {}", err))),
+    };
+    let src = match request.get("program").and_then(Value::as_str) {
+        Some(src) => src,
+        None => return (400, error_body("`program` must be a string")),
+    };
+    let input = match request.get("input") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(input) => match input.as_str().map(|input| BASE64.decode(input)) {
+            Some(Ok(input)) => input,
+            _ => return (400, error_body("`input` must be a base64 string")),
+        },
+    };
+    let step_limit = match request.get("max_steps") {
+        None | Some(Value::Null) => limits.step_limit,
+        Some(steps) => match steps.as_u64() {
+            Some(steps) => Some(limits.step_limit.map_or(steps, |limit| steps.min(limit))),
+            None => return (400, error_body("`max_steps` must be a number")),
+        },
+    };
+    let interpreter = match Interpreter::new(src) {
+        Ok(interpreter) => interpreter,
+        Err(err) => return (400, error_body(&err.to_string())),
+    };
+
+    let limits = Limits { step_limit, ..limits };
+    let (output, steps, result) = panic::catch_unwind(AssertUnwindSafe(|| run_within(interpreter.input(&input[..]), limits)))
+        .unwrap_or_else(|_| (Vec::new(), 0, Err(BrainfuckError::Panicked(String::from("the run panicked")))));
+    let (status, error) = match result {
+        Ok(()) => ("halted", None),
+        Err(BrainfuckError::StepLimitExceeded { .. }) => ("step_limit", Some(result_message(&result))),
+        Err(_) => ("error", Some(result_message(&result))),
+    };
+    (200, json!({ "output": BASE64.encode(output), "steps": steps, "status": status, "error": error }).to_string())
+}
+
+/// Returns the message of the error in `result`.
+fn result_message(result: &Result<(), BrainfuckError>) -> String {
+    result.as_ref().err().map(ToString::to_string).unwrap_or_default()
+}
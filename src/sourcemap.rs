@@ -0,0 +1,72 @@
+//! Which `.` printed which byte of the output, see
+//! [`BrainfuckVMStatus::record_output_map`](crate::vm::BrainfuckVMStatus::record_output_map).
+
+use alloc::vec::Vec;
+
+use crate::ast::{self, Position};
+
+/// The op that printed every byte of the output, for telling which part of
+/// a program wrote which part of what it printed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputSourceMap {
+    /// index of the byte in the output and of the `.` that printed it,
+    /// comments not counted, in output order
+    entries: Vec<(usize, usize)>,
+}
+
+impl OutputSourceMap {
+    /// Returns the index in the output of every byte printed and of the `.`
+    /// that printed it, comments not counted, in output order.
+    pub fn entries(&self) -> &[(usize, usize)] {
+        &self.entries
+    }
+
+    /// Returns the index of the `.` that printed byte `byte_idx` of the
+    /// output, comments not counted, `None` if it printed no such byte.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.record_output_map();
+    /// let mut output = Vec::new();
+    /// // one `.` in a loop prints 3, 2 and 1, another one 0
+    /// run_vm_bytes(&mut status, b"+++[.-].", &mut &b""[..], &mut output).unwrap();
+    /// assert_eq!(output, [3, 2, 1, 0]);
+    /// let map = status.output_map().unwrap();
+    /// assert_eq!(map.instruction_for_output_byte(2), Some(4));
+    /// assert_eq!(map.instruction_for_output_byte(3), Some(7));
+    /// assert_eq!(map.instruction_for_output_byte(4), None);
+    /// ```
+    pub fn instruction_for_output_byte(&self, byte_idx: usize) -> Option<usize> {
+        let i = self.entries.binary_search_by_key(&byte_idx, |&(byte, _)| byte).ok()?;
+        Some(self.entries[i].1)
+    }
+
+    /// Returns where in `src`, the program the map is for, the `.` that
+    /// printed byte `byte_idx` of the output is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let src = "header ++++++++[>++++++<-]>.\nfield +.";
+    /// let mut status = new_brainfuck_status();
+    /// status.record_output_map();
+    /// run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut Vec::new()).unwrap();
+    /// let at = status.output_map().unwrap().position_for_output_byte(src, 1).unwrap();
+    /// assert_eq!((at.line, at.column), (2, 8));
+    /// ```
+    pub fn position_for_output_byte(&self, src: &str, byte_idx: usize) -> Option<Position> {
+        let instruction = self.instruction_for_output_byte(byte_idx)?;
+        ast::tokens(src.as_bytes()).nth(instruction).map(|(_, at)| at)
+    }
+
+    /// Records that the `.` at `instruction` printed byte `byte_idx`.
+    pub(crate) fn push(&mut self, byte_idx: usize, instruction: usize) {
+        self.entries.push((byte_idx, instruction));
+    }
+}
@@ -0,0 +1,127 @@
+//! Runs programs sent over a plain TCP connection, one program per
+//! connection and a thread per connection.
+//!
+//! The client sends the program source, a NUL byte, then whatever the
+//! program reads with `,`, and shuts down its side of the connection once
+//! the input is complete, or right after the NUL if there is none. The
+//! server answers with a line telling how the run ended, `halted`,
+//! `step_limit` or `error`, the IR nodes it ran and how many bytes the
+//! program printed, followed by the message of the error if there was one:
+//!
+//! ```text
+//! halted 6 2\n
+//! error 0 0 unmatched '[' at line 1, column 1\n
+//! ```
+//!
+//! The bytes the program printed follow the line.
+
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use crate::batch::{run_within, Limits};
+use crate::{BrainfuckError, Interpreter};
+
+/// longest program accepted, in bytes
+const MAX_PROGRAM: usize = 1 << 20;
+
+/// how long a connection may leave the server waiting for the program or its input
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A server answering the protocol of this module on a TCP port.
+#[derive(Debug)]
+pub struct BrainfuckServer {
+    /// where connections come from
+    listener: TcpListener,
+    /// limits of every run
+    limits: Limits,
+}
+
+impl BrainfuckServer {
+    /// Listens on `addr`, each run limited to 10^8 steps, 1 MiB of tape and 1 MiB of output.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<BrainfuckServer> {
+        Ok(BrainfuckServer {
+            listener: TcpListener::bind(addr)?,
+            limits: Limits { step_limit: Some(100_000_000), timeout: None, memory_limit: Some(1 << 20), max_output: Some(1 << 20) },
+        })
+    }
+
+    /// Limits every run to `limits` instead, `None` leaves that part unlimited.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the limits of every run.
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Returns the address the server listens on, the only way to learn the port after binding port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answers connections until accepting one fails.
+    pub fn serve(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let limits = self.limits;
+            // a client that goes away mid-run only loses its own answer
+            thread::spawn(move || handle_connection(stream, limits));
+        }
+        Ok(())
+    }
+}
+
+/// Answers the single request on `stream`.
+fn handle_connection(stream: TcpStream, limits: Limits) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    handle(BufReader::new(&stream), BufWriter::new(&stream), limits)
+}
+
+/// Runs the program `request` holds within `limits` and writes the answer to `response`.
+///
+/// A request that ends before the NUL is taken as a program without input.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::batch::Limits;
+/// use rust_bf::tcp::handle;
+///
+/// let mut response = Vec::new();
+/// handle(&b",+.,+.\0HAL"[..], &mut response, Limits::default()).unwrap();
+/// assert_eq!(response, b"halted 6 2\nIB");
+///
+/// let mut response = Vec::new();
+/// handle(&b"+[]"[..], &mut response, Limits { step_limit: Some(100), ..Limits::default() }).unwrap();
+/// assert_eq!(response, b"step_limit 100 0 program ran past the step limit of 100 steps\n");
+/// ```
+pub fn handle(mut request: impl BufRead, mut response: impl Write, limits: Limits) -> io::Result<()> {
+    let mut program = Vec::new();
+    (&mut request).take(MAX_PROGRAM as u64 + 1).read_until(0, &mut program)?;
+    if program.last() == Some(&0) {
+        program.pop();
+    }
+    if program.len() > MAX_PROGRAM {
+        return writeln!(response, "error 0 0 the program is longer than {} bytes", MAX_PROGRAM);
+    }
+    let interpreter = match Interpreter::new(&String::from_utf8_lossy(&program)) {
+        Ok(interpreter) => interpreter,
+        Err(err) => return writeln!(response, "error 0 0 {}", err),
+    };
+
+    let (output, steps, result) = run_within(interpreter.input(request), limits);
+
+    match result {
+        Ok(()) => writeln!(response, "halted {} {}", steps, output.len())?,
+        Err(err @ BrainfuckError::StepLimitExceeded { .. }) => {
+            writeln!(response, "step_limit {} {} {}", steps, output.len(), err)?
+        },
+        Err(err) => writeln!(response, "error {} {} {}", steps, output.len(), err)?,
+    }
+    response.write_all(&output)?;
+    response.flush()
+}
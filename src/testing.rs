@@ -0,0 +1,220 @@
+//! Random well-formed programs for property tests, enable the `testing` feature to use them.
+//!
+//! [`GeneratedProgram`] implements proptest's `Arbitrary`, so it can be
+//! used with `any::<GeneratedProgram>()` or configured with
+//! `any_with::<GeneratedProgram>(GeneratorConfig { .. })`.
+//!
+//! [`fuzz_random`] and [`fuzz_many`] run random programs outside of
+//! proptest, to check that the interpreter does not panic on them, see
+//! `rust_bf --fuzz`.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use proptest::prelude::*;
+
+use crate::batch::panic_message;
+use crate::io::Discard;
+use crate::{BrainfuckError, Interpreter};
+
+/// Shape of the programs [`GeneratedProgram`] generates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneratorConfig {
+    /// deepest loop nesting
+    pub max_depth: usize,
+    /// most characters in a program
+    pub max_length: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig { max_depth: 3, max_length: 32 }
+    }
+}
+
+/// A random program with balanced loops and no comments.
+///
+/// Programs are generated as a flat list of ops that is then balanced by
+/// dropping unmatched brackets and loops nested too deep, so shrinking
+/// removes single ops and failures come out as short programs.
+///
+/// # Example
+///
+/// ```
+/// use proptest::prelude::*;
+/// use rust_bf::ast::parse;
+/// use rust_bf::testing::GeneratedProgram;
+///
+/// proptest!(|(program in any::<GeneratedProgram>())| {
+///     prop_assert!(parse(program.source()).is_ok());
+/// });
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct GeneratedProgram {
+    /// program text
+    source: String,
+}
+
+impl GeneratedProgram {
+    /// Returns the program text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Shows the source only, which keeps counterexamples readable.
+impl fmt::Debug for GeneratedProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
+
+impl fmt::Display for GeneratedProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Drops unmatched brackets and loops nested deeper than `max_depth`.
+fn balance(ops: &[char], max_depth: usize) -> String {
+    let mut keep = vec![true; ops.len()];
+    let mut pair = vec![0; ops.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (i, &op) in ops.iter().enumerate() {
+        match op {
+            '[' => {
+                keep[i] = false;
+                open.push(i);
+            },
+            ']' => match open.pop() {
+                Some(start) => {
+                    keep[start] = true;
+                    pair[start] = i;
+                },
+                None => keep[i] = false,
+            },
+            _ => (),
+        }
+    }
+
+    let mut depth = 0;
+    for (i, &op) in ops.iter().enumerate() {
+        if !keep[i] {
+            continue;
+        }
+        match op {
+            '[' if depth == max_depth => {
+                keep[i] = false;
+                keep[pair[i]] = false;
+            },
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => (),
+        }
+    }
+    ops.iter().zip(keep).filter(|&(_, keep)| keep).map(|(&op, _)| op).collect()
+}
+
+impl Arbitrary for GeneratedProgram {
+    type Parameters = GeneratorConfig;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(config: GeneratorConfig) -> Self::Strategy {
+        let op = prop::sample::select(vec!['+', '-', '>', '<', '.', ',', '[', ']']);
+        prop::collection::vec(op, 0..=config.max_length)
+            .prop_map(move |ops| GeneratedProgram { source: balance(&ops, config.max_depth) })
+            .boxed()
+    }
+}
+
+/// most bytes the tape of a fuzzed program may allocate
+const FUZZ_MEMORY_LIMIT: usize = 1 << 16;
+
+/// What [`fuzz_random`] and [`fuzz_many`] ran into.
+#[derive(Debug, Default)]
+pub struct FuzzReport {
+    /// programs run
+    pub programs_tested: u64,
+    /// runs that panicked, also listed in `errors` as [`BrainfuckError::Panicked`]
+    pub panics: u64,
+    /// every program that did not run to its end and why, limits included
+    pub errors: Vec<(Vec<char>, BrainfuckError)>,
+}
+
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c), as good for any seed, 0 included.
+struct SplitMix(u64);
+
+impl SplitMix {
+    /// Returns the next random number.
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Runs a random program of `program_length` ops, fewer once unmatched
+/// brackets are dropped, on random input, within `instruction_limit` IR
+/// nodes and a small tape, and reports whether it panicked or failed.
+///
+/// The same seed always makes the same program and input.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::testing::fuzz_random;
+///
+/// let report = fuzz_random(7, 64, 10_000);
+/// assert_eq!((report.programs_tested, report.panics), (1, 0));
+/// ```
+pub fn fuzz_random(seed: u64, program_length: usize, instruction_limit: u64) -> FuzzReport {
+    const OPS: [char; 8] = ['+', '-', '>', '<', '.', ',', '[', ']'];
+    let mut rng = SplitMix(seed);
+    let ops: Vec<char> = (0..program_length).map(|_| OPS[(rng.next() % 8) as usize]).collect();
+    let program: Vec<char> = balance(&ops, usize::MAX).chars().collect();
+    let input: Vec<u8> = (0..program_length).map(|_| rng.next() as u8).collect();
+
+    let source: String = program.iter().collect();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        Interpreter::new(&source)?
+            .input(&input[..])
+            .output(Discard)
+            .with_step_limit(instruction_limit)
+            .with_memory_limit(FUZZ_MEMORY_LIMIT)
+            .run()
+    }));
+    let mut report = FuzzReport { programs_tested: 1, ..FuzzReport::default() };
+    match result {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => report.errors.push((program, err)),
+        Err(panic) => {
+            report.panics = 1;
+            report.errors.push((program, BrainfuckError::Panicked(panic_message(&*panic))));
+        },
+    }
+    report
+}
+
+/// Runs `count` programs like [`fuzz_random`], with seeds `seed`,
+/// `seed + 1` and so on, so that any of them can be run again on its own.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::testing::fuzz_many;
+///
+/// let report = fuzz_many(1, 100, 32, 10_000);
+/// assert_eq!((report.programs_tested, report.panics), (100, 0));
+/// ```
+pub fn fuzz_many(seed: u64, count: u64, program_length: usize, instruction_limit: u64) -> FuzzReport {
+    let mut report = FuzzReport::default();
+    for i in 0..count {
+        let run = fuzz_random(seed.wrapping_add(i), program_length, instruction_limit);
+        report.programs_tested += run.programs_tested;
+        report.panics += run.panics;
+        report.errors.extend(run.errors);
+    }
+    report
+}
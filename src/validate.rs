@@ -0,0 +1,356 @@
+//! Checks of properties a program must have before it is run, worked out
+//! from the IR without running it.
+//!
+//! Every validator stands on its own, tuples of them check everything
+//! their parts check and fail with the first error found:
+//!
+//! ```
+//! use rust_bf::ast::parse;
+//! use rust_bf::ir::compile;
+//! use rust_bf::validate::{MemSafeValidator, PureComputeValidator, ValidationError, Validator};
+//!
+//! let hash = (PureComputeValidator, MemSafeValidator);
+//! assert_eq!(hash.validate(&compile(&parse("[->+<]>[->++<]").unwrap())), Ok(()));
+//! assert_eq!(hash.validate(&compile(&parse(">[-<+>]<.").unwrap())), Err(ValidationError::Io { index: 8 }));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ir::BrainfuckIR;
+
+/// Why a program failed a [`Validator`], at which IR index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// a `LoopStart` or `LoopEnd` does not point at its matching node
+    Unbalanced {
+        /// index of the node
+        index: usize,
+    },
+    /// a loop is entered on a nonzero cell and never changes it
+    NonTerminating {
+        /// index of the `LoopStart`
+        index: usize,
+    },
+    /// a node reads input or prints
+    Io {
+        /// index of the node
+        index: usize,
+    },
+    /// a node may take the pointer, or the cell it writes to, left of the first cell
+    LeftOfStart {
+        /// index of the node
+        index: usize,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Unbalanced { index } => write!(f, "the loop node at {} has no matching node", index),
+            ValidationError::NonTerminating { index } => write!(f, "the loop at {} never ends", index),
+            ValidationError::Io { index } => write!(f, "node {} does input or output", index),
+            ValidationError::LeftOfStart { index } => write!(f, "node {} may reach left of the first cell", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// A property of a compiled or optimized program.
+pub trait Validator {
+    /// Checks `ir`, returning the first place it lacks the property.
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError>;
+}
+
+impl<V: Validator + ?Sized> Validator for &V {
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+        (**self).validate(ir)
+    }
+}
+
+/// Implements [`Validator`] for tuples, checking every part in order.
+macro_rules! validator_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Validator),+> Validator for ($($name,)+) {
+            fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+                $(self.$index.validate(ir)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+validator_tuple!(A 0, B 1);
+validator_tuple!(A 0, B 1, C 2);
+validator_tuple!(A 0, B 1, C 2, D 3);
+
+/// Every `LoopStart` and `LoopEnd` points at its matching node, which
+/// [`ir::compile`](crate::ir::compile) and the optimizer always get right
+/// but IR built by hand may not.
+///
+/// The other validators check this first.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ir::BrainfuckIR;
+/// use rust_bf::validate::{BalancedValidator, ValidationError, Validator};
+///
+/// let ir = [BrainfuckIR::LoopStart(2), BrainfuckIR::Add(-1), BrainfuckIR::LoopEnd(0)];
+/// assert_eq!(BalancedValidator.validate(&ir), Ok(()));
+/// let ir = [BrainfuckIR::LoopStart(1), BrainfuckIR::LoopEnd(0), BrainfuckIR::LoopEnd(0)];
+/// assert_eq!(BalancedValidator.validate(&ir), Err(ValidationError::Unbalanced { index: 2 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BalancedValidator;
+
+impl Validator for BalancedValidator {
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+        let mut open = Vec::new();
+        for (i, node) in ir.iter().enumerate() {
+            match *node {
+                BrainfuckIR::LoopStart(_) => open.push(i),
+                BrainfuckIR::LoopEnd(start) => match open.pop() {
+                    Some(at) if at == start && ir[at] == BrainfuckIR::LoopStart(i) => (),
+                    _ => return Err(ValidationError::Unbalanced { index: i }),
+                },
+                _ => (),
+            }
+        }
+        match open.pop() {
+            Some(index) => Err(ValidationError::Unbalanced { index }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// No loop is obviously endless: entered on a cell known to be nonzero,
+/// with a body that cannot change it, like `+[]` or `+[>+<]`.
+///
+/// Cell values are followed from the start of the program, where they are
+/// all zero, and past loops, which leave their cell zero, as long as no
+/// `,` or loop makes them unknown; they are taken to be at least 8 bits
+/// wide. This only catches the obvious cases, passing does not prove that
+/// the program halts.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::compile;
+/// use rust_bf::validate::{TerminationValidator, ValidationError, Validator};
+///
+/// let validate = |src| TerminationValidator.validate(&compile(&parse(src).unwrap()));
+/// assert_eq!(validate("+[]"), Err(ValidationError::NonTerminating { index: 1 }));
+/// assert_eq!(validate(">+[<+>]"), Err(ValidationError::NonTerminating { index: 2 }));
+/// // never entered, or the cell is not known
+/// assert_eq!(validate("[]+[-][]"), Ok(()));
+/// assert_eq!(validate(",[]"), Ok(()));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TerminationValidator;
+
+/// What is known of the cells, relative to where the program started.
+struct Known {
+    /// where the pointer is
+    ptr: isize,
+    /// cells set since the start, `None` where the value is unknown
+    cells: BTreeMap<isize, Option<i64>>,
+    /// whether cells missing from `cells` are known to be zero
+    rest_zero: bool,
+}
+
+impl Known {
+    /// Returns the value of the cell at `at`, if known.
+    fn get(&self, at: isize) -> Option<i64> {
+        match self.cells.get(&at) {
+            Some(&value) => value,
+            None if self.rest_zero => Some(0),
+            None => None,
+        }
+    }
+
+    /// Adds `n` to the cell at `at`.
+    fn add(&mut self, at: isize, n: i64) {
+        let value = self.get(at).map(|value| value.wrapping_add(n));
+        self.cells.insert(at, value);
+    }
+
+    /// Forgets everything but that the current cell holds `value`.
+    fn only(&mut self, value: i64) {
+        self.cells.clear();
+        self.cells.insert(self.ptr, Some(value));
+        self.rest_zero = false;
+    }
+}
+
+/// Returns whether the loop body `body` can change the cell it starts on.
+fn changes_cell(body: &[BrainfuckIR]) -> bool {
+    let mut offset = 0;
+    let mut delta = 0;
+    for node in body {
+        match *node {
+            BrainfuckIR::Move(n) => offset += n as i64,
+            BrainfuckIR::Add(n) if offset == 0 => delta += n as i64,
+            BrainfuckIR::AddRange { len, n } if offset <= 0 && -offset < len as i64 => delta += n as i64,
+            BrainfuckIR::MulAdd { offset: target, .. } if offset + target as i64 == 0 => return true,
+            BrainfuckIR::Read | BrainfuckIR::Clear if offset == 0 => return true,
+            #[cfg(feature = "self_modifying")]
+            BrainfuckIR::SelfWrite if offset == 0 => return true,
+            #[cfg(feature = "debug_ops")]
+            BrainfuckIR::Reset => return true,
+            // inner loops may move the pointer anywhere
+            BrainfuckIR::LoopStart(_) | BrainfuckIR::Scan(_) => return true,
+            _ => (),
+        }
+    }
+    offset != 0 || delta != 0
+}
+
+impl Validator for TerminationValidator {
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+        BalancedValidator.validate(ir)?;
+        let mut known = Known { ptr: 0, cells: BTreeMap::new(), rest_zero: true };
+        let mut i = 0;
+        while i < ir.len() {
+            let ptr = known.ptr;
+            match ir[i] {
+                BrainfuckIR::Add(n) => known.add(ptr, n as i64),
+                BrainfuckIR::Move(n) => known.ptr += n as isize,
+                BrainfuckIR::Read => {
+                    known.cells.insert(ptr, None);
+                },
+                BrainfuckIR::Clear => {
+                    known.cells.insert(ptr, Some(0));
+                },
+                BrainfuckIR::MulAdd { offset, factor } => match known.get(ptr) {
+                    Some(value) => known.add(ptr + offset as isize, value.wrapping_mul(factor as i64)),
+                    None => {
+                        known.cells.insert(ptr + offset as isize, None);
+                    },
+                },
+                BrainfuckIR::AddRange { len, n } => (0..len as isize).for_each(|k| known.add(ptr + k, n as i64)),
+                // stores 61 or does nothing, depending on the run
+                #[cfg(feature = "self_modifying")]
+                BrainfuckIR::SelfWrite => {
+                    known.cells.insert(ptr, None);
+                },
+                // clears the tape or does nothing, depending on the run
+                #[cfg(feature = "debug_ops")]
+                BrainfuckIR::Reset => {
+                    known.cells.clear();
+                    known.rest_zero = false;
+                },
+                BrainfuckIR::LoopStart(end) => {
+                    let entered = known.get(ptr).map(|value| value & 0xff != 0);
+                    if entered == Some(true) && !changes_cell(&ir[i + 1..end]) {
+                        return Err(ValidationError::NonTerminating { index: i });
+                    }
+                    if entered == Some(false) {
+                        // a loop that is skipped keeps what is known
+                        i = end;
+                    } else {
+                        known.cells.clear();
+                        known.rest_zero = false;
+                    }
+                },
+                BrainfuckIR::LoopEnd(_) | BrainfuckIR::Scan(_) => known.only(0),
+                BrainfuckIR::Print => (),
+                #[cfg(feature = "debug_ops")]
+                BrainfuckIR::PrintNumber => (),
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+/// No node reads input or prints, for programs computing a function of
+/// the tape they are given, like hashes.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::compile;
+/// use rust_bf::validate::{PureComputeValidator, ValidationError, Validator};
+///
+/// let validate = |src| PureComputeValidator.validate(&compile(&parse(src).unwrap()));
+/// assert_eq!(validate("[->+>+<<]"), Ok(()));
+/// assert_eq!(validate("+[,]"), Err(ValidationError::Io { index: 2 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PureComputeValidator;
+
+impl Validator for PureComputeValidator {
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+        BalancedValidator.validate(ir)?;
+        match ir.iter().position(|node| matches!(node, BrainfuckIR::Print | BrainfuckIR::Read)) {
+            Some(index) => Err(ValidationError::Io { index }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The pointer never gets left of the cell it starts on, however often
+/// the loops run, so the program runs on a tape without cells to the left.
+///
+/// A loop that ends left of where it started may drift left of the first
+/// cell and fails, `MulAdd` fails when it writes left of the first cell.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ast::parse;
+/// use rust_bf::ir::compile;
+/// use rust_bf::validate::{MemSafeValidator, ValidationError, Validator};
+///
+/// let validate = |src| MemSafeValidator.validate(&compile(&parse(src).unwrap()));
+/// assert_eq!(validate(">+[>+]>>[-<<+>>]<"), Ok(()));
+/// assert_eq!(validate("><<"), Err(ValidationError::LeftOfStart { index: 0 }));
+/// // the loop at 1 walks left until it finds a zero cell
+/// assert_eq!(validate(">>[<]"), Err(ValidationError::LeftOfStart { index: 1 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemSafeValidator;
+
+impl Validator for MemSafeValidator {
+    fn validate(&self, ir: &[BrainfuckIR]) -> Result<(), ValidationError> {
+        BalancedValidator.validate(ir)?;
+        // the lowest cell the pointer can be on, and where each open loop started
+        let mut offset = 0isize;
+        let mut loops = Vec::new();
+        for (i, node) in ir.iter().enumerate() {
+            let reached = match *node {
+                BrainfuckIR::Move(n) => {
+                    offset += n as isize;
+                    offset
+                },
+                BrainfuckIR::MulAdd { offset: target, .. } => offset + target as isize,
+                // a scan left may go on past the first cell
+                BrainfuckIR::Scan(n) if n < 0 => -1,
+                BrainfuckIR::LoopStart(_) => {
+                    loops.push(offset);
+                    offset
+                },
+                BrainfuckIR::LoopEnd(start) => {
+                    let before = loops.pop().unwrap_or(0);
+                    if offset < before {
+                        return Err(ValidationError::LeftOfStart { index: start });
+                    }
+                    // skipped or run any number of times, the loop ends no further left
+                    offset = before;
+                    offset
+                },
+                _ => offset,
+            };
+            if reached < 0 {
+                return Err(ValidationError::LeftOfStart { index: i });
+            }
+        }
+        Ok(())
+    }
+}
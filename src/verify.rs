@@ -0,0 +1,332 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::ast::{self, BrainfuckNode, Position};
+use crate::error::BrainfuckError;
+use crate::ir::{self, BrainfuckIR};
+use crate::op::BrainfuckOp;
+use crate::optimizer;
+use crate::Interpreter;
+
+/// First difference [`verify`] found between the run walking the syntax
+/// tree, the naive one, and the optimized run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Divergence {
+    /// output byte `index` differs, `None` where a run wrote fewer bytes
+    Output {
+        /// index of the first differing output byte
+        index: usize,
+        /// byte written by the naive run
+        naive: Option<u8>,
+        /// byte written by the optimized run
+        optimized: Option<u8>,
+        /// index of the op of the source that wrote the byte in the naive
+        /// run, counted like [`ast::tokens`] counts them
+        naive_position: Option<usize>,
+        /// index of the optimized IR node that wrote the byte
+        optimized_position: Option<usize>,
+    },
+    /// output is the same but the final value of `cell` differs
+    Tape {
+        /// index of the first differing cell
+        cell: usize,
+        /// value left by the naive run
+        naive: u8,
+        /// value left by the optimized run
+        optimized: u8,
+    },
+    /// only one of the runs failed
+    Error {
+        /// error of the naive run
+        naive: Option<String>,
+        /// error of the optimized run
+        optimized: Option<String>,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Formats a byte that may be missing.
+        fn byte(byte: &Option<u8>) -> String {
+            byte.map_or_else(|| "nothing".to_string(), |byte| byte.to_string())
+        }
+        /// Formats an IR index that may be missing.
+        fn at(position: &Option<usize>) -> String {
+            position.map_or_else(|| "-".to_string(), |position| position.to_string())
+        }
+        /// Formats an error that may be missing.
+        fn outcome(error: &Option<String>) -> &str {
+            error.as_deref().unwrap_or("success")
+        }
+
+        match self {
+            Divergence::Output { index, naive, optimized, naive_position, optimized_position } => write!(
+                f,
+                "output byte {} differs: naive wrote {} at op {}, optimized wrote {} at IR {}",
+                index, byte(naive), at(naive_position), byte(optimized), at(optimized_position)
+            ),
+            Divergence::Tape { cell, naive, optimized } => write!(
+                f,
+                "cell {} differs: naive left {}, optimized left {}",
+                cell, naive, optimized
+            ),
+            Divergence::Error { naive, optimized } => write!(
+                f,
+                "outcome differs: naive ended with {}, optimized ended with {}",
+                outcome(naive), outcome(optimized)
+            ),
+        }
+    }
+}
+
+/// What a single run produced.
+struct Run {
+    /// every byte written by `.`
+    output: Vec<u8>,
+    /// index of the IR node that wrote each output byte
+    prints: Vec<usize>,
+    /// final tape
+    tape: Vec<u8>,
+    /// runtime error the run stopped with, if any
+    error: Option<BrainfuckError>,
+}
+
+/// Runs a syntax tree node by node, sharing no code with the IR and the
+/// interpreter it is checked against, on a tape like theirs: cells from 0
+/// on, 8 bits wide, and 0 read at end of input.
+struct Walker<'a> {
+    /// cells the pointer reached so far
+    tape: Vec<u8>,
+    /// current cell of the tape
+    ptr: usize,
+    /// bytes `,` has not read yet
+    input: &'a [u8],
+    /// what the run printed and the index of the op that printed each byte
+    run: Run,
+    /// byte offset of every op in the source, in order
+    offsets: Vec<usize>,
+}
+
+impl Walker<'_> {
+    /// Returns the index of the op at `at`.
+    fn index(&self, at: &Position) -> usize {
+        self.offsets.binary_search(&at.offset).expect("every op is a token")
+    }
+
+    /// Runs `nodes` in order, loops until their cell is 0.
+    fn walk(&mut self, nodes: &[BrainfuckNode]) -> Result<(), BrainfuckError> {
+        // every body being run with the nodes left of it, `nodes` at the bottom
+        let mut bodies = alloc::vec![(nodes, nodes.iter())];
+        while let Some((nodes, rest)) = bodies.last_mut() {
+            match rest.next() {
+                Some(BrainfuckNode::Op(op, at)) => self.op(*op, at)?,
+                Some(BrainfuckNode::Loop { body, .. }) => {
+                    if self.tape[self.ptr] != 0 {
+                        bodies.push((body, body.iter()));
+                    }
+                },
+                None => {
+                    let body = *nodes;
+                    bodies.pop();
+                    // the end of a loop body goes back to its start unless the cell is 0
+                    if !bodies.is_empty() && self.tape[self.ptr] != 0 {
+                        bodies.push((body, body.iter()));
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the single op `op` found at `at`.
+    fn op(&mut self, op: BrainfuckOp, at: &Position) -> Result<(), BrainfuckError> {
+        let cell = &mut self.tape[self.ptr];
+        match op {
+            BrainfuckOp::IncrementValueOp => *cell = cell.wrapping_add(1),
+            BrainfuckOp::DecrementValueOp => *cell = cell.wrapping_sub(1),
+            BrainfuckOp::IncrementPtrOp => {
+                self.ptr += 1;
+                if self.ptr == self.tape.len() {
+                    self.tape.push(0);
+                }
+            },
+            BrainfuckOp::DecrementPtrOp => match self.ptr.checked_sub(1) {
+                Some(ptr) => self.ptr = ptr,
+                None => return Err(BrainfuckError::PointerUnderflow { position: self.index(at) }),
+            },
+            BrainfuckOp::PrintOp => {
+                self.run.output.push(*cell);
+                let index = self.index(at);
+                self.run.prints.push(index);
+            },
+            BrainfuckOp::ReadOp => match self.input.split_first() {
+                Some((&byte, rest)) => {
+                    *cell = byte;
+                    self.input = rest;
+                },
+                None => *cell = 0,
+            },
+            // `=`, `$` and `!` are off in the interpreter too, and loops are `Loop` nodes
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Walks the syntax tree of `src` to its end.
+fn walk(src: &str, ast: &[BrainfuckNode], input: &[u8]) -> Run {
+    let offsets = ast::tokens(src.as_bytes()).map(|(_, at)| at.offset).collect();
+    let run = Run { output: Vec::new(), prints: Vec::new(), tape: Vec::new(), error: None };
+    let mut walker = Walker { tape: alloc::vec![0], ptr: 0, input, run, offsets };
+    walker.run.error = walker.walk(ast).err();
+    walker.run.tape = walker.tape;
+    walker.run
+}
+
+/// Steps `program` to its end, remembering where each output byte came from.
+fn run(program: Vec<BrainfuckIR>, input: &[u8]) -> Run {
+    let mut output = Vec::new();
+    let mut prints = Vec::new();
+    let mut interpreter = Interpreter::from_ir(program).input(input).output(&mut output);
+    let error = loop {
+        let ip = interpreter.instruction_pointer();
+        let print = interpreter.program().get(ip) == Some(&BrainfuckIR::Print);
+        match interpreter.step() {
+            Ok(true) => {
+                if print {
+                    prints.push(ip);
+                }
+            },
+            Ok(false) => break None,
+            Err(err) => break Some(err),
+        }
+    };
+    let tape = interpreter.tape().to_vec();
+    drop(interpreter);
+    Run { output, prints, tape, error }
+}
+
+/// Runs `program` once walking its syntax tree and once compiled to IR
+/// and optimized, with the same `input`, and returns the first difference
+/// in output, outcome or final tape, `None` if both runs agree.
+///
+/// The naive run shares nothing with the optimized one but the parser,
+/// so a difference points at the IR, the optimizer or the interpreter.
+///
+/// # Arguments
+///
+/// * `program` - brainfuck source
+/// * `input`   - bytes fed to `,` in both runs
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::verify::verify;
+///
+/// assert_eq!(verify("++++++++[>++++++++<-]>+.[-]", b"").unwrap(), None);
+/// assert_eq!(verify(",[>+<-]>.", b"A").unwrap(), None);
+/// ```
+pub fn verify(program: &str, input: &[u8]) -> Result<Option<Divergence>, BrainfuckError> {
+    let ast = ast::parse(program)?;
+    let optimized = optimizer::optimize(&ir::compile(&ast));
+    let naive = walk(program, &ast, input);
+    let fast = run(optimized, input);
+
+    let length = naive.output.len().max(fast.output.len());
+    if let Some(index) = (0..length).find(|&i| naive.output.get(i) != fast.output.get(i)) {
+        return Ok(Some(Divergence::Output {
+            index,
+            naive: naive.output.get(index).copied(),
+            optimized: fast.output.get(index).copied(),
+            naive_position: naive.prints.get(index).copied(),
+            optimized_position: fast.prints.get(index).copied(),
+        }));
+    }
+
+    let naive_error = naive.error.as_ref().map(|err| err.to_string());
+    let fast_error = fast.error.as_ref().map(|err| err.to_string());
+    // op and IR indices in errors differ, so only compare whether both failed
+    if naive_error.is_some() != fast_error.is_some() {
+        return Ok(Some(Divergence::Error { naive: naive_error, optimized: fast_error }));
+    }
+
+    let length = naive.tape.len().max(fast.tape.len());
+    let cell = |tape: &[u8], i: usize| tape.get(i).copied().unwrap_or(0);
+    Ok((0..length)
+        .find(|&i| cell(&naive.tape, i) != cell(&fast.tape, i))
+        .map(|i| Divergence::Tape { cell: i, naive: cell(&naive.tape, i), optimized: cell(&fast.tape, i) }))
+}
+
+/// What [`check_equivalence`] found running two programs on the same inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EquivalenceResult {
+    /// both printed the same on every input
+    Equivalent,
+    /// the first input the programs printed something different on
+    Diverged {
+        /// the input
+        input: Vec<u8>,
+        /// what the original program printed
+        original_output: Vec<u8>,
+        /// what the optimized program printed
+        optimized_output: Vec<u8>,
+    },
+}
+
+/// Runs `program` on `input` for at most `max_instructions` IR nodes, and
+/// returns what it printed and whether it ran out of steps.
+fn run_limited(program: &[BrainfuckIR], input: &[u8], max_instructions: u64) -> (Vec<u8>, bool) {
+    let mut output = Vec::new();
+    let result = Interpreter::from_ir(program.to_vec())
+        .with_step_limit(max_instructions)
+        .input(input)
+        .output(&mut output)
+        .run();
+    (output, matches!(result, Err(BrainfuckError::StepLimitExceeded { .. })))
+}
+
+/// Runs `original_ir` and `optimized_ir` on each of `test_inputs` and
+/// compares what they print.
+///
+/// Each run takes at most `max_instructions` IR nodes. An input the
+/// original program does not finish on within them proves nothing and is
+/// skipped, while the optimized program running out of steps where the
+/// original did not counts as a divergence, since optimizing never adds
+/// steps.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::ir::compile_source;
+/// use rust_bf::optimizer::optimize;
+/// use rust_bf::verify::{check_equivalence, EquivalenceResult};
+///
+/// let ir = compile_source(b",[->++<]>.").unwrap();
+/// assert_eq!(check_equivalence(&ir, &optimize(&ir), &[b"\x03", b""], 1000), EquivalenceResult::Equivalent);
+///
+/// let wrong = compile_source(b",[->+++<]>.").unwrap();
+/// assert_eq!(check_equivalence(&ir, &wrong, &[b"", b"\x01"], 1000), EquivalenceResult::Diverged {
+///     input: vec![1],
+///     original_output: vec![2],
+///     optimized_output: vec![3],
+/// });
+/// ```
+pub fn check_equivalence(
+    original_ir: &[BrainfuckIR],
+    optimized_ir: &[BrainfuckIR],
+    test_inputs: &[&[u8]],
+    max_instructions: u64,
+) -> EquivalenceResult {
+    for &input in test_inputs {
+        let (original_output, original_limited) = run_limited(original_ir, input, max_instructions);
+        if original_limited {
+            continue;
+        }
+        let (optimized_output, optimized_limited) = run_limited(optimized_ir, input, max_instructions);
+        if optimized_limited || optimized_output != original_output {
+            return EquivalenceResult::Diverged { input: input.to_vec(), original_output, optimized_output };
+        }
+    }
+    EquivalenceResult::Equivalent
+}
@@ -0,0 +1,1494 @@
+use core::convert::TryFrom;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "debug_ops")]
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::coverage::CoverageReport;
+use crate::sourcemap::OutputSourceMap;
+use crate::histogram::CellValueHistogram;
+use crate::error::BrainfuckError;
+use crate::ast;
+use crate::io::{BrainfuckInput, BrainfuckOutput, EofMode};
+use crate::op::BrainfuckOp;
+
+/// cells far from the others, `BTreeMap` stands in for `HashMap` without `std`
+#[cfg(feature = "std")]
+type FarCells = std::collections::HashMap<i32, i32>;
+#[cfg(not(feature = "std"))]
+type FarCells = alloc::collections::BTreeMap<i32, i32>;
+
+/// most untouched cells an array of [`Tape`] grows by to reach a cell,
+/// cells further out go in its map
+const MAX_GAP: usize = 1 << 16;
+
+/// virtual infinity length tape: the cells around cell 0 in two arrays
+/// growing away from it, the cells the pointer jumped far out to in a map
+#[derive(Default)]
+struct Tape {
+    /// cells 0, 1, 2 and on, `None` until touched
+    right: Vec<Option<i32>>,
+    /// cells -1, -2, -3 and on, `None` until touched
+    left: Vec<Option<i32>>,
+    /// touched cells more than [`MAX_GAP`] cells past the ends of the arrays
+    far: FarCells,
+    /// cells touched, in the arrays and in the map
+    touched: usize,
+}
+
+impl Tape {
+    /// Returns the array `address` is in when it is close enough, and its index there.
+    fn slot(address: i32) -> (bool, usize) {
+        if address >= 0 {
+            (true, address as usize)
+        } else {
+            (false, (-(address as i64) - 1) as usize)
+        }
+    }
+
+    /// Returns the value of the cell at `address`, `None` if it was never touched.
+    fn get(&self, address: i32) -> Option<i32> {
+        let (right, index) = Tape::slot(address);
+        let side = if right { &self.right } else { &self.left };
+        match side.get(index) {
+            Some(cell) => *cell,
+            None => self.far.get(&address).copied(),
+        }
+    }
+
+    /// Returns the cell at `address`, touching it if it was not touched yet.
+    fn entry(&mut self, address: i32) -> &mut i32 {
+        let (right, index) = Tape::slot(address);
+        let len = if right { self.right.len() } else { self.left.len() };
+        if index >= len {
+            if index - len > MAX_GAP {
+                let touched = &mut self.touched;
+                return self.far.entry(address).or_insert_with(|| {
+                    *touched += 1;
+                    0
+                });
+            }
+            // doubling, so that cells coming in from the map are looked for only now and then
+            let grown = (index + 1).max((len * 2).min(len + MAX_GAP));
+            let side = if right { &mut self.right } else { &mut self.left };
+            side.resize(grown, None);
+            // cells the array reaches now move in from the map
+            if !self.far.is_empty() {
+                let last = grown as i64 - 1;
+                let (low, high) = if right { (len as i64, last) } else { (-last - 1, -(len as i64) - 1) };
+                let moved: Vec<i32> = self.far.keys().copied().filter(|&at| (low..=high).contains(&(at as i64))).collect();
+                for at in moved {
+                    let (_, i) = Tape::slot(at);
+                    side[i] = self.far.remove(&at);
+                }
+            }
+        }
+        let side = if right { &mut self.right } else { &mut self.left };
+        let cell = &mut side[index];
+        if cell.is_none() {
+            self.touched += 1;
+        }
+        cell.get_or_insert(0)
+    }
+
+    /// Returns the address and value of every touched cell, in no particular order.
+    fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let right = self.right.iter().enumerate().filter_map(|(i, cell)| cell.map(|value| (i as i32, value)));
+        let left = self.left.iter().enumerate().filter_map(|(i, cell)| cell.map(|value| (-(i as i64) as i32 - 1, value)));
+        right.chain(left).chain(self.far.iter().map(|(&address, &value)| (address, value)))
+    }
+
+    /// Returns how many touched cells are left of cell 0.
+    fn negative(&self) -> usize {
+        self.left.iter().filter(|cell| cell.is_some()).count() + self.far.keys().filter(|&&address| address < 0).count()
+    }
+}
+
+/// What moving off either end of a fixed-size tape does, see
+/// [`BrainfuckVMStatus::set_tape_size`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapeBounds {
+    /// the pointer comes back in at the other end, the tape is a ring
+    Wrap,
+    /// the move fails with [`BrainfuckError::PointerUnderflow`] or
+    /// [`BrainfuckError::PointerOverflow`]
+    Error,
+    /// the pointer stays on the first or the last cell
+    Saturate,
+}
+
+/// What `+` on a cell holding the largest value it can and `-` on a cell
+/// holding 0 do, see [`BrainfuckVMStatus::set_overflow_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// the cell wraps around to 0 or to the largest value
+    #[default]
+    Wrap,
+    /// the op fails with [`BrainfuckError::CellOverflow`]
+    Error,
+    /// the cell stays at the largest value or at 0
+    Saturate,
+}
+
+/// The cells of the VM.
+enum Cells {
+    /// cells touched so far, on a tape without ends
+    Unbounded(Tape),
+    /// every cell of a fixed-size tape, by address from 0
+    Fixed(Vec<i32>),
+}
+
+impl Cells {
+    /// Returns the number of cells, the ones touched so far on a tape without ends.
+    fn len(&self) -> usize {
+        match self {
+            Cells::Unbounded(tape) => tape.touched,
+            Cells::Fixed(cells) => cells.len(),
+        }
+    }
+
+    /// Returns the value of the cell at `address`, `None` if it was never touched.
+    fn get(&self, address: i32) -> Option<i32> {
+        match self {
+            Cells::Unbounded(tape) => tape.get(address),
+            Cells::Fixed(cells) => cells.get(address as usize).copied(),
+        }
+    }
+
+    /// Returns the cell at `address`, which the pointer keeps within a fixed-size tape.
+    fn entry(&mut self, address: i32) -> &mut i32 {
+        match self {
+            Cells::Unbounded(tape) => tape.entry(address),
+            Cells::Fixed(cells) => &mut cells[address as usize],
+        }
+    }
+
+    /// Returns the address and value of every cell, in no particular order.
+    fn iter(&self) -> Box<dyn Iterator<Item = (i32, i32)> + '_> {
+        match self {
+            Cells::Unbounded(tape) => Box::new(tape.iter()),
+            Cells::Fixed(cells) => Box::new(cells.iter().enumerate().map(|(address, &value)| (address as i32, value))),
+        }
+    }
+
+    /// Returns the value of every cell, in no particular order.
+    fn values(&self) -> impl Iterator<Item = i32> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+/// How often one cell was accessed, see [`BrainfuckVMStatus::record_accesses`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellAccesses {
+    /// times `.` printed the cell or a loop tested it
+    pub reads: u64,
+    /// times `+`, `-` or `,` changed the cell
+    pub writes: u64,
+}
+
+/// One op the VM ran, handed to the hook set with [`BrainfuckVMStatus::set_trace`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// ops run so far including this one, the same count as [`BrainfuckVMStatus::ops_executed`]
+    pub step: u64,
+    /// index of the op among the ops of the program, comments not counted
+    pub ip: usize,
+    /// the op, one of `+-<>.,[]`
+    pub op: char,
+    /// address of the current cell before the op
+    pub ptr: i32,
+    /// value of that cell before the op
+    pub cell_before: i32,
+    /// value of that cell after the op
+    pub cell_after: i32,
+    /// byte printed by `.` or read by `,`, `None` for other ops and at end of input
+    pub byte: Option<u8>,
+}
+
+/// One line of `rust_bf --trace`: the step, the op index, the op, the
+/// pointer and the cell before and after, and for `.` and `,` the byte.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::TraceEvent;
+///
+/// let event = TraceEvent { step: 2, ip: 1, op: ',', ptr: 0, cell_before: 1, cell_after: 65, byte: Some(65) };
+/// assert_eq!(event.to_string(), "2 1 , ptr=0 cell=1->65 byte=65");
+/// let event = TraceEvent { op: '>', byte: None, ..event };
+/// assert_eq!(event.to_string(), "2 1 > ptr=0 cell=1->65");
+/// ```
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} ptr={} cell={}->{}", self.step, self.ip, self.op, self.ptr, self.cell_before, self.cell_after)?;
+        match (self.op, self.byte) {
+            ('.' | ',', Some(byte)) => write!(f, " byte={}", byte),
+            ('.' | ',', None) => write!(f, " byte=eof"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Hook called with every op the VM runs, an error stops the run.
+pub type TraceHook = Box<dyn FnMut(&TraceEvent) -> Result<(), BrainfuckError>>;
+
+/// Brainfuck virtual machine status
+pub struct BrainfuckVMStatus {
+    /// virtual infinity length tape, unless it was given a fixed size
+    tape: Cells,
+    /// what moving off the ends of a fixed-size tape does
+    bounds: TapeBounds,
+    /// what a `<` off cell 0 of a tape without ends does when the tape
+    /// has no left side, `None` while it has
+    left_end: Option<TapeBounds>,
+    /// current cell of the tape
+    tape_ptr: i32,
+    /// leftmost and rightmost cell the pointer was ever on
+    reached: (i32, i32),
+    /// valid brainfuck_ops since the outermost running loop started,
+    /// nothing before it can run again
+    instruction: Vec<BrainfuckOp>,
+    /// index of the first op in `instruction`
+    instruction_start: usize,
+    /// current brainfuck_op index, `None` before the first op
+    instruction_ptr_current: Option<usize>,
+    /// keeping track of loops
+    instruction_loop_ptr: Vec<usize>,
+    
+    /// flag of skipping loop, e.g
+    /// +-[[[------------++++++++++-.>>[>]>>>--<<<<<<--]]]++++
+    ///   ^skipping from, but we need all                ^end of skipping
+    ///      instructions inside.
+    jump_loop: usize,
+    /// number of ops run so far, skipped ops not included
+    ops_executed: u64,
+    /// lowest and highest address written by `+`, `-` or `,`
+    written: Option<(i32, i32)>,
+    /// what `,` stores at end of input
+    eof_mode: EofMode,
+    /// cell values are kept to these bits, so that they wrap at a power of two
+    cell_mask: u32,
+    /// what `+` and `-` do past the ends of the values a cell holds
+    overflow: OverflowMode,
+    /// most distinct cells the tape tracks, `None` for no limit
+    max_tape: Option<usize>,
+    /// most bytes `.` may print, `None` for no limit
+    max_output: Option<usize>,
+    /// bytes printed by `.` so far
+    bytes_printed: usize,
+    /// accesses per cell, only counted once asked for
+    accesses: Option<BTreeMap<i32, CellAccesses>>,
+    /// which ops ran, only recorded once asked for
+    coverage: Option<CoverageReport>,
+    /// which `.` printed each byte, only recorded once asked for
+    output_map: Option<OutputSourceMap>,
+    /// called with every op run
+    trace: Option<TraceHook>,
+    /// stops the run at the next loop back-edge once set
+    interrupt: Option<&'static AtomicBool>,
+    /// most times in a row a loop may go round with its cell unchanged,
+    /// `None` to let it go round for ever
+    hang_threshold: Option<u64>,
+    /// whether `$` and `!` are ops rather than comments
+    #[cfg(feature = "debug_ops")]
+    debug_ops: bool,
+    /// whether `=` is an op rather than doing nothing
+    #[cfg(feature = "self_modifying")]
+    self_modifying: bool
+}
+
+impl BrainfuckVMStatus {
+    /// Returns the number of ops run so far, ops in skipped loops do not count.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// for c in "++[-] [+]".chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// assert_eq!(status.ops_executed(), 7);
+    /// ```
+    pub fn ops_executed(&self) -> u64 {
+        self.ops_executed
+    }
+
+    /// Returns the number of non-zero cells.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// for c in "+>+++>-+<<<-".chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// assert_eq!(status.cell_count(), 3);
+    /// assert_eq!(status.max_cell_address(), Some(2));
+    /// assert_eq!(status.min_cell_address(), Some(-1));
+    /// assert_eq!(status.tape_checksum(), 1 ^ 3 ^ 255);
+    /// ```
+    pub fn cell_count(&self) -> usize {
+        self.tape.values().filter(|&value| value != 0).count()
+    }
+
+    /// Returns the number of cells the program has touched, which the
+    /// tape never shrinks below.
+    pub fn allocated_cells(&self) -> usize {
+        self.tape.len()
+    }
+
+    /// Returns the highest address ever written by `+`, `-` or `,`,
+    /// `None` if nothing was written at or right of cell 0.
+    pub fn max_cell_address(&self) -> Option<usize> {
+        self.written.and_then(|(_, max)| usize::try_from(max).ok())
+    }
+
+    /// Returns the lowest address ever written by `+`, `-` or `,`, which
+    /// is negative if the program wrote left of cell 0.
+    pub fn min_cell_address(&self) -> Option<i64> {
+        self.written.map(|(min, _)| min as i64)
+    }
+
+    /// Returns all cell values XORed together, a quick way to tell two
+    /// runs apart.
+    ///
+    /// Runs with the same checksum are not necessarily equal: cell
+    /// positions are not part of it and values can cancel out.
+    pub fn tape_checksum(&self) -> u64 {
+        self.tape.values().fold(0, |checksum, value| checksum ^ value as u32 as u64)
+    }
+
+    /// Returns the leftmost and the rightmost cell the pointer has been on,
+    /// loops included, the first one negative if it went left of cell 0.
+    ///
+    /// The pointer need not do anything with a cell to reach it, a
+    /// fixed-size tape has to hold all of them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// run_vm_bytes(&mut status, b"<<+++[>>+<<-]>>", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.pointer_extent(), (-2, 0));
+    /// ```
+    pub fn pointer_extent(&self) -> (i32, i32) {
+        self.reached
+    }
+
+    /// Returns the address of the current cell, negative left of cell 0.
+    pub fn tape_ptr(&self) -> i32 {
+        self.tape_ptr
+    }
+
+    /// Returns the address and value of every non-zero cell, lowest address first.
+    pub fn cells(&self) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = self.tape.iter().filter(|&(_, value)| value != 0).collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Returns how many cells hold each value, from the cells the program touched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// for c in "+>+><<<-".chars() {
+    ///     run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new()).unwrap();
+    /// }
+    /// let histogram = status.cell_value_histogram();
+    /// assert_eq!(histogram.nonzero_cells(), 3);
+    /// assert_eq!(histogram.values().collect::<Vec<_>>(), [(1, 2), (255, 1)]);
+    /// ```
+    pub fn cell_value_histogram(&self) -> CellValueHistogram {
+        CellValueHistogram::from_values(self.tape.values().map(|value| value as u32))
+    }
+
+    /// Sets what `,` stores once the input is exhausted, 0 by default.
+    pub fn set_eof_mode(&mut self, mode: EofMode) {
+        self.eof_mode = mode;
+    }
+
+    /// Returns what `,` stores once the input is exhausted.
+    pub fn eof_mode(&self) -> EofMode {
+        self.eof_mode
+    }
+
+    /// Makes cells `bits` wide, so that values wrap around at `2^bits`.
+    /// Cells are 8-bit by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `bits` is between 1 and 32.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_cell_bits(7);
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// // 128 wraps around to 0 in a 7-bit cell, and 0 - 1 to 127
+    /// let src = format!("{}.-.", "+".repeat(128));
+    /// for c in src.chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// assert_eq!(output, [0, 127]);
+    /// ```
+    pub fn set_cell_bits(&mut self, bits: u32) {
+        assert!((1..=32).contains(&bits), "cells must be 1 to 32 bits wide, not {}", bits);
+        self.cell_mask = u32::MAX >> (32 - bits);
+    }
+
+    /// Sets what `+` on a cell holding the largest value it can and `-` on
+    /// a cell holding 0 do, they wrap around by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, OverflowMode};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_overflow_mode(OverflowMode::Saturate);
+    /// run_vm_bytes(&mut status, b"->++", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.cells(), [(1, 2)]);
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_overflow_mode(OverflowMode::Error);
+    /// let err = run_vm_bytes(&mut status, b">-", &mut &b""[..], &mut Vec::new()).unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::CellOverflow { position: 1, cell: 1, value: -1 }));
+    /// ```
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.overflow = mode;
+    }
+
+    /// Swaps the tape without ends for `cells` cells from address 0 on,
+    /// all zero, with `bounds` saying what a `>` past the last cell and a
+    /// `<` before cell 0 do. The pointer goes back to cell 0, set the size
+    /// before the program runs.
+    ///
+    /// The cells are kept in a plain array, the tape limit of
+    /// [`BrainfuckVMStatus::set_max_tape`] does not apply to them.
+    ///
+    /// # Panics
+    ///
+    /// If `cells` is 0 or more than a 32-bit pointer can reach.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, TapeBounds};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_tape_size(3, TapeBounds::Wrap);
+    /// run_vm_bytes(&mut status, b"<+>+", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.cells(), [(0, 1), (2, 1)]);
+    ///
+    /// status.set_tape_size(3, TapeBounds::Error);
+    /// let err = run_vm_bytes(&mut status, b">><>>", &mut &b""[..], &mut Vec::new()).unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::PointerOverflow { position: 8 }));
+    /// ```
+    pub fn set_tape_size(&mut self, cells: usize, bounds: TapeBounds) {
+        assert!((1..=i32::MAX as usize).contains(&cells), "a fixed tape holds 1 to 2^31 - 1 cells, not {}", cells);
+        self.tape = Cells::Fixed(vec![0; cells]);
+        self.bounds = bounds;
+        self.tape_ptr = 0;
+        self.reached = (0, 0);
+    }
+
+    /// Takes away the cells left of cell 0 from the tape without ends, with
+    /// `bounds` saying what a `<` on cell 0 does: fail with
+    /// [`BrainfuckError::PointerUnderflow`] or keep the pointer there.
+    /// Set it before the program runs, the tape has both sides by default.
+    ///
+    /// # Panics
+    ///
+    /// If `bounds` is [`TapeBounds::Wrap`], a tape with no right end has
+    /// nowhere to wrap to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, TapeBounds};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_right_only(TapeBounds::Saturate);
+    /// run_vm_bytes(&mut status, b"<<+>+", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.cells(), [(0, 1), (1, 1)]);
+    ///
+    /// status.set_right_only(TapeBounds::Error);
+    /// let err = run_vm_bytes(&mut status, b"<<", &mut &b""[..], &mut Vec::new()).unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::PointerUnderflow { position: 6 }));
+    /// ```
+    pub fn set_right_only(&mut self, bounds: TapeBounds) {
+        assert!(bounds != TapeBounds::Wrap, "a tape with only a right side cannot wrap around");
+        self.left_end = Some(bounds);
+    }
+
+    /// Returns how many of the cells the program touched are left of cell 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// run_vm_bytes(&mut status, b"+<+<<+>>>>+", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.negative_cells(), 2);
+    /// ```
+    pub fn negative_cells(&self) -> usize {
+        match &self.tape {
+            Cells::Unbounded(tape) => tape.negative(),
+            Cells::Fixed(_) => 0,
+        }
+    }
+
+    /// Caps the number of distinct cells the tape tracks at `cells`, a
+    /// program touching one more fails with
+    /// [`BrainfuckError::TapeMemoryExceeded`]. The tape is unlimited by default.
+    ///
+    /// Every cell the program reads or writes counts, wherever it is, so
+    /// that a program scattering writes over the whole address range cannot
+    /// take up all memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_max_tape(2);
+    /// let mut run = |c| run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new());
+    /// for c in "+>+<->".chars() {
+    ///     run(c).unwrap();
+    /// }
+    /// run('>').unwrap();
+    /// assert!(matches!(run('+'), Err(BrainfuckError::TapeMemoryExceeded { limit: 2 })));
+    /// ```
+    pub fn set_max_tape(&mut self, cells: usize) {
+        self.max_tape = Some(cells);
+    }
+
+    /// Caps the bytes `.` may print at `bytes`, the `.` that would print one
+    /// more fails with [`BrainfuckError::OutputLimitExceeded`] without
+    /// printing it. Output is unlimited by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_max_output(3);
+    /// let mut output = Vec::new();
+    /// let err = "+[.]".chars().try_for_each(|c| run_vm(&mut status, c, false, &mut &b""[..], &mut output));
+    /// assert!(matches!(err, Err(BrainfuckError::OutputLimitExceeded { limit: 3 })));
+    /// assert_eq!(output, [1, 1, 1]);
+    /// assert_eq!(status.bytes_printed(), 3);
+    /// ```
+    pub fn set_max_output(&mut self, bytes: usize) {
+        self.max_output = Some(bytes);
+    }
+
+    /// Returns the number of bytes `.` printed so far.
+    pub fn bytes_printed(&self) -> usize {
+        self.bytes_printed
+    }
+
+    /// Returns the mask cell values are kept to, `0xff` for 8-bit cells.
+    pub fn cell_mask(&self) -> u32 {
+        self.cell_mask
+    }
+
+    /// Starts counting reads and writes of every cell.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm, CellAccesses};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.record_accesses();
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// for c in "++[-]>.".chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// let accesses = status.accesses().unwrap();
+    /// // tested at `[`, then at `]` after each of the two `-`
+    /// assert_eq!(accesses[&0], CellAccesses { reads: 3, writes: 4 });
+    /// assert_eq!(accesses[&1], CellAccesses { reads: 1, writes: 0 });
+    /// ```
+    pub fn record_accesses(&mut self) {
+        self.accesses.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Returns the accesses of every cell accessed so far, lowest address
+    /// first, `None` unless [`BrainfuckVMStatus::record_accesses`] was called.
+    pub fn accesses(&self) -> Option<&BTreeMap<i32, CellAccesses>> {
+        self.accesses.as_ref()
+    }
+
+    /// Starts recording which ops run, for ops fed to the VM from now on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.record_coverage();
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// // the second loop is skipped, `]` included
+    /// for c in "+[-]>[++]<..".chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// let coverage = status.coverage().unwrap();
+    /// assert_eq!(coverage.uncovered_positions(), [6, 7, 8]);
+    /// assert_eq!(coverage.coverage_percentage(), 75.0);
+    /// ```
+    pub fn record_coverage(&mut self) {
+        self.coverage.get_or_insert_with(CoverageReport::default);
+    }
+
+    /// Returns which ops ran, `None` unless [`BrainfuckVMStatus::record_coverage`] was called.
+    pub fn coverage(&self) -> Option<&CoverageReport> {
+        self.coverage.as_ref()
+    }
+
+    /// Starts recording which `.` prints each byte, for bytes printed from now on.
+    pub fn record_output_map(&mut self) {
+        self.output_map.get_or_insert_with(OutputSourceMap::default);
+    }
+
+    /// Returns which `.` printed each byte, `None` unless
+    /// [`BrainfuckVMStatus::record_output_map`] was called.
+    pub fn output_map(&self) -> Option<&OutputSourceMap> {
+        self.output_map.as_ref()
+    }
+
+    /// Counts a read of the current cell if accesses are recorded.
+    fn note_read(&mut self) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.entry(self.tape_ptr).or_default().reads += 1;
+        }
+    }
+
+    /// Returns whether the current cell is nonzero, as loops test it.
+    fn test_cell(&mut self) -> Result<bool, BrainfuckError> {
+        self.note_read();
+        Ok(*self.cell_mut()? != 0)
+    }
+
+    /// Returns the current cell, tracking it from now on if it was not tracked yet.
+    fn cell_mut(&mut self) -> Result<&mut i32, BrainfuckError> {
+        if let (Some(limit), Cells::Unbounded(tape)) = (self.max_tape, &self.tape) {
+            if tape.touched >= limit && tape.get(self.tape_ptr).is_none() {
+                return Err(BrainfuckError::TapeMemoryExceeded { limit });
+            }
+        }
+        Ok(self.tape.entry(self.tape_ptr))
+    }
+
+    /// Calls `hook` after every op the VM runs from now on, or for `[` and
+    /// `]` right after the cell was tested, before the loop body runs again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm};
+    ///
+    /// let ops = Rc::new(RefCell::new(String::new()));
+    /// let mut status = new_brainfuck_status();
+    /// let seen = ops.clone();
+    /// status.set_trace(Box::new(move |event| {
+    ///     seen.borrow_mut().push(event.op);
+    ///     Ok(())
+    /// }));
+    /// let mut input: &[u8] = b"";
+    /// let mut output = Vec::new();
+    /// for c in "++[-]".chars() {
+    ///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    /// }
+    /// assert_eq!(*ops.borrow(), "++[-]-");
+    /// ```
+    pub fn set_trace(&mut self, hook: TraceHook) {
+        self.trace = Some(hook);
+    }
+
+    /// Makes the run stop with [`BrainfuckError::Interrupted`] the next
+    /// time a loop jumps back after `flag` was set, for example by a
+    /// signal handler. The VM never clears the flag.
+    ///
+    /// Everything ran up to that loop is kept, which leaves the tape as it
+    /// was when the interrupt came in.
+    pub fn set_interrupt_flag(&mut self, flag: &'static AtomicBool) {
+        self.interrupt = Some(flag);
+    }
+
+    /// Makes the run stop with [`BrainfuckError::LoopHang`] once a loop
+    /// jumps back more than `iterations` times in a row with the pointer on
+    /// the same cell and that cell holding the same value, which it would do
+    /// for ever.
+    ///
+    /// A loop that keeps its cell for a while and changes it later still
+    /// trips this if the while is longer than `iterations`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    /// use rust_bf::BrainfuckError;
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// status.set_hang_threshold(100);
+    /// let mut output = Vec::new();
+    /// // the inner loop counts the cell of the outer one down, 3 rounds
+    /// run_vm_bytes(&mut status, b"+++[>+[<->-]<]", &mut &b""[..], &mut output).unwrap();
+    /// let err = run_vm_bytes(&mut status, b"+[>+<]", &mut &b""[..], &mut output).unwrap_err();
+    /// assert!(matches!(err, BrainfuckError::LoopHang { position: 15, iterations: 102 }));
+    /// ```
+    pub fn set_hang_threshold(&mut self, iterations: u64) {
+        self.hang_threshold = Some(iterations);
+    }
+
+    /// Makes `$` print the current cell as a decimal number and `!` set
+    /// every cell to 0 and the pointer back to cell 0. Off by default,
+    /// when they do nothing.
+    ///
+    /// Turning them on changes the language: a `$` or `!` in the comments
+    /// of a program written for plain brainfuck runs too. Off, they still
+    /// count as ops, for the op indices of errors and [`CoverageReport`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// let mut output = Vec::new();
+    /// run_vm_bytes(&mut status, b"+++$ costs 3!", &mut &b""[..], &mut output).unwrap();
+    /// assert_eq!((output.len(), status.cells()), (0, vec![(0, 3)]));
+    ///
+    /// status.set_debug_ops(true);
+    /// run_vm_bytes(&mut status, b">++++++++++++$!$", &mut &b""[..], &mut output).unwrap();
+    /// assert_eq!(output, b"120");
+    /// assert_eq!((status.tape_ptr(), status.cells()), (0, vec![]));
+    /// ```
+    #[cfg(feature = "debug_ops")]
+    pub fn set_debug_ops(&mut self, enabled: bool) {
+        self.debug_ops = enabled;
+    }
+
+    /// Makes `=` write its own character, 61, into the current cell. Off
+    /// by default, when it does nothing.
+    ///
+    /// Like [`BrainfuckVMStatus::set_debug_ops`] this changes the language,
+    /// an `=` in the comments of a plain brainfuck program runs too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+    ///
+    /// let mut status = new_brainfuck_status();
+    /// run_vm_bytes(&mut status, b"+ so cell 0 = 1", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.cells(), [(0, 1)]);
+    ///
+    /// status.set_self_modifying(true);
+    /// run_vm_bytes(&mut status, b">=", &mut &b""[..], &mut Vec::new()).unwrap();
+    /// assert_eq!(status.cells(), [(0, 1), (1, 61)]);
+    /// ```
+    #[cfg(feature = "self_modifying")]
+    pub fn set_self_modifying(&mut self, enabled: bool) {
+        self.self_modifying = enabled;
+    }
+
+    /// Returns whether the interrupt flag is set.
+    fn interrupted(&self) -> bool {
+        self.interrupt.is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Returns the index of the op being run.
+    fn ip(&self) -> usize {
+        self.instruction_ptr_current.expect("an op is running")
+    }
+
+    /// Moves the pointer `n` cells to the right, failing at the ends of the tape.
+    fn move_ptr(&mut self, n: i32) -> Result<(), BrainfuckError> {
+        self.tape_ptr = match &self.tape {
+            Cells::Unbounded(_) => match self.tape_ptr.checked_add(n) {
+                None if n > 0 || self.left_end.is_none() => return Err(BrainfuckError::PointerOverflow { position: self.ip() }),
+                Some(moved) if moved >= 0 || self.left_end.is_none() => moved,
+                _ if self.left_end == Some(TapeBounds::Saturate) => 0,
+                _ => return Err(BrainfuckError::PointerUnderflow { position: self.ip() }),
+            },
+            Cells::Fixed(cells) => {
+                let size = cells.len() as i64;
+                let moved = self.tape_ptr as i64 + n as i64;
+                match self.bounds {
+                    _ if (0..size).contains(&moved) => moved as i32,
+                    TapeBounds::Wrap => moved.rem_euclid(size) as i32,
+                    TapeBounds::Saturate => moved.clamp(0, size - 1) as i32,
+                    TapeBounds::Error if moved < 0 => return Err(BrainfuckError::PointerUnderflow { position: self.ip() }),
+                    TapeBounds::Error => return Err(BrainfuckError::PointerOverflow { position: self.ip() }),
+                }
+            },
+        };
+        self.note_move();
+        Ok(())
+    }
+
+    /// Widens the cells the pointer reached to the current one.
+    fn note_move(&mut self) {
+        if self.tape_ptr < self.reached.0 {
+            self.reached.0 = self.tape_ptr;
+        } else if self.tape_ptr > self.reached.1 {
+            self.reached.1 = self.tape_ptr;
+        }
+    }
+
+    /// Returns the value of the cell at `address`.
+    fn cell_at(&self, address: i32) -> i32 {
+        self.tape.get(address).unwrap_or(0)
+    }
+
+    /// Hands the op that just ran to the trace hook, if there is one.
+    fn note_op(&mut self, op: char, ptr: i32, cell_before: i32, byte: Option<u8>) -> Result<(), BrainfuckError> {
+        if self.trace.is_none() {
+            return Ok(());
+        }
+        let event = TraceEvent {
+            step: self.ops_executed,
+            ip: self.ip(),
+            op,
+            ptr,
+            cell_before,
+            cell_after: self.cell_at(ptr),
+            byte,
+        };
+        match &mut self.trace {
+            Some(trace) => trace(&event),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `value` wrapped to the cell width.
+    fn wrap(&self, value: i32) -> i32 {
+        (value as u32 & self.cell_mask) as i32
+    }
+
+    /// Adds `delta`, 1 for `+` and -1 for `-`, to the current cell, past the
+    /// ends of what it holds the way the overflow mode says.
+    fn add_to_cell(&mut self, delta: i64) -> Result<(), BrainfuckError> {
+        let (max, mode, ptr) = (self.cell_mask as i64, self.overflow, self.tape_ptr);
+        let position = self.ip();
+        let cell = self.cell_mut()?;
+        let value = *cell as u32 as i64 + delta;
+        *cell = match mode {
+            OverflowMode::Wrap => (value as u32 & max as u32) as i32,
+            OverflowMode::Saturate => value.clamp(0, max) as i32,
+            OverflowMode::Error if (0..=max).contains(&value) => value as i32,
+            OverflowMode::Error => return Err(BrainfuckError::CellOverflow { position, cell: ptr as i64, value }),
+        };
+        self.note_write();
+        Ok(())
+    }
+
+    /// Records a write to the current cell.
+    fn note_write(&mut self) {
+        let ptr = self.tape_ptr;
+        self.written = Some(match self.written {
+            Some((min, max)) => (min.min(ptr), max.max(ptr)),
+            None => (ptr, ptr),
+        });
+        if let Some(accesses) = &mut self.accesses {
+            accesses.entry(ptr).or_default().writes += 1;
+        }
+    }
+}
+
+/// Returns a new brainfuck VM status.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::new_brainfuck_status;
+///
+/// let status = new_brainfuck_status();
+/// ```
+pub fn new_brainfuck_status() -> BrainfuckVMStatus {
+    BrainfuckVMStatus {
+        tape: Cells::Unbounded(Tape::default()),
+        bounds: TapeBounds::Error,
+        left_end: None,
+        tape_ptr: 0,
+        reached: (0, 0),
+        instruction: Vec::new(),
+        instruction_start: 0,
+        instruction_ptr_current: None,
+        instruction_loop_ptr: Vec::new(),
+        jump_loop: 0,
+        ops_executed: 0,
+        written: None,
+        eof_mode: EofMode::Zero,
+        cell_mask: 0xff,
+        overflow: OverflowMode::Wrap,
+        max_tape: None,
+        max_output: None,
+        bytes_printed: 0,
+        accesses: None,
+        coverage: None,
+        output_map: None,
+        trace: None,
+        interrupt: None,
+        hang_threshold: None,
+        #[cfg(feature = "debug_ops")]
+        debug_ops: false,
+        #[cfg(feature = "self_modifying")]
+        self_modifying: false
+    }
+}
+
+/// Returns next corresponding BrainfuckOp of given `character`.
+///
+/// # Arguments
+///
+/// * `status`    - A mutable var that holds the status of current brainfuck VM status
+/// * `character` - char type op (may be invalid brainfuck op)
+/// * `via_loop`  - Due to the way I wrote, a flag is needed to avoid re-adding ops 
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::BrainfuckOp;
+/// use rust_bf::vm::{new_brainfuck_status, next_op};
+///
+/// let mut status = new_brainfuck_status();
+/// let next_op = next_op(&mut status, '+', false);
+/// assert_eq!(next_op, BrainfuckOp::IncrementValueOp);
+/// ```
+pub fn next_op(status: &mut BrainfuckVMStatus, character: char, via_loop: bool) -> BrainfuckOp {
+    // match BrainfuckOp for character
+    let op = BrainfuckOp::from_char(character);
+    
+    // do not append the char_op if we're retriving the next op inside a loop_op
+    if !via_loop {
+        match op {
+            BrainfuckOp::MonoStateOp => (),
+            _ => {
+                // increse the ptr of current instruction
+                let index = status.instruction_ptr_current.map_or(0, |index| index + 1);
+                status.instruction_ptr_current = Some(index);
+                // outside of loops no op can run again, so there is nothing to keep
+                if status.instruction_loop_ptr.is_empty() && status.jump_loop == 0 {
+                    status.instruction.clear();
+                    status.instruction_start = index;
+                }
+                // save the op to instruction, a byte rather than the char it came from
+                status.instruction.push(op);
+                if let Some(coverage) = &mut status.coverage {
+                    coverage.push();
+                }
+            }
+        };
+    }
+    // return next op
+    op
+}
+
+/// Run brainfuck VM
+///
+/// # Arguments
+///
+/// * `status`    - A mutable var that holds the status of current brainfuck VM status
+/// * `char_op`   - char type op (may be invalid brainfuck op)
+/// * `via_loop`  - Due to the way I wrote, a flag is needed to avoid re-adding ops 
+/// * `input`     - where `,` reads from
+/// * `output`    - where `.` writes to
+///
+/// Stops with the error of the first failed write.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::{new_brainfuck_status, run_vm};
+///
+/// let mut status = new_brainfuck_status();
+/// let mut input: &[u8] = b"";
+/// let mut output = Vec::new();
+/// for c in "++++++++[>++++++++<-]>+.".chars() {
+///     run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+/// }
+/// assert_eq!(output, b"A");
+/// ```
+pub fn run_vm(status: &mut BrainfuckVMStatus, char_op: char, via_loop: bool, input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    // get next op from char_op
+    let op = next_op(status, char_op, via_loop);
+    run_op(status, op, input, output)
+}
+
+/// Runs every op of the source `src`, as if [`run_vm`] got its bytes one at a time.
+///
+/// Every op is ASCII, so the bytes of other characters are comments just
+/// like the characters themselves, and `src` need not be UTF-8.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+///
+/// let mut status = new_brainfuck_status();
+/// let mut output = Vec::new();
+/// // a Latin-1 comment, not valid UTF-8
+/// run_vm_bytes(&mut status, b"+++\xe9t\xe9.", &mut &b""[..], &mut output).unwrap();
+/// assert_eq!(output, [3]);
+/// ```
+pub fn run_vm_bytes(status: &mut BrainfuckVMStatus, src: &[u8], input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    src.iter().try_for_each(|&byte| run_vm(status, byte as char, false, input, output))
+}
+
+/// Runs `op`, which [`next_op`] has already recorded or a loop runs again.
+fn run_op(status: &mut BrainfuckVMStatus, op: BrainfuckOp, input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    let char_op = match op.as_char() {
+        Some(char_op) => char_op,
+        None => return Ok(()),
+    };
+    let runs = status.jump_loop == 0;
+    if runs {
+        status.ops_executed += 1;
+        let ip = status.ip();
+        if let Some(coverage) = &mut status.coverage {
+            coverage.hit(ip);
+        }
+    }
+    // only looked up for the trace, this runs for every op
+    let ptr = status.tape_ptr;
+    let cell_before = if status.trace.is_some() { status.cell_at(ptr) } else { 0 };
+    // byte printed or read by this op
+    let mut byte = None;
+    match op {
+        BrainfuckOp::IncrementValueOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                status.add_to_cell(1)?;
+            }
+        },
+        BrainfuckOp::DecrementValueOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                status.add_to_cell(-1)?;
+            }
+        },
+        BrainfuckOp::IncrementPtrOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                status.move_ptr(1)?;
+            }
+        },
+        BrainfuckOp::DecrementPtrOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                status.move_ptr(-1)?;
+            }
+        },
+        BrainfuckOp::PrintOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                if let Some(limit) = status.max_output.filter(|&limit| status.bytes_printed >= limit) {
+                    return Err(BrainfuckError::OutputLimitExceeded { limit });
+                }
+                // take cell from tape
+                status.note_read();
+                let out = *status.cell_mut()?;
+                // print the low byte, which is the whole cell unless cells are wider than 8 bits
+                output.write_byte(out as u8)?;
+                let ip = status.ip();
+                if let Some(map) = &mut status.output_map {
+                    map.push(status.bytes_printed, ip);
+                }
+                status.bytes_printed += 1;
+                byte = Some(out as u8);
+            }
+        },
+        BrainfuckOp::ReadOp => {
+            // skip actual action if we're skipping loop
+            if status.jump_loop == 0 {
+                // read a single byte from input, wrapped to the cell width
+                byte = input.read_byte();
+                if byte.is_none() && status.eof_mode == EofMode::Error {
+                    return Err(BrainfuckError::InputRequired { position: status.ip() });
+                }
+                let input = byte.map(|byte| status.wrap(byte as i32));
+                let minus_one = status.wrap(-1);
+                let eof_mode = status.eof_mode;
+                // store in tape, what end of input stores depends on the eof mode
+                let out = status.cell_mut()?;
+                *out = match (input, eof_mode) {
+                    (Some(byte), _) => byte,
+                    (None, EofMode::Zero) => 0,
+                    (None, EofMode::MinusOne) => minus_one,
+                    // `Error` has returned above
+                    (None, EofMode::Unchanged) | (None, EofMode::Error) => *out,
+                };
+                status.note_write();
+            }
+        },
+        BrainfuckOp::LoopStartOp => {
+            // if and only if 1) `current_cell_value != 0`
+            //                2) and we're not do the skipping
+            // we can record the starting index of the if instruction
+            // besides, if we're in condition 1)
+            // the if statement should be also skipped
+            if status.jump_loop == 0 && status.test_cell()? {
+                status.instruction_loop_ptr.push(status.ip());
+                status.note_op(char_op, ptr, cell_before, None)?;
+            } else {
+                if runs {
+                    status.note_op(char_op, ptr, cell_before, None)?;
+                }
+                status.jump_loop += 1;
+            }
+        },
+        BrainfuckOp::LoopEndOp => {
+            // decrease the jump_loop value if we encounter the `]`
+            // and we were previously doing the skip
+            if status.jump_loop != 0 {
+                status.jump_loop -= 1;
+            } else {
+                // if we were not in skipping
+                // then we need to check the loop condition, `current_cell_value != 0`
+                // a `]` without an open loop has nowhere to jump back to
+                let nonzero = status.test_cell()?;
+                status.note_op(char_op, ptr, cell_before, None)?;
+                if nonzero && !status.instruction_loop_ptr.is_empty() {
+                    // cell tested at the last jump back, times in a row it was the same, rounds so far
+                    let mut guard = (status.tape_ptr, status.cell_at(status.tape_ptr));
+                    let mut unchanged = 0;
+                    let mut iterations = 1;
+                    // loop the instruction until condition satisfies no more
+                    loop {
+                        // save current instruction pointer
+                        let current = status.ip();
+                        // start the loop right after the index of `[`
+                        if let Some(last) = status.instruction_loop_ptr.last().cloned() {
+                            // run one op at a time
+                            // until the next op is the corresponding `]`
+                            for index in last + 1..current {
+                                status.instruction_ptr_current = Some(index);
+                                let current_op = status.instruction[index - status.instruction_start];
+                                run_op(status, current_op, input, output)?;
+                            }
+                            // restore the current instruction pointer
+                            status.instruction_ptr_current = Some(current);
+                        }
+                        if !status.test_cell()? {
+                            break;
+                        }
+                        // checked at the back-edge only, where every endless program passes by
+                        if status.interrupted() {
+                            return Err(BrainfuckError::Interrupted);
+                        }
+                        iterations += 1;
+                        if let Some(threshold) = status.hang_threshold {
+                            let tested = (status.tape_ptr, status.cell_at(status.tape_ptr));
+                            unchanged = if tested == guard { unchanged + 1 } else { 0 };
+                            guard = tested;
+                            if unchanged > threshold {
+                                let position = status.instruction_loop_ptr.last().copied().unwrap_or(0);
+                                return Err(BrainfuckError::LoopHang { position, iterations });
+                            }
+                        }
+                    }
+                }
+                // pop current loop starting index, also when the body ran only once,
+                // or an outer `]` would jump back to this loop's `[`
+                status.instruction_loop_ptr.pop();
+            }
+        },
+        #[cfg(feature = "self_modifying")]
+        BrainfuckOp::SelfWriteOp => {
+            // skip actual action if we're skipping loop or the op is off
+            if status.jump_loop == 0 && status.self_modifying {
+                // store the character of the op being run, `=` itself
+                let value = status.wrap(char_op as i32);
+                *status.cell_mut()? = value;
+                status.note_write();
+            }
+        },
+        #[cfg(feature = "debug_ops")]
+        BrainfuckOp::PrintNumberOp => {
+            // skip actual action if we're skipping loop or the op is off
+            if status.jump_loop == 0 && status.debug_ops {
+                status.note_read();
+                let number = (*status.cell_mut()? as u32).to_string();
+                for digit in number.bytes() {
+                    if let Some(limit) = status.max_output.filter(|&limit| status.bytes_printed >= limit) {
+                        return Err(BrainfuckError::OutputLimitExceeded { limit });
+                    }
+                    output.write_byte(digit)?;
+                    let ip = status.ip();
+                    if let Some(map) = &mut status.output_map {
+                        map.push(status.bytes_printed, ip);
+                    }
+                    status.bytes_printed += 1;
+                }
+            }
+        },
+        #[cfg(feature = "debug_ops")]
+        BrainfuckOp::ResetOp => {
+            // skip actual action if we're skipping loop or the op is off
+            if status.jump_loop == 0 && status.debug_ops {
+                match &mut status.tape {
+                    Cells::Unbounded(tape) => *tape = Tape::default(),
+                    Cells::Fixed(cells) => cells.fill(0),
+                }
+                status.tape_ptr = 0;
+            }
+        },
+        // has returned above
+        BrainfuckOp::MonoStateOp => ()
+    }
+    if runs && !matches!(op, BrainfuckOp::LoopStartOp | BrainfuckOp::LoopEndOp) {
+        status.note_op(char_op, ptr, cell_before, byte)?;
+    }
+    Ok(())
+}
+
+/// Runs `char_op` `count` times, as if [`run_vm`] got it `count` times in a row.
+///
+/// Outside of loops a run of `+`, `-`, `>` or `<` takes the same time
+/// whatever its length, unless a trace hook wants to see every op or
+/// coverage is recorded, so
+/// that run-length encoded programs run without expanding them.
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::{new_brainfuck_status, run_vm, run_vm_repeated};
+///
+/// let mut status = new_brainfuck_status();
+/// let mut input: &[u8] = b"";
+/// let mut output = Vec::new();
+/// run_vm_repeated(&mut status, '+', 65, &mut input, &mut output).unwrap();
+/// run_vm(&mut status, '.', false, &mut input, &mut output).unwrap();
+/// assert_eq!(output, b"A");
+/// assert_eq!(status.ops_executed(), 66);
+/// ```
+pub fn run_vm_repeated(status: &mut BrainfuckVMStatus, char_op: char, count: usize, input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    let op = BrainfuckOp::from_char(char_op);
+    // the ends of a fixed-size tape and cell 0 of a right-only one are left to `run_vm`
+    let moves = matches!(op, BrainfuckOp::IncrementPtrOp | BrainfuckOp::DecrementPtrOp);
+    let unbounded = matches!(status.tape, Cells::Unbounded(_)) && status.left_end.is_none();
+    let at_once = (matches!(op, BrainfuckOp::IncrementValueOp | BrainfuckOp::DecrementValueOp) || moves && unbounded)
+        && count > 0 && status.jump_loop == 0 && status.instruction_loop_ptr.is_empty() && status.trace.is_none() && status.coverage.is_none();
+    if !at_once {
+        for _ in 0..count {
+            run_vm(status, char_op, false, input, output)?;
+        }
+        return Ok(());
+    }
+
+    // nothing outside of loops is kept, see `next_op`
+    let first = status.instruction_ptr_current.map_or(0, |index| index + 1);
+    status.instruction.clear();
+    match op {
+        BrainfuckOp::IncrementPtrOp | BrainfuckOp::DecrementPtrOp => {
+            // room left before the end of the tape in that direction
+            let room = if op == BrainfuckOp::IncrementPtrOp {
+                (i32::MAX as i64 - status.tape_ptr as i64) as usize
+            } else {
+                (status.tape_ptr as i64 - i32::MIN as i64) as usize
+            };
+            let moved = count.min(room);
+            status.tape_ptr = if op == BrainfuckOp::IncrementPtrOp {
+                (status.tape_ptr as i64 + moved as i64) as i32
+            } else {
+                (status.tape_ptr as i64 - moved as i64) as i32
+            };
+            status.note_move();
+            if count > room {
+                // the op after the last one that fit fails
+                status.instruction_ptr_current = Some(first + room);
+                status.ops_executed += room as u64 + 1;
+                return Err(BrainfuckError::PointerOverflow { position: first + room });
+            }
+        },
+        _ => {
+            let up = op == BrainfuckOp::IncrementValueOp;
+            let (mask, mode, ptr) = (status.cell_mask, status.overflow, status.tape_ptr);
+            let cell = status.cell_mut()?;
+            let value = *cell as u32;
+            // ops left before the cell reaches the end of its values that way
+            let room = (if up { mask - value } else { value }) as usize;
+            // the ops that run, all but the ones after a failing one
+            let done = if mode == OverflowMode::Error { count.min(room) } else { count };
+            *cell = match mode {
+                // cells wrap at a power of two no larger than 2^32, so `count` can wrap at 2^32
+                OverflowMode::Wrap if up => (value.wrapping_add(count as u32) & mask) as i32,
+                OverflowMode::Wrap => (value.wrapping_sub(count as u32) & mask) as i32,
+                _ if up => (value + done.min(room) as u32) as i32,
+                _ => (value - done.min(room) as u32) as i32,
+            };
+            if done > 0 {
+                status.note_write();
+                if let Some(accesses) = &mut status.accesses {
+                    accesses.entry(ptr).or_default().writes += done as u64 - 1;
+                }
+            }
+            if done < count {
+                // the op after the last one that fit fails
+                status.instruction_ptr_current = Some(first + done);
+                status.ops_executed += done as u64 + 1;
+                let value = if up { mask as i64 + 1 } else { -1 };
+                return Err(BrainfuckError::CellOverflow { position: first + done, cell: ptr as i64, value });
+            }
+        },
+    }
+    status.instruction_ptr_current = Some(first + count - 1);
+    status.ops_executed += count as u64;
+    Ok(())
+}
+
+/// Runs a whole program on a fresh VM.
+///
+/// Unlike feeding characters to [`run_vm`] one at a time, unmatched
+/// brackets are reported before anything runs. Empty programs and
+/// programs without a single op successfully do nothing.
+///
+/// # Arguments
+///
+/// * `src`      - brainfuck source
+/// * `eof_mode` - what `,` stores once the input is exhausted
+/// * `input`    - where `,` reads from
+/// * `output`   - where `.` writes to
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::io::EofMode;
+/// use rust_bf::vm::run_program;
+/// use rust_bf::BrainfuckError;
+///
+/// let run = |src: &str, eof_mode| {
+///     let mut output = Vec::new();
+///     run_program(src, eof_mode, &mut &b""[..], &mut output).map(|()| output)
+/// };
+/// assert!(run_program("", EofMode::Zero, &mut &b""[..], &mut Vec::new()).is_ok());
+/// assert_eq!(run("", EofMode::Zero).unwrap(), b"");
+/// assert_eq!(run("only comments here", EofMode::Zero).unwrap(), b"");
+///
+/// // `,` on empty input
+/// assert_eq!(run("+,.", EofMode::Zero).unwrap(), [0]);
+/// assert_eq!(run("+,.", EofMode::MinusOne).unwrap(), [255]);
+/// assert_eq!(run("+,.", EofMode::Unchanged).unwrap(), [1]);
+/// assert!(matches!(run("+,.", EofMode::Error), Err(BrainfuckError::InputRequired { position: 1 })));
+///
+/// // an inner loop that ends on its first pass
+/// assert_eq!(run("++[>+[-]<-]+.", EofMode::Zero).unwrap(), [1]);
+///
+/// assert!(run("]", EofMode::Zero).is_err());
+/// ```
+pub fn run_program(src: &str, eof_mode: EofMode, input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    ast::check_brackets(src.as_bytes())?;
+    let mut status = new_brainfuck_status();
+    status.set_eof_mode(eof_mode);
+    run_vm_bytes(&mut status, src.as_bytes(), input, output)
+}
+
+/// Runs another whole program on `status`, starting on the tape and at
+/// the pointer the last one left behind.
+///
+/// Ops are counted from 0 again for the new program, while settings and
+/// [`BrainfuckVMStatus::ops_executed`] carry over. Coverage, if recorded,
+/// starts over for the new program. Unmatched brackets are
+/// reported before anything runs, which leaves the tape as it was.
+///
+/// # Arguments
+///
+/// * `status` - VM whose tape the program runs on
+/// * `src`    - brainfuck source
+/// * `input`  - where `,` reads from
+/// * `output` - where `.` writes to
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::{new_brainfuck_status, run_program_on};
+///
+/// let mut status = new_brainfuck_status();
+/// let mut output = Vec::new();
+/// // one program puts 6 and 7 on the tape, the next multiplies them
+/// run_program_on(&mut status, "++++++>+++++++<", &mut &b""[..], &mut output).unwrap();
+/// run_program_on(&mut status, "[>[>+>+<<-]>>[<<+>>-]<<<-]>>.", &mut &b""[..], &mut output).unwrap();
+/// assert_eq!(output, [42]);
+/// assert_eq!(status.cells(), [(1, 7), (2, 42)]);
+/// ```
+pub fn run_program_on(status: &mut BrainfuckVMStatus, src: &str, input: &mut dyn BrainfuckInput, output: &mut dyn BrainfuckOutput) -> Result<(), BrainfuckError> {
+    ast::check_brackets(src.as_bytes())?;
+    status.instruction.clear();
+    status.instruction_start = 0;
+    status.instruction_ptr_current = None;
+    status.instruction_loop_ptr.clear();
+    status.jump_loop = 0;
+    if let Some(coverage) = &mut status.coverage {
+        *coverage = CoverageReport::default();
+    }
+    run_vm_bytes(status, src.as_bytes(), input, output)
+}
+
+/// Runs a whole program on a fresh VM and returns the VM, where the
+/// program left its results on the tape.
+///
+/// Works like [`run_program`] with end of input read as 0, except that
+/// `input` is a byte slice and whatever the program prints is dropped.
+///
+/// # Arguments
+///
+/// * `src`   - brainfuck source
+/// * `input` - bytes fed to `,`
+///
+/// # Example
+///
+/// ```
+/// use rust_bf::vm::eval;
+///
+/// // multiply the two input bytes into cell 2
+/// let status = eval(",>,<[>[>+>+<<-]>>[<<+>>-]<<<-]>>", &[6, 7]).unwrap();
+/// assert_eq!(status.cells(), [(1, 7), (2, 42)]);
+/// assert_eq!(status.tape_ptr(), 2);
+/// ```
+pub fn eval(src: &str, input: &[u8]) -> Result<BrainfuckVMStatus, BrainfuckError> {
+    ast::check_brackets(src.as_bytes())?;
+    let mut status = new_brainfuck_status();
+    let mut input = input;
+    run_vm_bytes(&mut status, src.as_bytes(), &mut input, &mut Vec::new())?;
+    Ok(status)
+}
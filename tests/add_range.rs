@@ -0,0 +1,50 @@
+//! `AddRange` from runs like `+>+>+>+` behaves like the run, build with
+//! `--features simd` for the SSE2 path.
+
+use rust_bf::ast::parse;
+use rust_bf::ir::{compile, BrainfuckIR};
+use rust_bf::optimizer::optimize;
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// Returns the tape `ir` leaves behind and the error it stopped with.
+fn run(ir: Vec<BrainfuckIR>, limit: Option<usize>, wrap: bool) -> (Vec<u8>, Option<String>) {
+    let mut interpreter = Interpreter::from_ir(ir).with_wrap_tape(wrap).output(Vec::new());
+    if let Some(limit) = limit {
+        interpreter = interpreter.with_memory_limit(limit);
+    }
+    let err = interpreter.run().err().map(|err: BrainfuckError| err.to_string());
+    (interpreter.tape().to_vec(), err)
+}
+
+/// Checks that `src` runs the same with and without the optimizer, and that it made an `AddRange`.
+fn same(src: &str, limit: Option<usize>, wrap: bool) {
+    let ir = compile(&parse(src).unwrap());
+    let optimized = optimize(&ir);
+    assert!(optimized.iter().any(|node| matches!(node, BrainfuckIR::AddRange { .. })), "{}", src);
+    assert_eq!(run(optimized, limit, wrap), run(ir, limit, wrap), "{}", src);
+}
+
+#[test]
+fn ranges_of_every_length_add_the_same() {
+    for len in 4..70 {
+        // ranges that end apart from 16 cells, added to many times over
+        let src = format!(">>+++[-<{}{}>]", vec!["--"; len].join(">"), "<".repeat(len - 1));
+        same(&src, None, false);
+    }
+}
+
+#[test]
+fn ranges_past_the_memory_limit_fail_like_the_moves() {
+    let src = vec!["+++"; 40].join(">");
+    for limit in [1, 16, 17, 39, 40] {
+        same(&src, Some(limit), false);
+    }
+}
+
+#[test]
+fn ranges_wrap_around_a_circular_tape() {
+    let src = format!(">>>{}", vec!["+"; 40].join(">"));
+    for limit in [5, 16, 40, 41, 100] {
+        same(&src, Some(limit), true);
+    }
+}
@@ -0,0 +1,69 @@
+//! `--base64` and `--decode-b64` run programs passed base64-encoded and `encode`
+//! writes them that way, build with `--features base64`.
+
+use std::process::Command;
+
+use rust_bf::encoding::{decode_source, decode_url_safe};
+use rust_bf::format_error;
+
+/// prints `AH`
+const AH: &str = "KysrKysrKytbPisrKysrKysrPC1dPisuKysrKysrKy4=";
+
+#[test]
+fn encoded_programs_run() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--no-stdin", "--base64", AH]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"AH");
+}
+
+#[test]
+fn invalid_base64_is_reported() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--base64", "++[>+<-]"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: program is not valid base64: Invalid symbol 91, offset 2.\n");
+}
+
+#[test]
+fn encoded_bytes_must_be_utf8() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--base64", "/w=="]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: program is not valid base64: it decodes to bytes that are not UTF-8\n");
+}
+
+/// Runs `rust_bf` with `args` and no stdin, returning the exit code, stdout and stderr.
+fn run(args: &[&str]) -> (Option<i32>, Vec<u8>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(args).stdin(std::process::Stdio::null()).output().unwrap();
+    (output.status.code(), output.stdout, String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn hello_world_round_trips_through_url_safe_base64() {
+    let hello = concat!(env!("CARGO_MANIFEST_DIR"), "/src/examples/hello.bf");
+    for args in [&["encode", hello][..], &["encode", "--minify", hello]] {
+        let (code, encoded, stderr) = run(args);
+        assert_eq!(code, Some(0), "{}", stderr);
+        let encoded = String::from_utf8(encoded).unwrap();
+        assert!(encoded.trim_end().bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'), "{}", encoded);
+        assert_eq!(run(&["--decode-b64", "-e", &encoded]), (Some(0), b"Hello World!\n".to_vec(), String::new()));
+    }
+    let (_, full, _) = run(&["encode", hello]);
+    let (_, minified, _) = run(&["encode", "--minify", hello]);
+    assert!(minified.len() < full.len());
+}
+
+#[test]
+fn url_safe_decoding_errors_point_at_the_character() {
+    let (code, stdout, stderr) = run(&["--decode-b64", "-e", "KysrK*srKytb"]);
+    assert_eq!((code, stdout), (Some(1), Vec::new()));
+    assert_eq!(stderr, "error: program is not valid base64: `*` at offset 5 is not URL-safe base64\n");
+    assert_eq!(run(&["-e", "+++.", "hello.bf"]).0, Some(2));
+}
+
+#[test]
+fn the_note_on_invalid_base64_fits_either_alphabet() {
+    for err in [decode_source("++[>+<-]").unwrap_err(), decode_url_safe("KysrK*srKytb").unwrap_err()] {
+        let report = format_error("", &err);
+        assert!(report.ends_with("\nnote: check the program was encoded whole, in the base64 alphabet it is read with\n"), "{}", report);
+    }
+}
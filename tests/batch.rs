@@ -0,0 +1,72 @@
+//! `batch` runs every entry of a manifest and reports which ones passed.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Writes `files` into a fresh directory and runs `batch` on its `manifest.toml`,
+/// returning the exit code, stdout and stderr.
+fn batch(name: &str, files: &[(&str, &str)], args: &[&str]) -> (Option<i32>, String, String) {
+    let dir = std::env::temp_dir().join(format!("rust_bf-batch-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("batch").arg(dir.join("manifest.toml")).args(args).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap().replace(&*dir.to_string_lossy(), "DIR");
+    (output.status.code(), String::from_utf8(output.stdout).unwrap(), stderr)
+}
+
+/// prints `hi\n`
+const HI: &str = "++++++++[>+++++++++++++<-]>.+.[-]++++++++++.";
+
+#[test]
+fn sample_manifest_passes() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/batch/manifest.toml");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("batch").arg(&manifest).args(["--jobs", "2"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.ends_with("2 of 2 passed\n"), "{}", stdout);
+}
+
+#[test]
+fn wrong_output_fails_with_a_diff() {
+    let manifest = "[[entry]]\nprogram = \"hi.bf\"\nexpected = \"hi.out\"\n\n[[entry]]\nprogram = \"hi.bf\"\nexpected = \"ho.out\"\n";
+    let (code, stdout, _) = batch("fail", &[("manifest.toml", manifest), ("hi.bf", HI), ("hi.out", "hi\n"), ("ho.out", "ho\n")], &[]);
+    assert_eq!(code, Some(1), "{}", stdout);
+    assert_eq!(
+        stdout,
+        "program  result\n\
+         hi.bf    PASS\n\
+         hi.bf    FAIL wrong output\n    line 1:\n    - ho\n    + hi\n\
+         1 of 2 passed\n"
+    );
+}
+
+#[test]
+fn step_limit_fails_the_entry() {
+    let manifest = "[[entry]]\nprogram = \"loop.bf\"\nexpected = \"loop.out\"\nstep_limit = 1_000\n";
+    let (code, stdout, _) = batch("step-limit", &[("manifest.toml", manifest), ("loop.bf", "+[]"), ("loop.out", "")], &["--jobs", "4"]);
+    assert_eq!(code, Some(1), "{}", stdout);
+    assert!(stdout.contains("FAIL program ran past the step limit of 1000 steps\n"), "{}", stdout);
+    assert!(stdout.ends_with("0 of 1 passed\n"), "{}", stdout);
+}
+
+#[test]
+fn entries_run_on_fresh_interpreters() {
+    // every entry starts on a zeroed tape, whatever the one before left there
+    let mut manifest = String::new();
+    for _ in 0..8 {
+        manifest.push_str("[[entry]]\nprogram = \"hi.bf\"\nexpected = \"hi.out\"\n");
+    }
+    let (code, stdout, _) = batch("fresh", &[("manifest.toml", &manifest), ("hi.bf", HI), ("hi.out", "hi\n")], &["--jobs", "3"]);
+    assert_eq!(code, Some(0), "{}", stdout);
+    assert!(stdout.ends_with("8 of 8 passed\n"), "{}", stdout);
+}
+
+#[test]
+fn manifest_errors_name_the_line() {
+    let (code, stdout, stderr) = batch("bad", &[("manifest.toml", "[[entry]]\nprogram = hi.bf\n")], &[]);
+    assert_eq!((code, stdout.as_str()), (Some(2), ""));
+    assert_eq!(stderr, "error: DIR/manifest.toml: line 2: `program` must be a string\n");
+}
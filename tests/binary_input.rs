@@ -0,0 +1,54 @@
+//! `,` reads a 0 byte as input, only the end of input goes by the `EofMode`.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use rust_bf::io::EofMode;
+use rust_bf::vm::run_program;
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// sets the cell to 7, reads over it and prints it
+const READ_ONE: &str = "+++++++,.";
+
+#[test]
+fn nul_bytes_are_input_for_the_vm() {
+    for mode in [EofMode::Zero, EofMode::MinusOne, EofMode::Unchanged, EofMode::Error] {
+        let mut output = Vec::new();
+        run_program(READ_ONE, mode, &mut &b"\0"[..], &mut output).unwrap();
+        assert_eq!(output, [0], "{:?}", mode);
+    }
+    // once the NUL is used up the end of input is told apart
+    let mut output = Vec::new();
+    let err = run_program(",.,.", EofMode::Error, &mut &b"\0"[..], &mut output).unwrap_err();
+    assert!(matches!(err, BrainfuckError::InputRequired { .. }), "{:?}", err);
+    assert_eq!(output, [0]);
+}
+
+#[test]
+fn nul_bytes_are_input_for_the_interpreter() {
+    for mode in [EofMode::MinusOne, EofMode::Unchanged, EofMode::Error] {
+        let mut output = Vec::new();
+        Interpreter::new(READ_ONE).unwrap().with_eof_mode(mode).input(&b"\0"[..]).output(&mut output).run().unwrap();
+        assert_eq!(output, [0], "{:?}", mode);
+    }
+}
+
+#[test]
+fn binary_stdin_passes_through_the_cli() {
+    let dir = std::env::temp_dir().join(format!("rust_bf_binary_input_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("copy.bf");
+    // copies four bytes, NULs included, which `,[.,]` would stop at
+    std::fs::write(&program, ",.,.,.,.").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .arg(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"\0\xff\0a").unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\0\xff\0a");
+}
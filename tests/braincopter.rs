@@ -0,0 +1,45 @@
+//! BrainCopter images decode to the program drawn in them, run with `cargo test --features braincopter`.
+
+use std::fs;
+use std::path::Path;
+
+use rust_bf::braincopter::{decode_braincopter, encode_braincopter};
+use rust_bf::minify::minify_program;
+use rust_bf::Interpreter;
+
+/// Reads a file of the corpus or the fixtures.
+fn read(path: &str) -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(path)).unwrap()
+}
+
+#[test]
+fn known_image_prints_hello_world() {
+    // hello.bf drawn column by column in noisy colors, snaking down and up
+    let src = decode_braincopter(&read("fixtures/hello.png")).unwrap();
+    let hello = String::from_utf8(read("corpus/hello.bf")).unwrap();
+    assert_eq!(src, minify_program(&hello, false).unwrap());
+    assert_eq!(Interpreter::run_string(&src, "").unwrap(), String::from_utf8(read("corpus/hello.out")).unwrap());
+}
+
+#[test]
+fn encoding_round_trips() {
+    let hello = String::from_utf8(read("corpus/hello.bf")).unwrap();
+    let ops = minify_program(&hello, false).unwrap();
+    for &(width, height) in &[(3, 106), (10, 14), (50, 3), (108, 1)] {
+        let png = encode_braincopter(&hello, width, height).unwrap();
+        assert_eq!(decode_braincopter(&png).unwrap(), ops, "{}x{}", width, height);
+    }
+    // and the known image drawn again the encoder's way
+    let src = decode_braincopter(&read("fixtures/hello.png")).unwrap();
+    assert_eq!(decode_braincopter(&encode_braincopter(&src, 20, 8).unwrap()).unwrap(), src);
+}
+
+#[test]
+fn every_path_walks_off_the_image() {
+    // a square of clockwise turns, the path bounces off it rather than going round
+    let image = image::RgbImage::from_pixel(2, 2, image::Rgb([0, 0, 8]));
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+    assert_eq!(decode_braincopter(&png).unwrap(), "");
+    assert!(decode_braincopter(b"not a png").is_err());
+}
@@ -0,0 +1,60 @@
+//! Breakpoints stop a run before an IR node, and the run goes on from there with `resume`.
+
+use rust_bf::{BrainfuckError, Interpreter, Snapshot};
+
+/// Returns the breakpoint the run that returned `result` stopped at, and the state there.
+fn stop(result: Result<(), BrainfuckError>) -> (usize, Snapshot) {
+    match result {
+        Err(BrainfuckError::BreakpointHit { position, vm_state }) => (position, vm_state),
+        result => panic!("expected a breakpoint, got {:?}", result),
+    }
+}
+
+#[test]
+fn loops_stop_at_a_breakpoint_on_every_trip() {
+    let mut output = Vec::new();
+    // prints 3, 2, 1
+    let mut interpreter = Interpreter::new("+++[.-]").unwrap().output(&mut output);
+    interpreter.set_breakpoint(2);
+    let (position, state) = stop(interpreter.run());
+    assert_eq!((position, state.instruction_pointer(), state.tape(), state.steps()), (2, 2, &[3][..], 2));
+    let mut cells = Vec::new();
+    for _ in 0..2 {
+        let (_, state) = stop(interpreter.resume());
+        cells.push(state.tape()[0]);
+    }
+    assert_eq!(cells, [2, 1]);
+    interpreter.resume().unwrap();
+    drop(interpreter);
+    assert_eq!(output, [3, 2, 1]);
+}
+
+#[test]
+fn cleared_breakpoints_no_longer_stop() {
+    let mut interpreter = Interpreter::new("+>+>+").unwrap();
+    interpreter.set_breakpoint(1);
+    interpreter.set_breakpoint(3);
+    interpreter.clear_breakpoint(1);
+    assert_eq!(stop(interpreter.run()).0, 3);
+    interpreter.reset();
+    interpreter.clear_all_breakpoints();
+    interpreter.run().unwrap();
+    assert_eq!(interpreter.tape(), &[1, 1, 1]);
+}
+
+#[test]
+fn stepping_stops_at_breakpoints_too() {
+    let mut interpreter = Interpreter::new("+>+").unwrap();
+    interpreter.set_breakpoint(1);
+    assert!(interpreter.step().unwrap());
+    assert!(matches!(interpreter.step(), Err(BrainfuckError::BreakpointHit { position: 1, .. })));
+    assert_eq!(interpreter.instruction_pointer(), 1);
+    assert_eq!(BrainfuckError::BreakpointHit { position: 1, vm_state: interpreter.snapshot() }.to_string(), "instruction 1 is a breakpoint");
+
+    // a restored state runs past the breakpoint it stopped at, moved one cell further
+    let (_, mut state) = stop(interpreter.run());
+    state.set_tape_pointer(2);
+    interpreter.restore(state);
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.tape(), &[1, 0, 0, 1]);
+}
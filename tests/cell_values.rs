@@ -0,0 +1,37 @@
+//! Both backends agree on which values the cells hold, and `--cell-values` reports them.
+
+use std::process::Command;
+
+use rust_bf::vm::{new_brainfuck_status, run_vm};
+use rust_bf::Interpreter;
+
+/// leaves 0, 4, 2 and 255 on the tape
+const SRC: &str = "++[>++<-]>>++>-<<";
+
+#[test]
+fn vm_and_interpreter_agree() {
+    let mut interpreter = Interpreter::new(SRC).unwrap();
+    interpreter.run().unwrap();
+    let mut status = new_brainfuck_status();
+    for c in SRC.chars() {
+        run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new()).unwrap();
+    }
+
+    let histogram = interpreter.cell_value_histogram();
+    assert_eq!(histogram, status.cell_value_histogram());
+    assert_eq!(histogram.nonzero_cells(), 3);
+    assert_eq!(histogram.values().collect::<Vec<_>>(), [(2, 1), (4, 1), (255, 1)]);
+}
+
+#[test]
+fn cli_reports_cell_values() {
+    let program = std::env::temp_dir().join(format!("rust_bf-cell-values-{}.bf", std::process::id()));
+    std::fs::write(&program, "+>+>++").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--cell-values").arg(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "3 non-zero cells of 3 allocated\n         1            2   66.7%\n         2            1   33.3%\n"
+    );
+}
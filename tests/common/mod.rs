@@ -0,0 +1,50 @@
+//! Helpers shared by the integration tests that run the `rust_bf` binary on fixtures.
+
+// every test crate includes all of them, most use only some
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Returns the path of the fixture `name`, relative to `tests/fixtures`.
+pub fn fixture(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name).to_string_lossy().into_owned()
+}
+
+/// Runs `rust_bf` with `args`, returning the exit code, stdout and stderr.
+pub fn run(args: &[&str]) -> (Option<i32>, Vec<u8>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(args).output().unwrap();
+    (output.status.code(), output.stdout, String::from_utf8(output.stderr).unwrap())
+}
+
+/// A program written to a file of its own in the temp directory, removed
+/// again when dropped, so that a failing assert does not leave it behind.
+struct ScratchFile(PathBuf);
+
+impl ScratchFile {
+    /// Writes `src` to a file no other scratch file of the test run shares.
+    fn new(src: &str) -> ScratchFile {
+        // tests run in parallel threads of one process
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!("rust_bf-test-{}-{}.bf", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+        let file = ScratchFile(std::env::temp_dir().join(name));
+        fs::write(&file.0, src).unwrap();
+        file
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Runs `rust_bf` with `args` on a scratch file holding `src`, no stdin,
+/// returning the exit code, stdout and stderr.
+pub fn run_file(src: &str, args: &[&str]) -> (Option<i32>, Vec<u8>, String) {
+    let file = ScratchFile::new(src);
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(args).arg(&file.0).stdin(Stdio::null()).output().unwrap();
+    (output.status.code(), output.stdout, String::from_utf8(output.stderr).unwrap())
+}
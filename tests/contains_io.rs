@@ -0,0 +1,24 @@
+//! Programs tell whether they read or print before they run.
+
+use rust_bf::ast::contains_io;
+use rust_bf::Interpreter;
+
+#[test]
+fn io_is_found_wherever_it_is() {
+    assert_eq!(contains_io(""), (false, false));
+    assert_eq!(contains_io("+++[>++<-]>[-]"), (false, false));
+    assert_eq!(contains_io(",[>+<-]"), (true, false));
+    assert_eq!(contains_io(include_str!("../src/examples/hello.bf")), (false, true));
+    assert_eq!(contains_io(include_str!("fixtures/dbfi.bf")), (true, true));
+    // unmatched brackets are no reason not to answer
+    assert_eq!(contains_io("]]."), (false, true));
+}
+
+#[test]
+fn programs_without_input_run_without_it() {
+    let src = "++++++++[>++++++++<-]>+.";
+    assert_eq!(contains_io(src), (false, true));
+    let mut output = Vec::new();
+    Interpreter::new(src).unwrap().without_input().output(&mut output).run().unwrap();
+    assert_eq!(output, b"A");
+}
@@ -0,0 +1,80 @@
+//! Golden-file tests, every `tests/corpus/NAME.bf` must print `NAME.out`.
+//!
+//! `NAME.in`, if present, is fed to `,`. Run with `BLESS=1` to write the
+//! current output to the `.out` files instead of comparing.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use rust_bf::Interpreter;
+
+/// Returns a unified diff of `expected` against `actual`, by line.
+fn unified_diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // longest common subsequence table, lcs[i][j] covers old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut diff = format!("--- expected\n+++ actual\n@@ -1,{} +1,{} @@\n", old.len(), new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Runs a case and returns its output.
+fn run(program: &Path) -> Vec<u8> {
+    let src = fs::read_to_string(program).unwrap();
+    let input = fs::read(program.with_extension("in")).unwrap_or_default();
+    let mut output = Vec::new();
+    Interpreter::new(&src).unwrap().input(&input[..]).output(&mut output).run().unwrap();
+    output
+}
+
+#[test]
+fn corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let bless = env::var_os("BLESS").is_some();
+
+    let mut programs: Vec<_> = fs::read_dir(&corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bf"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty(), "no programs in {}", corpus.display());
+
+    let mut failures = Vec::new();
+    for program in &programs {
+        let actual = run(program);
+        let golden = program.with_extension("out");
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read(&golden).unwrap_or_else(|_| panic!("{} is missing, run with BLESS=1", golden.display()));
+        if actual != expected {
+            let diff = unified_diff(&String::from_utf8_lossy(&expected), &String::from_utf8_lossy(&actual));
+            failures.push(format!("{}\n{}", program.display(), diff));
+        }
+    }
+    assert!(failures.is_empty(), "output differs from the golden files:\n\n{}", failures.join("\n"));
+}
@@ -0,0 +1,3 @@
+reading past the end of input stores 0 so this echoes the input and then prints 0
+,[.,]
+,>++++++[<++++++++>-]<.
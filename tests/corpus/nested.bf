@@ -0,0 +1,4 @@
+three nested multiplication loops: 4 * 4 * 4 = 64 then print 'A'
+++++[>++++[>++++[>+<-]<-]<-]>>>+.
+clear loops after use
+[-]<[-]<[-]
@@ -0,0 +1,2 @@
+read a line and print it reversed
+>,----------[++++++++++>,----------]<[.<]
@@ -0,0 +1,3 @@
+cells wrap below zero and above 255
+-.+.
+>++++++++[<++++++++++++++++++++++++++++++++>-]<.
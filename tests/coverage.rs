@@ -0,0 +1,22 @@
+//! `--cover` shows which ops of a program ran.
+
+use std::process::Command;
+
+#[test]
+fn cover_marks_ops_that_never_ran() {
+    let dir = std::env::temp_dir();
+    let program = dir.join(format!("rust_bf-cover-{}.bf", std::process::id()));
+    let report = dir.join(format!("rust_bf-cover-{}.html", std::process::id()));
+    // the branch on cell 1 is dead, it is always zero, and its `]` is jumped over
+    std::fs::write(&program, "set + clear [-]\nbranch >[+++<]\n").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--cover").arg(&report).arg(&program).output().unwrap().status;
+    let html = std::fs::read_to_string(&report).unwrap();
+    std::fs::remove_file(&program).unwrap();
+    std::fs::remove_file(&report).unwrap();
+    assert!(status.success());
+
+    assert!(html.contains("<span class=\"badge low\">54.5%</span>"), "{}", html);
+    assert!(html.contains("<p>6 of 11 ops ran</p>"), "{}", html);
+    assert!(html.contains("set <span class=\"hit\">+</span> clear <span class=\"hit\">[-]</span>\n"), "{}", html);
+    assert!(html.contains("branch <span class=\"hit\">&gt;[</span><span class=\"miss\">+++&lt;]</span>\n"), "{}", html);
+}
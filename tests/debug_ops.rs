@@ -0,0 +1,77 @@
+//! `$` prints the cell as a number and `!` resets the tape once turned on, build with `--features debug_ops`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::ir::{compile, BrainfuckIR};
+use rust_bf::packed::{load, pack};
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+use rust_bf::{ast::parse, BrainfuckOp, Interpreter};
+
+/// prints 72 as a byte and as a number, resets the tape and prints 1 the same way
+const DUMP: &str = "++++++++[>+++++++++<-]>.$!+.$";
+
+/// Runs `src` on a fresh VM with debugging ops `on` or off, returning what it printed and the cells.
+fn run_on_vm(src: &str, on: bool) -> (Vec<u8>, Vec<(i32, i32)>) {
+    let mut status = new_brainfuck_status();
+    status.set_debug_ops(on);
+    let mut output = Vec::new();
+    run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut output).unwrap();
+    (output, status.cells())
+}
+
+/// Runs `src` on an interpreter with debugging ops `on` or off, returning what it printed and the tape.
+fn run_on_interpreter(src: &str, on: bool) -> (Vec<u8>, Vec<u8>) {
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::new(src).unwrap().output(&mut output).with_debug_ops(on);
+    interpreter.run().unwrap();
+    let tape = interpreter.tape().to_vec();
+    drop(interpreter);
+    (output, tape)
+}
+
+#[test]
+fn dollar_and_bang_are_ops() {
+    assert_eq!(BrainfuckOp::from_char('$'), BrainfuckOp::PrintNumberOp);
+    assert_eq!(BrainfuckOp::from_char('!'), BrainfuckOp::ResetOp);
+    assert_eq!(BrainfuckOp::ResetOp.as_char(), Some('!'));
+    let ir = compile(&parse("+$!").unwrap());
+    assert_eq!(ir, vec![BrainfuckIR::Add(1), BrainfuckIR::PrintNumber, BrainfuckIR::Reset]);
+}
+
+#[test]
+fn they_do_nothing_while_off() {
+    let plain = DUMP.replace(['$', '!'], "");
+    assert_eq!(run_on_vm(DUMP, false), run_on_vm(&plain, false));
+    assert_eq!(run_on_vm(DUMP, false), (vec![72, 73], vec![(1, 73)]));
+    assert_eq!(run_on_interpreter(DUMP, false), run_on_interpreter(&plain, false));
+}
+
+#[test]
+fn they_print_numbers_and_reset_the_tape_while_on() {
+    assert_eq!(run_on_vm(DUMP, true), (b"H72\x011".to_vec(), vec![(0, 1)]));
+    let (output, tape) = run_on_interpreter(DUMP, true);
+    assert_eq!((output, tape), (b"H72\x011".to_vec(), vec![1]));
+    // cells wider than a byte print all of their value
+    let mut status = new_brainfuck_status();
+    status.set_debug_ops(true);
+    status.set_cell_bits(16);
+    let mut output = Vec::new();
+    run_vm_bytes(&mut status, b"-$", &mut &b""[..], &mut output).unwrap();
+    assert_eq!(output, b"65535");
+}
+
+#[test]
+fn packed_programs_leave_them_out() {
+    assert_eq!(load(&pack(b"+$!.").unwrap()).unwrap(), compile(&parse("+.").unwrap()));
+}
+
+#[test]
+fn the_command_line_turns_them_on() {
+    let run = |args: &[&str]| {
+        let (code, stdout, _) = run_file(DUMP, args);
+        (code, stdout)
+    };
+    assert_eq!(run(&[]), (Some(0), b"HI".to_vec()));
+    assert_eq!(run(&["--debug-ops"]), (Some(0), b"H72\x011".to_vec()));
+}
@@ -0,0 +1,62 @@
+//! Programs with loops nested far deeper than the stack could hold a frame per loop for.
+
+use std::convert::TryFrom;
+
+use rust_bf::ast::parse;
+use rust_bf::check::check;
+use rust_bf::dot::to_dot;
+use rust_bf::explain::explain;
+use rust_bf::ir::{compile, compile_source, BrainfuckIR};
+use rust_bf::verify::verify;
+use rust_bf::Program;
+
+/// loops nested in each other in a program
+const DEPTH: usize = 100_000;
+
+/// Returns a program of `DEPTH` loops, one inside the other, around a `-`.
+fn nested() -> String {
+    format!("+{}-{}", "[".repeat(DEPTH), "]".repeat(DEPTH))
+}
+
+#[test]
+fn the_syntax_tree_compiles_and_drops() {
+    let src = nested();
+    let ir = compile(&parse(&src).unwrap());
+    assert_eq!(ir.len(), 2 * DEPTH + 2);
+    assert_eq!(ir[1], BrainfuckIR::LoopStart(2 * DEPTH + 1));
+    assert_eq!(ir, compile_source(src.as_bytes()).unwrap());
+    let program = Program::try_from(src.as_str()).unwrap();
+    assert_eq!(program.compile(), ir);
+    assert_eq!(program.to_string(), src);
+}
+
+#[test]
+fn check_walks_the_syntax_tree() {
+    assert_eq!(check(&parse(&nested()).unwrap()), vec![]);
+    // the loops inside one that moves away from its cell and back
+    let warnings = check(&parse(&format!("+[>{}<]", &nested()[1..])).unwrap());
+    assert_eq!(warnings.iter().map(|warning| (warning.code, warning.position.offset)).collect::<Vec<_>>(), vec![("W005", 1)]);
+}
+
+#[test]
+fn verify_walks_the_syntax_tree() {
+    assert_eq!(verify(&nested(), b"").unwrap(), None);
+}
+
+#[test]
+fn the_control_flow_graph_builds() {
+    let graph = to_dot(&parse(&nested()).unwrap());
+    // entry, `+`, every `[`, `-`, every `]` and exit
+    assert!(graph.contains(&format!("    n{} [label=\"exit\", shape=oval];", 2 * DEPTH + 3)));
+    assert!(graph.contains(&format!("    n{} -> n3 [label=\"back\"];", 2 * DEPTH + 2)));
+}
+
+#[test]
+fn explain_notes_every_loop() {
+    // a bracket per line, on one line the notes would take quadratic room
+    let text = explain(&nested().replace('[', "\n[")).unwrap();
+    let notes: Vec<&str> = text.lines().filter(|line| line.starts_with('^')).collect();
+    assert_eq!(notes.len(), DEPTH);
+    assert!(notes[..DEPTH - 1].iter().all(|note| *note == "^ general loop"));
+    assert_eq!(notes[DEPTH - 1], "^ clear loop: cell = 0");
+}
@@ -0,0 +1,35 @@
+//! Loops that never change the cell they test are caught, statically by `check`
+//! and while they run by `--detect-hangs`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::ast::parse;
+use rust_bf::check::check;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+use rust_bf::BrainfuckError;
+
+#[test]
+fn endless_loops_stop_with_where_they_are() {
+    let (code, stdout, stderr) = run_file("++.\n  +[>+<]", &["--detect-hangs", "--hang-threshold", "1000"]);
+    assert_eq!((code, stdout), (Some(1), vec![2]));
+    assert_eq!(stderr, "error: 2:4: loop went round 1,002 times without its cell changing\n");
+
+    let mut status = new_brainfuck_status();
+    status.set_hang_threshold(10);
+    let err = run_vm_bytes(&mut status, b"+[[-]+]", &mut &b""[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::LoopHang { position: 1, iterations: 12 }), "{:?}", err);
+    assert!(check(&parse("+[>+<]").unwrap()).iter().any(|warning| warning.code == "W005"));
+}
+
+#[test]
+fn loops_changing_their_cell_in_an_inner_loop_run_to_the_end() {
+    // every round the inner loop takes 2 off the outer loop's cell
+    let src = "++++++++[>++[<->-]<]+++++++++[>+++++++<-]>.";
+    assert_eq!(run_file(src, &["--detect-hangs", "--hang-threshold", "0"]), (Some(0), b"?".to_vec(), String::new()));
+    assert!(check(&parse(src).unwrap()).is_empty());
+
+    // scanning moves the pointer, so its cell is another one every round
+    let (code, _, stderr) = run_file("+>+>+>+<<<[>]", &["--detect-hangs", "--hang-threshold", "0"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+}
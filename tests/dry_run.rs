@@ -0,0 +1,59 @@
+//! `--dry-run` and `Interpreter::dry_run` run a program without I/O.
+
+mod common;
+
+use common::run_file;
+use rust_bf::vm::{new_brainfuck_status, TapeBounds};
+use rust_bf::{BrainfuckError, Interpreter};
+
+#[test]
+fn reads_see_zeros_and_output_is_dropped() {
+    // a cat that would print its input, the first `,` already ends it
+    let report = Interpreter::dry_run(",[.,]").unwrap();
+    assert_eq!(report.instructions_executed, 2);
+    assert_eq!(report.cells_touched, 1);
+}
+
+#[test]
+fn runs_that_fail_return_the_error() {
+    let mut status = new_brainfuck_status();
+    status.set_right_only(TapeBounds::Error);
+    let err = Interpreter::dry_run_on(&mut status, "+[<+]").unwrap_err();
+    assert!(matches!(err, BrainfuckError::PointerUnderflow { .. }), "{:?}", err);
+}
+
+#[test]
+fn the_pointer_extent_covers_every_cell_it_reached() {
+    let report = Interpreter::dry_run(">+<<+>.").unwrap();
+    assert_eq!((report.min_tape_ptr, report.max_tape_ptr, report.cells_touched), (-1, 1, 2));
+    // a loop the optimizer would turn into a single multiply-add
+    let report = Interpreter::dry_run("+[->>>+<<<]").unwrap();
+    assert_eq!((report.min_tape_ptr, report.max_tape_ptr, report.cells_touched), (0, 3, 2));
+    let report = Interpreter::dry_run("+>+>+>+<<<").unwrap();
+    assert_eq!((report.max_tape_ptr, report.cells_touched), (3, 4));
+}
+
+#[test]
+fn the_report_goes_to_stdout() {
+    let (code, stdout, stderr) = run_file("++++++++[>++++++++<-]>+.", &["--dry-run"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(
+        String::from_utf8(stdout).unwrap(),
+        "instructions executed: 101\ntape pointer:          0 to 1\ncells touched:         2\n"
+    );
+}
+
+#[test]
+fn dry_runs_end_like_normal_runs() {
+    // moves left of cell 0, which the VM has room for
+    assert_eq!(run_file(">+<<+>.", &[]), (Some(0), vec![0], String::new()));
+    let (code, stdout, stderr) = run_file(">+<<+>.", &["--dry-run"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert!(String::from_utf8(stdout).unwrap().contains("tape pointer:          -1 to 1\n"));
+
+    // unless the tape is told to end there
+    let (code, _, stderr) = run_file("+[<+]", &["--tape=right-only"]);
+    assert_eq!((code, stderr.as_str()), (Some(1), "error: instruction 2 moved the pointer left of cell 0\n"));
+    let (dry_code, _, dry_stderr) = run_file("+[<+]", &["--dry-run", "--tape=right-only"]);
+    assert_eq!((dry_code, dry_stderr), (code, stderr));
+}
@@ -0,0 +1,70 @@
+//! Every embedded example prints what it should, and `--example` finds them.
+
+use std::process::Command;
+
+use rust_bf::examples::EXAMPLES;
+use rust_bf::Interpreter;
+
+/// Returns the input and the expected output of the example called `name`.
+fn golden(name: &str) -> (&'static str, String) {
+    match name {
+        "cat" => ("meow\n", String::from("meow\n")),
+        "hello" => ("", String::from("Hello World!\n")),
+        "quine" => ("", String::from(rust_bf::examples::find("quine").unwrap().source)),
+        "rot13" => ("Hello, World! 123 [Zz]\n", String::from("Uryyb, Jbeyq! 123 [Mm]\n")),
+        "sierpinski" => ("", include_str!("fixtures/sierpinski.out").to_string()),
+        _ => panic!("no golden output for the example {}", name),
+    }
+}
+
+#[test]
+fn every_example_prints_its_golden_output() {
+    for example in EXAMPLES {
+        let (input, expected) = golden(example.name);
+        assert_eq!(Interpreter::run_string(example.source, input).unwrap(), expected, "{}", example.name);
+    }
+}
+
+#[test]
+fn rot13_twice_is_the_input() {
+    let rot13 = rust_bf::examples::find("rot13").unwrap().source;
+    let input: String = (1..128u8).map(char::from).collect();
+    let once = Interpreter::run_string(rot13, &input).unwrap();
+    assert_ne!(once, input);
+    assert_eq!(Interpreter::run_string(rot13, &once).unwrap(), input);
+}
+
+#[test]
+fn cli_runs_an_example_on_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .args(["--example", "rot13"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"abc").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"nop");
+}
+
+#[test]
+fn cli_lists_the_examples() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--list-examples").output().unwrap();
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(listing.lines().count(), EXAMPLES.len());
+    assert!(listing.contains("rot13       rotates the letters of its input by 13\n"));
+}
+
+#[test]
+fn misspelled_examples_suggest_the_closest() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--example", "rot31"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: no example named 'rot31', did you mean 'rot13'?\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--example", "mandelbrot"]).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: no example named 'mandelbrot', see --list-examples\n");
+}
@@ -0,0 +1,48 @@
+//! `execute_with_limits` stops a program at the first limit it hits, with the error of that limit.
+
+use std::time::{Duration, Instant};
+
+use rust_bf::batch::{execute_with_limits, Limits};
+use rust_bf::BrainfuckError;
+
+#[test]
+fn programs_within_the_limits_print_their_output() {
+    let limits = Limits {
+        step_limit: Some(1000),
+        timeout: Some(Duration::from_secs(10)),
+        memory_limit: Some(16),
+        max_output: Some(2),
+    };
+    assert_eq!(execute_with_limits(",[.,]", b"hi", limits).unwrap(), b"hi");
+}
+
+#[test]
+fn each_limit_has_its_own_error() {
+    let steps = Limits { step_limit: Some(50), ..Limits::default() };
+    let err = execute_with_limits("+[]", b"", steps).unwrap_err();
+    assert!(matches!(err, BrainfuckError::StepLimitExceeded { limit: 50 }), "{:?}", err);
+
+    let memory = Limits { memory_limit: Some(10), ..Limits::default() };
+    let err = execute_with_limits("+[>+]", b"", memory).unwrap_err();
+    assert!(matches!(err, BrainfuckError::MemoryLimitExceeded { limit: 10, .. }), "{:?}", err);
+
+    let output = Limits { max_output: Some(5), ..Limits::default() };
+    let err = execute_with_limits("+[.]", b"", output).unwrap_err();
+    assert!(matches!(err, BrainfuckError::OutputLimitExceeded { limit: 5 }), "{:?}", err);
+}
+
+#[test]
+fn timeouts_stop_programs_that_never_halt() {
+    let limit = Duration::from_millis(50);
+    let started = Instant::now();
+    let err = execute_with_limits("+[]", b"", Limits { timeout: Some(limit), ..Limits::default() }).unwrap_err();
+    assert!(matches!(err, BrainfuckError::TimeLimitExceeded { limit: l } if l == limit), "{:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(err.to_string(), "program ran past the time limit of 50ms");
+}
+
+#[test]
+fn programs_that_do_not_compile_fail_before_running() {
+    let err = execute_with_limits("+[", b"", Limits::default()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::UnmatchedOpen(_)), "{:?}", err);
+}
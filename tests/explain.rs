@@ -0,0 +1,55 @@
+//! `explain` notes what the loops of the shipped examples do, checked against the snapshots in `tests/fixtures/explain`.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use rust_bf::examples;
+use rust_bf::explain::explain;
+
+/// Returns the snapshot of the example `name`.
+fn snapshot(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/explain").join(name).with_extension("txt");
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn examples_match_their_snapshots() {
+    for name in ["hello", "rot13", "sierpinski"] {
+        let example = examples::find(name).unwrap();
+        assert_eq!(explain(example.source).unwrap(), snapshot(name), "{}", name);
+    }
+}
+
+#[test]
+fn subcommand_prints_the_notes() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["explain", "--example", "hello"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), snapshot("hello"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["explain", "-e", "+[-"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("1:2: error: unmatched '['"));
+}
+
+#[test]
+fn nested_loops_are_only_explained_in_general_loops() {
+    let text = explain("[[-]>[<]]\n[->[-]<]").unwrap();
+    let notes: Vec<&str> = text.lines().filter(|line| line.trim_start().starts_with(['^', '~'])).map(str::trim).collect();
+    assert_eq!(notes, [
+        "^ general loop",
+        "^ clear loop: cell = 0",
+        "~ pointer +1",
+        "^ scan loop: moves left 1 cell at a time to a zero cell",
+        "^ general loop",
+        "~~ pointer +1",
+        "^ clear loop: cell = 0",
+        "~ pointer -1",
+    ]);
+}
+
+#[test]
+fn notes_go_under_columns_past_the_formatting_width() {
+    let src = format!("{}[-]", " ".repeat(70_000));
+    assert_eq!(explain(&src).unwrap(), format!("{}\n{}^ clear loop: cell = 0\n", src, " ".repeat(70_000)));
+}
@@ -0,0 +1,43 @@
+//! `tape_extent` sees the same cells in the compiled and the optimized IR.
+
+use rust_bf::ast::parse;
+use rust_bf::extent::{min_tape_size, tape_extent, AnalysisError};
+use rust_bf::ir::compile;
+use rust_bf::optimizer::optimize;
+use rust_bf::{BrainfuckError, Interpreter};
+
+#[test]
+fn optimizing_keeps_the_extent() {
+    for src in ["++++++++[>++++++++<-]>+.", "<<[-]>>>[->+++<<<+>>]", "+>+>+>+>+<<<<<", ",[>+<-]>[<<+>>-]<<"] {
+        let ir = compile(&parse(src).unwrap());
+        assert_eq!(tape_extent(&optimize(&ir)), tape_extent(&ir), "{}", src);
+    }
+}
+
+#[test]
+fn nested_drifting_loops_are_all_listed() {
+    // the outer loop drifts because the inner one does, the last one walks back
+    let ir = compile(&parse("+[[>]+]<[<]").unwrap());
+    let extent = tape_extent(&ir);
+    assert_eq!(extent.unbounded_loops, vec![2, 1, 8]);
+    assert_eq!((extent.min, extent.max), (-1, 1));
+}
+
+#[test]
+fn programs_run_on_a_tape_of_their_minimum_size() {
+    for src in ["++++++++[>++++++++<-]>+.", ">>>+<<[->>+<<]>+>>>.", "+>+>+>+>+<<<<"] {
+        let ir = optimize(&compile(&parse(src).unwrap()));
+        let size = min_tape_size(&ir).unwrap();
+        let run = |limit| Interpreter::from_ir(ir.clone()).with_memory_limit(limit).output(Vec::new()).run();
+        assert!(run(size).is_ok(), "{}", src);
+        assert!(matches!(run(size - 1), Err(BrainfuckError::MemoryLimitExceeded { .. })), "{}", src);
+    }
+}
+
+#[test]
+fn drifting_loops_leave_the_size_open() {
+    for src in ["+[>+]", "+[[>]+]", ">+[<]"] {
+        let ir = compile(&parse(src).unwrap());
+        assert_eq!(min_tape_size(&ir), Err(AnalysisError::Inconclusive), "{}", src);
+    }
+}
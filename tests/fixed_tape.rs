@@ -0,0 +1,59 @@
+//! Fixed-size tapes wrap, fail or saturate at both ends, see `--tape-size` and `--tape-bounds`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, run_vm_repeated, TapeBounds};
+use rust_bf::BrainfuckError;
+
+/// Runs `src` on a tape of 5 cells with `bounds`, returning where the pointer ends up and the non-zero cells.
+fn run(src: &str, bounds: TapeBounds) -> Result<(i32, Vec<(i32, i32)>), BrainfuckError> {
+    let mut status = new_brainfuck_status();
+    status.set_tape_size(5, bounds);
+    run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut Vec::new())?;
+    Ok((status.tape_ptr(), status.cells()))
+}
+
+#[test]
+fn wrapping_tapes_are_rings() {
+    assert_eq!(run("<+", TapeBounds::Wrap).unwrap(), (4, vec![(4, 1)]));
+    assert_eq!(run(">>>>>+", TapeBounds::Wrap).unwrap(), (0, vec![(0, 1)]));
+    // a scan finds the zero cell past the end
+    assert_eq!(run(">>+>+>+[>]+", TapeBounds::Wrap).unwrap(), (0, vec![(0, 1), (2, 1), (3, 1), (4, 1)]));
+}
+
+#[test]
+fn erroring_tapes_fail_at_the_op_past_either_end() {
+    assert!(matches!(run("+<", TapeBounds::Error), Err(BrainfuckError::PointerUnderflow { position: 1 })));
+    assert!(matches!(run(">>>>+>", TapeBounds::Error), Err(BrainfuckError::PointerOverflow { position: 5 })));
+    assert_eq!(run(">>>><<<<", TapeBounds::Error).unwrap(), (0, vec![]));
+}
+
+#[test]
+fn saturating_tapes_stop_at_the_first_and_last_cell() {
+    assert_eq!(run("<<+", TapeBounds::Saturate).unwrap(), (0, vec![(0, 1)]));
+    assert_eq!(run(">>>>>>>>+", TapeBounds::Saturate).unwrap(), (4, vec![(4, 1)]));
+}
+
+#[test]
+fn runs_of_moves_respect_the_ends() {
+    let mut status = new_brainfuck_status();
+    status.set_tape_size(5, TapeBounds::Wrap);
+    run_vm_repeated(&mut status, '>', 12, &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.tape_ptr(), 2);
+    status.set_tape_size(5, TapeBounds::Error);
+    let err = run_vm_repeated(&mut status, '<', 3, &mut &b""[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::PointerUnderflow { position: 12 }), "{:?}", err);
+}
+
+#[test]
+fn the_command_line_picks_the_size_and_bounds() {
+    // prints cell 0 after moving left of it
+    let run = |args: &[&str]| run_file("+++<++.", args);
+    assert_eq!(run(&["--tape-size", "30000", "--tape-bounds=wrap"]), (Some(0), vec![2], String::new()));
+    assert_eq!(run(&["--tape-size", "30000", "--tape-bounds=saturate"]), (Some(0), vec![5], String::new()));
+    assert_eq!(run(&["--tape-size", "30000"]), (Some(1), vec![], String::from("error: instruction 3 moved the pointer left of cell 0\n")));
+    // without --tape-size the tape goes on left of cell 0
+    assert_eq!(run(&[]), (Some(0), vec![2], String::new()));
+    assert_eq!(run(&["--tape-bounds=wrap"]).0, Some(2));
+}
@@ -0,0 +1,4 @@
+sets the second cell to 72 and prints H then i
+#include "h.bf"
+.
++++++++++++++++++++++++++++++++++ .
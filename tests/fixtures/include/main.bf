@@ -0,0 +1,2 @@
+prints Hi through two levels of includes
+#include "lib/hi.bf"
@@ -0,0 +1,26 @@
+//! Forked interpreters go their own way.
+
+use rust_bf::Interpreter;
+
+#[test]
+fn writing_the_fork_leaves_the_original_alone() {
+    // sets up a few cells, then clears them one by one
+    let mut original = Interpreter::new("+++>++>+<<[-]>[-]>[-]").unwrap();
+    for _ in 0..5 {
+        original.step().unwrap();
+    }
+    assert_eq!(original.tape(), &[3, 2, 1]);
+
+    let mut fork = original.fork();
+    assert_eq!((fork.instruction_pointer(), fork.tape_pointer()), (original.instruction_pointer(), original.tape_pointer()));
+    fork.run().unwrap();
+    assert_eq!(fork.tape(), &[0, 0, 0]);
+    assert_eq!(original.tape(), &[3, 2, 1]);
+    assert_eq!(original.instruction_pointer(), 5);
+
+    // and the other way around
+    let fork = original.fork();
+    original.run().unwrap();
+    assert_eq!(fork.tape(), &[3, 2, 1]);
+    assert!(!fork.is_halted());
+}
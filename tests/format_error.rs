@@ -0,0 +1,70 @@
+//! `format_error` output for every error variant.
+
+use rust_bf::ast::parse;
+use rust_bf::{format_error, BrainfuckError, Interpreter};
+
+#[test]
+fn unmatched_open_points_at_the_bracket() {
+    let src = "++\n> [-<+>\n.";
+    let err = parse(src).unwrap_err();
+    assert_eq!(
+        format_error(src, &err),
+        "2:3: error: unmatched '['\n    > [-<+>\n      ^ this '[' is never closed by a ']'\n"
+    );
+}
+
+#[test]
+fn unmatched_close_points_at_the_bracket() {
+    let src = "+[-]]";
+    let err = parse(src).unwrap_err();
+    assert_eq!(
+        format_error(src, &err),
+        "1:5: error: unmatched ']'\n    +[-]]\n        ^ this ']' has no '[' to close\n"
+    );
+}
+
+#[test]
+fn caret_lines_up_after_tabs_and_wide_characters() {
+    let src = "\té ]\r\n";
+    let err = parse(src).unwrap_err();
+    assert_eq!(
+        format_error(src, &err),
+        "1:4: error: unmatched ']'\n    \té ]\n    \t  ^ this ']' has no '[' to close\n"
+    );
+}
+
+#[test]
+fn memory_limit_gets_a_note() {
+    let err = Interpreter::new(">>>>+").unwrap().with_memory_limit(2).run().unwrap_err();
+    assert_eq!(
+        format_error(">>>>+", &err),
+        "error: tape needs 5 bytes, exceeding the memory limit of 2 bytes\nnote: raise the memory limit or use fewer cells\n"
+    );
+}
+
+#[test]
+fn pointer_underflow_gets_a_note() {
+    let err = Interpreter::new("+<").unwrap().run().unwrap_err();
+    assert_eq!(
+        format_error("+<", &err),
+        "error: instruction 1 moved the pointer left of cell 0\nnote: the tape starts at cell 0, there is nothing left of it\n"
+    );
+}
+
+#[test]
+fn step_limit_gets_a_note() {
+    let err = Interpreter::new("+[]").unwrap().with_step_limit(10).run().unwrap_err();
+    assert_eq!(
+        format_error("+[]", &err),
+        "error: program ran past the step limit of 10 steps\nnote: the program may never halt, or needs a higher step limit\n"
+    );
+}
+
+#[test]
+fn io_error_gets_a_note() {
+    let err = BrainfuckError::Io(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "broken pipe"));
+    assert_eq!(
+        format_error(".", &err),
+        "error: cannot write output: broken pipe\nnote: the output went away or cannot take more bytes\n"
+    );
+}
@@ -0,0 +1,38 @@
+//! Programs in other dialects run like the brainfuck they stand for, see `--lang`.
+
+mod common;
+
+use std::fs;
+
+use common::{fixture, run};
+use rust_bf::frontend::{by_name, to_source, Brainfuck, Frontend, Ook, Spoon};
+use rust_bf::minify::minify_program;
+use rust_bf::Interpreter;
+
+#[test]
+fn spoon_and_ook_fixtures_say_hello() {
+    for (frontend, name) in [(&Spoon as &dyn Frontend, "hello.spoon"), (&Ook, "hello.ook")] {
+        let src = to_source(&frontend.parse(&fs::read(fixture(name)).unwrap()).unwrap());
+        assert_eq!(Interpreter::run_string(&src, "").unwrap(), "Hello World!\n", "{}", name);
+    }
+    assert_eq!(run(&["--lang", "spoon", &fixture("hello.spoon")]), (Some(0), b"Hello World!\n".to_vec(), String::new()));
+}
+
+#[test]
+fn brainfuck_front_end_keeps_only_the_ops() {
+    let src = include_str!("../src/examples/hello.bf");
+    assert_eq!(to_source(&Brainfuck.parse(src.as_bytes()).unwrap()), minify_program(src, false).unwrap());
+    assert!(by_name("brainfuck").is_some() && by_name("ook").is_some() && by_name("spoon").is_some());
+    assert!(by_name("whitespace").is_none());
+}
+
+#[test]
+fn undecodable_programs_fail_with_their_offset() {
+    assert_eq!(Spoon.parse(b"1\n1 0102").unwrap_err().offset, 7);
+    assert_eq!(Ook.parse(b"Ook. Ook. Ook.").unwrap_err().message, "the last `Ook` has no second half");
+    let bad = fixture("hi.bfm");
+    let (code, stdout, stderr) = run(&["--lang", "spoon", &bad]);
+    assert_eq!((code, stdout), (Some(1), Vec::new()));
+    assert!(stderr.starts_with("error: byte 0: `p` is not a bit"), "{}", stderr);
+    assert_eq!(run(&["--lang", "whitespace"]).0, Some(2));
+}
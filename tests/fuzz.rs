@@ -0,0 +1,46 @@
+//! Random programs run without panicking the interpreter, and a seed always makes the same one.
+
+use std::process::{Command, Stdio};
+
+use rust_bf::testing::{fuzz_many, fuzz_random};
+use rust_bf::BrainfuckError;
+
+#[test]
+fn seeds_make_the_same_programs_every_time() {
+    let programs = |seed| -> Vec<String> {
+        fuzz_many(seed, 50, 40, 1_000).errors.into_iter().map(|(program, _)| program.into_iter().collect()).collect()
+    };
+    assert_eq!(programs(3), programs(3));
+    assert_ne!(programs(3), programs(4));
+
+    let report = fuzz_many(3, 50, 40, 1_000);
+    assert_eq!(report.programs_tested, 50);
+    assert_eq!(report.panics, 0);
+    // a run of fuzz_many is the runs of fuzz_random one seed after another
+    let single: usize = (3..53).map(|seed| fuzz_random(seed, 40, 1_000).errors.len()).sum();
+    assert_eq!(report.errors.len(), single);
+}
+
+#[test]
+fn limits_stop_programs_that_run_away() {
+    let report = fuzz_many(0, 200, 64, 500);
+    assert_eq!(report.panics, 0);
+    assert!(report.errors.iter().any(|(_, err)| matches!(err, BrainfuckError::StepLimitExceeded { limit: 500 })));
+    assert!(report.errors.iter().any(|(_, err)| matches!(err, BrainfuckError::PointerUnderflow { .. })));
+    for (program, _) in &report.errors {
+        assert!(program.len() <= 64);
+        assert!(program.iter().all(|op| "+-<>.,[]".contains(*op)));
+    }
+}
+
+#[test]
+fn fuzzing_from_the_command_line_reports_the_counts() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .args(["--fuzz", "--seed", "0", "--count", "200", "--max-steps", "500"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let errors = fuzz_many(0, 200, 64, 500).errors.len();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("tested 200 programs, 0 panicked, {} stopped with an error\n", errors));
+}
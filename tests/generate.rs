@@ -0,0 +1,54 @@
+//! `generate` writes programs that print the text they were made from.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use rust_bf::compress::{synthesize, synthesize_small};
+use rust_bf::Interpreter;
+
+/// Returns what `src` prints.
+fn run(src: &str) -> Vec<u8> {
+    let mut output = Vec::new();
+    Interpreter::new(src).unwrap().input(&b""[..]).output(&mut output).run().unwrap();
+    output
+}
+
+/// Runs `rust_bf generate` with `args` on `stdin` and returns the program it wrote.
+fn generate(args: &[&str], stdin: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .arg("generate")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn smaller_programs_print_the_same() {
+    let every_byte: Vec<u8> = (0..=255).rev().collect();
+    let targets: [&[u8]; 5] = [b"", b"a", "Grüße, 世界\n".as_bytes(), b"the quick brown fox jumps over the lazy dog", &every_byte];
+    for target in targets {
+        let small = synthesize_small(target);
+        assert_eq!(run(&small), target, "{}", small);
+        assert!(small.len() <= synthesize(target).len());
+    }
+}
+
+#[test]
+fn cli_generates_from_the_argument_or_stdin() {
+    for args in [&["Hello, World!"][..], &["--optimize-size", "Hello, World!"]] {
+        assert_eq!(run(&generate(args, b"")), b"Hello, World!");
+    }
+    // stdin is taken as raw bytes, which need not be UTF-8
+    let bytes = [0xff, 0, 0x80, b'\n', 0xc3];
+    for args in [&[][..], &["--optimize-size"]] {
+        assert_eq!(run(&generate(args, &bytes)), bytes);
+    }
+    let long = "Brainfuck is an esoteric programming language created in 1993.";
+    assert!(generate(&["--optimize-size", long], b"").len() < generate(&[long], b"").len());
+}
@@ -0,0 +1,32 @@
+//! `--heatmap` counts accesses per cell.
+
+use std::process::Command;
+
+#[test]
+fn heatmap_counts_accesses_per_cell() {
+    let dir = std::env::temp_dir();
+    let program = dir.join(format!("rust_bf-heatmap-{}.bf", std::process::id()));
+    let heatmap = dir.join(format!("rust_bf-heatmap-{}.csv", std::process::id()));
+    // touch cell 0 once, then count cell 3 up to 100 and back down
+    std::fs::write(&program, format!("+>>>{}.[-]", "+".repeat(100))).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .arg("--heatmap")
+        .arg(&heatmap)
+        .arg("--heatmap-format=csv")
+        .arg(&program)
+        .output()
+        .unwrap()
+        .status;
+    let csv = std::fs::read_to_string(&heatmap).unwrap();
+    std::fs::remove_file(&program).unwrap();
+    std::fs::remove_file(&heatmap).unwrap();
+    assert!(status.success());
+
+    let rows: Vec<Vec<u64>> = csv.lines().skip(1).map(|line| line.split(',').map(|n| n.parse().unwrap()).collect()).collect();
+    // untouched cells 1 and 2 are left out
+    assert_eq!(rows.iter().map(|row| row[0]).collect::<Vec<_>>(), [0, 3], "{}", csv);
+    assert_eq!(rows[0][1..], [0, 1], "{}", csv);
+    assert_eq!(rows[1][2], 200, "{}", csv);
+    // printed once and tested by the loop 101 times
+    assert_eq!(rows[1][1], 102, "{}", csv);
+}
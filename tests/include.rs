@@ -0,0 +1,80 @@
+//! `#include "path"` splices files in, with `--allow-include`, `--pp` or for `.bfm` files.
+
+mod common;
+
+use common::run;
+use rust_bf::preprocess::{include, load_relative, preprocess};
+use rust_bf::Interpreter;
+
+/// Returns the path of the include fixture `name`.
+fn fixture(name: &str) -> String {
+    common::fixture(&format!("include/{}", name))
+}
+
+#[test]
+fn two_levels_of_includes_resolve_relative_to_each_file() {
+    let main = fixture("main.bf");
+    let included = include(&main, &std::fs::read_to_string(&main).unwrap(), load_relative).unwrap();
+    assert_eq!(Interpreter::run_string(&included.source, "").unwrap(), "Hi");
+
+    // the loop of `lib/h.bf`, included by `lib/hi.bf`
+    let origin = included.origin(included.source.find('[').unwrap());
+    assert_eq!(origin.file, fixture("lib/h.bf"));
+    assert_eq!((origin.position.line, origin.position.column), (1, 9));
+
+    assert_eq!(run(&["--allow-include", &main]), (Some(0), b"Hi".to_vec(), String::new()));
+}
+
+#[test]
+fn included_files_may_use_macros() {
+    let load = |_: &str, path: &str| Ok((String::from(path), String::from("#define two { ++ }\n")));
+    let included = include("main.bf", "#include \"two.bfm\"\n@two@two.", load).unwrap();
+    let expanded = preprocess(&included.source).unwrap();
+    assert_eq!(Interpreter::run_string(&expanded.source, "").unwrap(), "\u{4}");
+}
+
+#[test]
+fn cycles_are_detected() {
+    let load = |_: &str, path: &str| Ok((String::from(path), format!("#include \"{}\"\n", if path == "b.bf" { "a.bf" } else { "b.bf" })));
+    let err = include("a.bf", "+\n#include \"b.bf\"\n", load).unwrap_err();
+    assert_eq!(err.to_string(), "b.bf:1:1: `a.bf` includes itself: a.bf -> b.bf -> a.bf");
+
+    let (a, b) = (fixture("cycle/a.bf"), fixture("cycle/b.bf"));
+    let (code, stdout, stderr) = run(&["--allow-include", &a]);
+    assert_eq!(code, Some(1));
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, format!("{}:1:1: error: `{}` includes itself: {} -> {} -> {}\n", b, a, a, b, a));
+}
+
+#[test]
+fn missing_files_name_the_include_site_and_the_path() {
+    let dir = std::env::temp_dir().join(format!("rust_bf_include_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.bf");
+    std::fs::write(&program, "+\n  #include \"gone.bf\"\n").unwrap();
+    let missing = run(&["--allow-include", program.to_str().unwrap()]);
+    std::fs::write(&program, "+\n#include \"open.bf\"\n").unwrap();
+    std::fs::write(dir.join("open.bf"), "-\n+[\n").unwrap();
+    let open = run(&["--pp", program.to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let (code, _, stderr) = missing;
+    assert_eq!(code, Some(1));
+    let prefix = format!("2:3: error: cannot include \"gone.bf\": {}: ", dir.join("gone.bf").display());
+    assert!(stderr.starts_with(&prefix), "{}", stderr);
+
+    // the `[` is reported in the file it is in
+    let (code, _, stderr) = open;
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        stderr,
+        format!("{}:2:2: error: unmatched '['\n    +[\n     ^ this '[' is never closed by a ']'\n", dir.join("open.bf").display())
+    );
+}
+
+#[test]
+fn malformed_includes_are_errors() {
+    let load = |_: &str, _: &str| Err(String::from("unreachable"));
+    let err = include("main.bf", "#include two.bf\n", load).unwrap_err();
+    assert_eq!(err.to_string(), "main.bf:1:1: expected a quoted path after `#include`");
+}
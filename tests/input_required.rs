@@ -0,0 +1,60 @@
+//! Programs that get no input fail at their first `,` instead of blocking.
+
+use std::process::Command;
+
+use rust_bf::io::EofMode;
+use rust_bf::vm::run_program;
+use rust_bf::{BrainfuckError, Interpreter};
+
+#[test]
+fn interpreter_without_input_fails_at_the_read() {
+    // compiles to Add, Move, LoopStart, Add, LoopEnd, Read
+    let err = Interpreter::new("+++>[-],").unwrap().without_input().run().unwrap_err();
+    assert!(matches!(err, BrainfuckError::InputRequired { position: 5 }), "{:?}", err);
+}
+
+#[test]
+fn exhausted_input_fails_with_the_error_mode() {
+    let mut output = Vec::new();
+    let err = Interpreter::new(",.,.").unwrap().input(&b"a"[..]).output(&mut output).with_eof_mode(EofMode::Error).run().unwrap_err();
+    assert!(matches!(err, BrainfuckError::InputRequired { position: 2 }), "{:?}", err);
+    assert_eq!(output, b"a");
+
+    // the VM counts ops, comments left out
+    let err = run_program("read , print . read again ,", EofMode::Error, &mut &b"a"[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::InputRequired { position: 2 }), "{:?}", err);
+}
+
+#[test]
+fn programs_without_reads_are_unaffected() {
+    let mut output = Vec::new();
+    Interpreter::new("++++++++[>++++++++<-]>+.").unwrap().without_input().output(&mut output).run().unwrap();
+    assert_eq!(output, b"A");
+    let mut output = Vec::new();
+    run_program("++++++++[>++++++++<-]>+.", EofMode::Error, &mut &b""[..], &mut output).unwrap();
+    assert_eq!(output, b"A");
+}
+
+/// Runs `src` with `--no-stdin`, stdin left open as it would be in CI.
+fn run_no_stdin(name: &str, src: &str) -> std::process::Output {
+    let program = std::env::temp_dir().join(format!("rust_bf-no-stdin-{}-{}.bf", name, std::process::id()));
+    std::fs::write(&program, src).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--no-stdin").arg(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    output
+}
+
+#[test]
+fn no_stdin_reports_the_read() {
+    let output = run_no_stdin("read", "+++[>++<-]>.,");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, [6]);
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: instruction 12 reads input but there is none\n");
+}
+
+#[test]
+fn no_stdin_runs_programs_without_reads() {
+    let output = run_no_stdin("print", "++++++++[>++++++++<-]>+.");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A");
+}
@@ -0,0 +1,43 @@
+//! The interrupt flag stops the VM at the next loop.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use rust_bf::vm::{new_brainfuck_status, run_vm};
+use rust_bf::BrainfuckError;
+
+#[test]
+fn setting_the_flag_stops_an_endless_loop() {
+    static FLAG: AtomicBool = AtomicBool::new(false);
+    let mut status = new_brainfuck_status();
+    status.set_interrupt_flag(&FLAG);
+    let setter = thread::spawn(|| {
+        thread::sleep(Duration::from_millis(50));
+        FLAG.store(true, Ordering::SeqCst);
+    });
+
+    let mut input: &[u8] = b"";
+    let mut output = Vec::new();
+    let result = "+[>+<]".chars().try_for_each(|c| run_vm(&mut status, c, false, &mut input, &mut output));
+    setter.join().unwrap();
+
+    assert!(matches!(result, Err(BrainfuckError::Interrupted)), "{:?}", result);
+    // stopped at the back-edge, with the loop body run a whole number of times
+    assert_eq!(status.tape_ptr(), 0);
+    assert_eq!(status.cells()[0], (0, 1));
+    assert!(status.ops_executed() > 3);
+}
+
+#[test]
+fn an_unset_flag_changes_nothing() {
+    static FLAG: AtomicBool = AtomicBool::new(false);
+    let mut status = new_brainfuck_status();
+    status.set_interrupt_flag(&FLAG);
+    let mut input: &[u8] = b"";
+    let mut output = Vec::new();
+    for c in "++++++++[>++++++++<-]>+.".chars() {
+        run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    }
+    assert_eq!(output, b"A");
+}
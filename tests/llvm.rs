@@ -0,0 +1,48 @@
+//! Generated LLVM IR prints what the interpreter prints, checked by running it with `lli`,
+//! build with `--features llvm_codegen`. Skipped where `lli` is not on the PATH.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use rust_bf::codegen::llvm::to_llvm_ir;
+use rust_bf::ir::{compile_source, BrainfuckIR};
+use rust_bf::optimizer::optimize;
+use rust_bf::Interpreter;
+
+/// Runs `ir` compiled to LLVM IR with `lli`, feeding it `input`, and returns what it printed.
+fn run_with_lli(ir: &[BrainfuckIR], name: &str, input: &[u8]) -> Vec<u8> {
+    let module = std::env::temp_dir().join(format!("rust_bf-llvm-{}-{}.ll", name, std::process::id()));
+    fs::write(&module, to_llvm_ir(ir, name)).unwrap();
+    let mut lli = Command::new("lli").arg(&module).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    lli.stdin.take().unwrap().write_all(input).unwrap();
+    let output = lli.wait_with_output().unwrap();
+    fs::remove_file(&module).unwrap();
+    assert!(output.status.success(), "lli failed on {}", name);
+    output.stdout
+}
+
+/// Returns what `ir` prints on the interpreter given `input`.
+fn run_on_interpreter(ir: Vec<BrainfuckIR>, input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    Interpreter::from_ir(ir).input(input).output(&mut output).run().unwrap();
+    output
+}
+
+#[test]
+fn corpus_programs_print_the_same_under_lli() {
+    if Command::new("lli").arg("--version").stdout(Stdio::null()).status().is_err() {
+        eprintln!("lli is not on the PATH, skipping");
+        return;
+    }
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    for name in ["hello", "nested", "wrap", "cat", "reverse", "eof"] {
+        let program = corpus.join(name).with_extension("bf");
+        let ir = compile_source(&fs::read(&program).unwrap()).unwrap();
+        let input = fs::read(program.with_extension("in")).unwrap_or_default();
+        let expected = run_on_interpreter(ir.clone(), &input);
+        assert_eq!(run_with_lli(&ir, name, &input), expected, "{}", name);
+        assert_eq!(run_with_lli(&optimize(&ir), name, &input), expected, "{} optimized", name);
+    }
+}
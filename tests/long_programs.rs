@@ -0,0 +1,56 @@
+//! VM indices hold up past 2^31 ops, fed run-length encoded.
+
+use rust_bf::io::EofMode;
+use rust_bf::vm::{new_brainfuck_status, run_vm, run_vm_repeated};
+use rust_bf::BrainfuckError;
+
+/// ops in the long run, more than an `i32` holds
+const LONG: usize = (1 << 31) + 5;
+
+#[test]
+fn indices_count_past_two_to_the_31() {
+    let mut status = new_brainfuck_status();
+    status.set_eof_mode(EofMode::Error);
+    let mut input: &[u8] = b"";
+    let mut output = Vec::new();
+    run_vm_repeated(&mut status, '+', LONG, &mut input, &mut output).unwrap();
+    run_vm_repeated(&mut status, '>', 3, &mut input, &mut output).unwrap();
+    run_vm_repeated(&mut status, '<', 3, &mut input, &mut output).unwrap();
+    run_vm(&mut status, '.', false, &mut input, &mut output).unwrap();
+    assert_eq!(output, [5]);
+    assert_eq!(status.ops_executed(), LONG as u64 + 7);
+
+    // the `,` right after is op LONG + 7, counted from 0
+    let err = run_vm(&mut status, ',', false, &mut input, &mut output).unwrap_err();
+    assert!(matches!(err, BrainfuckError::InputRequired { position } if position == LONG + 7), "{:?}", err);
+}
+
+#[test]
+fn loops_after_a_long_run_jump_back_correctly() {
+    let mut status = new_brainfuck_status();
+    let mut input: &[u8] = b"";
+    let mut output = Vec::new();
+    run_vm_repeated(&mut status, '-', LONG, &mut input, &mut output).unwrap();
+    // -(2^31 + 5) wraps to 251 in an 8-bit cell, which the loop moves to cell 1
+    for c in "[>+<-]>.".chars() {
+        run_vm(&mut status, c, false, &mut input, &mut output).unwrap();
+    }
+    assert_eq!(output, [251]);
+    assert_eq!(status.cells(), [(1, 251)]);
+}
+
+#[test]
+fn the_pointer_stops_at_the_end_of_the_tape() {
+    let mut status = new_brainfuck_status();
+    let mut input: &[u8] = b"";
+    let mut output = Vec::new();
+    let err = run_vm_repeated(&mut status, '>', LONG, &mut input, &mut output).unwrap_err();
+    // op 2^31 - 1 is the first to move past address 2^31 - 1
+    assert!(matches!(err, BrainfuckError::PointerOverflow { position } if position == i32::MAX as usize), "{:?}", err);
+    assert_eq!(status.tape_ptr(), i32::MAX);
+
+    // one op at a time fails the same way
+    let err = run_vm(&mut status, '>', false, &mut input, &mut output).unwrap_err();
+    assert!(matches!(err, BrainfuckError::PointerOverflow { .. }), "{:?}", err);
+    assert_eq!(status.tape_ptr(), i32::MAX);
+}
@@ -0,0 +1,79 @@
+//! `--max-output`, `LimitedOutput` and `set_max_output` cap what a program prints.
+
+use std::process::Command;
+
+use rust_bf::io::{LimitedOutput, OutputLimitAction};
+use rust_bf::vm::{new_brainfuck_status, run_vm};
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// prints 1 forever
+const ENDLESS: &str = "+[.]";
+
+#[test]
+fn endless_printer_stops_at_the_limit() {
+    let mut output = Vec::new();
+    let err = Interpreter::new(ENDLESS).unwrap()
+        .output(LimitedOutput::new(&mut output, 100, OutputLimitAction::Error))
+        .run()
+        .unwrap_err();
+    assert!(matches!(err, BrainfuckError::OutputLimitExceeded { limit: 100 }), "{:?}", err);
+    assert_eq!(output.len(), 100);
+}
+
+#[test]
+fn endless_printer_runs_on_truncated() {
+    let mut output = LimitedOutput::new(Vec::new(), 100, OutputLimitAction::Truncate);
+    let err = Interpreter::new(ENDLESS).unwrap().with_step_limit(1000).output(&mut output).run().unwrap_err();
+    // it kept running until the step limit instead
+    assert!(matches!(err, BrainfuckError::StepLimitExceeded { limit: 1000 }), "{:?}", err);
+    assert_eq!(output.written(), 100);
+    assert!(output.dropped() > 300, "{}", output.dropped());
+}
+
+#[test]
+fn endless_printer_stops_at_the_limit_of_the_vm() {
+    let mut status = new_brainfuck_status();
+    status.set_max_output(100);
+    let mut output = Vec::new();
+    let err = ENDLESS.chars().map(|c| run_vm(&mut status, c, false, &mut &b""[..], &mut output)).find_map(Result::err).unwrap();
+    assert!(matches!(err, BrainfuckError::OutputLimitExceeded { limit: 100 }), "{:?}", err);
+    assert_eq!(output, [1; 100]);
+    assert_eq!(status.bytes_printed(), 100);
+}
+
+#[test]
+fn vm_output_up_to_the_limit_runs_to_the_end() {
+    let mut status = new_brainfuck_status();
+    status.set_max_output(2);
+    let mut output = Vec::new();
+    for c in "+.+.".chars() {
+        run_vm(&mut status, c, false, &mut &b""[..], &mut output).unwrap();
+    }
+    assert_eq!(output, [1, 2]);
+}
+
+/// Runs `src` with `args`, returning the exit code, stdout and stderr.
+fn run(name: &str, src: &str, args: &[&str]) -> (Option<i32>, Vec<u8>, String) {
+    let program = std::env::temp_dir().join(format!("rust_bf-max-output-{}-{}.bf", name, std::process::id()));
+    std::fs::write(&program, src).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(args).arg(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    (output.status.code(), output.stdout, String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn max_output_fails_the_run() {
+    let (code, stdout, stderr) = run("error", ENDLESS, &["--max-output", "100"]);
+    assert_eq!(code, Some(1));
+    assert_eq!(stdout, [1; 100]);
+    assert_eq!(stderr, "error: program printed more than the output limit of 100 bytes\n");
+}
+
+#[test]
+fn max_output_truncate_runs_to_the_end() {
+    // prints 1000 bytes, then `A` which the limit drops as well
+    let src = "++++++++++[>++++++++++[>++++++++++[>+.<-]<-]<-]>>>[-]++++++++[<++++++++>-]<+.";
+    let (code, stdout, stderr) = run("truncate", src, &["--max-output", "100", "--max-output-action=truncate"]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(stdout, (1..=100).collect::<Vec<u8>>());
+}
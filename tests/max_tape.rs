@@ -0,0 +1,39 @@
+//! `--max-tape` and `set_max_tape` cap the cells the VM tape tracks.
+
+use std::process::Command;
+
+use rust_bf::vm::{new_brainfuck_status, run_vm};
+use rust_bf::BrainfuckError;
+
+/// writes a cell every 100 cells, forever
+const SCATTER: &str = "+[>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>+]";
+
+#[test]
+fn scattered_writes_stop_at_the_limit() {
+    let mut status = new_brainfuck_status();
+    status.set_max_tape(1000);
+    let err = SCATTER.chars().map(|c| run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new())).find_map(Result::err).unwrap();
+    assert!(matches!(err, BrainfuckError::TapeMemoryExceeded { limit: 1000 }), "{:?}", err);
+    assert_eq!(status.allocated_cells(), 1000);
+}
+
+#[test]
+fn revisited_cells_do_not_count_again() {
+    let mut status = new_brainfuck_status();
+    status.set_max_tape(2);
+    // cells 0 and 1, back and forth
+    for c in "+>+<+>+<+>+<[-]>[-]".chars() {
+        run_vm(&mut status, c, false, &mut &b""[..], &mut Vec::new()).unwrap();
+    }
+    assert_eq!(status.allocated_cells(), 2);
+}
+
+#[test]
+fn cli_fails_past_the_limit() {
+    let program = std::env::temp_dir().join(format!("rust_bf-max-tape-{}.bf", std::process::id()));
+    std::fs::write(&program, SCATTER).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--max-tape", "100"]).arg(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "error: program touched more than the tape limit of 100 cells\n");
+}
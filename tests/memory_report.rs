@@ -0,0 +1,34 @@
+//! The cells the pointer reaches, loops included, and the fixed tape that fits them, see `--memory-report`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, run_vm_repeated};
+
+/// Walks 100 cells right in a loop carrying a counter along, then 103 cells back left.
+fn walk() -> String {
+    format!("+++++++++[>+++++++++++<-]>[[->+<]>-]{}+", "<".repeat(103))
+}
+
+#[test]
+fn runs_report_the_cells_the_pointer_reached() {
+    let mut status = new_brainfuck_status();
+    run_vm_bytes(&mut status, walk().as_bytes(), &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.pointer_extent(), (-3, 100));
+
+    let (code, _, stderr) = run_file(&walk(), &["--memory-report"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stderr, "\
+pointer reached cells -3 to 100, 102 cells touched
+went left of cell 0 by 3 cells and touched 1 cell there, a fixed tape needs 104 cells with the pointer starting at cell 3
+");
+}
+
+#[test]
+fn repeated_moves_widen_the_extent_too() {
+    let mut status = new_brainfuck_status();
+    run_vm_repeated(&mut status, '>', 30_000, &mut &b""[..], &mut Vec::new()).unwrap();
+    run_vm_repeated(&mut status, '<', 29_990, &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.pointer_extent(), (0, 30_000));
+    assert_eq!(status.tape_ptr(), 10);
+}
@@ -0,0 +1,30 @@
+//! An interpreter iterates over the bytes its program prints, running only as far as it is asked to.
+
+use rust_bf::{BrainfuckError, Interpreter};
+
+#[test]
+fn hello_world_collects_into_its_output() {
+    let hello = Interpreter::new(include_str!("../src/examples/hello.bf")).unwrap();
+    assert_eq!(hello.collect::<Result<Vec<u8>, _>>().unwrap(), b"Hello World!\n");
+}
+
+#[test]
+fn endless_programs_run_only_as_far_as_taken() {
+    let mut interpreter = Interpreter::new("+[.+]").unwrap();
+    let first: Vec<u8> = interpreter.by_ref().take(5).map(Result::unwrap).collect();
+    assert_eq!(first, [1, 2, 3, 4, 5]);
+    // stopped right after the fifth `.`, on the `+` after it
+    assert_eq!(interpreter.instruction_pointer(), 3);
+    assert_eq!(interpreter.next().unwrap().unwrap(), 6);
+}
+
+#[test]
+fn errors_end_collecting() {
+    let interpreter = Interpreter::new("+.<.").unwrap();
+    let result: Result<Vec<u8>, _> = interpreter.collect();
+    assert!(matches!(result, Err(BrainfuckError::PointerUnderflow { .. })));
+
+    let mut interpreter = Interpreter::new("+.[]").unwrap().with_step_limit(100);
+    assert_eq!(interpreter.next().unwrap().unwrap(), 1);
+    assert!(matches!(interpreter.next(), Some(Err(BrainfuckError::StepLimitExceeded { limit: 100 }))));
+}
@@ -0,0 +1,98 @@
+//! Cells that wrap around, fail or saturate past their largest value and below 0, see `--overflow`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::ir::{compile, BrainfuckIR};
+use rust_bf::optimizer::optimize;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, run_vm_repeated, OverflowMode};
+use rust_bf::{ast::parse, BrainfuckError, Interpreter};
+
+/// Runs `src` on a fresh VM with cells overflowing the `mode` way, returning the cells.
+fn run_on_vm(src: &str, mode: OverflowMode) -> Result<Vec<(i32, i32)>, BrainfuckError> {
+    let mut status = new_brainfuck_status();
+    status.set_overflow_mode(mode);
+    run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut Vec::new())?;
+    Ok(status.cells())
+}
+
+/// Runs `ir` on an interpreter with cells overflowing the `mode` way, returning the tape.
+fn run_on_interpreter(ir: Vec<BrainfuckIR>, mode: OverflowMode) -> Result<Vec<u8>, BrainfuckError> {
+    let mut interpreter = Interpreter::from_ir(ir).output(Vec::new()).with_overflow_mode(mode);
+    interpreter.run()?;
+    Ok(interpreter.tape().to_vec())
+}
+
+#[test]
+fn minus_on_a_fresh_cell_wraps_fails_or_stays_0() {
+    assert_eq!(run_on_vm("-", OverflowMode::Wrap).unwrap(), [(0, 255)]);
+    let err = run_on_vm("-", OverflowMode::Error).unwrap_err();
+    assert!(matches!(err, BrainfuckError::CellOverflow { position: 0, cell: 0, value: -1 }), "{:?}", err);
+    assert_eq!(run_on_vm("-", OverflowMode::Saturate).unwrap(), []);
+
+    let ir = compile(&parse("-").unwrap());
+    assert_eq!(run_on_interpreter(ir.clone(), OverflowMode::Wrap).unwrap(), [255]);
+    let err = run_on_interpreter(ir.clone(), OverflowMode::Error).unwrap_err();
+    assert!(matches!(err, BrainfuckError::CellOverflow { position: 0, cell: 0, value: -1 }), "{:?}", err);
+    assert_eq!(run_on_interpreter(ir, OverflowMode::Saturate).unwrap(), [0]);
+}
+
+#[test]
+fn plus_past_the_largest_value_does_the_same() {
+    let src = "+".repeat(256);
+    assert_eq!(run_on_vm(&src, OverflowMode::Wrap).unwrap(), []);
+    let err = run_on_vm(&src, OverflowMode::Error).unwrap_err();
+    assert!(matches!(err, BrainfuckError::CellOverflow { position: 255, cell: 0, value: 256 }), "{:?}", err);
+    assert_eq!(run_on_vm(&src, OverflowMode::Saturate).unwrap(), [(0, 255)]);
+
+    // wider cells have room for more
+    let mut status = new_brainfuck_status();
+    status.set_overflow_mode(OverflowMode::Error);
+    status.set_cell_bits(16);
+    run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.cells(), [(0, 256)]);
+}
+
+#[test]
+fn runs_of_ops_stop_at_the_one_that_overflows() {
+    let mut status = new_brainfuck_status();
+    status.set_overflow_mode(OverflowMode::Error);
+    run_vm_repeated(&mut status, '+', 10, &mut &b""[..], &mut Vec::new()).unwrap();
+    let err = run_vm_repeated(&mut status, '-', 20, &mut &b""[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::CellOverflow { position: 20, cell: 0, value: -1 }), "{:?}", err);
+    assert_eq!((status.cells(), status.ops_executed()), (vec![], 21));
+
+    let mut status = new_brainfuck_status();
+    status.set_overflow_mode(OverflowMode::Saturate);
+    run_vm_repeated(&mut status, '+', 1000, &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.cells(), [(0, 255)]);
+}
+
+#[test]
+fn optimized_multiplications_overflow_like_their_loops() {
+    // 100 times 3 is past 255
+    let src = "++++++++++[>++++++++++<-]>[->+++<]";
+    let optimized = optimize(&compile(&parse(src).unwrap()));
+    assert!(optimized.contains(&BrainfuckIR::MulAdd { offset: 1, factor: 3 }));
+
+    assert_eq!(run_on_vm(src, OverflowMode::Wrap).unwrap(), [(2, 44)]);
+    assert_eq!(run_on_interpreter(optimized.clone(), OverflowMode::Wrap).unwrap(), [0, 0, 44]);
+    assert_eq!(run_on_vm(src, OverflowMode::Saturate).unwrap(), [(2, 255)]);
+    assert_eq!(run_on_interpreter(optimized.clone(), OverflowMode::Saturate).unwrap(), [0, 0, 255]);
+
+    assert!(matches!(run_on_vm(src, OverflowMode::Error).unwrap_err(), BrainfuckError::CellOverflow { cell: 2, value: 256, .. }));
+    let position = optimized.iter().position(|node| matches!(node, BrainfuckIR::MulAdd { factor: 3, .. })).unwrap();
+    let err = run_on_interpreter(optimized, OverflowMode::Error).unwrap_err();
+    assert!(matches!(err, BrainfuckError::CellOverflow { position: p, cell: 2, value: 300 } if p == position), "{:?}", err);
+}
+
+#[test]
+fn the_command_line_picks_the_mode() {
+    let run = |args: &[&str]| run_file(">-.", args);
+    assert_eq!(run(&[]), (Some(0), vec![255], String::new()));
+    assert_eq!(run(&["--overflow=wrap"]), (Some(0), vec![255], String::new()));
+    assert_eq!(run(&["--overflow=saturate"]), (Some(0), vec![0], String::new()));
+    let failed = (Some(1), vec![], String::from("error: instruction 1 took cell 1 to -1, out of the range of a cell\n"));
+    assert_eq!(run(&["--overflow=error"]), failed);
+    assert_eq!(run(&["--overflow=sometimes"]).0, Some(2));
+}
@@ -0,0 +1,156 @@
+//! Packed programs load back into the program they were packed from, see `--emit bfc`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{fixture, run};
+use rust_bf::ast::tokens;
+use rust_bf::frontend::to_source;
+use rust_bf::ir::compile_source;
+use rust_bf::minify::minify_program;
+use rust_bf::packed::{is_packed, load, pack, read_program, unpack, write_program, PackedError, MAGIC, RUNS_VERSION};
+use rust_bf::Interpreter;
+
+/// Returns a scratch file named after `name`.
+fn scratch(name: &str) -> String {
+    std::env::temp_dir().join(format!("rust_bf-packed-{}-{}.bfc", name, std::process::id())).to_string_lossy().into_owned()
+}
+
+#[test]
+fn programs_round_trip_through_the_packed_form() {
+    let dbfi = fs::read_to_string(fixture("dbfi.bf")).unwrap();
+    for src in ["", "+", "[]", "[[][[]]]", include_str!("../src/examples/hello.bf"), &dbfi] {
+        let bytes = pack(src.as_bytes()).unwrap();
+        assert!(is_packed(&bytes));
+        assert_eq!(to_source(&unpack(&bytes).unwrap()), minify_program(src, false).unwrap());
+        assert_eq!(load(&bytes).unwrap(), compile_source(src.as_bytes()).unwrap());
+    }
+    assert!(!is_packed(b"+[>]"));
+}
+
+#[test]
+fn corrupted_programs_fail_with_what_is_wrong() {
+    let bytes = pack(b"+[[-]>]").unwrap();
+    let mut magic = bytes.clone();
+    magic[0] = b'B';
+    assert_eq!(unpack(&magic), Err(PackedError::BadMagic));
+    let mut version = bytes.clone();
+    version[4] = 9;
+    assert_eq!(unpack(&version), Err(PackedError::UnsupportedVersion(9)));
+    for len in [MAGIC.len(), 7, 10, 14, bytes.len() - 1] {
+        assert_eq!(unpack(&bytes[..len]), Err(PackedError::Truncated), "{}", len);
+    }
+    // more ops than the file holds
+    let mut count = bytes.clone();
+    count[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(unpack(&count), Err(PackedError::Truncated));
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(unpack(&trailing), Err(PackedError::TrailingBytes(1)));
+
+    // the jump table starts after 4 + 1 + 4 bytes of header, 3 of ops and 4 of its length
+    let table = 16;
+    let mut swapped = bytes.clone();
+    let (first, second) = swapped[table..].split_at_mut(8);
+    first.swap_with_slice(second);
+    assert_eq!(load(&swapped), Err(PackedError::InvalidJumpTable { index: 0 }));
+    let mut missing = bytes;
+    missing[table - 4] = 1;
+    missing.truncate(table + 8);
+    assert_eq!(load(&missing), Err(PackedError::InvalidJumpTable { index: 1 }));
+}
+
+/// Returns what the IR `ir` prints given `input`.
+fn output_of(ir: Vec<rust_bf::ir::BrainfuckIR>, input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    Interpreter::from_ir(ir).input(input).output(&mut output).run().unwrap();
+    output
+}
+
+#[test]
+fn programs_round_trip_through_runs_of_ops() {
+    let dbfi = fs::read_to_string(fixture("dbfi.bf")).unwrap();
+    let long = format!("{}[>{}<-]", "+".repeat(200), "-".repeat(100_000));
+    for src in ["", "+", "[]", "[[][[]]]", include_str!("../src/examples/hello.bf"), &dbfi, &long] {
+        let ops: Vec<_> = tokens(src.as_bytes()).map(|(op, _)| op).collect();
+        let bytes = write_program(&ops);
+        assert!(is_packed(&bytes));
+        assert_eq!(bytes[4], RUNS_VERSION);
+        assert_eq!(read_program(&bytes).unwrap(), ops);
+        assert_eq!(unpack(&bytes).unwrap(), ops);
+        assert_eq!(load(&bytes).unwrap(), compile_source(src.as_bytes()).unwrap());
+    }
+    // a loaded program runs like its source
+    let rot13 = include_str!("../src/examples/rot13.bf");
+    let bytes = write_program(&tokens(rot13.as_bytes()).map(|(op, _)| op).collect::<Vec<_>>());
+    let input = b"Hello, World!\n";
+    assert_eq!(output_of(load(&bytes).unwrap(), input), output_of(compile_source(rot13.as_bytes()).unwrap(), input));
+    assert_eq!(output_of(load(&bytes).unwrap(), input), b"Uryyb, Jbeyq!\n");
+}
+
+#[test]
+fn corrupted_runs_fail_with_what_is_wrong() {
+    // one run of 3 `+`
+    let header = [&MAGIC[..], &[RUNS_VERSION]].concat();
+    let program = |runs: &[u8]| [&header[..], runs].concat();
+    assert_eq!(read_program(&program(&[1, 0, 3])).unwrap().len(), 3);
+    assert_eq!(read_program(&program(&[1, 0])), Err(PackedError::Truncated));
+    assert_eq!(read_program(&program(&[1, 0, 0x80])), Err(PackedError::Truncated));
+    assert_eq!(read_program(&program(&[2, 0, 3])), Err(PackedError::Truncated));
+    assert_eq!(read_program(&program(&[1, 0, 3, 4])), Err(PackedError::TrailingBytes(1)));
+    assert_eq!(read_program(&program(&[1, 8])), Err(PackedError::UnknownOp(8)));
+    assert_eq!(read_program(&program(&[2, 4, 0, 0])), Err(PackedError::InvalidCount { run: 1 }));
+    // 2^31 `>` in a row is more than a move can hold
+    assert_eq!(read_program(&program(&[1, 2, 0x80, 0x80, 0x80, 0x80, 0x08])), Err(PackedError::InvalidCount { run: 0 }));
+    let max = [0xff, 0xff, 0xff, 0xff, 0x07];
+    let twice = program(&[&[2, 2][..], &max, &[2], &max].concat());
+    assert_eq!(read_program(&twice[..5 + 7]).map(|ops| ops.len()), Err(PackedError::Truncated));
+    assert_eq!(read_program(&twice), Err(PackedError::InvalidCount { run: 1 }));
+    assert_eq!(load(&twice), Err(PackedError::InvalidCount { run: 1 }));
+    // runs of `+` and `-` that fold back to nothing still count every op
+    let seesaw = program(&[&[4, 0][..], &max, &[1], &max, &[0], &max, &[1], &max].concat());
+    assert_eq!(read_program(&seesaw), Err(PackedError::InvalidCount { run: 1 }));
+    assert_eq!(load(&seesaw), Err(PackedError::InvalidCount { run: 1 }));
+    // a `[` with no room left after a full run
+    assert_eq!(load(&program(&[&[3, 0][..], &max, &[6, 7]].concat())), Err(PackedError::InvalidCount { run: 1 }));
+    // `[`, `+`, 1 and a `]` too many
+    assert_eq!(read_program(&program(&[4, 6, 0, 1, 7, 7])), Err(PackedError::UnmatchedBracket { index: 3 }));
+    // `[[]`, and `[[`, the innermost `[` left open is the one named
+    assert_eq!(read_program(&program(&[3, 6, 6, 7])), Err(PackedError::UnmatchedBracket { index: 0 }));
+    assert_eq!(read_program(&program(&[2, 6, 6])), Err(PackedError::UnmatchedBracket { index: 1 }));
+    assert_eq!(read_program(&pack(b"+").unwrap()), Err(PackedError::UnsupportedVersion(1)));
+}
+
+#[test]
+fn emitted_programs_run_from_their_file() {
+    let file = scratch("hello");
+    let hello = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/examples/hello.bf");
+    let (code, _, stderr) = run(&["--emit", "bfc", "-o", &file, &hello.to_string_lossy()]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert!(fs::metadata(&file).unwrap().len() < fs::metadata(&hello).unwrap().len());
+    assert_eq!(run(&[&file]), (Some(0), b"Hello World!\n".to_vec(), String::new()));
+
+    let mut bytes = fs::read(&file).unwrap();
+    bytes.pop();
+    fs::write(&file, bytes).unwrap();
+    let (code, stdout, stderr) = run(&[&file]);
+    fs::remove_file(&file).unwrap();
+    assert_eq!((code, stdout), (Some(1), Vec::new()));
+    assert_eq!(stderr, "error: packed program is truncated\n");
+
+    let (code, _, stderr) = run(&["--emit", "bfc-runs", "-o", &file, &hello.to_string_lossy()]);
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert_eq!(fs::read(&file).unwrap()[4], RUNS_VERSION);
+    let result = run(&[&file]);
+    assert_eq!(result, (Some(0), b"Hello World!\n".to_vec(), String::new()));
+
+    // four runs of 2^31 - 1 `+` and `-` are rejected before any of them is expanded
+    let max = [0xff, 0xff, 0xff, 0xff, 0x07];
+    let runs = [&[4, 0][..], &max, &[1], &max, &[0], &max, &[1], &max].concat();
+    fs::write(&file, [&MAGIC[..], &[RUNS_VERSION], &runs].concat()).unwrap();
+    let result = run(&[&file]);
+    fs::remove_file(&file).unwrap();
+    assert_eq!(result, (Some(1), Vec::new(), String::from("error: run 1 of the packed program has an invalid count\n")));
+}
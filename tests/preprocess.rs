@@ -0,0 +1,80 @@
+//! Macros expand before parsing, with `--pp` or for `.bfm` files.
+
+use std::process::Command;
+
+use rust_bf::preprocess::preprocess;
+use rust_bf::Interpreter;
+
+#[test]
+fn nested_macros_expand_inside_out() {
+    let src = "#define inc { + }\n#define three { @inc@inc@inc }\n#define nine { @three @three @three }\n@nine>@three";
+    let expanded = preprocess(src).unwrap();
+    let ops: String = expanded.source.chars().filter(|c| "+-<>[].,".contains(*c)).collect();
+    assert_eq!(ops, format!("{}>{}", "+".repeat(9), "+++"));
+}
+
+#[test]
+fn macros_may_be_used_before_they_are_defined() {
+    let expanded = preprocess("@dot\n#define dot { . }").unwrap();
+    assert_eq!(expanded.source.trim(), ".");
+}
+
+#[test]
+fn undefined_macros_are_errors_at_the_call() {
+    let err = preprocess("#define a { + }\n  @a @b").unwrap_err();
+    assert_eq!((err.position.line, err.position.column), (2, 6));
+    assert_eq!(err.message, "macro `b` is not defined");
+}
+
+#[test]
+fn recursion_is_detected() {
+    let err = preprocess("#define a { +@a }\n@a").unwrap_err();
+    assert_eq!(err.to_string(), "1:14: macro `a` expands itself: a -> a");
+    let err = preprocess("#define a { @b }\n#define b { @c }\n#define c { @a }\n@a").unwrap_err();
+    assert_eq!(err.message, "macro `a` expands itself: a -> b -> c -> a");
+}
+
+#[test]
+fn chains_deeper_than_the_limit_are_errors() {
+    let mut src: String = (0..100).map(|i| format!("#define m{} {{ @m{} }}\n", i, i + 1)).collect();
+    src.push_str("#define m100 { + }\n@m0");
+    assert_eq!(preprocess(&src).unwrap_err().message, "macros are expanded more than 64 deep");
+}
+
+#[test]
+fn malformed_definitions_are_errors() {
+    assert_eq!(preprocess("#define { + }").unwrap_err().message, "expected a macro name after `#define`");
+    assert_eq!(preprocess("#define a + }").unwrap_err().message, "expected `{` after `#define a`");
+    assert_eq!(preprocess("#define a { +").unwrap_err().message, "`#define a` is never closed by a `}`");
+    assert_eq!(preprocess("#define a {}\n#define a {}").unwrap_err().message, "macro `a` is defined twice");
+}
+
+#[test]
+fn the_fixture_prints_hi() {
+    let src = include_str!("fixtures/hi.bfm");
+    assert_eq!(Interpreter::run_string(&preprocess(src).unwrap().source, "").unwrap(), "Hi");
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hi.bfm");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg(path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"Hi");
+}
+
+#[test]
+fn cli_expands_with_pp_and_reports_original_positions() {
+    let dir = std::env::temp_dir().join(format!("rust_bf_preprocess_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("open.bf");
+    std::fs::write(&program, "#define open { + [ }\n@open -\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--pp").arg(&program).output().unwrap();
+    std::fs::write(&program, "@nope").unwrap();
+    let undefined = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--pp").arg(&program).output().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "1:18: error: unmatched '['\n    #define open { + [ }\n                     ^ this '[' is never closed by a ']'\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&undefined.stderr), "1:1: error: macro `nope` is not defined\n");
+}
@@ -0,0 +1,30 @@
+//! Programs print back as the ops they were parsed from.
+
+use std::convert::TryFrom;
+
+use rust_bf::minify::minify;
+use rust_bf::{Interpreter, Program};
+
+const DBFI: &str = include_str!("fixtures/dbfi.bf");
+
+#[test]
+fn dbfi_round_trips() {
+    let program = Program::try_from(DBFI).unwrap();
+    let text = program.to_string();
+    assert!(text.chars().all(|c| "+-<>.,[]".contains(c)), "{}", text);
+    assert_eq!(text, minify(DBFI));
+
+    let reparsed = Program::try_from(text.as_str()).unwrap();
+    assert_eq!(reparsed.to_string(), text);
+    assert_eq!(reparsed.compile(), program.compile());
+    assert_eq!(Interpreter::run_string(&text, ",[.,]!echo").unwrap(), "echo");
+}
+
+#[test]
+fn dbfi_loop_metrics() {
+    let program = Program::try_from(DBFI).unwrap();
+    assert_eq!(program.loop_count(), 58);
+    assert_eq!(program.max_loop_depth(), 7);
+    let flat = Program::try_from("no loops, just +.").unwrap();
+    assert_eq!((flat.loop_count(), flat.max_loop_depth()), (0, 0));
+}
@@ -0,0 +1,159 @@
+//! Properties every well-formed program has, run with `cargo test --features testing`.
+
+use proptest::prelude::*;
+use rust_bf::ast::{check_brackets, parse};
+use rust_bf::compress::{compress, synthesize, synthesize_small};
+use rust_bf::format::{format_program, FormatOptions};
+use rust_bf::ir::{compile, compile_source, BrainfuckIR};
+use rust_bf::minify::minify_program;
+use rust_bf::optimizer::optimize;
+use rust_bf::testing::GeneratedProgram;
+use rust_bf::verify::{check_equivalence, EquivalenceResult};
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// IR nodes a generated program may run, most of them never halt
+const STEP_LIMIT: u64 = 10_000;
+
+/// Runs `program` to its end, `None` if it hit the step limit.
+fn run(program: Vec<BrainfuckIR>, input: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let mut output = Vec::new();
+    let result = Interpreter::from_ir(program).with_step_limit(STEP_LIMIT).input(input).output(&mut output).run();
+    match result {
+        Err(BrainfuckError::StepLimitExceeded { .. }) => None,
+        result => Some((output, result.is_ok())),
+    }
+}
+
+proptest! {
+    #[test]
+    fn compiling_source_matches_compiling_its_tree(src in "[-+<>.,\\[\\]a\u{e9}\n]{0,64}") {
+        let tree = parse(&src).map(|ast| compile(&ast)).map_err(|err| err.to_string());
+        prop_assert_eq!(compile_source(src.as_bytes()).map_err(|err| err.to_string()), tree.clone());
+        prop_assert_eq!(check_brackets(src.as_bytes()).map_err(|err| err.to_string()), tree.map(|_| ()));
+    }
+
+    #[test]
+    fn formatting_keeps_the_ops(program in any::<GeneratedProgram>()) {
+        let formatted = format_program(program.source(), &FormatOptions::default()).unwrap();
+        prop_assert_eq!(minify_program(&formatted, false).unwrap(), program.source());
+        prop_assert_eq!(compile(&parse(&formatted).unwrap()), compile(&parse(program.source()).unwrap()));
+    }
+
+    #[test]
+    fn optimizing_keeps_the_output(program in any::<GeneratedProgram>(), input in any::<Vec<u8>>()) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let naive = run(ir.clone(), &input);
+        prop_assume!(naive.is_some());
+        // the optimized program never runs more steps than the naive one
+        prop_assert_eq!(run(optimize(&ir), &input), naive);
+    }
+
+    #[test]
+    fn optimized_programs_are_equivalent(program in any::<GeneratedProgram>(), inputs in prop::collection::vec(any::<Vec<u8>>(), 1..4)) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let inputs: Vec<&[u8]> = inputs.iter().map(Vec::as_slice).collect();
+        prop_assert_eq!(check_equivalence(&ir, &optimize(&ir), &inputs, STEP_LIMIT), EquivalenceResult::Equivalent);
+    }
+
+    #[test]
+    fn compressing_keeps_the_output(program in any::<GeneratedProgram>(), input in any::<Vec<u8>>()) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let naive = run(ir.clone(), &input);
+        // dropped loops can merge moves around them, hiding a pointer underflow
+        if !matches!(naive, Some((_, true))) {
+            return Ok(());
+        }
+        for ir in [ir.clone(), optimize(&ir)] {
+            let compressed = compile(&parse(&compress(&ir)).unwrap());
+            // compressed constants take more steps, so allow for a few more
+            let mut output = Vec::new();
+            let result = Interpreter::from_ir(compressed).with_step_limit(100 * STEP_LIMIT).input(&input[..]).output(&mut output).run();
+            prop_assert_eq!(Some((output, result.is_ok())), naive.clone());
+        }
+    }
+
+    #[test]
+    fn synthesized_programs_print_their_target(target in any::<Vec<u8>>()) {
+        // synthesized programs always halt, long ones just run more than `STEP_LIMIT` steps
+        let mut output = Vec::new();
+        Interpreter::new(&synthesize(&target)).unwrap().input(&b""[..]).output(&mut output).run().unwrap();
+        prop_assert_eq!(output, target);
+    }
+
+    #[test]
+    fn smaller_synthesized_programs_print_their_target(target in any::<Vec<u8>>()) {
+        let mut output = Vec::new();
+        Interpreter::new(&synthesize_small(&target)).unwrap().input(&b""[..]).output(&mut output).run().unwrap();
+        prop_assert_eq!(output, target);
+    }
+
+    #[test]
+    fn stepping_matches_running(program in any::<GeneratedProgram>(), input in any::<Vec<u8>>()) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let expected = run(ir.clone(), &input);
+        prop_assume!(expected.is_some());
+
+        let mut output = Vec::new();
+        let mut interpreter = Interpreter::from_ir(ir).input(&input[..]).output(&mut output);
+        let ok = loop {
+            match interpreter.step() {
+                Ok(true) => (),
+                Ok(false) => break true,
+                Err(_) => break false,
+            }
+        };
+        drop(interpreter);
+        prop_assert_eq!(Some((output, ok)), expected);
+    }
+
+    #[test]
+    fn iterating_yields_what_running_prints(program in any::<GeneratedProgram>(), input in any::<Vec<u8>>()) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let expected = run(ir.clone(), &input);
+        prop_assume!(expected.is_some());
+
+        let interpreter = Interpreter::from_ir(ir).with_step_limit(STEP_LIMIT).input(&input[..]);
+        let mut output = Vec::new();
+        let mut ok = true;
+        for byte in interpreter {
+            match byte {
+                Ok(byte) => output.push(byte),
+                Err(_) => {
+                    ok = false;
+                    break;
+                },
+            }
+        }
+        prop_assert_eq!(Some((output, ok)), expected);
+    }
+
+    #[test]
+    fn snapshots_resume_where_they_left_off(
+        program in any::<GeneratedProgram>(),
+        input in any::<Vec<u8>>(),
+        steps in 0..100usize,
+    ) {
+        let ir = compile(&parse(program.source()).unwrap());
+        let expected = run(ir.clone(), &input);
+        prop_assume!(expected.is_some());
+
+        let mut rest = &input[..];
+        let mut output = Vec::new();
+        let mut interpreter = Interpreter::from_ir(ir).input(&mut rest).output(&mut output);
+        let mut failed = false;
+        for _ in 0..steps {
+            match interpreter.step() {
+                Ok(_) => (),
+                Err(_) => {
+                    failed = true;
+                    break;
+                },
+            }
+        }
+        let snapshot = interpreter.snapshot();
+        drop(interpreter);
+
+        let ok = !failed && Interpreter::from_snapshot(snapshot).input(rest).output(&mut output).run().is_ok();
+        prop_assert_eq!(Some((output, ok)), expected);
+    }
+}
@@ -0,0 +1,50 @@
+//! `is_quine` on a known quine and on programs that are not.
+//!
+//! The `quine` example keeps every op of its second half as one cell
+//! of 4 in the first half, holding the op's character code minus 42. The
+//! second half prints the first half back as `>>>>` and that many `+`,
+//! then itself by adding 42 to every cell.
+
+use std::process::Command;
+
+use rust_bf::quine::is_quine;
+use rust_bf::BrainfuckError;
+
+/// IR steps the fixture needs are well below this
+const LIMIT: u64 = 1_000_000;
+
+fn fixture() -> String {
+    std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/src/examples/quine.bf")).unwrap()
+}
+
+#[test]
+fn known_quine_is_a_quine() {
+    assert!(is_quine(&fixture(), LIMIT).unwrap());
+}
+
+#[test]
+fn comments_are_ignored() {
+    let src = format!("this prints itself\n{}\nbut not this line\n", fixture());
+    assert!(is_quine(&src, LIMIT).unwrap());
+}
+
+#[test]
+fn changed_quine_is_not_a_quine() {
+    // one more `+` in the first cell prints a different op
+    let src = format!(">>>>+{}", &fixture()[4..]);
+    assert!(!is_quine(&src, LIMIT).unwrap());
+    assert!(!is_quine(&format!("{}.", fixture()), LIMIT).unwrap());
+}
+
+#[test]
+fn endless_program_hits_the_limit() {
+    assert!(matches!(is_quine("+[]", LIMIT), Err(BrainfuckError::StepLimitExceeded { limit: LIMIT })));
+}
+
+#[test]
+fn cli_prints_the_quine() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/examples/quine.bf");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg(path).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), fixture());
+}
@@ -0,0 +1,82 @@
+//! `--repl` runs lines on one VM and keeps them in `~/.brainfuck_history`.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use rust_bf::repl::{BrainfuckREPLHistory, HISTORY_FILE};
+
+#[test]
+fn history_entries_run_again_by_number() {
+    let mut history = BrainfuckREPLHistory::new();
+    for line in ["+++", "", "+++", ">."] {
+        history.push(line);
+    }
+    // blank lines and repeats of the latest entry are left out
+    assert_eq!(history.entries(), ["+++", ">."]);
+    assert_eq!(history.resolve(" !2 "), Ok(">."));
+    assert_eq!(history.resolve("!0"), Err(String::from("no history entry 0, there are 2")));
+    // anything else starting with `!` is a program, `!` is an op with `debug_ops`
+    for line in ["!", "!+.", "!x", "!1+"] {
+        assert_eq!(history.resolve(line), Ok(line));
+    }
+}
+
+#[test]
+fn history_survives_saving_and_loading() {
+    let path = std::env::temp_dir().join(format!("rust_bf_history_{}", std::process::id()));
+    assert_eq!(BrainfuckREPLHistory::load(&path).unwrap(), BrainfuckREPLHistory::new());
+    let mut history = BrainfuckREPLHistory::new();
+    history.push("++[>+<-]");
+    history.push("#define x { + } is kept as typed");
+    history.save(&path).unwrap();
+    let loaded = BrainfuckREPLHistory::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, history);
+}
+
+/// Types `lines` at `rust_bf --repl` run with `args`, in a home directory whose
+/// history holds `+.`, and returns what it printed and the history it left.
+fn repl(name: &str, args: &[&str], lines: &[u8]) -> (Output, String) {
+    let home = std::env::temp_dir().join(format!("rust_bf_repl_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    std::fs::write(home.join(HISTORY_FILE), "+.\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .args(["--repl", "--no-stdin"])
+        .args(args)
+        .env("HOME", &home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(lines).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let history = std::fs::read_to_string(home.join(HISTORY_FILE)).unwrap();
+    std::fs::remove_dir_all(&home).unwrap();
+    (output, history)
+}
+
+#[test]
+fn cli_keeps_the_vm_and_the_history_across_lines() {
+    // the loop of the first line is closed by the second, `!1` is the line of the earlier session
+    let (output, history) = repl("vm", &[], b">++++++++[<++++++++\n>-]<\n!1\n,\n+.\n");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    // `A` by `!1`, then 1 on a fresh VM after `,` found no input
+    assert_eq!(output.stdout, b"A\x01");
+    assert!(stderr.contains("error: instruction 25 reads input but there is none"), "{}", stderr);
+    assert_eq!(history, "+.\n>++++++++[<++++++++\n>-]<\n+.\n,\n+.\n");
+}
+
+#[cfg(feature = "debug_ops")]
+#[test]
+fn lines_may_start_with_the_reset_op() {
+    // `!` resets the tape before the line goes on, `!3` runs the line before again
+    let (output, history) = repl("debug_ops", &["--debug-ops"], b"+++$\n!++$\n!3\n!\n$\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("error"), "{}", stderr);
+    assert_eq!(output.stdout, b"3220");
+    assert_eq!(history, "+.\n+++$\n!++$\n!\n$\n");
+}
@@ -0,0 +1,47 @@
+//! A reset interpreter runs like a new one.
+
+use std::convert::TryFrom;
+
+use rust_bf::{BrainfuckError, Interpreter, Program};
+
+#[test]
+fn nothing_leaks_between_runs() {
+    let dirty = Program::try_from("+++++[>+++++<-]>[>++<-]>>-").unwrap();
+    // prints the first four cells without touching them
+    let peek = Program::try_from(".>.>.>.").unwrap();
+
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::from_ir(Vec::new()).with_step_limit(1000).output(&mut output);
+    interpreter.set_program(dirty.clone());
+    interpreter.run().unwrap();
+    assert_eq!(interpreter.tape(), &[0, 0, 50, 255]);
+
+    interpreter.set_program(peek);
+    assert!(interpreter.tape().is_empty());
+    interpreter.run().unwrap();
+    assert_eq!(interpreter.tape(), &[0, 0, 0, 0]);
+
+    // the step count starts over as well, the limit applies to every run
+    for _ in 0..3 {
+        interpreter.set_program(dirty.clone());
+        interpreter.run().unwrap();
+    }
+    interpreter.set_program(Program::try_from("+[]").unwrap());
+    assert!(matches!(interpreter.run(), Err(BrainfuckError::StepLimitExceeded { limit: 1000 })));
+    drop(interpreter);
+    assert_eq!(output, [0, 0, 0, 0]);
+}
+
+#[test]
+fn reset_runs_the_same_program_again() {
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::new("++++++++[>++++++++<-]>+.").unwrap().output(&mut output);
+    interpreter.run().unwrap();
+    let tape = interpreter.tape().to_vec();
+    interpreter.reset();
+    assert!(!interpreter.is_halted());
+    interpreter.run().unwrap();
+    assert_eq!(interpreter.tape(), &tape[..]);
+    drop(interpreter);
+    assert_eq!(output, b"AA");
+}
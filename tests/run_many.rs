@@ -0,0 +1,44 @@
+//! `run_many` runs programs at once, each on its own interpreter with its own limits.
+
+use std::convert::TryFrom;
+
+use rust_bf::batch::{run_many, Limits};
+use rust_bf::{BrainfuckError, Program};
+
+#[test]
+fn hundred_programs_run_independently() {
+    // program `i` prints its input plus `i`, every tenth one never halts
+    let programs: Vec<(Program, Vec<u8>)> = (0..100u8)
+        .map(|i| {
+            let src = if i % 10 == 3 { String::from("+[>+<]") } else { format!(",{}.", "+".repeat(i as usize)) };
+            (Program::try_from(src.as_str()).unwrap(), vec![i])
+        })
+        .collect();
+    let results = run_many(&programs, Limits { step_limit: Some(1000), ..Limits::default() });
+
+    assert_eq!(results.len(), 100);
+    for (i, result) in results.iter().enumerate() {
+        if i % 10 == 3 {
+            assert!(matches!(result, Err(BrainfuckError::StepLimitExceeded { limit: 1000 })), "{}: {:?}", i, result);
+        } else {
+            let outcome = result.as_ref().unwrap();
+            assert_eq!(outcome.output, [2 * i as u8], "{}", i);
+            // `,`, the run of `+` if there is one, and `.`
+            assert_eq!(outcome.steps, if i == 0 { 2 } else { 3 }, "{}", i);
+        }
+    }
+}
+
+#[test]
+fn limits_apply_to_every_run() {
+    let programs = vec![
+        (Program::try_from("+[.]").unwrap(), Vec::new()),
+        (Program::try_from("+.").unwrap(), Vec::new()),
+        (Program::try_from("+[>+]").unwrap(), Vec::new()),
+    ];
+    let limits = Limits { step_limit: Some(100_000), timeout: None, memory_limit: Some(64), max_output: Some(8) };
+    let results = run_many(&programs, limits);
+    assert!(matches!(results[0], Err(BrainfuckError::OutputLimitExceeded { limit: 8 })), "{:?}", results[0]);
+    assert_eq!(results[1].as_ref().unwrap().output, [1]);
+    assert!(matches!(results[2], Err(BrainfuckError::MemoryLimitExceeded { limit: 64, .. })), "{:?}", results[2]);
+}
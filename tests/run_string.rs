@@ -0,0 +1,84 @@
+//! `Interpreter::run_string` on well-known programs.
+
+use rust_bf::ast::Position;
+use rust_bf::{BrainfuckError, Interpreter};
+
+#[test]
+fn hello_world() {
+    let src = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+    assert_eq!(Interpreter::run_string(src, "").unwrap(), "Hello World!\n");
+}
+
+#[test]
+fn cat() {
+    assert_eq!(Interpreter::run_string(",[.,]", "copy me\n").unwrap(), "copy me\n");
+}
+
+#[test]
+fn cat_without_input() {
+    assert_eq!(Interpreter::run_string(",[.,]", "").unwrap(), "");
+}
+
+#[test]
+fn reverse_line() {
+    let src = ">,----------[++++++++++>,----------]<[.<]";
+    assert_eq!(Interpreter::run_string(src, "stressed\n").unwrap(), "desserts");
+}
+
+#[test]
+fn add_two_digits() {
+    let src = ",>,[<+>-]<------------------------------------------------.";
+    assert_eq!(Interpreter::run_string(src, "34").unwrap(), "7");
+}
+
+#[test]
+fn uppercase() {
+    let src = ",[--------------------------------.,]";
+    assert_eq!(Interpreter::run_string(src, "shout").unwrap(), "SHOUT");
+}
+
+#[test]
+fn digits() {
+    let src = "++++++++[>++++++<-]>>++++++++++[<.+>-]";
+    assert_eq!(Interpreter::run_string(src, "").unwrap(), "0123456789");
+}
+
+#[test]
+fn nested_loops() {
+    assert_eq!(Interpreter::run_string("++++[>++++[>++++[>+<-]<-]<-]>>>+.", "").unwrap(), "A");
+}
+
+#[test]
+fn empty_program() {
+    assert_eq!(Interpreter::run_string("", "").unwrap(), "");
+}
+
+#[test]
+fn comments_only() {
+    assert_eq!(Interpreter::run_string("this program does nothing", "").unwrap(), "");
+}
+
+#[test]
+fn invalid_utf8_is_replaced() {
+    // 255 after wrapping below zero is not valid UTF-8 on its own
+    assert_eq!(Interpreter::run_string("-.", "").unwrap(), "\u{fffd}");
+}
+
+#[test]
+fn compile_errors_are_returned() {
+    assert!(matches!(
+        Interpreter::run_string("+[", ""),
+        Err(BrainfuckError::UnmatchedOpen(Position { offset: 1, .. }))
+    ));
+}
+
+#[test]
+fn compile_errors_know_line_and_column() {
+    let src = "read a byte ,\r\n\tthen print it .\n  and loop forever +[ [-]\n";
+    let err = Interpreter::run_string(src, "").unwrap_err();
+    assert!(matches!(err, BrainfuckError::UnmatchedOpen(Position { line: 3, column: 21, .. })), "{:?}", err);
+    assert_eq!(err.to_string(), "unmatched '[' at line 3, column 21");
+
+    let err = Interpreter::run_string("+\n+\n ]", "").unwrap_err();
+    assert_eq!(err.to_string(), "unmatched ']' at line 3, column 2");
+}
@@ -0,0 +1,56 @@
+//! `Scan` from loops like `[>]` and `[<]` ends on the same cell as the loop.
+
+use rust_bf::ast::parse;
+use rust_bf::ir::{compile, BrainfuckIR};
+use rust_bf::optimizer::optimize;
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// Returns the tape, the cell the pointer ends on and the error `ir` stopped with.
+fn run(ir: Vec<BrainfuckIR>, limit: Option<usize>, wrap: bool) -> (Vec<u8>, usize, Option<String>) {
+    let mut interpreter = Interpreter::from_ir(ir).with_wrap_tape(wrap).output(Vec::new());
+    if let Some(limit) = limit {
+        interpreter = interpreter.with_memory_limit(limit);
+    }
+    let err = interpreter.run().err().map(|err: BrainfuckError| match err {
+        // the IR index of the failing node differs
+        BrainfuckError::PointerUnderflow { .. } => String::from("underflow"),
+        err => err.to_string(),
+    });
+    (interpreter.tape().to_vec(), interpreter.tape_pointer(), err)
+}
+
+/// Checks that `src` runs the same with and without the optimizer, and that it made a `Scan`.
+fn same(src: &str, limit: Option<usize>, wrap: bool) {
+    let ir = compile(&parse(src).unwrap());
+    let optimized = optimize(&ir);
+    assert!(optimized.iter().any(|node| matches!(node, BrainfuckIR::Scan(_))), "{}", src);
+    assert_eq!(run(optimized, limit, wrap), run(ir, limit, wrap), "{}", src);
+}
+
+#[test]
+fn scans_end_on_the_first_zero_cell() {
+    for len in 0..40 {
+        let cells = "+>".repeat(len);
+        // right to the end of the tape and past it, then back left to cell 0
+        same(&format!(">{}{}<[<]", cells, "<".repeat(len)), None, false);
+        same(&format!(">{}<<[-]+>>[<]", cells), None, false);
+        // a zero cell in the middle of the run
+        same(&format!(">{}[-]{}[>]", cells, "<".repeat(len / 2 + 1)), None, false);
+    }
+}
+
+#[test]
+fn wider_steps_skip_cells() {
+    same("+>+>+>+>+>+<<<<<[>>]", None, false);
+    same(">>>>>>+<+<+<+<+<+>>>>>>[<<<]", None, false);
+}
+
+#[test]
+fn scans_fail_like_the_loops() {
+    same("+[<]", None, false);
+    same("+>+>+<<[>]", Some(3), false);
+    same("+>+>+<<[>]", Some(4), false);
+    // around a circular tape to the one zero cell
+    same("+>+>+>+>>+<<[>]", Some(6), true);
+    same("+>+>+>+>>+<<<<[<]", Some(6), true);
+}
@@ -0,0 +1,24 @@
+//! Programs can start on a tape already holding data, see `Interpreter::with_tape`.
+
+use rust_bf::Interpreter;
+
+#[test]
+fn seeded_text_prints_back() {
+    let mut output = Vec::new();
+    Interpreter::new("[.>]").unwrap().with_tape(b"ABC").output(&mut output).run().unwrap();
+    assert_eq!(output, b"ABC");
+}
+
+#[test]
+fn seeded_cells_are_data_like_any_other() {
+    // adds cell 1 to cell 0, the pointer starts on cell 0 however long the seed is
+    let mut interpreter = Interpreter::new(">[-<+>]<").unwrap().with_tape(&[40, 2, 9]);
+    interpreter.run().unwrap();
+    assert_eq!((interpreter.tape(), interpreter.tape_pointer()), (&[42, 0, 9][..], 0));
+
+    interpreter.reset();
+    assert!(interpreter.tape().is_empty());
+    let mut interpreter = Interpreter::new("+").unwrap().with_tape(&[]);
+    interpreter.run().unwrap();
+    assert_eq!(interpreter.tape(), &[1]);
+}
@@ -0,0 +1,25 @@
+//! Runs a brainfuck interpreter written in brainfuck on top of this one.
+
+use rust_bf::Interpreter;
+
+const DBFI: &str = include_str!("fixtures/dbfi.bf");
+
+#[test]
+fn dbfi_runs_hello_world() {
+    let hello = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+    let input = format!("{}!", hello);
+    assert_eq!(Interpreter::run_string(DBFI, &input).unwrap(), "Hello World!\n");
+}
+
+#[test]
+fn dbfi_feeds_input_to_the_inner_program() {
+    // the inner program reverses the line it reads
+    let input = ">,----------[++++++++++>,----------]<[.<]!stressed\n";
+    assert_eq!(Interpreter::run_string(DBFI, input).unwrap(), "desserts");
+}
+
+#[test]
+fn dbfi_passes_end_of_input_through() {
+    // the inner cat stops when its `,` reads the 0 left at end of input
+    assert_eq!(Interpreter::run_string(DBFI, ",[.,]!echo").unwrap(), "echo");
+}
@@ -0,0 +1,75 @@
+//! `=` writes its own character into the current cell once turned on, build with `--features self_modifying`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::ir::{compile, BrainfuckIR};
+use rust_bf::packed::{load, pack};
+use rust_bf::vm::{new_brainfuck_status, run_vm};
+use rust_bf::{ast::parse, BrainfuckOp, Interpreter};
+
+/// writes each of its `=` into a cell of its own, then prints them back
+const ECHO: &str = "=>=>=<<.>.>.";
+
+/// Runs `src` a character at a time on a fresh VM with `=` `on` or off and returns what it printed.
+fn run_on_vm(src: &str, on: bool) -> Vec<u8> {
+    let mut status = new_brainfuck_status();
+    status.set_self_modifying(on);
+    let mut output = Vec::new();
+    for c in src.chars() {
+        run_vm(&mut status, c, false, &mut &b""[..], &mut output).unwrap();
+    }
+    output
+}
+
+#[test]
+fn equals_sign_is_an_op() {
+    assert_eq!(BrainfuckOp::from_char('='), BrainfuckOp::SelfWriteOp);
+    assert_eq!(BrainfuckOp::SelfWriteOp.as_char(), Some('='));
+    let ir = compile(&parse("+=.").unwrap());
+    assert_eq!(ir, vec![BrainfuckIR::Add(1), BrainfuckIR::SelfWrite, BrainfuckIR::Print]);
+}
+
+#[test]
+fn programs_read_back_what_they_wrote_of_themselves() {
+    let source: Vec<u8> = ECHO.bytes().filter(|&c| c == b'=').collect();
+    let mut interpreter = Interpreter::new(ECHO).unwrap().output(Vec::new()).with_self_modifying(true);
+    interpreter.run().unwrap();
+    assert_eq!(&interpreter.tape()[..3], source);
+    assert_eq!(run_on_vm(ECHO, true), source);
+}
+
+#[test]
+fn equals_sign_overwrites_the_cell_inside_loops() {
+    // every round stores `=` in the second cell and adds one to it
+    let src = "+++[>=+<-]>.";
+    assert_eq!(run_on_vm(src, true), b">");
+    let mut output = Vec::new();
+    Interpreter::new(src).unwrap().output(&mut output).with_self_modifying(true).run().unwrap();
+    assert_eq!(output, b">");
+}
+
+#[test]
+fn equals_sign_does_nothing_while_off() {
+    // a comment like the ones in tests/corpus/nested.bf
+    let src = "4 * 4 * 4 = 64 ++++++++[>++++++++<-]>+.";
+    assert_eq!(run_on_vm(src, false), b"A");
+    let mut output = Vec::new();
+    Interpreter::new(src).unwrap().output(&mut output).run().unwrap();
+    assert_eq!(output, b"A");
+}
+
+#[test]
+fn packed_programs_leave_it_out() {
+    assert_eq!(load(&pack(ECHO.as_bytes()).unwrap()).unwrap(), compile(&parse(">><<.>.>.").unwrap()));
+}
+
+#[test]
+fn the_command_line_turns_it_on() {
+    let run = |args: &[&str]| {
+        let (code, stdout, _) = run_file(ECHO, args);
+        (code, stdout)
+    };
+    assert_eq!(run(&[]), (Some(0), vec![0, 0, 0]));
+    assert_eq!(run(&["--self-modifying"]), (Some(0), b"===".to_vec()));
+}
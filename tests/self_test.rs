@@ -0,0 +1,45 @@
+//! `self_test` passes on every VM setting it checks, and `--self-test` reports it.
+
+use std::process::Command;
+
+use rust_bf::io::EofMode;
+use rust_bf::selftest::self_test;
+
+#[test]
+fn default_vm_passes_every_check() {
+    let results = self_test(|_| ());
+    assert_eq!(results.len(), 6);
+    for check in &results {
+        assert!(check.passed(), "{}: {:?}", check.name, check.failure);
+    }
+}
+
+#[test]
+fn every_cell_width_and_eof_mode_passes() {
+    for bits in 1..=32 {
+        for mode in [EofMode::Zero, EofMode::MinusOne, EofMode::Unchanged, EofMode::Error] {
+            let results = self_test(|status| {
+                status.set_cell_bits(bits);
+                status.set_eof_mode(mode);
+            });
+            let failed: Vec<_> = results.iter().filter(|check| !check.passed()).collect();
+            assert!(failed.is_empty(), "{} bits, {:?}: {:?}", bits, mode, failed);
+        }
+    }
+}
+
+#[test]
+fn cli_prints_a_line_per_check_and_fails_on_a_failure() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--self-test").output().unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.starts_with("PASS  cell wrapping\n"));
+    assert!(report.ends_with("6 of 6 checks passed\n"));
+
+    // a tape too small for the checks fails them
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--self-test", "--max-tape", "2"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("FAIL  large moves: the program failed: program touched more than the tape limit of 2 cells\n"));
+    assert!(report.ends_with("4 of 6 checks passed\n"));
+}
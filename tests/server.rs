@@ -0,0 +1,111 @@
+//! `serve` answers `POST /run` requests, run with `cargo test --features server`.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// The server process, killed once the test is done with it.
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    /// Starts a server on a port the system picks.
+    fn start(args: &[&str]) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+            .args(["serve", "--port", "0"])
+            .args(args)
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap()).read_line(&mut line).unwrap();
+        let address = line.trim().strip_prefix("listening on http://").unwrap_or_else(|| panic!("{}", line)).to_string();
+        Server { child, address }
+    }
+
+    /// Sends a request and returns the status code and body of the answer.
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", method, path, body.len(), body).unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        let (head, body) = answer.split_once("\r\n\r\n").unwrap();
+        let code = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (code, body.to_string())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn runs_a_program() {
+    let server = Server::start(&[]);
+    // `,[.,]` echoes "hi", base64 "aGk="
+    let (code, body) = server.request("POST", "/run", r#"{"program": ",[.,]", "input": "aGk="}"#);
+    assert_eq!(code, 200);
+    assert_eq!(body, r#"{"error":null,"output":"aGk=","status":"halted","steps":8}"#);
+}
+
+#[test]
+fn stops_at_the_step_limit() {
+    let server = Server::start(&["--max-steps", "500"]);
+    // asking for more steps than the server allows gets its limit
+    let (code, body) = server.request("POST", "/run", r#"{"program": "+[.]", "max_steps": 1000000}"#);
+    assert_eq!(code, 200);
+    assert!(body.contains(r#""status":"step_limit""#), "{}", body);
+    assert!(body.contains(r#""steps":500"#), "{}", body);
+    assert!(body.contains("step limit of 500 steps"), "{}", body);
+
+    let (_, body) = server.request("POST", "/run", r#"{"program": "+[.]", "max_steps": 10}"#);
+    assert!(body.contains(r#""steps":10"#), "{}", body);
+}
+
+#[test]
+fn rejects_invalid_programs() {
+    let server = Server::start(&[]);
+    let (code, body) = server.request("POST", "/run", r#"{"program": "+[>"}"#);
+    assert_eq!(code, 400);
+    assert_eq!(body, r#"{"error":"unmatched '[' at line 1, column 2","output":"","status":"error","steps":0}"#);
+
+    let (code, _) = server.request("POST", "/run", "not json");
+    assert_eq!(code, 400);
+    let (code, _) = server.request("GET", "/run", "");
+    assert_eq!(code, 405);
+    let (code, _) = server.request("POST", "/", "");
+    assert_eq!(code, 404);
+}
+
+#[test]
+fn answers_requests_at_once() {
+    let server = Server::start(&["--jobs", "2"]);
+    let answers: Vec<(u16, String)> = std::thread::scope(|scope| {
+        let requests: Vec<_> = (0..8)
+            .map(|i| {
+                let server = &server;
+                scope.spawn(move || server.request("POST", "/run", &format!(r#"{{"program": "{}."}}"#, "+".repeat(65 + i))))
+            })
+            .collect();
+        requests.into_iter().map(|request| request.join().unwrap()).collect()
+    });
+    for (i, (code, body)) in answers.iter().enumerate() {
+        assert_eq!(*code, 200);
+        // program `i` prints letter `i`, each on a VM of its own
+        let letter = [b'A' + i as u8];
+        let expected = format!(r#""output":"{}""#, base64_of(&letter));
+        assert!(body.contains(&expected), "{}: {}", i, body);
+    }
+}
+
+/// Returns the base64 of a single byte.
+fn base64_of(bytes: &[u8; 1]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let byte = bytes[0] as usize;
+    format!("{}{}==", ALPHABET[byte >> 2] as char, ALPHABET[(byte & 3) << 4] as char)
+}
@@ -0,0 +1,42 @@
+//! Every byte of the output maps back to the `.` that printed it.
+
+use rust_bf::ast::tokens;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes};
+use rust_bf::BrainfuckOp;
+
+#[test]
+fn csv_fields_map_back_to_the_lines_that_print_them() {
+    // prints "a,b\n", one line of the program per byte
+    let src = "first field  ++++++++++[>++++++++++<-]>---.\n\
+               comma        >+++++[<---------->-]<---.\n\
+               second field >+++++[<++++++++++>-]<++++.\n\
+               newline      [-]++++++++++.\n";
+    let mut status = new_brainfuck_status();
+    status.record_output_map();
+    let mut output = Vec::new();
+    run_vm_bytes(&mut status, src.as_bytes(), &mut &b""[..], &mut output).unwrap();
+    assert_eq!(output, b"a,b\n");
+
+    let map = status.output_map().unwrap();
+    assert_eq!(map.entries().len(), output.len());
+    for (byte, line) in [(0, 1), (1, 2), (2, 3), (3, 4)] {
+        let instruction = map.instruction_for_output_byte(byte).unwrap();
+        let (op, at) = tokens(src.as_bytes()).nth(instruction).unwrap();
+        assert_eq!(op, BrainfuckOp::PrintOp);
+        assert_eq!(at.line, line);
+        assert_eq!(map.position_for_output_byte(src, byte), Some(at));
+    }
+    assert_eq!(map.instruction_for_output_byte(4), None);
+}
+
+#[test]
+fn only_bytes_printed_after_recording_starts_are_mapped() {
+    let mut status = new_brainfuck_status();
+    let mut output = Vec::new();
+    run_vm_bytes(&mut status, b"+..", &mut &b""[..], &mut output).unwrap();
+    assert!(status.output_map().is_none());
+    status.record_output_map();
+    run_vm_bytes(&mut status, b"+.", &mut &b""[..], &mut output).unwrap();
+    assert_eq!(status.output_map().unwrap().entries(), [(2, 4)]);
+    assert_eq!(status.output_map().unwrap().instruction_for_output_byte(0), None);
+}
@@ -0,0 +1,24 @@
+//! Stepping in batches leaves the interpreter where stepping one node at a time does.
+
+use rust_bf::Interpreter;
+
+#[test]
+fn a_batch_of_steps_matches_single_steps() {
+    let src = "++[>+++[>++<-]<-]>>.";
+    let mut batched = Interpreter::new(src).unwrap();
+    let mut single = Interpreter::new(src).unwrap();
+    while !single.is_halted() {
+        assert_eq!(batched.step_n(5).unwrap(), (0..5).filter(|_| single.step().unwrap()).count() as u64);
+        assert_eq!(batched.snapshot(), single.snapshot());
+    }
+    assert!(batched.is_halted());
+    assert_eq!(batched.step_n(5).unwrap(), 0);
+}
+
+#[test]
+fn a_failing_step_stops_the_batch() {
+    let mut interpreter = Interpreter::new("+++<").unwrap();
+    assert!(interpreter.step_n(5).is_err());
+    assert_eq!(interpreter.instruction_pointer(), 1);
+    assert_eq!(interpreter.tape(), &[3]);
+}
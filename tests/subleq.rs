@@ -0,0 +1,45 @@
+//! Programs compiled to Subleq print what they print in brainfuck.
+
+use rust_bf::ast::parse;
+use rust_bf::codegen::subleq::{run_subleq, to_subleq};
+use rust_bf::ir::compile;
+use rust_bf::optimizer::optimize;
+use rust_bf::Interpreter;
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+/// Runs `src` compiled to Subleq, naive and optimized, and checks that both print what the interpreter prints.
+fn check(src: &str, input: &[u8]) {
+    let ir = compile(&parse(src).unwrap());
+    let mut expected = Vec::new();
+    Interpreter::from_ir(ir.clone()).input(input).output(&mut expected).run().unwrap();
+    for ir in [ir.clone(), optimize(&ir)] {
+        assert_eq!(run_subleq(&to_subleq(&ir), input).unwrap(), expected, "{}", src);
+    }
+}
+
+#[test]
+fn hello_world() {
+    check(HELLO, b"");
+    let ir = compile(&parse(HELLO).unwrap());
+    assert_eq!(run_subleq(&to_subleq(&ir), b"").unwrap(), b"Hello World!\n");
+}
+
+#[test]
+fn cells_wrap_at_256() {
+    check("-.+.", b"");
+    check(&format!("{}.", "+".repeat(300)), b"");
+    check("++[--->+<]>.", b"");
+}
+
+#[test]
+fn input_and_end_of_input() {
+    check(",[.,]", b"echo");
+    check("+,.", b"");
+}
+
+#[test]
+fn multiplication_loops() {
+    check(",>,<[>[>+>+<<-]>>[<<+>>-]<<<-]>>.", &[6, 7]);
+    check("++++[>+++++<-]>[>-----<-]>.", b"");
+}
@@ -0,0 +1,74 @@
+//! Tapes with cells on both sides of cell 0 or only right of it, see `--tape`.
+
+mod common;
+
+use common::run_file;
+use rust_bf::vm::{new_brainfuck_status, run_vm_bytes, run_vm_repeated, TapeBounds};
+use rust_bf::BrainfuckError;
+
+/// Adds 2 and 3 in cell -1 and prints the sum from cell 0.
+const NEGATIVE: &str = "<++>+++<[->+<]>.";
+
+#[test]
+fn both_sided_tapes_keep_the_cells_left_of_cell_0() {
+    let mut status = new_brainfuck_status();
+    let mut output = Vec::new();
+    run_vm_bytes(&mut status, NEGATIVE.as_bytes(), &mut &b""[..], &mut output).unwrap();
+    assert_eq!(output, [5]);
+    assert_eq!(status.negative_cells(), 1);
+    assert_eq!(status.allocated_cells(), 2);
+}
+
+#[test]
+fn right_only_tapes_fail_or_stay_at_cell_0() {
+    let mut status = new_brainfuck_status();
+    status.set_right_only(TapeBounds::Error);
+    let err = run_vm_bytes(&mut status, NEGATIVE.as_bytes(), &mut &b""[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::PointerUnderflow { position: 0 }), "{:?}", err);
+
+    let mut status = new_brainfuck_status();
+    status.set_right_only(TapeBounds::Saturate);
+    run_vm_bytes(&mut status, b"<<+>+<<+", &mut &b""[..], &mut Vec::new()).unwrap();
+    assert_eq!(status.cells(), [(0, 2), (1, 1)]);
+    assert_eq!(status.negative_cells(), 0);
+
+    // a run of moves stops at the op that leaves the tape
+    let mut status = new_brainfuck_status();
+    status.set_right_only(TapeBounds::Error);
+    run_vm_repeated(&mut status, '>', 10, &mut &b""[..], &mut Vec::new()).unwrap();
+    let err = run_vm_repeated(&mut status, '<', 20, &mut &b""[..], &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, BrainfuckError::PointerUnderflow { position: 20 }), "{:?}", err);
+}
+
+#[test]
+fn cells_far_from_cell_0_are_kept_too() {
+    let mut status = new_brainfuck_status();
+    let mut output = Vec::new();
+    run_vm_repeated(&mut status, '<', 1_000_000, &mut &b""[..], &mut output).unwrap();
+    run_vm_bytes(&mut status, b"+++", &mut &b""[..], &mut output).unwrap();
+    run_vm_repeated(&mut status, '>', 2_000_000, &mut &b""[..], &mut output).unwrap();
+    run_vm_bytes(&mut status, b"++", &mut &b""[..], &mut output).unwrap();
+    // walking back over the cells in between finds both again
+    run_vm_repeated(&mut status, '<', 2_000_000, &mut &b""[..], &mut output).unwrap();
+    run_vm_bytes(&mut status, b".[>+<-]>.", &mut &b""[..], &mut output).unwrap();
+    assert_eq!(output, [3, 3]);
+    assert_eq!(status.cells(), [(-999_999, 3), (1_000_000, 2)]);
+    assert_eq!(status.negative_cells(), 2);
+}
+
+#[test]
+fn the_command_line_picks_the_sides() {
+    let run = |args: &[&str]| run_file(NEGATIVE, args);
+    assert_eq!(run(&[]), (Some(0), vec![5], String::new()));
+    assert_eq!(run(&["--tape=both"]), (Some(0), vec![5], String::new()));
+    let failed = (Some(1), vec![], String::from("error: instruction 0 moved the pointer left of cell 0\n"));
+    assert_eq!(run(&["--tape=right-only"]), failed);
+    assert_eq!(run(&["--tape=right-only", "--tape-bounds=error"]), failed);
+    // the 2 land in cell 0 and are added to the 3 in cell 1
+    assert_eq!(run(&["--tape=right-only", "--tape-bounds=saturate"]), (Some(0), vec![5], String::new()));
+    assert_eq!(run(&["--tape=right-only", "--tape-bounds=wrap"]).0, Some(2));
+    assert_eq!(run(&["--tape=right-only", "--tape-size", "10"]).0, Some(2));
+
+    let report = run(&["--tape=both", "--memory-report"]).2;
+    assert!(report.contains("went left of cell 0 by 1 cells and touched 1 cell there"), "{}", report);
+}
@@ -0,0 +1,66 @@
+//! `BrainfuckServer` answers programs sent over plain TCP.
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::thread;
+
+use rust_bf::batch::Limits;
+use rust_bf::tcp::BrainfuckServer;
+
+/// Starts a server on a port the system picks and returns its address.
+fn start(limits: Option<Limits>) -> SocketAddr {
+    let mut server = BrainfuckServer::bind("127.0.0.1:0").unwrap();
+    if let Some(limits) = limits {
+        server = server.with_limits(limits);
+    }
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.serve());
+    address
+}
+
+/// Sends `request`, closes the sending side and returns the whole answer.
+fn request(address: SocketAddr, request: &[u8]) -> Vec<u8> {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+    let mut answer = Vec::new();
+    stream.read_to_end(&mut answer).unwrap();
+    answer
+}
+
+#[test]
+fn programs_read_what_follows_the_nul() {
+    let address = start(None);
+    assert_eq!(request(address, b",[.,]\0hi there"), b"halted 26 8\nhi there");
+    assert_eq!(request(address, b"++++++++[>++++++++<-]>+.\0"), b"halted 45 1\nA");
+}
+
+#[test]
+fn limits_hold_per_connection() {
+    let address = start(Some(Limits { step_limit: Some(1000), max_output: Some(4), ..Limits::default() }));
+    assert_eq!(request(address, b"+[]\0"), b"step_limit 1000 0 program ran past the step limit of 1000 steps\n".to_vec());
+    assert_eq!(
+        request(address, b"+[.]\0"),
+        b"error 11 4 program printed more than the output limit of 4 bytes\n\x01\x01\x01\x01".to_vec()
+    );
+}
+
+#[test]
+fn compile_errors_are_answered() {
+    let address = start(None);
+    assert_eq!(request(address, b"+[\0"), b"error 0 0 unmatched '[' at line 1, column 2\n".to_vec());
+}
+
+#[test]
+fn connections_are_served_at_the_same_time() {
+    let address = start(None);
+    // the first connection waits for input that only comes once the second one is answered
+    let mut waiting = TcpStream::connect(address).unwrap();
+    waiting.write_all(b",.\0").unwrap();
+    assert_eq!(request(address, b"+++.\0"), b"halted 2 1\n\x03".to_vec());
+    waiting.write_all(b"x").unwrap();
+    waiting.shutdown(Shutdown::Write).unwrap();
+    let mut answer = Vec::new();
+    waiting.read_to_end(&mut answer).unwrap();
+    assert_eq!(answer, b"halted 2 1\nx");
+}
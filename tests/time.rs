@@ -0,0 +1,21 @@
+//! `--time` reports the run on a single stderr line.
+
+use std::process::Command;
+
+#[test]
+fn time_reports_ops_and_cells() {
+    let program = std::env::temp_dir().join(format!("rust_bf-time-{}.bf", std::process::id()));
+    std::fs::write(&program, "++++++++++[>++++++++++[>++++++++++<-]<-]").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).arg("--time").arg(&program).output().unwrap();
+    std::fs::remove_file(&program).unwrap();
+    assert!(output.status.success());
+
+    // executed 1,462 ops in 0.43ms (3.38M ops/s), peak tape cells: 3
+    let report = String::from_utf8(output.stderr).unwrap();
+    let words: Vec<&str> = report.split_whitespace().collect();
+    assert_eq!(words[..3], ["executed", "1,462", "ops"], "{}", report);
+    let time = words[4].trim_end_matches(|c: char| c.is_alphabetic() || c == 'µ');
+    assert!(time.parse::<f64>().unwrap() > 0.0, "{}", report);
+    assert!(words[5].starts_with('('), "{}", report);
+    assert!(report.trim_end().ends_with("peak tape cells: 3"), "{}", report);
+}
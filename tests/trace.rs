@@ -0,0 +1,57 @@
+//! `--trace` writes one line of text per op run.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs `program` on `input` with `args` and returns the text and JSON traces.
+fn trace(name: &str, program: &str, input: &[u8], args: &[&str]) -> (String, String) {
+    let dir = std::env::temp_dir();
+    let name = format!("rust_bf-text-trace-{}-{}", std::process::id(), name);
+    let (source, text, json) = (dir.join(format!("{}.bf", name)), dir.join(format!("{}.txt", name)), dir.join(format!("{}.jsonl", name)));
+    std::fs::write(&source, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .arg("--trace")
+        .arg(&text)
+        .arg("--trace-json")
+        .arg(&json)
+        .args(args)
+        .arg(&source)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    assert!(child.wait().unwrap().success());
+    let traces = (std::fs::read_to_string(&text).unwrap(), std::fs::read_to_string(&json).unwrap());
+    for file in [source, text, json] {
+        std::fs::remove_file(file).unwrap();
+    }
+    traces
+}
+
+#[test]
+fn every_op_is_a_line() {
+    let (text, _) = trace("ops", "+,>[-] x .,", b"A", &[]);
+    assert_eq!(
+        text,
+        "1 0 + ptr=0 cell=0->1\n\
+         2 1 , ptr=0 cell=1->65 byte=65\n\
+         3 2 > ptr=0 cell=65->65\n\
+         4 3 [ ptr=1 cell=0->0\n\
+         5 6 . ptr=1 cell=0->0 byte=0\n\
+         6 7 , ptr=1 cell=0->0 byte=eof\n"
+    );
+    assert_eq!(text.lines().filter(|line| line.contains(" , ")).count(), 2);
+}
+
+#[test]
+fn text_and_json_traces_cover_the_same_ops() {
+    let (text, json) = trace("sampled", "++++++++[>++++++++<-]>+.", b"", &["--trace-sample", "5"]);
+    assert_eq!(text.lines().count(), json.lines().count());
+    for (line, object) in text.lines().zip(json.lines()) {
+        let step = line.split(' ').next().unwrap();
+        assert!(object.starts_with(&format!("{{\"step\":{},", step)), "{} {}", line, object);
+    }
+    // the same run traces the same, so that traces of two versions can be diffed
+    assert_eq!(trace("again", "++++++++[>++++++++<-]>+.", b"", &["--trace-sample", "5"]).0, text);
+}
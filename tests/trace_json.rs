@@ -0,0 +1,88 @@
+//! `--trace-json` writes one JSON object per op run.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Parses a flat JSON object of numbers, strings without escapes and nulls into its fields.
+fn fields(line: &str) -> BTreeMap<String, String> {
+    let mut rest = line.strip_prefix('{').expect(line);
+    let mut fields = BTreeMap::new();
+    while let Some(field) = rest.strip_prefix('"') {
+        let (key, value) = field.split_once("\":").expect(line);
+        let (value, after) = match value.strip_prefix('"') {
+            Some(string) => string.split_once('"').expect(line),
+            None => value.split_at(value.find([',', '}']).expect(line)),
+        };
+        fields.insert(key.to_string(), value.to_string());
+        rest = after.strip_prefix(',').unwrap_or(after);
+    }
+    assert_eq!(rest, "}", "{}", line);
+    fields
+}
+
+/// Runs `program` with `--trace-json` and `args`, feeding it `input`, and returns the trace.
+fn trace(program: &str, input: &[u8], args: &[&str]) -> Vec<BTreeMap<String, String>> {
+    let dir = std::env::temp_dir();
+    let name = format!("rust_bf-trace-{}-{}", std::process::id(), args.len());
+    let (source, trace) = (dir.join(format!("{}.bf", name)), dir.join(format!("{}.jsonl", name)));
+    std::fs::write(&source, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_bf"))
+        .arg("--trace-json")
+        .arg(&trace)
+        .args(args)
+        .arg(&source)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    assert!(child.wait().unwrap().success());
+    let jsonl = std::fs::read_to_string(&trace).unwrap();
+    std::fs::remove_file(&source).unwrap();
+    std::fs::remove_file(&trace).unwrap();
+    jsonl.lines().map(fields).collect()
+}
+
+#[test]
+fn every_op_is_an_event() {
+    // runs 10 ops, the loop body once
+    let events = trace("+,.>++<[-]", &[1], &[]);
+    assert_eq!(events.len(), 10);
+
+    let ops: String = events.iter().map(|event| event["op"].as_str()).collect();
+    assert_eq!(ops, "+,.>++<[-]");
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event["step"], (i + 1).to_string());
+        assert_eq!(event["ip"], i.to_string());
+        let keys: Vec<&str> = event.keys().map(String::as_str).collect();
+        let io = matches!(event["op"].as_str(), "." | ",");
+        let expected = if io {
+            ["byte", "cell_after", "cell_before", "ip", "op", "ptr", "step"].to_vec()
+        } else {
+            ["cell_after", "cell_before", "ip", "op", "ptr", "step"].to_vec()
+        };
+        assert_eq!(keys, expected);
+    }
+
+    // `,` stores the byte read, which `.` prints
+    assert_eq!((events[1]["cell_before"].as_str(), events[1]["cell_after"].as_str(), events[1]["byte"].as_str()), ("1", "1", "1"));
+    assert_eq!(events[2]["byte"], "1");
+    // `>` reports the cell it moved away from, the `+` after it cell 1
+    assert_eq!((events[3]["ptr"].as_str(), events[4]["ptr"].as_str()), ("0", "1"));
+    assert_eq!((events[5]["cell_before"].as_str(), events[5]["cell_after"].as_str()), ("1", "2"));
+    assert_eq!((events[8]["cell_before"].as_str(), events[8]["cell_after"].as_str()), ("1", "0"));
+}
+
+#[test]
+fn end_of_input_reads_null() {
+    let events = trace(",", b"", &[]);
+    assert_eq!(events[0]["byte"], "null");
+}
+
+#[test]
+fn sampling_keeps_every_nth_event() {
+    let events = trace("+,.>++<[-]", &[1], &["--trace-sample", "3"]);
+    let steps: Vec<&str> = events.iter().map(|event| event["step"].as_str()).collect();
+    assert_eq!(steps, ["3", "6", "9"]);
+}
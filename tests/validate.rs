@@ -0,0 +1,54 @@
+//! The validators agree with what running the programs does.
+
+use rust_bf::ast::parse;
+use rust_bf::ir::compile;
+use rust_bf::optimizer::optimize;
+use rust_bf::validate::{
+    BalancedValidator, MemSafeValidator, PureComputeValidator, TerminationValidator, ValidationError, Validator,
+};
+use rust_bf::{BrainfuckError, Interpreter};
+
+/// Returns how `validator` finds `src`, compiled and optimized, checking both agree.
+fn validate(validator: impl Validator, src: &str) -> Result<(), ValidationError> {
+    let ir = compile(&parse(src).unwrap());
+    let compiled = validator.validate(&ir);
+    assert_eq!(validator.validate(&optimize(&ir)).is_ok(), compiled.is_ok(), "{}", src);
+    compiled
+}
+
+#[test]
+fn compiled_programs_are_balanced() {
+    for src in ["", "[]", "+[[->+<]>[-]<-]", "[[[[]]]][]"] {
+        assert_eq!(validate(BalancedValidator, src), Ok(()), "{}", src);
+    }
+}
+
+#[test]
+fn endless_loops_hit_the_step_limit() {
+    for src in ["+[]", "-[.]", "++>+<[>+<]", "+>>+++<<[>+<]"] {
+        assert!(matches!(validate(TerminationValidator, src), Err(ValidationError::NonTerminating { .. })), "{}", src);
+        let mut interpreter = Interpreter::new(src).unwrap().output(Vec::new()).with_step_limit(10_000);
+        assert!(matches!(interpreter.run(), Err(BrainfuckError::StepLimitExceeded { .. })), "{}", src);
+    }
+}
+
+#[test]
+fn mem_safe_programs_run_on_a_tape_without_cells_to_the_left() {
+    for src in ["++++++++[>++++++++<-]>+.", "+[>+]", ">>+<[-]>[-<+>]<<"] {
+        assert_eq!(validate(MemSafeValidator, src), Ok(()), "{}", src);
+    }
+    assert_eq!(validate(MemSafeValidator, ">+[<<+>>-]"), Err(ValidationError::LeftOfStart { index: 3 }));
+    assert_eq!(validate(MemSafeValidator, "+[-<+>]"), Err(ValidationError::LeftOfStart { index: 3 }));
+}
+
+#[test]
+fn combined_validators_report_the_first_failure() {
+    let all = (BalancedValidator, TerminationValidator, PureComputeValidator, MemSafeValidator);
+    assert_eq!(validate(all, "+++[->++<]"), Ok(()));
+    assert_eq!(validate(all, "+[.]<"), Err(ValidationError::NonTerminating { index: 1 }));
+    assert_eq!(validate(all, ".<"), Err(ValidationError::Io { index: 0 }));
+    let validators: [&dyn Validator; 2] = [&PureComputeValidator, &MemSafeValidator];
+    let ir = compile(&parse("<").unwrap());
+    assert_eq!(validators.iter().find_map(|v| v.validate(&ir).err()), Some(ValidationError::LeftOfStart { index: 0 }));
+    assert_eq!(ValidationError::LeftOfStart { index: 0 }.to_string(), "node 0 may reach left of the first cell");
+}
@@ -0,0 +1,45 @@
+//! `--verify` must pass on every program in `tests/corpus`.
+
+use std::process::Command;
+
+use rust_bf::ir::compile_source;
+use rust_bf::optimizer::optimize;
+use rust_bf::verify::{check_equivalence, verify, Divergence, EquivalenceResult};
+
+#[test]
+fn corpus_verifies() {
+    let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_bf")).args(["--verify", corpus]).output().unwrap();
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", report);
+    assert!(report.lines().count() >= 5, "{}", report);
+    assert!(report.lines().all(|line| line.ends_with(": ok")), "{}", report);
+}
+
+#[test]
+fn walking_the_tree_agrees_with_the_optimized_ir() {
+    assert_eq!(verify(include_str!("../src/examples/rot13.bf"), b"Hello, World!\n").unwrap(), None);
+    assert_eq!(verify(include_str!("../src/examples/sierpinski.bf"), b"").unwrap(), None);
+    // both runs fail, whatever op they fail at
+    assert_eq!(verify("+.[>]<<", b"").unwrap(), None);
+    assert!(verify("[", b"").is_err());
+}
+
+#[test]
+fn divergences_say_which_ops_printed_the_bytes() {
+    let divergence = Divergence::Output { index: 2, naive: Some(65), optimized: None, naive_position: Some(7), optimized_position: None };
+    assert_eq!(divergence.to_string(), "output byte 2 differs: naive wrote 65 at op 7, optimized wrote nothing at IR -");
+}
+
+#[test]
+fn equivalence_skips_inputs_the_original_does_not_finish_on() {
+    // loops forever once it reads a nonzero byte
+    let original = compile_source(b",[+[]]").unwrap();
+    let stalled = compile_source(b",[+[]]+.").unwrap();
+    assert_eq!(check_equivalence(&original, &stalled, &[b"\x01"], 100), EquivalenceResult::Equivalent);
+    assert!(matches!(check_equivalence(&original, &stalled, &[b"\x01", b""], 100), EquivalenceResult::Diverged { input, .. } if input.is_empty()));
+
+    let ir = compile_source(include_bytes!("../src/examples/rot13.bf")).unwrap();
+    let inputs: [&[u8]; 3] = [b"", b"Hello, World!\n", b"\xff\x00abc"];
+    assert_eq!(check_equivalence(&ir, &optimize(&ir), &inputs, 1_000_000), EquivalenceResult::Equivalent);
+}
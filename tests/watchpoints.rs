@@ -0,0 +1,62 @@
+//! Watchpoints stop a run right after a cell changes, and the run goes on from there with `resume`.
+
+use rust_bf::ir::compile_source;
+use rust_bf::optimizer::optimize;
+use rust_bf::{BrainfuckError, Interpreter, WatchTrigger};
+
+/// Returns the cell, old and new value and instruction of the watchpoint the run that returned `result` stopped at.
+fn stop(result: Result<(), BrainfuckError>) -> (usize, u8, u8, usize) {
+    match result {
+        Err(BrainfuckError::WatchpointTriggered { cell, old_value, new_value, instruction_position }) => {
+            (cell, old_value, new_value, instruction_position)
+        },
+        result => panic!("expected a watchpoint, got {:?}", result),
+    }
+}
+
+#[test]
+fn triggers_stop_on_the_changes_they_match() {
+    // counts cell 0 down from 3 and back up, reading cell 1 twice
+    let mut interpreter = Interpreter::new("+++[-]+++>,,").unwrap().input(&b"AA"[..]);
+    interpreter.set_watchpoint(0, WatchTrigger::Equals(1));
+    interpreter.set_watchpoint(1, WatchTrigger::AnyChange);
+    assert_eq!(stop(interpreter.run()), (0, 2, 1, 2));
+    interpreter.set_watchpoint(0, WatchTrigger::LessThan(1));
+    assert_eq!(stop(interpreter.resume()), (0, 1, 0, 2));
+    interpreter.clear_watchpoint(0);
+    // the second `,` reads the same byte, which is no change
+    assert_eq!(stop(interpreter.resume()), (1, 0, b'A', 6));
+    interpreter.set_watchpoint(1, WatchTrigger::GreaterThan(b'A'));
+    interpreter.resume().unwrap();
+    assert_eq!(interpreter.tape(), b"\x03A");
+}
+
+#[test]
+fn optimized_programs_stop_on_cells_they_write_from_afar() {
+    let ir = optimize(&compile_source(b"++++[->++>+++<<]").unwrap());
+    let mut interpreter = Interpreter::from_ir(ir);
+    interpreter.set_watchpoint(2, WatchTrigger::AnyChange);
+    let (cell, old_value, new_value, position) = stop(interpreter.run());
+    assert_eq!((cell, old_value, new_value), (2, 0, 12));
+    assert!(matches!(interpreter.program()[position], rust_bf::ir::BrainfuckIR::MulAdd { offset: 2, factor: 3 }));
+    // cells that do not exist yet are 0
+    assert_eq!(BrainfuckError::WatchpointTriggered { cell, old_value, new_value, instruction_position: 3 }.to_string(), "instruction 3 changed watched cell 2 from 0 to 12");
+}
+
+#[test]
+fn watchpoints_and_breakpoints_stop_the_same_run() {
+    let mut interpreter = Interpreter::new("+>+>+").unwrap();
+    interpreter.set_watchpoint(0, WatchTrigger::AnyChange);
+    interpreter.set_breakpoint(1);
+    let mut fork = interpreter.fork();
+    for interpreter in [&mut interpreter, &mut fork] {
+        assert_eq!(stop(interpreter.run()), (0, 0, 1, 0));
+        // resuming after the watchpoint does not run past the breakpoint that follows it
+        assert!(matches!(interpreter.resume(), Err(BrainfuckError::BreakpointHit { position: 1, .. })));
+        interpreter.resume().unwrap();
+        assert_eq!(interpreter.tape(), &[1, 1, 1]);
+    }
+    interpreter.reset();
+    interpreter.clear_all_watchpoints();
+    assert!(matches!(interpreter.run(), Err(BrainfuckError::BreakpointHit { position: 1, .. })));
+}